@@ -0,0 +1,103 @@
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+/// Harmonics the Additive instrument sums, fundamental included.
+pub const HARMONIC_COUNT: usize = 32;
+
+/// Level (0..1) of each harmonic of the Additive instrument, fundamental
+/// first. Saved as a plain list; a list of another length is cut or padded
+/// with silent harmonics.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<f32>", into = "Vec<f32>")]
+pub struct Harmonics(pub [f32; HARMONIC_COUNT]);
+
+impl Default for Harmonics {
+    /// The first eight harmonics at 1/n: a mellow saw.
+    fn default() -> Self {
+        Self(std::array::from_fn(|index| {
+            if index < 8 {
+                1.0 / (index + 1) as f32
+            } else {
+                0.0
+            }
+        }))
+    }
+}
+
+impl From<Vec<f32>> for Harmonics {
+    fn from(levels: Vec<f32>) -> Self {
+        Self(std::array::from_fn(|index| {
+            levels.get(index).copied().unwrap_or(0.0)
+        }))
+    }
+}
+
+impl From<Harmonics> for Vec<f32> {
+    fn from(harmonics: Harmonics) -> Self {
+        harmonics.0.to_vec()
+    }
+}
+
+impl Harmonics {
+    /// Only the fundamental.
+    pub fn sine() -> Self {
+        Self(std::array::from_fn(
+            |index| if index == 0 { 1.0 } else { 0.0 },
+        ))
+    }
+
+    /// Odd harmonics at 1/n.
+    pub fn square() -> Self {
+        Self(std::array::from_fn(|index| {
+            if index % 2 == 0 {
+                1.0 / (index + 1) as f32
+            } else {
+                0.0
+            }
+        }))
+    }
+
+    /// A drawbar-organ registration: strong low harmonics with a little of
+    /// the 6th and 8th.
+    pub fn organ() -> Self {
+        Self(std::array::from_fn(|index| match index {
+            0 => 1.0,
+            1 => 0.8,
+            2 => 0.6,
+            3 => 0.5,
+            5 | 7 => 0.3,
+            _ => 0.0,
+        }))
+    }
+
+    pub fn clamp(&mut self) {
+        for level in &mut self.0 {
+            *level = if level.is_nan() {
+                0.0
+            } else {
+                level.clamp(0.0, 1.0)
+            };
+        }
+    }
+
+    /// One sample at `phase` (0..1) of the sum of the first `audible`
+    /// harmonics, scaled by the total of all levels so it never passes ±1.
+    /// Harmonics above `audible` are left out rather than aliasing.
+    pub fn sample(&self, phase: f32, audible: usize) -> f32 {
+        let total: f32 = self.0.iter().sum();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        // sin((n + 1)x) = 2 cos(x) sin(nx) - sin((n - 1)x): one sin_cos per
+        // sample instead of one sin per harmonic.
+        let (sin, cos) = (TAU * phase).sin_cos();
+        let (mut previous, mut current) = (0.0, sin);
+        let mut sum = 0.0;
+        for &level in self.0.iter().take(audible) {
+            sum += level * current;
+            (previous, current) = (current, 2.0 * cos * current - previous);
+        }
+        sum / total
+    }
+}
@@ -1,29 +1,45 @@
 use std::sync::{Arc, Mutex};
 
+use cpal::Sample;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 
+use crate::meters::{MeterWriter, OutputMeters};
+use crate::mic::{MicBuffer, MicReader};
 use crate::scope::ScopeBuffer;
-use crate::synth::{SynthEngine, SynthShared};
+use crate::synth::{SynthEngine, SynthShared, SynthSnapshot};
+use crate::watchdog::{AudioWatchdog, CallbackTimer};
 
 const SNAPSHOT_REFRESH_INTERVAL: usize = 64;
+/// Frames the render block holds before it has to grow; larger than any
+/// common device buffer.
+const BLOCK_CAPACITY: usize = 8192;
+/// Frames per callback asked for on phones and tablets, whose default
+/// buffers are sized for media playback and make playing feel sluggish.
+#[cfg(any(target_os = "android", target_os = "ios"))]
+const MOBILE_BUFFER_FRAMES: u32 = 256;
 
 #[derive(Clone)]
 pub struct SynthAudio {
     _stream: Arc<cpal::Stream>,
     pub device_name: String,
+    pub sample_rate: f32,
+    pub meters: Arc<OutputMeters>,
+    pub watchdog: Arc<AudioWatchdog>,
 }
 
 impl SynthAudio {
     pub fn new(
         shared: Arc<Mutex<SynthShared>>,
-        scope: Arc<Mutex<ScopeBuffer>>,
+        scope: Arc<ScopeBuffer>,
+        mic: Arc<Mutex<MicBuffer>>,
     ) -> Result<Self, String> {
-        Self::new_with_device(shared, scope, None)
+        Self::new_with_device(shared, scope, mic, None)
     }
 
     pub fn new_with_device(
         shared: Arc<Mutex<SynthShared>>,
-        scope: Arc<Mutex<ScopeBuffer>>,
+        scope: Arc<ScopeBuffer>,
+        mic: Arc<Mutex<MicBuffer>>,
         device_name: Option<&str>,
     ) -> Result<Self, String> {
         let host = cpal::default_host();
@@ -35,27 +51,29 @@ impl SynthAudio {
             .default_output_config()
             .map_err(|err| format!("Could not query default output: {err}"))?;
         let sample_format = supported_config.sample_format();
-        let config: cpal::StreamConfig = supported_config.into();
+        let buffer_size = output_buffer_size(supported_config.buffer_size());
+        let mut config: cpal::StreamConfig = supported_config.into();
+        config.buffer_size = buffer_size;
         let sample_rate = config.sample_rate.0 as f32;
         let channels = config.channels as usize;
 
-        let err_fn = |err| eprintln!("Audio stream error: {err}");
+        let meters = Arc::new(OutputMeters::new(sample_rate));
+        let watchdog = Arc::new(AudioWatchdog::new(sample_rate));
+        let err_fn = {
+            let watchdog = Arc::clone(&watchdog);
+            move |err| {
+                watchdog.record_stream_error(format!("Audio stream error: {err}"));
+            }
+        };
         let stream = match sample_format {
             cpal::SampleFormat::F32 => {
-                let mut engine = SynthEngine::new(sample_rate);
                 let shared_state = Arc::clone(&shared);
-                let scope_state = Arc::clone(&scope);
+                let mut state = CallbackState::new(sample_rate, &scope, &meters, &watchdog, &mic);
                 device
                     .build_output_stream(
                         &config,
                         move |data: &mut [f32], _| {
-                            write_samples_f32(
-                                &shared_state,
-                                &mut engine,
-                                data,
-                                channels,
-                                &scope_state,
-                            );
+                            write_samples_f32(&shared_state, &mut state, data, channels);
                         },
                         err_fn,
                         None,
@@ -63,20 +81,13 @@ impl SynthAudio {
                     .map_err(|err| format!("Failed to build f32 stream: {err}"))?
             }
             cpal::SampleFormat::I16 => {
-                let mut engine = SynthEngine::new(sample_rate);
                 let shared_state = Arc::clone(&shared);
-                let scope_state = Arc::clone(&scope);
+                let mut state = CallbackState::new(sample_rate, &scope, &meters, &watchdog, &mic);
                 device
                     .build_output_stream(
                         &config,
                         move |data: &mut [i16], _| {
-                            write_samples_i16(
-                                &shared_state,
-                                &mut engine,
-                                data,
-                                channels,
-                                &scope_state,
-                            );
+                            write_samples_i16(&shared_state, &mut state, data, channels);
                         },
                         err_fn,
                         None,
@@ -84,20 +95,13 @@ impl SynthAudio {
                     .map_err(|err| format!("Failed to build i16 stream: {err}"))?
             }
             cpal::SampleFormat::U16 => {
-                let mut engine = SynthEngine::new(sample_rate);
                 let shared_state = Arc::clone(&shared);
-                let scope_state = Arc::clone(&scope);
+                let mut state = CallbackState::new(sample_rate, &scope, &meters, &watchdog, &mic);
                 device
                     .build_output_stream(
                         &config,
                         move |data: &mut [u16], _| {
-                            write_samples_u16(
-                                &shared_state,
-                                &mut engine,
-                                data,
-                                channels,
-                                &scope_state,
-                            );
+                            write_samples_u16(&shared_state, &mut state, data, channels);
                         },
                         err_fn,
                         None,
@@ -114,10 +118,31 @@ impl SynthAudio {
         Ok(Self {
             _stream: Arc::new(stream),
             device_name: resolved_device_name,
+            sample_rate,
+            meters,
+            watchdog,
         })
     }
 }
 
+/// On Android (AAudio through oboe) and iOS (Core Audio), a small buffer
+/// within what the device supports, for low latency.
+#[cfg(any(target_os = "android", target_os = "ios"))]
+fn output_buffer_size(supported: &cpal::SupportedBufferSize) -> cpal::BufferSize {
+    match supported {
+        cpal::SupportedBufferSize::Range { min, max } => {
+            cpal::BufferSize::Fixed(MOBILE_BUFFER_FRAMES.clamp(*min, *max))
+        }
+        cpal::SupportedBufferSize::Unknown => cpal::BufferSize::Default,
+    }
+}
+
+/// Desktop hosts already default to a low-latency buffer.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+fn output_buffer_size(_supported: &cpal::SupportedBufferSize) -> cpal::BufferSize {
+    cpal::BufferSize::Default
+}
+
 fn select_output_device(host: &cpal::Host, name: Option<&str>) -> Result<cpal::Device, String> {
     if let Some(target) = name {
         if let Ok(devices) = host.output_devices() {
@@ -147,103 +172,244 @@ pub fn list_output_device_names() -> Vec<String> {
         .unwrap_or_default()
 }
 
+/// A capture stream feeding the microphone, mixed down to mono, into the
+/// buffer the output stream reads from.
+pub struct MicInput {
+    _stream: cpal::Stream,
+    pub device_name: String,
+    errors: Arc<Mutex<Vec<String>>>,
+}
+
+impl MicInput {
+    /// Opens `device_name`, or the default input when `None`.
+    pub fn open(device_name: Option<&str>, buffer: Arc<Mutex<MicBuffer>>) -> Result<Self, String> {
+        let host = cpal::default_host();
+        let device = match device_name {
+            Some(target) => host
+                .input_devices()
+                .ok()
+                .and_then(|mut devices| {
+                    devices.find(|device| device.name().is_ok_and(|name| name == target))
+                })
+                .ok_or_else(|| format!("Input device '{target}' not found"))?,
+            None => host
+                .default_input_device()
+                .ok_or_else(|| "No audio input device available".to_string())?,
+        };
+        let resolved_device_name = device
+            .name()
+            .unwrap_or_else(|_| "<unknown input>".to_string());
+        let supported_config = device
+            .default_input_config()
+            .map_err(|err| format!("Could not query default input: {err}"))?;
+        let sample_format = supported_config.sample_format();
+        let config: cpal::StreamConfig = supported_config.into();
+        let channels = config.channels as usize;
+        if let Ok(mut buffer) = buffer.lock() {
+            buffer.sample_rate = config.sample_rate.0 as f32;
+        }
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let sink = MicStreamSink {
+            channels,
+            buffer,
+            errors: Arc::clone(&errors),
+        };
+        let stream = match sample_format {
+            cpal::SampleFormat::F32 => build_mic_stream::<f32>(&device, &config, sink),
+            cpal::SampleFormat::I16 => build_mic_stream::<i16>(&device, &config, sink),
+            cpal::SampleFormat::U16 => build_mic_stream::<u16>(&device, &config, sink),
+            other => return Err(format!("Unsupported input sample format: {other:?}")),
+        }
+        .map_err(|err| format!("Failed to open the input: {err}"))?;
+        stream
+            .play()
+            .map_err(|err| format!("Failed to start the input: {err}"))?;
+        Ok(Self {
+            _stream: stream,
+            device_name: resolved_device_name,
+            errors,
+        })
+    }
+
+    /// Backend error messages reported since the last call.
+    pub fn take_errors(&self) -> Vec<String> {
+        self.errors
+            .lock()
+            .map(|mut errors| std::mem::take(&mut *errors))
+            .unwrap_or_default()
+    }
+}
+
+/// Where a capture stream's callbacks deliver samples and errors.
+struct MicStreamSink {
+    channels: usize,
+    buffer: Arc<Mutex<MicBuffer>>,
+    errors: Arc<Mutex<Vec<String>>>,
+}
+
+fn build_mic_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sink: MicStreamSink,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: cpal::SizedSample,
+    f32: cpal::FromSample<T>,
+{
+    let MicStreamSink {
+        channels,
+        buffer,
+        errors,
+    } = sink;
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            if let Ok(mut buffer) = buffer.lock() {
+                buffer.record(data.chunks(channels).map(|frame| {
+                    frame
+                        .iter()
+                        .map(|sample| f32::from_sample(*sample))
+                        .sum::<f32>()
+                        / channels as f32
+                }));
+            }
+        },
+        move |err| {
+            if let Ok(mut errors) = errors.lock() {
+                errors.push(format!("Audio input error: {err}"));
+            }
+        },
+        None,
+    )
+}
+
+pub fn list_input_device_names() -> Vec<String> {
+    let host = cpal::default_host();
+    host.input_devices()
+        .map(|devices| devices.filter_map(|d| d.name().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Everything one output stream's callback owns. `block` and `snapshot`
+/// keep their capacity between callbacks, so steady-state rendering never
+/// allocates for them.
+struct CallbackState {
+    engine: SynthEngine,
+    snapshot: SynthSnapshot,
+    scope: Arc<ScopeBuffer>,
+    meter_writer: MeterWriter,
+    timer: CallbackTimer,
+    mic: MicReader,
+    block: Vec<[f32; 2]>,
+}
+
+impl CallbackState {
+    fn new(
+        sample_rate: f32,
+        scope: &Arc<ScopeBuffer>,
+        meters: &Arc<OutputMeters>,
+        watchdog: &Arc<AudioWatchdog>,
+        mic: &Arc<Mutex<MicBuffer>>,
+    ) -> Self {
+        Self {
+            engine: SynthEngine::new(sample_rate),
+            snapshot: SynthShared::default().snapshot(),
+            scope: Arc::clone(scope),
+            meter_writer: MeterWriter::new(Arc::clone(meters)),
+            timer: CallbackTimer::new(Arc::clone(watchdog)),
+            mic: MicReader::new(Arc::clone(mic), sample_rate),
+            block: Vec::with_capacity(BLOCK_CAPACITY),
+        }
+    }
+
+    /// Renders `frames` stereo frames into `block` and feeds the scope,
+    /// meters and any take being recorded, timing it for the watchdog.
+    fn render(&mut self, shared: &Mutex<SynthShared>, frames: usize) {
+        self.timer.start(frames);
+        {
+            let mut shared = shared.lock().expect("Synth parameters poisoned");
+            shared.set_voice_levels(self.engine.voice_levels());
+            shared.snapshot_into(&mut self.snapshot);
+        }
+        self.engine.update_eq(&self.snapshot);
+        self.mic.fetch();
+        self.block.clear();
+        for i in 0..frames {
+            if i > 0 && i.is_multiple_of(SNAPSHOT_REFRESH_INTERVAL) {
+                shared
+                    .lock()
+                    .expect("Synth parameters poisoned")
+                    .snapshot_into(&mut self.snapshot);
+                self.engine.update_eq(&self.snapshot);
+            }
+            let stereo = self
+                .engine
+                .next_frame(&self.snapshot, self.mic.next_sample(&self.snapshot));
+            self.scope.push(mono(stereo));
+            self.block.push(stereo);
+        }
+        self.mic.finish_block(&self.block);
+        self.meter_writer.record(&self.block);
+        self.meter_writer
+            .record_gain_reduction(self.engine.take_gain_reduction_db(), frames);
+        self.timer.finish();
+    }
+}
+
 fn write_samples_f32(
-    shared: &Arc<Mutex<SynthShared>>,
-    engine: &mut SynthEngine,
+    shared: &Mutex<SynthShared>,
+    state: &mut CallbackState,
     buffer: &mut [f32],
     channels: usize,
-    scope: &Arc<Mutex<ScopeBuffer>>,
 ) {
-    let mut snapshot = {
-        let shared = shared.lock().expect("Synth parameters poisoned");
-        shared.snapshot()
-    };
-    engine.update_eq(&snapshot.params);
-    let mut scope_block = Vec::with_capacity(buffer.len() / channels);
-
-    for (i, frame) in buffer.chunks_mut(channels).enumerate() {
-        if i % SNAPSHOT_REFRESH_INTERVAL == 0 {
-            snapshot = {
-                let shared = shared.lock().expect("Synth parameters poisoned");
-                shared.snapshot()
-            };
-            engine.update_eq(&snapshot.params);
-        }
-        let sample = engine.next_sample(&snapshot);
-        scope_block.push(sample);
-        for channel in frame {
-            *channel = sample;
+    state.render(shared, buffer.len() / channels);
+    for (frame, stereo) in buffer.chunks_mut(channels).zip(&state.block) {
+        for (index, channel) in frame.iter_mut().enumerate() {
+            *channel = channel_sample(*stereo, channels, index);
         }
     }
-    record_scope(scope, &scope_block);
 }
 
 fn write_samples_i16(
-    shared: &Arc<Mutex<SynthShared>>,
-    engine: &mut SynthEngine,
+    shared: &Mutex<SynthShared>,
+    state: &mut CallbackState,
     buffer: &mut [i16],
     channels: usize,
-    scope: &Arc<Mutex<ScopeBuffer>>,
 ) {
-    let mut snapshot = {
-        let shared = shared.lock().expect("Synth parameters poisoned");
-        shared.snapshot()
-    };
-    engine.update_eq(&snapshot.params);
-    let mut scope_block = Vec::with_capacity(buffer.len() / channels);
-
-    for (i, frame) in buffer.chunks_mut(channels).enumerate() {
-        if i % SNAPSHOT_REFRESH_INTERVAL == 0 {
-            snapshot = {
-                let shared = shared.lock().expect("Synth parameters poisoned");
-                shared.snapshot()
-            };
-            engine.update_eq(&snapshot.params);
-        }
-        let sample = engine.next_sample(&snapshot);
-        scope_block.push(sample);
-        let scaled = (sample * i16::MAX as f32).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
-        for channel in frame {
-            *channel = scaled;
+    state.render(shared, buffer.len() / channels);
+    for (frame, stereo) in buffer.chunks_mut(channels).zip(&state.block) {
+        for (index, channel) in frame.iter_mut().enumerate() {
+            let sample = channel_sample(*stereo, channels, index);
+            *channel = (sample * i16::MAX as f32).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
         }
     }
-    record_scope(scope, &scope_block);
 }
 
 fn write_samples_u16(
-    shared: &Arc<Mutex<SynthShared>>,
-    engine: &mut SynthEngine,
+    shared: &Mutex<SynthShared>,
+    state: &mut CallbackState,
     buffer: &mut [u16],
     channels: usize,
-    scope: &Arc<Mutex<ScopeBuffer>>,
 ) {
-    let mut snapshot = {
-        let shared = shared.lock().expect("Synth parameters poisoned");
-        shared.snapshot()
-    };
-    engine.update_eq(&snapshot.params);
-    let mut scope_block = Vec::with_capacity(buffer.len() / channels);
-
-    for (i, frame) in buffer.chunks_mut(channels).enumerate() {
-        if i % SNAPSHOT_REFRESH_INTERVAL == 0 {
-            snapshot = {
-                let shared = shared.lock().expect("Synth parameters poisoned");
-                shared.snapshot()
-            };
-            engine.update_eq(&snapshot.params);
-        }
-        let normalized = engine.next_sample(&snapshot).clamp(-1.0, 1.0);
-        scope_block.push(normalized);
-        let value = ((normalized * 0.5 + 0.5) * u16::MAX as f32) as u16;
-        for channel in frame {
-            *channel = value;
+    state.render(shared, buffer.len() / channels);
+    for (frame, stereo) in buffer.chunks_mut(channels).zip(&state.block) {
+        for (index, channel) in frame.iter_mut().enumerate() {
+            let normalized = channel_sample(*stereo, channels, index).clamp(-1.0, 1.0);
+            *channel = ((normalized * 0.5 + 0.5) * u16::MAX as f32) as u16;
         }
     }
-    record_scope(scope, &scope_block);
 }
 
-fn record_scope(scope: &Arc<Mutex<ScopeBuffer>>, block: &[f32]) {
-    if let Ok(mut buffer) = scope.lock() {
-        buffer.record(block);
+fn mono([left, right]: [f32; 2]) -> f32 {
+    (left + right) * 0.5
+}
+
+/// Left and right go to the first two channels; mono devices and any
+/// channels past the second get the mono sum.
+fn channel_sample(stereo: [f32; 2], channels: usize, index: usize) -> f32 {
+    match index {
+        0 | 1 if channels >= 2 => stereo[index],
+        _ => mono(stereo),
     }
 }
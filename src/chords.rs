@@ -0,0 +1,51 @@
+pub const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Intervals above the root, in semitones, with the suffix shown after the
+/// root name. Sevenths and sixths come before the triads they contain.
+const CHORD_SHAPES: &[(&[u8], &str)] = &[
+    (&[0, 4, 7, 11], "maj7"),
+    (&[0, 4, 7, 10], "7"),
+    (&[0, 3, 7, 10], "m7"),
+    (&[0, 3, 6, 10], "m7b5"),
+    (&[0, 3, 6, 9], "dim7"),
+    (&[0, 3, 7, 11], "m(maj7)"),
+    (&[0, 4, 7, 9], "6"),
+    (&[0, 3, 7, 9], "m6"),
+    (&[0, 4, 7], " major"),
+    (&[0, 3, 7], " minor"),
+    (&[0, 3, 6], " diminished"),
+    (&[0, 4, 8], " augmented"),
+    (&[0, 2, 7], "sus2"),
+    (&[0, 5, 7], "sus4"),
+    (&[0, 7], "5"),
+];
+
+/// Names the chord formed by `notes`, e.g. "C major" or "Am7/G" when the
+/// lowest note is not the root. Octave doublings are ignored; `None` when the
+/// pitch classes match no known shape.
+pub fn chord_name(notes: &[u8]) -> Option<String> {
+    let bass = notes.iter().min()? % 12;
+    let classes = notes.iter().fold(0_u16, |set, note| set | 1 << (note % 12));
+    // Try the bass note as root first so root-position readings win.
+    let roots = (0..12).map(|step| (bass + step) % 12);
+    for root in roots.filter(|root| classes & (1 << root) != 0) {
+        let shape = (0..12)
+            .filter(|interval| classes & (1 << ((root + interval) % 12)) != 0)
+            .fold(0_u16, |set, interval| set | 1 << interval);
+        let Some((_, suffix)) = CHORD_SHAPES
+            .iter()
+            .find(|(intervals, _)| intervals.iter().fold(0_u16, |set, i| set | 1 << i) == shape)
+        else {
+            continue;
+        };
+        let mut name = format!("{}{suffix}", PITCH_CLASS_NAMES[root as usize]);
+        if root != bass {
+            name.push('/');
+            name.push_str(PITCH_CLASS_NAMES[bass as usize]);
+        }
+        return Some(name);
+    }
+    None
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use crate::params::ParamId;
+use crate::synth::SynthParams;
+
+/// MIDI control change sent by breath controllers.
+pub const BREATH_CC: u8 = 2;
+/// Octaves the filter closes at zero breath with the cutoff amount at 1.
+const BREATH_CUTOFF_OCTAVES: f32 = 4.0;
+
+/// A MIDI control change number driving a synth parameter across its range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CcMapping {
+    pub cc: u8,
+    pub param: ParamId,
+}
+
+/// How breath pressure maps onto the level it drives.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreathCurve {
+    /// Opens up early, for players with a light touch.
+    Soft,
+    #[default]
+    Linear,
+    /// Needs more pressure before much happens.
+    Hard,
+}
+
+impl BreathCurve {
+    pub const ALL: [BreathCurve; 3] = [BreathCurve::Soft, BreathCurve::Linear, BreathCurve::Hard];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BreathCurve::Soft => "Soft",
+            BreathCurve::Linear => "Linear",
+            BreathCurve::Hard => "Hard",
+        }
+    }
+
+    pub fn apply(self, breath: f32) -> f32 {
+        let breath = breath.clamp(0.0, 1.0);
+        match self {
+            BreathCurve::Soft => breath.sqrt(),
+            BreathCurve::Linear => breath,
+            BreathCurve::Hard => breath * breath,
+        }
+    }
+}
+
+/// Where breath controller pressure goes: the volume, the filter cutoff or
+/// both, through its own response curve.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreathRouting {
+    pub enabled: bool,
+    pub curve: BreathCurve,
+    /// 0..1; at 1 the part is silent without breath.
+    pub amplitude: f32,
+    /// 0..1; at 1 the filter closes four octaves without breath.
+    pub cutoff: f32,
+}
+
+impl Default for BreathRouting {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            curve: BreathCurve::Linear,
+            amplitude: 1.0,
+            cutoff: 0.5,
+        }
+    }
+}
+
+impl BreathRouting {
+    /// Shapes `params` for a breath pressure of 0..1.
+    pub fn apply(&self, breath: f32, params: &mut SynthParams) {
+        let missing = 1.0 - self.curve.apply(breath);
+        params.gain *= 1.0 - self.amplitude.clamp(0.0, 1.0) * missing;
+        params.filter_cutoff_hz *=
+            2_f32.powf(-self.cutoff.clamp(0.0, 1.0) * missing * BREATH_CUTOFF_OCTAVES);
+    }
+}
+
+/// Ready-made mappings for popular controllers, using the CC numbers their
+/// knobs and faders send out of the box.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControllerTemplate {
+    LaunchkeyMk3,
+    KeyStep37,
+    NanoKontrol2,
+}
+
+impl ControllerTemplate {
+    pub const ALL: [ControllerTemplate; 3] = [
+        ControllerTemplate::LaunchkeyMk3,
+        ControllerTemplate::KeyStep37,
+        ControllerTemplate::NanoKontrol2,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ControllerTemplate::LaunchkeyMk3 => "Novation Launchkey MK3",
+            ControllerTemplate::KeyStep37 => "Arturia KeyStep 37",
+            ControllerTemplate::NanoKontrol2 => "Korg nanoKONTROL2",
+        }
+    }
+
+    pub fn mappings(self) -> Vec<CcMapping> {
+        let pairs: &[(u8, ParamId)] = match self {
+            // Eight knobs on CC 21–28, mod wheel on CC 1.
+            ControllerTemplate::LaunchkeyMk3 => &[
+                (1, ParamId::VibratoDepth),
+                (21, ParamId::FilterCutoff),
+                (22, ParamId::FilterResonance),
+                (23, ParamId::Attack),
+                (24, ParamId::Decay),
+                (25, ParamId::Sustain),
+                (26, ParamId::Release),
+                (27, ParamId::UnisonSpread),
+                (28, ParamId::NoiseMix),
+            ],
+            // Four knobs on CC 74, 71, 76 and 77, mod strip on CC 1.
+            ControllerTemplate::KeyStep37 => &[
+                (1, ParamId::VibratoDepth),
+                (74, ParamId::FilterCutoff),
+                (71, ParamId::FilterResonance),
+                (76, ParamId::Attack),
+                (77, ParamId::Release),
+            ],
+            // Faders on CC 0–7, knobs on CC 16–23.
+            ControllerTemplate::NanoKontrol2 => &[
+                (0, ParamId::Gain),
+                (1, ParamId::Attack),
+                (2, ParamId::Decay),
+                (3, ParamId::Sustain),
+                (4, ParamId::Release),
+                (5, ParamId::EqLowGain),
+                (6, ParamId::EqMidGain),
+                (7, ParamId::EqHighGain),
+                (16, ParamId::FilterCutoff),
+                (17, ParamId::FilterResonance),
+                (18, ParamId::VibratoRate),
+                (19, ParamId::VibratoDepth),
+                (20, ParamId::UnisonSpread),
+                (21, ParamId::NoiseMix),
+                (22, ParamId::Autotune),
+                (23, ParamId::Glide),
+            ],
+        };
+        pairs
+            .iter()
+            .map(|&(cc, param)| CcMapping { cc, param })
+            .collect()
+    }
+}
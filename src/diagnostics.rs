@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use crate::watchdog::XrunCounts;
+
+/// Oldest events are dropped past this many.
+const MAX_EVENTS: usize = 200;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    AudioError,
+    Device,
+    Xrun,
+}
+
+impl EventKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            EventKind::AudioError => "Audio error",
+            EventKind::Device => "Device",
+            EventKind::Xrun => "Xrun",
+        }
+    }
+}
+
+pub struct DiagnosticEvent {
+    /// Time since the app started.
+    pub at: Duration,
+    pub kind: EventKind,
+    pub message: String,
+}
+
+/// Engine figures at the moment a report is made.
+pub struct EngineStats {
+    pub output_device: String,
+    pub input_device: Option<String>,
+    pub sample_rate: f32,
+    pub buffer_frames: u32,
+    pub active_voices: usize,
+    pub dsp_load: f32,
+    pub xruns: XrunCounts,
+}
+
+/// Recent audio errors, device changes and xruns for the Diagnostics panel,
+/// plus a plain-text report to paste into bug reports. Everything recorded
+/// is logged as well.
+pub struct Diagnostics {
+    started: Instant,
+    events: VecDeque<DiagnosticEvent>,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            events: VecDeque::new(),
+        }
+    }
+}
+
+impl Diagnostics {
+    pub fn record(&mut self, kind: EventKind, message: impl Into<String>) {
+        let message = message.into();
+        match kind {
+            EventKind::AudioError => log::error!("{message}"),
+            EventKind::Device => log::info!("{message}"),
+            EventKind::Xrun => log::warn!("{message}"),
+        }
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(DiagnosticEvent {
+            at: self.started.elapsed(),
+            kind,
+            message,
+        });
+    }
+
+    /// Oldest first.
+    pub fn events(&self) -> impl DoubleEndedIterator<Item = &DiagnosticEvent> {
+        self.events.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn report(&self, stats: &EngineStats) -> String {
+        let mut report = format!(
+            "Angel Synth {} on {} {}\n",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        let _ = writeln!(report, "Output: {}", stats.output_device);
+        let _ = writeln!(
+            report,
+            "Input: {}",
+            stats.input_device.as_deref().unwrap_or("off")
+        );
+        let _ = writeln!(
+            report,
+            "Sample rate: {} Hz, buffer: {} frames ({:.1} ms)",
+            stats.sample_rate,
+            stats.buffer_frames,
+            stats.buffer_frames as f32 * 1000.0 / stats.sample_rate
+        );
+        let _ = writeln!(
+            report,
+            "Active voices: {}, DSP load: {:.0}%",
+            stats.active_voices,
+            stats.dsp_load * 100.0
+        );
+        let _ = writeln!(
+            report,
+            "Xruns: {} under-runs, {} over-runs, {} stream errors",
+            stats.xruns.underruns, stats.xruns.overruns, stats.xruns.stream_errors
+        );
+        let _ = writeln!(report, "\nRecent events ({}):", self.events.len());
+        for event in &self.events {
+            let _ = writeln!(
+                report,
+                "[{}] {}: {}",
+                format_uptime(event.at),
+                event.kind.label(),
+                event.message
+            );
+        }
+        report
+    }
+}
+
+/// "1:02:03", or "2:03" under an hour.
+pub fn format_uptime(at: Duration) -> String {
+    let seconds = at.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
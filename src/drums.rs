@@ -0,0 +1,219 @@
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sampler::SampleFile;
+
+/// Pads in a drum kit.
+pub const PAD_COUNT: usize = 8;
+
+/// Frequencies of the six square waves in the hats' metallic ring, as on
+/// the TR-808.
+const HAT_SQUARES: [f32; 6] = [205.3, 304.4, 369.6, 522.7, 540.0, 800.0];
+
+/// A drum the Drums instrument synthesizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrumSound {
+    Kick,
+    Snare,
+    Clap,
+    ClosedHat,
+    OpenHat,
+    LowTom,
+    HighTom,
+    Rim,
+}
+
+impl DrumSound {
+    pub const ALL: [DrumSound; 8] = [
+        DrumSound::Kick,
+        DrumSound::Snare,
+        DrumSound::Clap,
+        DrumSound::ClosedHat,
+        DrumSound::OpenHat,
+        DrumSound::LowTom,
+        DrumSound::HighTom,
+        DrumSound::Rim,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DrumSound::Kick => "Kick",
+            DrumSound::Snare => "Snare",
+            DrumSound::Clap => "Clap",
+            DrumSound::ClosedHat => "Closed hat",
+            DrumSound::OpenHat => "Open hat",
+            DrumSound::LowTom => "Low tom",
+            DrumSound::HighTom => "High tom",
+            DrumSound::Rim => "Rim",
+        }
+    }
+
+    /// A few letters for the keys of the keyboard.
+    pub fn short_label(self) -> &'static str {
+        match self {
+            DrumSound::Kick => "BD",
+            DrumSound::Snare => "SD",
+            DrumSound::Clap => "CP",
+            DrumSound::ClosedHat => "CH",
+            DrumSound::OpenHat => "OH",
+            DrumSound::LowTom => "LT",
+            DrumSound::HighTom => "HT",
+            DrumSound::Rim => "RS",
+        }
+    }
+
+    /// How long a hit lasts: until its slowest decay is down by 60 dB.
+    fn seconds(self) -> f32 {
+        match self {
+            DrumSound::Kick => 2.1,
+            DrumSound::Snare => 1.1,
+            DrumSound::Clap => 0.9,
+            DrumSound::ClosedHat => 0.3,
+            DrumSound::OpenHat => 1.2,
+            DrumSound::LowTom | DrumSound::HighTom => 1.8,
+            DrumSound::Rim => 0.1,
+        }
+    }
+}
+
+/// One key of a drum kit: the drum it plays, or its sample when one is
+/// loaded.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrumPad {
+    pub note: u8,
+    pub sound: DrumSound,
+    #[serde(default)]
+    pub sample: SampleFile,
+}
+
+/// The keys the Drums instrument plays and what each one triggers. Saved
+/// as a plain list; a list of another length is cut, or padded from the
+/// default kit.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<DrumPad>", into = "Vec<DrumPad>")]
+pub struct DrumKit(pub [DrumPad; PAD_COUNT]);
+
+impl Default for DrumKit {
+    /// The General MIDI drum keys.
+    fn default() -> Self {
+        let gm = [
+            (36, DrumSound::Kick),
+            (37, DrumSound::Rim),
+            (38, DrumSound::Snare),
+            (39, DrumSound::Clap),
+            (42, DrumSound::ClosedHat),
+            (45, DrumSound::LowTom),
+            (46, DrumSound::OpenHat),
+            (50, DrumSound::HighTom),
+        ];
+        Self(gm.map(|(note, sound)| DrumPad {
+            note,
+            sound,
+            sample: SampleFile::default(),
+        }))
+    }
+}
+
+impl From<Vec<DrumPad>> for DrumKit {
+    fn from(pads: Vec<DrumPad>) -> Self {
+        let mut kit = Self::default();
+        for (slot, pad) in kit.0.iter_mut().zip(pads) {
+            *slot = pad;
+        }
+        kit
+    }
+}
+
+impl From<DrumKit> for Vec<DrumPad> {
+    fn from(kit: DrumKit) -> Self {
+        kit.0.to_vec()
+    }
+}
+
+impl DrumKit {
+    /// The pad on `note`; the first one when two share a key.
+    pub fn pad(&self, note: u8) -> Option<&DrumPad> {
+        self.0.iter().find(|pad| pad.note == note)
+    }
+
+    pub fn clamp(&mut self) {
+        for pad in &mut self.0 {
+            pad.note = pad.note.min(127);
+        }
+    }
+}
+
+/// The progress of one synthesized drum hit.
+#[derive(Clone, Copy, Default)]
+pub struct DrumHit {
+    seconds: f32,
+    last_noise: f32,
+}
+
+impl DrumHit {
+    /// The next sample of `sound`, from white `noise` (-1..1); `None` once
+    /// the hit has died away.
+    pub fn next(&mut self, sound: DrumSound, noise: f32, sample_rate: f32) -> Option<f32> {
+        let t = self.seconds;
+        if t >= sound.seconds() {
+            return None;
+        }
+        self.seconds += 1.0 / sample_rate;
+        // Differenced noise: white noise with its lows thinned out.
+        let bright = (noise - self.last_noise) * 0.5;
+        self.last_noise = noise;
+        let decay = |seconds: f32| (-t / seconds).exp();
+        let value = match sound {
+            DrumSound::Kick => {
+                let click = noise * decay(0.003) * 0.3;
+                sweep(t, 45.0, 2.4, 0.035) * decay(0.3) + click
+            }
+            DrumSound::Snare => {
+                (TAU * 185.0 * t).sin() * decay(0.08) * 0.5 + noise * decay(0.15) * 0.6
+            }
+            DrumSound::Clap => {
+                // Three quick claps, then the room's tail.
+                let bursts = [0.0, 0.011, 0.022]
+                    .into_iter()
+                    .filter(|start| t >= *start)
+                    .map(|start| (-(t - start) / 0.007).exp())
+                    .fold(0.0, f32::max);
+                let tail = if t >= 0.03 {
+                    (-(t - 0.03) / 0.12).exp() * 0.6
+                } else {
+                    0.0
+                };
+                bright * 1.5 * bursts.max(tail)
+            }
+            DrumSound::ClosedHat | DrumSound::OpenHat => {
+                let ring = HAT_SQUARES
+                    .iter()
+                    .map(|freq| if (freq * t).fract() < 0.5 { 1.0 } else { -1.0 })
+                    .sum::<f32>()
+                    / HAT_SQUARES.len() as f32;
+                let length = if sound == DrumSound::ClosedHat {
+                    0.04
+                } else {
+                    0.16
+                };
+                (bright * 0.8 + ring * 0.2) * decay(length)
+            }
+            DrumSound::LowTom => sweep(t, 95.0, 0.5, 0.05) * decay(0.25),
+            DrumSound::HighTom => sweep(t, 160.0, 0.5, 0.05) * decay(0.2),
+            DrumSound::Rim => {
+                (TAU * 1_700.0 * t).sin() * decay(0.012) * 0.6 + bright * decay(0.004)
+            }
+        };
+        Some(value)
+    }
+}
+
+/// A sine starting at `base × (1 + bend)` and falling to `base` with time
+/// constant `fall`, as a drum's skin settles.
+fn sweep(t: f32, base: f32, bend: f32, fall: f32) -> f32 {
+    // The integral of the falling frequency, so no phase needs keeping.
+    let phase = base * t + base * bend * fall * (1.0 - (-t / fall).exp());
+    (TAU * phase).sin()
+}
@@ -0,0 +1,279 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr_fmt;
+use crate::synth::SynthShared;
+use crate::theory::ChordKind;
+
+/// Intervals asked in the interval exercise, in semitones.
+const INTERVALS: [(&str, u8); 12] = [
+    ("Minor 2nd", 1),
+    ("Major 2nd", 2),
+    ("Minor 3rd", 3),
+    ("Major 3rd", 4),
+    ("Perfect 4th", 5),
+    ("Tritone", 6),
+    ("Perfect 5th", 7),
+    ("Minor 6th", 8),
+    ("Major 6th", 9),
+    ("Minor 7th", 10),
+    ("Major 7th", 11),
+    ("Octave", 12),
+];
+/// Questions are rooted between C3 and F#4 so every shape stays mid-range.
+const LOWEST_ROOT: u8 = 48;
+const ROOT_SPAN: u32 = 19;
+/// Interval notes are played one after the other, this far apart.
+const NOTE_STEP_SECS: f32 = 0.6;
+const RING_SECS: f32 = 1.2;
+const QUESTION_VELOCITY: f32 = 0.75;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exercise {
+    Intervals,
+    Chords,
+}
+
+impl Exercise {
+    pub const ALL: [Exercise; 2] = [Exercise::Intervals, Exercise::Chords];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Exercise::Intervals => "Intervals",
+            Exercise::Chords => "Chords",
+        }
+    }
+
+    /// Names and shapes (semitones above the root) to choose from.
+    pub fn choices(self) -> Vec<(&'static str, Vec<u8>)> {
+        match self {
+            Exercise::Intervals => INTERVALS
+                .iter()
+                .map(|(name, semitones)| (*name, vec![0, *semitones]))
+                .collect(),
+            Exercise::Chords => ChordKind::ALL
+                .iter()
+                .map(|kind| (kind.label(), kind.intervals().to_vec()))
+                .collect(),
+        }
+    }
+}
+
+/// How a question is answered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnswerMode {
+    /// Pick its name from a list.
+    Identify,
+    /// Play it back on the keyboard, from any starting note.
+    PlayBack,
+}
+
+impl AnswerMode {
+    pub const ALL: [AnswerMode; 2] = [AnswerMode::Identify, AnswerMode::PlayBack];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AnswerMode::Identify => "Name it",
+            AnswerMode::PlayBack => "Play it back",
+        }
+    }
+}
+
+/// Practice results, kept in the settings so streaks carry across sessions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EarStats {
+    pub streak: u32,
+    pub best_streak: u32,
+    pub correct: u32,
+    pub attempts: u32,
+}
+
+impl EarStats {
+    fn record(&mut self, correct: bool) {
+        self.attempts += 1;
+        if correct {
+            self.correct += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.streak = 0;
+        }
+    }
+}
+
+struct Question {
+    exercise: Exercise,
+    choice: usize,
+    notes: Vec<u8>,
+    answered: bool,
+}
+
+/// Ear training: plays a random interval or chord through the current patch
+/// and checks the answer, picked by name or played back on the keyboard.
+pub struct EarTrainer {
+    pub exercise: Exercise,
+    pub answer_mode: AnswerMode,
+    /// Outcome of the last answer, e.g. "Correct: Perfect 5th".
+    pub feedback: Option<(bool, String)>,
+    question: Option<Question>,
+    playing: Option<Instant>,
+    sounding: Vec<u8>,
+    /// Notes of the attempt being played back, collected until every key is up.
+    attempt: Vec<u8>,
+    seed: u32,
+}
+
+impl Default for EarTrainer {
+    fn default() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.subsec_nanos());
+        Self {
+            exercise: Exercise::Intervals,
+            answer_mode: AnswerMode::Identify,
+            feedback: None,
+            question: None,
+            playing: None,
+            sounding: Vec::new(),
+            attempt: Vec::new(),
+            seed: nanos | 1,
+        }
+    }
+}
+
+impl EarTrainer {
+    /// True while a question waits for its answer.
+    pub fn is_asking(&self) -> bool {
+        self.question
+            .as_ref()
+            .is_some_and(|question| !question.answered)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing.is_some()
+    }
+
+    /// Picks and plays a new question.
+    pub fn next_question(&mut self) {
+        let choices = self.exercise.choices();
+        let choice = self.random(choices.len() as u32) as usize;
+        let root = LOWEST_ROOT + self.random(ROOT_SPAN) as u8;
+        let notes = choices[choice].1.iter().map(|step| root + step).collect();
+        self.question = Some(Question {
+            exercise: self.exercise,
+            choice,
+            notes,
+            answered: false,
+        });
+        self.feedback = None;
+        self.replay();
+    }
+
+    pub fn replay(&mut self) {
+        if self.question.is_some() {
+            self.playing = Some(Instant::now());
+            self.attempt.clear();
+        }
+    }
+
+    /// Answers the current question with the choice at `index`.
+    pub fn answer(&mut self, index: usize, stats: &mut EarStats) {
+        self.finish(|question| question.choice == index, stats);
+    }
+
+    /// Sounds the question and, when answering by ear, checks what is played
+    /// back once every key is released. Returns true when `stats` changed.
+    pub fn poll(&mut self, shared: &mut SynthShared, stats: &mut EarStats) -> bool {
+        let wanted = self.wanted_notes();
+        for note in &self.sounding {
+            if !wanted.contains(note) {
+                shared.release_note(*note);
+            }
+        }
+        for note in &wanted {
+            if !self.sounding.contains(note) {
+                shared.press_note(*note, QUESTION_VELOCITY);
+            }
+        }
+        self.sounding = wanted;
+        if self.playing.is_some() || self.answer_mode != AnswerMode::PlayBack || !self.is_asking() {
+            return false;
+        }
+
+        let held = shared.held_notes();
+        if !held.is_empty() {
+            for note in held {
+                if !self.attempt.contains(&note) {
+                    self.attempt.push(note);
+                }
+            }
+            return false;
+        }
+        if self.attempt.is_empty() {
+            return false;
+        }
+        let mut attempt = std::mem::take(&mut self.attempt);
+        attempt.sort_unstable();
+        // Any transposition counts; it's the shape being trained.
+        self.finish(|question| shape(&question.notes) == shape(&attempt), stats);
+        true
+    }
+
+    fn finish(&mut self, is_correct: impl Fn(&Question) -> bool, stats: &mut EarStats) {
+        let Some(question) = self.question.as_mut().filter(|question| !question.answered) else {
+            return;
+        };
+        question.answered = true;
+        let correct = is_correct(question);
+        stats.record(correct);
+        let name = question.exercise.choices()[question.choice].0;
+        let text = if correct {
+            tr_fmt("Correct: {name}", &[("name", &name)])
+        } else {
+            tr_fmt("It was {name}", &[("name", &name)])
+        };
+        self.feedback = Some((correct, text));
+    }
+
+    /// Notes the question should be sounding right now.
+    fn wanted_notes(&mut self) -> Vec<u8> {
+        let (Some(started), Some(question)) = (self.playing, &self.question) else {
+            return Vec::new();
+        };
+        let elapsed = started.elapsed().as_secs_f32();
+        // Intervals are heard melodically, chords all at once.
+        let step = match question.exercise {
+            Exercise::Intervals => NOTE_STEP_SECS,
+            Exercise::Chords => 0.0,
+        };
+        let length = step * (question.notes.len() - 1) as f32 + RING_SECS;
+        if elapsed >= length {
+            self.playing = None;
+            return Vec::new();
+        }
+        question
+            .notes
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| elapsed >= step * *index as f32)
+            .map(|(_, note)| *note)
+            .collect()
+    }
+
+    /// A number below `bound` from a xorshift generator; good enough for
+    /// picking questions.
+    fn random(&mut self, bound: u32) -> u32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed % bound.max(1)
+    }
+}
+
+/// Semitones of each note above the lowest of sorted `notes`.
+fn shape(notes: &[u8]) -> Vec<u8> {
+    let lowest = notes.first().copied().unwrap_or_default();
+    notes.iter().map(|note| note - lowest).collect()
+}
@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth::flush_denormal;
+
+/// Comb and allpass lengths of the Freeverb reverb, in samples at 44.1 kHz.
+const COMB_TUNING: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_TUNING: [usize; 4] = [556, 441, 341, 225];
+/// Extra samples on the right channel's delay lines to decorrelate it from the left.
+const STEREO_SPREAD: usize = 23;
+/// Scales the summed comb input down to keep the tank out of clipping.
+const REVERB_INPUT_GAIN: f32 = 0.015;
+pub const MAX_DELAY_SECONDS: f32 = 2.0;
+
+/// Shared effects every part can send to, so a single reverb and delay
+/// serve the whole mix instead of one per part.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SendBuses {
+    /// 0..1, from a small room to a long hall.
+    pub reverb_size: f32,
+    /// 0..1; higher takes more treble out of the tail.
+    pub reverb_damping: f32,
+    /// 0..1 level of the reverb in the mix.
+    pub reverb_return: f32,
+    pub delay_seconds: f32,
+    /// 0..0.9 share of each echo fed back into the next.
+    pub delay_feedback: f32,
+    /// 0..1 level of the delay in the mix.
+    pub delay_return: f32,
+}
+
+impl Default for SendBuses {
+    fn default() -> Self {
+        Self {
+            reverb_size: 0.6,
+            reverb_damping: 0.4,
+            reverb_return: 0.8,
+            delay_seconds: 0.35,
+            delay_feedback: 0.35,
+            delay_return: 0.8,
+        }
+    }
+}
+
+/// The reverb and delay buses, fed by each part's send levels.
+pub struct BusEffects {
+    reverb: Reverb,
+    delay: Delay,
+}
+
+impl BusEffects {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            reverb: Reverb::new(sample_rate),
+            delay: Delay::new(sample_rate),
+        }
+    }
+
+    /// Processes one frame of the summed sends and returns the left/right
+    /// output of both buses at their return levels.
+    pub fn next_frame(&mut self, buses: &SendBuses, reverb_in: f32, delay_in: f32) -> [f32; 2] {
+        let reverb = self.reverb.next_frame(buses, reverb_in);
+        let delay = self.delay.next_frame(buses, delay_in);
+        [0, 1].map(|side| reverb[side] * buses.reverb_return + delay[side] * buses.delay_return)
+    }
+
+    pub fn clear(&mut self) {
+        self.reverb.clear();
+        self.delay.clear();
+    }
+}
+
+/// Freeverb: eight parallel damped combs into four serial allpasses per side.
+struct Reverb {
+    combs: [Vec<Comb>; 2],
+    allpasses: [Vec<Allpass>; 2],
+}
+
+impl Reverb {
+    fn new(sample_rate: f32) -> Self {
+        let scale = |length: usize| ((length as f32 * sample_rate / 44_100.0) as usize).max(1);
+        let side = |spread: usize| {
+            let combs = COMB_TUNING
+                .iter()
+                .map(|length| Comb::new(scale(length + spread)))
+                .collect();
+            let allpasses = ALLPASS_TUNING
+                .iter()
+                .map(|length| Allpass::new(scale(length + spread)))
+                .collect();
+            (combs, allpasses)
+        };
+        let (left_combs, left_allpasses) = side(0);
+        let (right_combs, right_allpasses) = side(STEREO_SPREAD);
+        Self {
+            combs: [left_combs, right_combs],
+            allpasses: [left_allpasses, right_allpasses],
+        }
+    }
+
+    fn next_frame(&mut self, buses: &SendBuses, input: f32) -> [f32; 2] {
+        let feedback = 0.7 + buses.reverb_size.clamp(0.0, 1.0) * 0.28;
+        let damping = buses.reverb_damping.clamp(0.0, 1.0) * 0.4;
+        let input = input * REVERB_INPUT_GAIN;
+        [0, 1].map(|side| {
+            let mut out = 0.0;
+            for comb in &mut self.combs[side] {
+                out += comb.process(input, feedback, damping);
+            }
+            for allpass in &mut self.allpasses[side] {
+                out = allpass.process(out);
+            }
+            out
+        })
+    }
+
+    fn clear(&mut self) {
+        for comb in self.combs.iter_mut().flatten() {
+            comb.buffer.fill(0.0);
+            comb.filtered = 0.0;
+        }
+        for allpass in self.allpasses.iter_mut().flatten() {
+            allpass.buffer.fill(0.0);
+        }
+    }
+}
+
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    /// One-pole lowpass state in the feedback path.
+    filtered: f32,
+}
+
+impl Comb {
+    fn new(length: usize) -> Self {
+        Self {
+            buffer: vec![0.0; length],
+            index: 0,
+            filtered: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let out = self.buffer[self.index];
+        self.filtered = flush_denormal(out * (1.0 - damping) + self.filtered * damping);
+        self.buffer[self.index] = input + self.filtered * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        out
+    }
+}
+
+struct Allpass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl Allpass {
+    fn new(length: usize) -> Self {
+        Self {
+            buffer: vec![0.0; length],
+            index: 0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.index];
+        self.buffer[self.index] = input + delayed * 0.5;
+        self.index = (self.index + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+/// Ping-pong delay: the first echo comes from the left, then they alternate.
+struct Delay {
+    lines: [Vec<f32>; 2],
+    index: usize,
+    sample_rate: f32,
+}
+
+impl Delay {
+    fn new(sample_rate: f32) -> Self {
+        let length = (MAX_DELAY_SECONDS * sample_rate) as usize + 1;
+        Self {
+            lines: [vec![0.0; length], vec![0.0; length]],
+            index: 0,
+            sample_rate,
+        }
+    }
+
+    fn next_frame(&mut self, buses: &SendBuses, input: f32) -> [f32; 2] {
+        let length = self.lines[0].len();
+        let delay =
+            (buses.delay_seconds.clamp(0.01, MAX_DELAY_SECONDS) * self.sample_rate) as usize;
+        let read = (self.index + length - delay.clamp(1, length - 1)) % length;
+        let feedback = buses.delay_feedback.clamp(0.0, 0.9);
+        let out = [self.lines[0][read], self.lines[1][read]];
+        self.lines[0][self.index] = input + out[1] * feedback;
+        self.lines[1][self.index] = out[0] * feedback;
+        self.index = (self.index + 1) % length;
+        out
+    }
+
+    fn clear(&mut self) {
+        for line in &mut self.lines {
+            line.fill(0.0);
+        }
+    }
+}
+
+/// Lowest of the twelve strings left free to ring by the sustain pedal; they
+/// cover the octave below middle C, which every played note has harmonics near.
+const RESONANCE_LOW_NOTE: f32 = 48.0;
+/// Share of the played sound that excites the strings.
+const RESONANCE_DRIVE: f32 = 0.005;
+/// Level of the ringing strings against the dry sound.
+const RESONANCE_LEVEL: f32 = 0.12;
+/// Feedback per pass while the pedal is down, and once the dampers are back.
+const RESONANCE_FEEDBACK: f32 = 0.996;
+const DAMPED_FEEDBACK: f32 = 0.9;
+/// Alternating string detune, in semitones, so the strings beat a little.
+const RESONANCE_DETUNE: f32 = 0.03;
+
+/// Sympathetic resonance of undamped piano strings: a quiet bank of
+/// slightly detuned string resonators the played notes set ringing while
+/// the sustain pedal is down.
+pub struct PedalResonance {
+    strings: Vec<ResonantString>,
+}
+
+impl PedalResonance {
+    pub fn new(sample_rate: f32) -> Self {
+        let strings = (0..12)
+            .map(|index| {
+                let detune = if index % 2 == 0 {
+                    RESONANCE_DETUNE
+                } else {
+                    -RESONANCE_DETUNE
+                };
+                let note = RESONANCE_LOW_NOTE + index as f32 + detune;
+                let freq = 440.0 * 2_f32.powf((note - 69.0) / 12.0);
+                ResonantString::new(((sample_rate / freq) as usize).max(2))
+            })
+            .collect();
+        Self { strings }
+    }
+
+    /// Returns the strings' output for one sample of the played sound.
+    pub fn process(&mut self, input: f32, sustain: bool) -> f32 {
+        let feedback = if sustain {
+            RESONANCE_FEEDBACK
+        } else {
+            DAMPED_FEEDBACK
+        };
+        let drive = if sustain {
+            input * RESONANCE_DRIVE
+        } else {
+            0.0
+        };
+        let mut out = 0.0;
+        for string in &mut self.strings {
+            out += string.process(drive, feedback);
+        }
+        out * RESONANCE_LEVEL
+    }
+
+    pub fn clear(&mut self) {
+        for string in &mut self.strings {
+            string.buffer.fill(0.0);
+            string.previous = 0.0;
+        }
+    }
+}
+
+/// Karplus-Strong style string: a delay line tuned to the string's pitch
+/// with a two-point average in the loop so the highs die away first.
+struct ResonantString {
+    buffer: Vec<f32>,
+    index: usize,
+    previous: f32,
+}
+
+impl ResonantString {
+    fn new(length: usize) -> Self {
+        Self {
+            buffer: vec![0.0; length],
+            index: 0,
+            previous: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32) -> f32 {
+        let out = self.buffer[self.index];
+        let averaged = (out + self.previous) * 0.5;
+        self.previous = out;
+        self.buffer[self.index] = input + averaged * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        out
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+/// Something a patch names by path and reads from the disk when needed:
+/// a wavetable, a sample or a SoundFont.
+pub trait Loadable: Sized {
+    fn load(path: &str) -> Result<Self, String>;
+}
+
+/// A file named by its path, with its contents attached once loaded.
+/// Only the path is saved; two of these are equal when their paths are.
+#[derive(Serialize, Deserialize)]
+#[serde(from = "String", into = "String", bound = "")]
+pub struct FileRef<T> {
+    path: Arc<str>,
+    loaded: Option<Arc<T>>,
+}
+
+impl<T> FileRef<T> {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: Arc::from(path.trim()),
+            loaded: None,
+        }
+    }
+
+    /// Empty when nothing has been picked.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn loaded(&self) -> Option<&T> {
+        self.loaded.as_deref()
+    }
+}
+
+impl<T> Clone for FileRef<T> {
+    fn clone(&self) -> Self {
+        Self {
+            path: Arc::clone(&self.path),
+            loaded: self.loaded.clone(),
+        }
+    }
+}
+
+impl<T> Default for FileRef<T> {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl<T> PartialEq for FileRef<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl<T> From<String> for FileRef<T> {
+    fn from(path: String) -> Self {
+        Self::new(&path)
+    }
+}
+
+impl<T> From<FileRef<T>> for String {
+    fn from(file: FileRef<T>) -> Self {
+        file.path.to_string()
+    }
+}
+
+/// Files loaded so far, by path, so every frame can make sure each patch
+/// has its files without going back to the disk. Failures are kept too,
+/// so a missing file is reported rather than retried. Files are read on a
+/// worker thread: the UI resolves patches while holding the synth lock,
+/// and the audio thread must never wait on the disk for it.
+pub struct FileCache<T> {
+    loaded: HashMap<String, Result<Arc<T>, String>>,
+    /// Paths sent to the worker that haven't come back yet.
+    loading: HashSet<String>,
+    requests: Sender<String>,
+    results: Receiver<(String, Result<Arc<T>, String>)>,
+}
+
+impl<T: Loadable + Send + Sync + 'static> Default for FileCache<T> {
+    fn default() -> Self {
+        let (requests, pending) = mpsc::channel::<String>();
+        let (finished, results) = mpsc::channel();
+        // Ends once the cache, and with it the request sender, is dropped.
+        thread::spawn(move || {
+            while let Ok(path) = pending.recv() {
+                let loaded = T::load(&path).map(Arc::new);
+                if finished.send((path, loaded)).is_err() {
+                    return;
+                }
+            }
+        });
+        Self {
+            loaded: HashMap::new(),
+            loading: HashSet::new(),
+            requests,
+            results,
+        }
+    }
+}
+
+impl<T> FileCache<T> {
+    /// Attaches what `file` names once it has been read, asking the worker
+    /// for it the first time.
+    pub fn resolve_file(&mut self, file: &mut FileRef<T>) {
+        if file.path.is_empty() || file.loaded.is_some() {
+            return;
+        }
+        self.receive();
+        match self.loaded.get(&*file.path) {
+            Some(loaded) => file.loaded = loaded.as_ref().ok().cloned(),
+            None => {
+                if self.loading.insert(file.path.to_string()) {
+                    let _ = self.requests.send(file.path.to_string());
+                }
+            }
+        }
+    }
+
+    /// Blocks until every file asked for has been read, for callers such
+    /// as the command line that have no next frame to wait for.
+    pub fn wait(&mut self) {
+        while !self.loading.is_empty() {
+            match self.results.recv() {
+                Ok(result) => self.finish(result),
+                Err(_) => return,
+            }
+        }
+    }
+
+    fn receive(&mut self) {
+        while let Ok(result) = self.results.try_recv() {
+            self.finish(result);
+        }
+    }
+
+    fn finish(&mut self, (path, loaded): (String, Result<Arc<T>, String>)) {
+        if let Err(err) = &loaded {
+            log::warn!("{err}");
+        }
+        self.loading.remove(&path);
+        self.loaded.insert(path, loaded);
+    }
+
+    /// The worker is still reading `path`.
+    pub fn is_loading(&self, path: &str) -> bool {
+        self.loading.contains(path)
+    }
+
+    pub fn error(&self, path: &str) -> Option<&str> {
+        self.loaded.get(path)?.as_ref().err().map(String::as_str)
+    }
+
+    /// Forgets `path`, so the next `resolve` reads the file again.
+    pub fn forget(&mut self, path: &str) {
+        self.loaded.remove(path);
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs};
+
+use crate::synth::SynthShared;
+
+/// Note the lowest pad button plays before the octave shift.
+const BASE_NOTE: u8 = 60;
+/// Sticks ignore movement smaller than this so a resting stick stays neutral.
+const STICK_DEADZONE: f32 = 0.12;
+/// Master gain change per second with a trigger fully pulled.
+const TRIGGER_GAIN_RATE: f32 = 0.5;
+const MAJOR_SCALE: [u8; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+/// D-pad and face buttons walk up a C major scale, one degree each.
+const BUTTON_DEGREES: &[(Button, u8)] = &[
+    (Button::DPadDown, 0),
+    (Button::DPadLeft, 1),
+    (Button::DPadUp, 2),
+    (Button::DPadRight, 3),
+    (Button::South, 4),
+    (Button::West, 5),
+    (Button::North, 6),
+    (Button::East, 7),
+];
+
+/// Polls connected gamepads once per frame and turns their input into notes
+/// and performance changes. The left stick bends the pitch sideways and adds
+/// vibrato upwards. Holding the left shoulder makes each button play
+/// the diatonic triad on its degree instead of a single note.
+pub struct GamepadInput {
+    gilrs: Option<Gilrs>,
+    pub error: Option<String>,
+    held: HashMap<(GamepadId, Button), Vec<u8>>,
+    chord_mode: bool,
+    gain_up: f32,
+    gain_down: f32,
+}
+
+impl Default for GamepadInput {
+    fn default() -> Self {
+        let (gilrs, error) = match Gilrs::new() {
+            Ok(gilrs) => (Some(gilrs), None),
+            Err(err) => (None, Some(format!("Gamepad support unavailable: {err}"))),
+        };
+        Self {
+            gilrs,
+            error,
+            held: HashMap::new(),
+            chord_mode: false,
+            gain_up: 0.0,
+            gain_down: 0.0,
+        }
+    }
+}
+
+impl GamepadInput {
+    /// Names of the pads currently connected.
+    pub fn connected(&self) -> Vec<String> {
+        let Some(gilrs) = &self.gilrs else {
+            return Vec::new();
+        };
+        gilrs
+            .gamepads()
+            .map(|(_, pad)| pad.name().to_string())
+            .collect()
+    }
+
+    /// Drains pending events into `shared`. `dt` is the frame time in seconds,
+    /// used to ramp the master gain while a trigger is held.
+    pub fn poll(&mut self, shared: &mut SynthShared, octave_offset: i32, velocity: f32, dt: f32) {
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(Button::LeftTrigger, _) => self.chord_mode = true,
+                EventType::ButtonReleased(Button::LeftTrigger, _) => self.chord_mode = false,
+                EventType::ButtonPressed(button, _) => {
+                    let Some(degree) = button_degree(button) else {
+                        continue;
+                    };
+                    let degrees = if self.chord_mode {
+                        vec![degree, degree + 2, degree + 4]
+                    } else {
+                        vec![degree]
+                    };
+                    let notes: Vec<u8> = degrees
+                        .iter()
+                        .filter_map(|degree| degree_note(*degree, octave_offset))
+                        .collect();
+                    for note in &notes {
+                        shared.press_note(*note, velocity);
+                    }
+                    self.held.insert((event.id, button), notes);
+                }
+                EventType::ButtonReleased(button, _) => {
+                    for note in self.held.remove(&(event.id, button)).unwrap_or_default() {
+                        shared.release_note(note);
+                    }
+                }
+                EventType::ButtonChanged(Button::RightTrigger2, value, _) => self.gain_up = value,
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _) => self.gain_down = value,
+                EventType::AxisChanged(Axis::LeftStickX, value, _) => {
+                    shared.performance.pitch_bend = deadzone(value);
+                }
+                EventType::AxisChanged(Axis::LeftStickY, value, _) => {
+                    shared.performance.mod_wheel = deadzone(value).max(0.0);
+                }
+                EventType::AxisChanged(Axis::RightStickY, value, _) => {
+                    shared.performance.cutoff_sweep = deadzone(value);
+                }
+                EventType::Disconnected => {
+                    // A stick that was bent when the pad went away would
+                    // otherwise leave every note out of tune.
+                    shared.performance.pitch_bend = 0.0;
+                    self.held.retain(|(pad, _), notes| {
+                        if *pad != event.id {
+                            return true;
+                        }
+                        for note in notes.drain(..) {
+                            shared.release_note(note);
+                        }
+                        false
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let ramp = (self.gain_up - self.gain_down) * TRIGGER_GAIN_RATE * dt;
+        if ramp != 0.0 {
+            shared.params.gain = (shared.params.gain + ramp).clamp(0.0, 1.0);
+        }
+    }
+}
+
+fn button_degree(button: Button) -> Option<u8> {
+    BUTTON_DEGREES
+        .iter()
+        .find(|(candidate, _)| *candidate == button)
+        .map(|(_, degree)| *degree)
+}
+
+fn degree_note(degree: u8, octave_offset: i32) -> Option<u8> {
+    let step = MAJOR_SCALE[degree as usize % MAJOR_SCALE.len()];
+    let octave = (degree as usize / MAJOR_SCALE.len()) as i32;
+    let note = BASE_NOTE as i32 + step as i32 + (octave + octave_offset) * 12;
+    u8::try_from(note).ok().filter(|note| *note <= 127)
+}
+
+fn deadzone(value: f32) -> f32 {
+    if value.abs() < STICK_DEADZONE {
+        0.0
+    } else {
+        value.clamp(-1.0, 1.0)
+    }
+}
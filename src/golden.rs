@@ -0,0 +1,272 @@
+//! Golden-audio regression tests: a fixed note sequence is rendered offline
+//! with every instrument and waveform and compared against the reference
+//! WAVs in `tests/golden`, so DSP refactors can't quietly change the sound.
+//! The sample-based instruments play a tone generated at test time.
+//! After an intended change, regenerate them with
+//! `ANGEL_BLESS_GOLDEN=1 cargo test golden` and listen to the new files.
+
+use std::f32::consts::TAU;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::sampler::SampleFile;
+use crate::soundfont::SoundFontFile;
+use crate::synth::{InstrumentKind, NoteEvent, SynthParams, Waveform, render};
+use crate::wav::encode_stereo_pcm16;
+
+const SAMPLE_RATE: u32 = 12_000;
+const LENGTH_SECS: f32 = 0.3;
+/// Largest per-sample difference allowed: well above 16-bit rounding and
+/// libm differences between platforms, well below an audible change.
+const TOLERANCE: f32 = 2e-3;
+const WAV_HEADER_LEN: usize = 44;
+
+/// Two overlapping notes at different velocities, then both release tails.
+const SEQUENCE: &[(f32, NoteEvent)] = &[
+    (
+        0.0,
+        NoteEvent::On {
+            note: 60,
+            velocity: 0.9,
+        },
+    ),
+    (
+        0.05,
+        NoteEvent::On {
+            note: 67,
+            velocity: 0.6,
+        },
+    ),
+    (0.12, NoteEvent::Off { note: 60 }),
+    (0.2, NoteEvent::Off { note: 67 }),
+];
+
+/// Kick, snare and closed hat from the default kit, the hat over the kick.
+const DRUM_SEQUENCE: &[(f32, NoteEvent)] = &[
+    (
+        0.0,
+        NoteEvent::On {
+            note: 36,
+            velocity: 0.9,
+        },
+    ),
+    (
+        0.0,
+        NoteEvent::On {
+            note: 42,
+            velocity: 0.5,
+        },
+    ),
+    (0.05, NoteEvent::Off { note: 36 }),
+    (0.05, NoteEvent::Off { note: 42 }),
+    (
+        0.12,
+        NoteEvent::On {
+            note: 38,
+            velocity: 0.8,
+        },
+    ),
+    (0.15, NoteEvent::Off { note: 38 }),
+];
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn render_frames(params: &SynthParams) -> Vec<[f32; 2]> {
+    render_sequence(params, SEQUENCE)
+}
+
+fn render_sequence(params: &SynthParams, sequence: &[(f32, NoteEvent)]) -> Vec<[f32; 2]> {
+    render(params, sequence, SAMPLE_RATE, LENGTH_SECS)
+        .chunks_exact(2)
+        .map(|frame| [frame[0], frame[1]])
+        .collect()
+}
+
+/// A scratch folder for files a test generates, emptied first.
+fn fixture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("angel_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("create fixture dir");
+    dir
+}
+
+/// Half a second of a decaying 220 Hz tone, the stand-in recording for the
+/// sample-based instruments.
+fn write_tone_wav(path: &Path) {
+    let frames: Vec<[f32; 2]> = (0..SAMPLE_RATE / 2)
+        .map(|index| {
+            let time = index as f32 / SAMPLE_RATE as f32;
+            let sample = (TAU * 220.0 * time).sin() * 0.8 * (-3.0 * time).exp();
+            [sample, sample]
+        })
+        .collect();
+    fs::write(path, encode_stereo_pcm16(&frames, SAMPLE_RATE)).expect("write tone fixture");
+}
+
+/// Reads back what `encode_stereo_pcm16` wrote.
+fn decode_stereo_pcm16(wav: &[u8]) -> Vec<[f32; 2]> {
+    let sample = |bytes: &[u8]| i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32;
+    wav.get(WAV_HEADER_LEN..)
+        .unwrap_or_default()
+        .chunks_exact(4)
+        .map(|frame| [sample(&frame[..2]), sample(&frame[2..])])
+        .collect()
+}
+
+#[test]
+fn renders_match_golden_references() {
+    let bless = std::env::var_os("ANGEL_BLESS_GOLDEN").is_some();
+    let mut failures = Vec::new();
+    for instrument in InstrumentKind::ALL {
+        // Covered below: they need a file to play, or, for Drums, kit keys.
+        if matches!(
+            instrument,
+            InstrumentKind::Sampler
+                | InstrumentKind::SoundFont
+                | InstrumentKind::Granular
+                | InstrumentKind::Drums
+        ) {
+            continue;
+        }
+        for waveform in Waveform::ALL {
+            // Additive plays its harmonics whatever the waveform.
+            if instrument == InstrumentKind::Additive && waveform != Waveform::ALL[0] {
+                continue;
+            }
+            let params = SynthParams {
+                instrument,
+                waveform,
+                ..SynthParams::default()
+            };
+            let name = format!("{}_{}", instrument.label(), waveform.label()).to_lowercase();
+            compare_with_golden(&name, &render_frames(&params), bless, &mut failures);
+        }
+    }
+
+    let drums = SynthParams {
+        instrument: InstrumentKind::Drums,
+        ..SynthParams::default()
+    };
+    let rendered = render_sequence(&drums, DRUM_SEQUENCE);
+    compare_with_golden("drums", &rendered, bless, &mut failures);
+
+    let dir = fixture_dir("golden");
+    let tone = dir.join("tone.wav");
+    write_tone_wav(&tone);
+    let sfz = dir.join("tone.sfz");
+    fs::write(
+        &sfz,
+        "<region> sample=tone.wav pitch_keycenter=57 lokey=0 hikey=127\n",
+    )
+    .expect("write SFZ fixture");
+    for instrument in [
+        InstrumentKind::Sampler,
+        InstrumentKind::Granular,
+        InstrumentKind::SoundFont,
+    ] {
+        let mut params = SynthParams {
+            instrument,
+            sample: SampleFile::new(&tone.to_string_lossy()),
+            soundfont: SoundFontFile::new(&sfz.to_string_lossy()),
+            ..SynthParams::default()
+        };
+        crate::resolve_files(&mut params).expect("load fixtures");
+        let name = instrument.label().to_lowercase();
+        compare_with_golden(&name, &render_frames(&params), bless, &mut failures);
+    }
+    let _ = fs::remove_dir_all(&dir);
+
+    assert!(
+        failures.is_empty(),
+        "Renders differ from tests/golden:\n{}",
+        failures.join("\n")
+    );
+}
+
+/// Checks `rendered` against `tests/golden/<name>.wav`, or rewrites that file
+/// when blessing.
+fn compare_with_golden(name: &str, rendered: &[[f32; 2]], bless: bool, failures: &mut Vec<String>) {
+    let path = golden_dir().join(format!("{name}.wav"));
+    if bless {
+        fs::create_dir_all(golden_dir()).expect("create tests/golden");
+        fs::write(&path, encode_stereo_pcm16(rendered, SAMPLE_RATE))
+            .expect("write golden reference");
+        return;
+    }
+    let Ok(bytes) = fs::read(&path) else {
+        failures.push(format!(
+            "{name}: no reference at {}; run with ANGEL_BLESS_GOLDEN=1",
+            path.display()
+        ));
+        return;
+    };
+    let reference = decode_stereo_pcm16(&bytes);
+    if reference.len() != rendered.len() {
+        failures.push(format!(
+            "{name}: {} frames rendered, {} in the reference",
+            rendered.len(),
+            reference.len()
+        ));
+        return;
+    }
+    // The reference was clamped to ±1 when it was written.
+    let worst = rendered
+        .iter()
+        .zip(&reference)
+        .enumerate()
+        .flat_map(|(index, (new, old))| {
+            (0..2).map(move |channel| {
+                let diff = (new[channel].clamp(-1.0, 1.0) - old[channel]).abs();
+                (index, diff)
+            })
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    if let Some((index, diff)) = worst.filter(|(_, diff)| *diff > TOLERANCE) {
+        failures.push(format!(
+            "{name}: off by {diff:.4} at {:.3} s",
+            index as f32 / SAMPLE_RATE as f32
+        ));
+    }
+}
+
+#[test]
+fn renders_are_repeatable() {
+    let params = SynthParams {
+        noise_mix: 0.5,
+        ..SynthParams::default()
+    };
+    assert!(render_frames(&params) == render_frames(&params));
+}
+
+#[test]
+fn renders_are_not_silent() {
+    let rendered = render_frames(&SynthParams::default());
+    let peak = rendered
+        .iter()
+        .flatten()
+        .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+    assert!(peak > 0.05, "peak {peak}");
+}
+
+#[test]
+fn render_to_file_loads_samples() {
+    let dir = fixture_dir("render");
+    let tone = dir.join("tone.wav");
+    write_tone_wav(&tone);
+    let params = SynthParams {
+        instrument: InstrumentKind::Sampler,
+        sample: SampleFile::new(&tone.to_string_lossy()),
+        ..SynthParams::default()
+    };
+    let out = dir.join("out.wav");
+    crate::render_to_file(&params, &out, &[]).expect("render sample patch");
+    let rendered = decode_stereo_pcm16(&fs::read(&out).expect("read render"));
+    let peak = rendered
+        .iter()
+        .flatten()
+        .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+    let _ = fs::remove_dir_all(&dir);
+    assert!(peak > 0.05, "peak {peak}");
+}
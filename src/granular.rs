@@ -0,0 +1,98 @@
+use std::f32::consts::TAU;
+
+use crate::sampler::Sample;
+use crate::synth::SynthParams;
+
+/// Most grains a voice plays at once; past it, new grains are skipped.
+const MAX_GRAINS: usize = 32;
+
+#[derive(Clone, Copy, Default)]
+struct Grain {
+    /// Read position in the sample, in its own samples.
+    pos: f64,
+    /// Output samples played so far, and the grain's full length in them;
+    /// a length of zero marks a free slot.
+    age: f32,
+    length: f32,
+}
+
+/// The grains one voice of the Granular instrument is playing: short,
+/// windowed snatches of the sample started around the grain position at
+/// the grain density, each read at the note's speed.
+#[derive(Clone)]
+pub struct GrainCloud {
+    grains: [Grain; MAX_GRAINS],
+    /// Output samples until the next grain starts.
+    until_next: f32,
+    seed: u32,
+}
+
+impl GrainCloud {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            grains: [Grain::default(); MAX_GRAINS],
+            until_next: 0.0,
+            seed,
+        }
+    }
+
+    /// Drops every grain, so the next sample starts a fresh one.
+    pub fn reset(&mut self) {
+        self.grains = [Grain::default(); MAX_GRAINS];
+        self.until_next = 0.0;
+    }
+
+    /// One sample of the cloud. `speed` is how far each grain reads through
+    /// the sample per output sample.
+    pub fn next(
+        &mut self,
+        sample: &Sample,
+        params: &SynthParams,
+        speed: f32,
+        sample_rate: f32,
+    ) -> f32 {
+        let length = (params.grain_size_ms * 0.001 * sample_rate).max(1.0);
+        self.until_next -= 1.0;
+        if self.until_next <= 0.0 {
+            self.until_next += sample_rate / params.grain_density_hz.max(0.01);
+            let start =
+                (params.grain_position + params.grain_spray * self.next_random()).clamp(0.0, 1.0);
+            if let Some(free) = self.grains.iter_mut().find(|grain| grain.length == 0.0) {
+                *free = Grain {
+                    pos: start as f64 * sample.length() as f64,
+                    age: 0.0,
+                    length,
+                };
+            }
+        }
+
+        let mut sum = 0.0;
+        for grain in &mut self.grains {
+            if grain.length == 0.0 {
+                continue;
+            }
+            // Hann window, so grains fade in and out without clicks.
+            let window = 0.5 - 0.5 * (TAU * grain.age / grain.length).cos();
+            sum += sample.at(grain.pos) * window;
+            grain.pos += speed as f64;
+            grain.age += 1.0;
+            if grain.age >= grain.length {
+                grain.length = 0.0;
+            }
+        }
+        // Overlapping grains are unrelated snatches, so they add up in
+        // power: scale by the root of how many overlap.
+        let overlap = params.grain_density_hz * params.grain_size_ms * 0.001;
+        sum / overlap.max(1.0).sqrt()
+    }
+
+    /// An LCG mapped to -1..1, like the voices' noise.
+    fn next_random(&mut self) -> f32 {
+        self.seed = self
+            .seed
+            .wrapping_mul(1_664_525)
+            .wrapping_add(1_013_904_223);
+        let value = ((self.seed >> 9) & 0x7FFFFF) as f32 / 0x7FFFFF as f32;
+        value * 2.0 - 1.0
+    }
+}
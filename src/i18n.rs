@@ -0,0 +1,1490 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// Language the interface is shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::German, Language::Spanish];
+
+    /// The language's name in that language, so it can be found without reading the current one.
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+            Language::Spanish => "Español",
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => &[],
+            Language::German => GERMAN,
+            Language::Spanish => SPANISH,
+        }
+    }
+}
+
+/// The whole UI shares one language, so it lives here instead of being
+/// threaded through every widget function.
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(language: Language) {
+    CURRENT.store(language as u8, Ordering::Relaxed);
+}
+
+fn current() -> Language {
+    Language::ALL
+        .get(CURRENT.load(Ordering::Relaxed) as usize)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Translates an English UI string into the current language. The English
+/// text is the lookup key, so anything without a translation shows as is.
+pub fn tr(text: &'static str) -> &'static str {
+    current()
+        .table()
+        .iter()
+        .find(|(english, _)| *english == text)
+        .map_or(text, |(_, translated)| translated)
+}
+
+/// Like [`tr`] for text with values in it. Placeholders are named in braces,
+/// e.g. `tr_fmt("Most played: {list}", &[("list", &list)])`, so a
+/// translation can move them wherever its grammar wants them.
+pub fn tr_fmt(text: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = tr(text).to_string();
+    for (name, value) in args {
+        out = out.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    out
+}
+
+const GERMAN: &[(&str, &str)] = &[
+    ("Settings", "Einstellungen"),
+    ("Reset sound", "Klang zurücksetzen"),
+    ("Panic", "Panik"),
+    ("Hold", "Halten"),
+    ("UI focus", "UI-Fokus"),
+    ("App Settings", "App-Einstellungen"),
+    ("Theme", "Design"),
+    ("Language", "Sprache"),
+    ("Always on top", "Immer im Vordergrund"),
+    ("Keybinds", "Tastenbelegung"),
+    ("Shortcuts", "Tastenkürzel"),
+    ("Keymap", "Tastenzuordnung"),
+    ("Built-in", "Eingebaut"),
+    ("Edit keymaps…", "Tastenzuordnungen bearbeiten…"),
+    ("Gamepad", "Gamepad"),
+    ("None connected", "Keines verbunden"),
+    ("Output", "Ausgabe"),
+    ("Input", "Eingang"),
+    ("On launch", "Beim Start"),
+    ("Layout & sizing", "Anordnung & Größe"),
+    ("Import / export", "Import / Export"),
+    ("Wave Scope", "Oszilloskop"),
+    ("Keyboard", "Klaviatur"),
+    ("XY Pad", "XY-Pad"),
+    ("Tone & Filter", "Klang & Filter"),
+    ("Motion & Noise", "Bewegung & Rauschen"),
+    ("EQ", "EQ"),
+    ("Harmonics", "Obertöne"),
+    ("Granular", "Granular"),
+    ("Preset Morph", "Preset-Morph"),
+    ("Progressions", "Akkordfolgen"),
+    ("Mixer", "Mischpult"),
+    ("Reverb", "Hall"),
+    ("Ribbon", "Band"),
+    ("Soft", "Leise"),
+    ("Drone", "Bordun"),
+    ("Fifth", "Quinte"),
+    ("Level", "Pegel"),
+    ("MIDI in", "MIDI-Eingang"),
+    ("Rescan", "Neu suchen"),
+    ("Snap to scale", "An Tonleiter einrasten"),
+    ("Delay", "Echo"),
+    ("Limiter", "Limiter"),
+    ("Practice Stats", "Übungsstatistik"),
+    ("Diagnostics", "Diagnose"),
+    ("Log", "Protokoll"),
+    ("Layout", "Anordnung"),
+    ("Main", "Haupt"),
+    ("Left dock", "Linkes Dock"),
+    ("Right dock", "Rechtes Dock"),
+    ("Bottom dock", "Unteres Dock"),
+    ("Window", "Fenster"),
+    (
+        "Keyboard disabled while settings are open.",
+        "Die Klaviatur ist gesperrt, solange die Einstellungen offen sind.",
+    ),
+    ("Glide between keys", "Zwischen Tasten gleiten"),
+    ("Glide every note", "Jede Note gleiten"),
+    ("Key labels", "Tastenbeschriftung"),
+    ("Zoom", "Zoom"),
+    ("Split", "Teilung"),
+    ("below", "unter"),
+    ("Use current", "Aktuellen übernehmen"),
+    ("Theory", "Theorie"),
+    ("Practice", "Üben"),
+    ("Layer", "Schicht"),
+    ("Expand", "Aufklappen"),
+    ("Collapse", "Zuklappen"),
+    ("Freeze", "Einfrieren"),
+    ("Scope", "Oszilloskop"),
+    (
+        "The scope is in its own window.",
+        "Das Oszilloskop ist in einem eigenen Fenster.",
+    ),
+    ("Pop back in", "Zurückholen"),
+    ("Pop out", "Abtrennen"),
+    (
+        "Move the scope and meters into a separate window",
+        "Oszilloskop und Pegelanzeigen in ein eigenes Fenster verschieben",
+    ),
+    (
+        "Show the computer key that plays each piano key",
+        "Die Computertaste zeigen, die jede Klaviertaste spielt",
+    ),
+    (
+        "Show fewer, larger keys; scroll or use the strip below",
+        "Weniger, größere Tasten zeigen; scrollen oder den Streifen darunter nutzen",
+    ),
+    ("Share", "Teilen"),
+    (
+        "Copy the patch as text for chat, or load one someone sent",
+        "Den Patch als Text für den Chat kopieren oder einen zugeschickten laden",
+    ),
+    (
+        "Latch: released notes keep sounding until Hold is turned off",
+        "Halten: losgelassene Noten klingen weiter, bis Halten ausgeschaltet wird",
+    ),
+    (
+        "Soft pedal: notes play gentler and darker while it is on",
+        "Leisepedal: Noten klingen sanfter und dunkler, solange es an ist",
+    ),
+    (
+        "Play together with other instances on the local network",
+        "Mit anderen Instanzen im lokalen Netzwerk zusammen spielen",
+    ),
+    (
+        "Ear training: name or play back intervals and chords",
+        "Gehörbildung: Intervalle und Akkorde benennen oder nachspielen",
+    ),
+    ("Bounce", "Bouncen"),
+    (
+        "Render the notes you are holding to a WAV, tails included",
+        "Die gehaltenen Noten samt Ausklang als WAV rendern",
+    ),
+    (
+        "Alt+1…9 sets the keyboard velocity. Hold Shift to accent a note, Ctrl to play it softly.",
+        "Alt+1…9 stellt die Tastaturanschlagstärke ein. Umschalt gedrückt halten betont eine Note, Strg spielt sie leise.",
+    ),
+    ("Restore session?", "Sitzung wiederherstellen?"),
+    (
+        "Angel didn't shut down cleanly last time.",
+        "Angel wurde beim letzten Mal nicht sauber beendet.",
+    ),
+    ("Restore", "Wiederherstellen"),
+    ("Discard", "Verwerfen"),
+    (
+        "Play with keyboard or mouse",
+        "Mit Tastatur oder Maus spielen",
+    ),
+    ("Reset to default", "Auf Standard zurücksetzen"),
+    ("Copy value", "Wert kopieren"),
+    ("Paste value", "Wert einfügen"),
+    ("Curve", "Kurve"),
+    (
+        "Shape of the envelope stages: exponential creeps in and tails off naturally, logarithmic snaps up and holds before it falls",
+        "Form der Hüllkurvenphasen: exponentiell setzt sanft ein und klingt natürlich aus, logarithmisch springt hoch und hält, bevor es fällt",
+    ),
+    ("Instrument", "Instrument"),
+    (
+        "Character added to the waveform: Bass adds a sub-octave, Lead an overtone and drive",
+        "Charakter, der der Wellenform hinzugefügt wird: Bass fügt eine Suboktave hinzu, Lead einen Oberton und Verzerrung",
+    ),
+    ("Voices", "Stimmen"),
+    (
+        "Poly plays chords; Mono plays one note at a time, restarting the envelope on each; Legato glides between overlapping notes without restarting it",
+        "Poly spielt Akkorde; Mono spielt eine Note nach der anderen und startet die Hüllkurve jedes Mal neu; Legato gleitet zwischen überlappenden Noten, ohne sie neu zu starten",
+    ),
+    (
+        "Most notes the patch sounds at once; past it, a voice is stolen",
+        "Höchstzahl gleichzeitig klingender Noten; darüber hinaus wird eine Stimme übernommen",
+    ),
+    (
+        "Which voice gives way to a new note once all are in use; released notes always go first",
+        "Welche Stimme einer neuen Note weicht, wenn alle belegt sind; losgelassene Noten gehen immer zuerst",
+    ),
+    (
+        "Which held key sounds: the last one pressed, the lowest or the highest",
+        "Welche gehaltene Taste klingt: die zuletzt gedrückte, die tiefste oder die höchste",
+    ),
+    ("Transpose", "Transponieren"),
+    (
+        "Semitones every note sounds away from its key; -2 plays along with a B♭ instrument's part in concert pitch",
+        "Halbtöne, um die jede Note von ihrer Taste abweicht; -2 spielt die Stimme eines B-Instruments in Konzerttonhöhe mit",
+    ),
+    (
+        "Reference pitch every note is tuned from: 440 is concert pitch, 415 baroque; nudge it to match a recording",
+        "Kammerton, von dem aus jede Note gestimmt wird: 440 ist Konzertstimmung, 415 Barock; zum Anpassen an eine Aufnahme verschieben",
+    ),
+    (
+        "Additive plays the Harmonics card's bars instead of a waveform.",
+        "Additiv spielt die Balken der Obertöne-Karte statt einer Wellenform.",
+    ),
+    ("Waveform", "Wellenform"),
+    (
+        "Oscillator shape: sine is pure, triangle soft, square hollow, saw bright",
+        "Oszillatorform: Sinus ist rein, Dreieck weich, Rechteck hohl, Sägezahn hell",
+    ),
+    ("Filter", "Filter"),
+    (
+        "Low-pass darkens, high-pass thins, band-pass keeps a band around the cutoff and notch removes it; ladder is a driven four-pole low-pass that screams at high resonance",
+        "Tiefpass dunkelt ab, Hochpass macht dünner, Bandpass behält ein Band um die Grenzfrequenz und Kerbfilter entfernt es; Ladder ist ein übersteuerter vierpoliger Tiefpass, der bei hoher Resonanz schreit",
+    ),
+    ("Table", "Tabelle"),
+    ("Built-in shapes", "Eingebaute Formen"),
+    (
+        "Go back to the built-in shapes",
+        "Zu den eingebauten Formen zurückkehren",
+    ),
+    ("Path to a .wav", "Pfad zu einer .wav"),
+    ("Load", "Laden"),
+    ("Load preset", "Preset laden"),
+    ("Load template…", "Vorlage laden…"),
+    (
+        "A single cycle, or a table of 2048-sample frames such as Serum writes",
+        "Ein einzelner Zyklus oder eine Tabelle aus Frames zu 2048 Samples, wie Serum sie schreibt",
+    ),
+    ("Sample", "Sample"),
+    ("None loaded", "Nichts geladen"),
+    ("Clear", "Entfernen"),
+    ("Stop playing the sample", "Das Sample nicht mehr spielen"),
+    (
+        "Any WAV; only the first minute is kept",
+        "Beliebige WAV; nur die erste Minute wird behalten",
+    ),
+    ("Root", "Grundton"),
+    (
+        "The note the sample was recorded at; drag to retune it",
+        "Die Note, auf der das Sample aufgenommen wurde; ziehen zum Umstimmen",
+    ),
+    ("Detect", "Erkennen"),
+    (
+        "Go back to the root note found in the file",
+        "Zum in der Datei gefundenen Grundton zurückkehren",
+    ),
+    ("Loop", "Schleife"),
+    (
+        "Keep repeating between the loop points instead of stopping at the end",
+        "Zwischen den Schleifenpunkten wiederholen, statt am Ende aufzuhören",
+    ),
+    ("Loop start", "Schleifenanfang"),
+    ("Loop end", "Schleifenende"),
+    (
+        "Drag to move the pad to another key",
+        "Ziehen, um das Pad auf eine andere Taste zu legen",
+    ),
+    ("Synthesized", "Synthetisiert"),
+    (
+        "Go back to the synthesized drum",
+        "Zur synthetisierten Trommel zurückkehren",
+    ),
+    (
+        "Play the WAV in the path field below on this pad",
+        "Die WAV aus dem Pfadfeld unten auf diesem Pad spielen",
+    ),
+    (
+        "Path to a .wav, then Load on a pad",
+        "Pfad zu einer .wav, dann Laden auf einem Pad",
+    ),
+    (
+        "Each hit plays out in full, however long its key is held.",
+        "Jeder Schlag klingt vollständig aus, egal wie lange die Taste gehalten wird.",
+    ),
+    ("Font", "Font"),
+    (
+        "Stop playing the SoundFont",
+        "Den SoundFont nicht mehr spielen",
+    ),
+    ("Preset", "Preset"),
+    (
+        "Bank and program number, then the preset's name",
+        "Bank- und Programmnummer, dann der Name des Presets",
+    ),
+    ("Path to a .sf2 or .sfz", "Pfad zu einer .sf2 oder .sfz"),
+    (
+        "An SFZ's samples are read from the folder it names them in",
+        "Die Samples einer SFZ werden aus dem Ordner gelesen, den sie angibt",
+    ),
+    ("Unison", "Unisono"),
+    (
+        "Copies stacked on each note, detuned across the unison spread",
+        "Auf jede Note gestapelte Kopien, über die Unisono-Breite verstimmt",
+    ),
+    (
+        "Pick a scale above the piano to get progressions in its key.",
+        "Über dem Klavier eine Tonleiter wählen, um Akkordfolgen in ihrer Tonart zu bekommen.",
+    ),
+    (
+        "Progressions need a seven-note scale such as major or minor.",
+        "Akkordfolgen brauchen eine siebentönige Tonleiter wie Dur oder Moll.",
+    ),
+    ("Suggest another", "Andere vorschlagen"),
+    (
+        "Global tempo; the progression plays one chord per bar",
+        "Globales Tempo; die Akkordfolge spielt einen Akkord pro Takt",
+    ),
+    ("Morph A → B", "Morph A → B"),
+    ("Snapshot", "Schnappschuss"),
+    (
+        "Save the current in-between patch as a new preset",
+        "Den aktuellen Zwischen-Patch als neues Preset speichern",
+    ),
+    ("Copy patch as text", "Patch als Text kopieren"),
+    ("Paste patch", "Patch einfügen"),
+    (
+        "Heard with the Granular instrument.",
+        "Zu hören mit dem Granular-Instrument.",
+    ),
+    ("Use Granular", "Granular verwenden"),
+    (
+        "Load a sample in the Tone card to granulate it.",
+        "Ein Sample in der Klang-Karte laden, um es zu granulieren.",
+    ),
+    (
+        "Heard with the Additive instrument.",
+        "Zu hören mit dem Additiv-Instrument.",
+    ),
+    ("Use Additive", "Additiv verwenden"),
+    (
+        "Gain reduction: how far the limiter (in the Mixer) is turning the mix down to keep it under its ceiling. A few dB on the loudest notes is fine; more means lower the gain or the part volumes.",
+        "Pegelreduktion: wie weit der Limiter (im Mischpult) den Mix absenkt, um ihn unter der Obergrenze zu halten. Ein paar dB bei den lautesten Noten sind in Ordnung; mehr heißt, Verstärkung oder Spurlautstärken senken.",
+    ),
+    ("Reset", "Zurücksetzen"),
+    (
+        "Restart integrated loudness",
+        "Integrierte Lautheit neu starten",
+    ),
+    ("Session", "Sitzung"),
+    ("All time", "Insgesamt"),
+    ("Time played", "Spielzeit"),
+    ("Notes", "Noten"),
+    ("Copy report", "Bericht kopieren"),
+    ("No events yet.", "Noch keine Ereignisse."),
+    ("Mono cancel", "Mono-Auslöschung"),
+    (
+        "Record the synth and the microphone into one WAV; set their levels under Take mix in Settings",
+        "Synth und Mikrofon in eine WAV aufnehmen; ihre Pegel unter Aufnahmemix in den Einstellungen festlegen",
+    ),
+    ("Hold for", "Halten für"),
+    (
+        "How long the notes are held before they are let go to ring out",
+        "Wie lange die Noten gehalten werden, bevor sie zum Ausklingen losgelassen werden",
+    ),
+    ("Folder", "Ordner"),
+    ("Bounce held notes", "Gehaltene Noten bouncen"),
+    ("Export PNG", "PNG exportieren"),
+    ("Export CSV", "CSV exportieren"),
+    ("Envelope", "Hüllkurve"),
+    (
+        "Show the loudness outline of the last few seconds",
+        "Den Lautstärkeverlauf der letzten Sekunden zeigen",
+    ),
+    ("Trigger", "Trigger"),
+    (
+        "Lock the trace to rising zero crossings; free-runs when silent",
+        "Die Kurve an steigenden Nulldurchgängen ausrichten; läuft bei Stille frei",
+    ),
+    ("Gain", "Verstärkung"),
+    (
+        "Snap to the selected scale, or to semitones without one",
+        "An der gewählten Tonleiter einrasten, ohne eine an Halbtönen",
+    ),
+    (
+        "Click or drag to scroll the keyboard",
+        "Klicken oder ziehen, um die Klaviatur zu verschieben",
+    ),
+    (
+        "Click and drag to play across keys. Click lower on a key to play louder. Ctrl+scroll to zoom.",
+        "Klicken und ziehen, um über die Tasten zu spielen. Weiter unten auf eine Taste klicken spielt lauter. Strg+Scrollen zoomt.",
+    ),
+    ("Scale", "Tonleiter"),
+    ("Off", "Aus"),
+    (
+        "Tint the keys in this scale and mark its root",
+        "Die Tasten dieser Tonleiter einfärben und ihren Grundton markieren",
+    ),
+    (
+        "Hold a pedal tone under whatever you play, through the main patch",
+        "Einen Orgelpunkt unter allem halten, was du spielst, über den Haupt-Patch",
+    ),
+    (
+        "Drag to change the drone note",
+        "Ziehen, um die Bordunnote zu ändern",
+    ),
+    ("Add the fifth above", "Die Quinte darüber hinzufügen"),
+    (
+        "Label every key with its interval from a root and outline a chord",
+        "Jede Taste mit ihrem Intervall zu einem Grundton beschriften und einen Akkord umreißen",
+    ),
+    (
+        "Root the intervals are counted from",
+        "Grundton, von dem aus die Intervalle gezählt werden",
+    ),
+    ("No chord", "Kein Akkord"),
+    (
+        "Outline this chord's notes on the keys",
+        "Die Noten dieses Akkords auf den Tasten umreißen",
+    ),
+    (
+        "Play a second patch on the keys below the split point",
+        "Einen zweiten Patch auf den Tasten unter dem Teilungspunkt spielen",
+    ),
+    (
+        "Copy the sound being edited into the lower zone",
+        "Den bearbeiteten Klang in die untere Zone kopieren",
+    ),
+    (
+        "Stack a second patch over the whole keyboard",
+        "Einen zweiten Patch über die ganze Klaviatur legen",
+    ),
+    (
+        "Copy the sound being edited into the layer",
+        "Den bearbeiteten Klang in die Schicht kopieren",
+    ),
+    ("Mute", "Stumm"),
+    (
+        "Solo: while any part is soloed, only soloed parts play",
+        "Solo: solange ein Part solo ist, spielen nur Solo-Parts",
+    ),
+    ("Rev", "Hall"),
+    ("Send to the shared reverb", "Anteil zum gemeinsamen Hall"),
+    ("Dly", "Echo"),
+    ("Send to the shared delay", "Anteil zum gemeinsamen Echo"),
+    (
+        "Una corda: follows the Soft pedal",
+        "Una corda: folgt dem Leisepedal",
+    ),
+    (
+        "Copy the sound being edited into this part",
+        "Den bearbeiteten Klang in diesen Part kopieren",
+    ),
+    ("Keys", "Tasten"),
+    (
+        "Only play this part on a range of keys",
+        "Diesen Part nur auf einem Tastenbereich spielen",
+    ),
+    ("Remove this part", "Diesen Part entfernen"),
+    ("Add part", "Part hinzufügen"),
+    (
+        "Add a part playing a copy of the sound being edited",
+        "Einen Part mit einer Kopie des bearbeiteten Klangs hinzufügen",
+    ),
+    (
+        "Duck under another part",
+        "Unter einem anderen Part absenken",
+    ),
+    (
+        "Turn this part down while the chosen part plays",
+        "Diesen Part leiser stellen, während der gewählte spielt",
+    ),
+    ("Amount", "Stärke"),
+    ("Attack", "Anstieg"),
+    ("Release", "Ausklang"),
+    ("Size", "Größe"),
+    ("Damping", "Dämpfung"),
+    (
+        "Higher darkens the tail",
+        "Höher macht den Nachhall dunkler",
+    ),
+    ("Return", "Rückweg"),
+    ("Time", "Zeit"),
+    ("Feedback", "Rückkopplung"),
+    (
+        "Turns the whole mix down just enough to keep its peaks under the ceiling, instead of clipping at the output",
+        "Senkt den ganzen Mix gerade so weit ab, dass seine Spitzen unter der Obergrenze bleiben, statt am Ausgang zu übersteuern",
+    ),
+    ("Ceiling", "Obergrenze"),
+    ("Vol", "Lautst."),
+    ("Pan", "Pan"),
+    ("Double-click to centre", "Doppelklick zum Zentrieren"),
+    ("Transpose in semitones", "Transponierung in Halbtönen"),
+    (
+        "Dragging across the piano slides the pitch instead of retriggering",
+        "Ziehen über das Klavier lässt die Tonhöhe gleiten, statt neu anzuschlagen",
+    ),
+    (
+        "Every new note slides in from the last one, not only dragged and legato notes",
+        "Jede neue Note gleitet von der letzten aus, nicht nur gezogene und Legato-Noten",
+    ),
+    ("Default output", "Standardausgabe"),
+    ("Middle C", "Mittleres C"),
+    (
+        "Octave numbering used for note names throughout the app",
+        "Oktavnummerierung für Notennamen in der ganzen App",
+    ),
+    (
+        "Keep the synth above notation software or a lesson video",
+        "Den Synth über Notensatzprogrammen oder einem Lernvideo halten",
+    ),
+    (
+        "D-pad and face buttons play a C major scale, hold LB for triads. Left stick up adds vibrato, right stick sweeps the filter, RT/LT raise and lower the master gain.",
+        "Steuerkreuz und Aktionstasten spielen eine C-Dur-Tonleiter, LB halten für Dreiklänge. Linker Stick nach oben fügt Vibrato hinzu, der rechte Stick bewegt den Filter, RT/LT heben und senken die Gesamtlautstärke.",
+    ),
+    (
+        "Look again for controllers plugged in since launch",
+        "Erneut nach seit dem Start angeschlossenen Controllern suchen",
+    ),
+    (
+        "Play from a MIDI keyboard on any channel. The sustain pedal (CC 64) latches like Hold, CC 67 works the Soft pedal, and the CC mappings below drive the sound.",
+        "Von einem MIDI-Keyboard auf jedem Kanal spielen. Das Haltepedal (CC 64) hält wie Halten, CC 67 bedient das Leisepedal, und die CC-Zuordnungen unten steuern den Klang.",
+    ),
+    ("Bend range", "Pitch-Bend-Bereich"),
+    (
+        "Semitones the pitch bend reaches at either end",
+        "Halbtöne, die das Pitch-Bend an beiden Enden erreicht",
+    ),
+    ("Tuning", "Stimmung"),
+    (
+        "Key the tuning is built up from, which keeps its equal-tempered pitch; just and meantone sound purest in keys near it",
+        "Taste, von der aus die Stimmung aufgebaut wird und die ihre gleichstufige Tonhöhe behält; rein und mitteltönig klingen in nahen Tonarten am reinsten",
+    ),
+    ("Path to a .scl", "Pfad zu einer .scl"),
+    (
+        "Tune every part to a Scala scale",
+        "Alle Parts auf eine Scala-Skala stimmen",
+    ),
+    ("Mic mode", "Mikrofonmodus"),
+    (
+        "Autotune pulls your voice onto the nearest note of the scale picked above the piano, as strongly and as fast as the Autotune and Retune time sliders say, and plays it through the EQ. Play notes turns the pitch you sing, hum or whistle into synth notes, one at a time.",
+        "Autotune zieht deine Stimme auf die nächste Note der über dem Klavier gewählten Tonleiter, so stark und so schnell, wie die Regler Autotune und Nachstimmzeit sagen, und spielt sie über den EQ. Noten spielen macht aus der Tonhöhe, die du singst, summst oder pfeifst, einzelne Synth-Noten.",
+    ),
+    ("Take mix", "Aufnahmemix"),
+    ("synth", "Synth"),
+    ("mic", "Mikro"),
+    (
+        "Levels of the synth and the dry microphone in a recorded take",
+        "Pegel von Synth und trockenem Mikrofon in einer Aufnahme",
+    ),
+    ("MIDI controls", "MIDI-Steuerung"),
+    (
+        "Let breath pressure shape the sound, for wind controllers",
+        "Den Atemdruck den Klang formen lassen, für Blascontroller",
+    ),
+    ("Response", "Ansprache"),
+    ("To volume", "Auf Lautstärke"),
+    (
+        "At full, notes are silent until you blow",
+        "Voll aufgedreht bleiben Noten stumm, bis du hineinbläst",
+    ),
+    ("To cutoff", "Auf Grenzfrequenz"),
+    (
+        "How far the filter closes as the breath eases off",
+        "Wie weit sich der Filter schließt, wenn der Atem nachlässt",
+    ),
+    ("Template", "Vorlage"),
+    (
+        "Replace the table with the stock knob and fader layout of a controller",
+        "Die Tabelle durch die Standardbelegung der Regler und Fader eines Controllers ersetzen",
+    ),
+    ("No controls mapped", "Keine Regler zugeordnet"),
+    ("Remove", "Entfernen"),
+    ("Add mapping", "Zuordnung hinzufügen"),
+    ("Jam session", "Jam-Session"),
+    ("Joined", "Beigetreten"),
+    ("Waiting for other players…", "Warte auf andere Spieler…"),
+    ("measuring…", "wird gemessen…"),
+    (
+        "One-way latency, half the measured round trip",
+        "Latenz in eine Richtung, die Hälfte der gemessenen Umlaufzeit",
+    ),
+    ("Jitter buffer (ms)", "Jitterpuffer (ms)"),
+    (
+        "Remote notes are delayed by this much so they keep their timing over a busy network",
+        "Entfernte Noten werden um so viel verzögert, damit sie ihr Timing über ein ausgelastetes Netzwerk behalten",
+    ),
+    ("Leave", "Verlassen"),
+    ("Your name", "Dein Name"),
+    ("Port", "Port"),
+    ("Host", "Hosten"),
+    ("Join", "Beitreten"),
+    ("host or host:port", "Host oder Host:Port"),
+    ("Find sessions", "Sitzungen suchen"),
+    ("Searching…", "Suche läuft…"),
+    ("Ear training", "Gehörbildung"),
+    ("Replay", "Nochmal"),
+    ("What was that?", "Was war das?"),
+    (
+        "Play it back on the piano, starting from any note.",
+        "Spiel es auf dem Klavier nach, von einer beliebigen Note aus.",
+    ),
+    ("Keymap editor", "Tastenzuordnungs-Editor"),
+    ("New keymap", "Neue Tastenzuordnung"),
+    (
+        "Create a keymap to start assigning keys.",
+        "Lege eine Tastenzuordnung an, um Tasten zuzuweisen.",
+    ),
+    ("Delete", "Löschen"),
+    (
+        "Use for the computer keyboard",
+        "Für die Computertastatur verwenden",
+    ),
+    (
+        "Click a key on the piano, then press a computer key.",
+        "Klicke eine Klaviertaste an und drücke dann eine Computertaste.",
+    ),
+    ("File", "Datei"),
+    ("Export", "Exportieren"),
+    ("Import", "Importieren"),
+    ("Card padding (px)", "Kartenabstand (px)"),
+    ("Card rounding (px)", "Kartenrundung (px)"),
+    ("Scope height (px)", "Oszilloskophöhe (px)"),
+    ("Keyboard height scale", "Klaviaturhöhe"),
+    ("Touch layout", "Touch-Anordnung"),
+    (
+        "Taller keys and bigger controls for playing on a touch screen",
+        "Höhere Tasten und größere Bedienelemente zum Spielen auf einem Touchscreen",
+    ),
+    ("Key colours", "Tastenfarben"),
+    ("From", "Von"),
+    ("to", "bis"),
+    (
+        "Drag onto another card to move this one in front of it",
+        "Auf eine andere Karte ziehen, um diese davor zu setzen",
+    ),
+    ("Jam", "Jam"),
+    ("Record", "Aufnehmen"),
+    ("Stop", "Stopp"),
+    ("Play", "Abspielen"),
+    ("MIDI learn", "MIDI-Lernen"),
+    ("Cancel MIDI learn", "MIDI-Lernen abbrechen"),
+    ("Move a knob or fader…", "Einen Regler oder Fader bewegen…"),
+    (
+        "Open a MIDI input in Settings first",
+        "Zuerst in den Einstellungen einen MIDI-Eingang öffnen",
+    ),
+    ("Forget MIDI mapping", "MIDI-Zuordnung vergessen"),
+    (
+        "Alt+1…9 sets the keyboard velocity. Hold Shift to accent a note.",
+        "Alt+1…9 stellt die Tastaturanschlagstärke ein. Umschalt gedrückt halten betont eine Note.",
+    ),
+    (
+        "FL-style minimal layout · {down}/{up} = octave",
+        "Minimales Layout im FL-Stil · {down}/{up} = Oktave",
+    ),
+    ("All notes off ({shortcut})", "Alle Noten aus ({shortcut})"),
+    (
+        "Use the keyboard for the controls instead of notes: Tab moves between them, arrows nudge sliders, Enter or Space presses and opens ({shortcut})",
+        "Die Tastatur bedient die Regler statt Noten zu spielen: Tab wechselt zwischen ihnen, die Pfeiltasten verschieben Schieberegler, Enter oder Leertaste drückt und öffnet ({shortcut})",
+    ),
+    ("Vel {velocity}", "Anschl. {velocity}"),
+    ("Audio: {error}", "Audio: {error}"),
+    (
+        "An autosaved session from {minutes} min ago is available.",
+        "Eine vor {minutes} Min. automatisch gesicherte Sitzung ist verfügbar.",
+    ),
+    ("Range: {min} to {max}", "Bereich: {min} bis {max}"),
+    (
+        "Another common progression in {scale}",
+        "Eine andere gängige Akkordfolge in {scale}",
+    ),
+    (
+        "Filter + EQ response (±{range} dB)",
+        "Filter- + EQ-Kurve (±{range} dB)",
+    ),
+    ("Harmonic {number}: {level}%", "Oberton {number}: {level} %"),
+    ("{count} harmonics", "{count} Obertöne"),
+    (
+        "L peak {left_peak} dB, RMS {left_rms} dB\nR peak {right_peak} dB, RMS {right_rms} dB\nClick to reset the clip lamps",
+        "L Spitze {left_peak} dB, RMS {left_rms} dB\nR Spitze {right_peak} dB, RMS {right_rms} dB\nKlicken setzt die Übersteuerungsanzeigen zurück",
+    ),
+    (
+        "ITU-R BS.1770 loudness. Momentary {momentary} LUFS (400 ms), short-term over 3 s, integrated since the last reset.",
+        "Lautheit nach ITU-R BS.1770. Momentan {momentary} LUFS (400 ms), kurzzeitig über 3 s, integriert seit dem letzten Zurücksetzen.",
+    ),
+    ("{count} sessions so far", "Bisher {count} Sitzungen"),
+    ("Most played: {list}", "Am häufigsten gespielt: {list}"),
+    ("Scales: {list}", "Tonleitern: {list}"),
+    (
+        "Xruns {count} · DSP {load}%",
+        "Xruns {count} · DSP {load} %",
+    ),
+    (
+        "{underruns} under-runs (late callbacks), {overruns} over-runs (rendering slower than real time), {errors} stream errors, {frames} frames per buffer. Details go to the log. Click to clear.",
+        "{underruns} Unterläufe (verspätete Callbacks), {overruns} Überläufe (Berechnung langsamer als Echtzeit), {errors} Stream-Fehler, {frames} Frames pro Puffer. Details stehen im Protokoll. Klicken setzt zurück.",
+    ),
+    (
+        "Phase correlation {correlation}. Below 0 the sound partly cancels in mono.",
+        "Phasenkorrelation {correlation}. Unter 0 löscht sich der Klang in Mono teilweise aus.",
+    ),
+    ("Saved {path}", "{path} gespeichert"),
+    (
+        "Failed to write {path}: {error}",
+        "{path} konnte nicht geschrieben werden: {error}",
+    ),
+    (
+        "The synth is unavailable",
+        "Der Synthesizer ist nicht verfügbar",
+    ),
+    ("Oct 0", "Okt 0"),
+    ("Oct {offset}", "Okt {offset}"),
+    (
+        "You played {notes}: {description}",
+        "Gespielt: {notes}: {description}",
+    ),
+    ("Part {number}", "Stimme {number}"),
+    (
+        "How much is written to {path}; older logs are kept next to it",
+        "Wie viel in {path} geschrieben wird; ältere Protokolle liegen daneben",
+    ),
+    ("Breath controller (CC {cc})", "Blaswandler (CC {cc})"),
+    ("Hosting on port {port}", "Gastgeber auf Port {port}"),
+    ("Join {name} ({address})", "{name} beitreten ({address})"),
+    ("Streak {count}", "Serie {count}"),
+    ("best {count}", "beste {count}"),
+    (
+        "{correct} of {attempts} right",
+        "{correct} von {attempts} richtig",
+    ),
+    (
+        "Press a computer key for {note}",
+        "Eine Computertaste für {note} drücken",
+    ),
+    ("Exported to {path}", "Nach {path} exportiert"),
+    ("Imported {path}", "{path} importiert"),
+    ("Correct: {name}", "Richtig: {name}"),
+    ("It was {name}", "Es war {name}"),
+    ("Octave down", "Oktave runter"),
+    ("Octave up", "Oktave hoch"),
+    ("Previous preset", "Vorheriges Preset"),
+    ("Next preset", "Nächstes Preset"),
+    ("Panic (all notes off)", "Panik (alle Noten aus)"),
+    (
+        "UI focus (navigate controls from the keyboard)",
+        "UI-Fokus (Regler mit der Tastatur bedienen)",
+    ),
+    ("Record a take", "Eine Aufnahme starten oder stoppen"),
+];
+
+const SPANISH: &[(&str, &str)] = &[
+    ("Settings", "Ajustes"),
+    ("Reset sound", "Restablecer sonido"),
+    ("Panic", "Pánico"),
+    ("Hold", "Sostener"),
+    ("UI focus", "Foco de interfaz"),
+    ("App Settings", "Ajustes de la aplicación"),
+    ("Theme", "Tema"),
+    ("Language", "Idioma"),
+    ("Always on top", "Siempre visible"),
+    ("Keybinds", "Esquema de teclas"),
+    ("Shortcuts", "Atajos"),
+    ("Keymap", "Mapa de teclas"),
+    ("Built-in", "Integrado"),
+    ("Edit keymaps…", "Editar mapas de teclas…"),
+    ("Gamepad", "Mando"),
+    ("None connected", "Ninguno conectado"),
+    ("Output", "Salida"),
+    ("Input", "Entrada"),
+    ("On launch", "Al iniciar"),
+    ("Layout & sizing", "Diseño y tamaño"),
+    ("Import / export", "Importar / exportar"),
+    ("Wave Scope", "Osciloscopio"),
+    ("Keyboard", "Teclado"),
+    ("XY Pad", "Pad XY"),
+    ("Tone & Filter", "Tono y filtro"),
+    ("Motion & Noise", "Movimiento y ruido"),
+    ("EQ", "EQ"),
+    ("Harmonics", "Armónicos"),
+    ("Granular", "Granular"),
+    ("Preset Morph", "Morph de presets"),
+    ("Progressions", "Progresiones"),
+    ("Mixer", "Mezclador"),
+    ("Reverb", "Reverberación"),
+    ("Ribbon", "Cinta"),
+    ("Soft", "Suave"),
+    ("Drone", "Bordón"),
+    ("Fifth", "Quinta"),
+    ("Level", "Nivel"),
+    ("MIDI in", "Entrada MIDI"),
+    ("Rescan", "Buscar de nuevo"),
+    ("Snap to scale", "Ajustar a la escala"),
+    ("Delay", "Retardo"),
+    ("Limiter", "Limitador"),
+    ("Practice Stats", "Estadísticas de práctica"),
+    ("Diagnostics", "Diagnóstico"),
+    ("Log", "Registro"),
+    ("Layout", "Diseño"),
+    ("Main", "Principal"),
+    ("Left dock", "Panel izquierdo"),
+    ("Right dock", "Panel derecho"),
+    ("Bottom dock", "Panel inferior"),
+    ("Window", "Ventana"),
+    (
+        "Keyboard disabled while settings are open.",
+        "El teclado está desactivado mientras los ajustes están abiertos.",
+    ),
+    ("Glide between keys", "Deslizar entre teclas"),
+    ("Glide every note", "Deslizar cada nota"),
+    ("Key labels", "Etiquetas de teclas"),
+    ("Zoom", "Zoom"),
+    ("Split", "División"),
+    ("below", "por debajo de"),
+    ("Use current", "Usar actual"),
+    ("Theory", "Teoría"),
+    ("Practice", "Práctica"),
+    ("Layer", "Capa"),
+    ("Expand", "Expandir"),
+    ("Collapse", "Contraer"),
+    ("Freeze", "Congelar"),
+    ("Scope", "Osciloscopio"),
+    (
+        "The scope is in its own window.",
+        "El osciloscopio está en su propia ventana.",
+    ),
+    ("Pop back in", "Volver a integrar"),
+    ("Pop out", "Separar"),
+    (
+        "Move the scope and meters into a separate window",
+        "Mover el osciloscopio y los medidores a una ventana aparte",
+    ),
+    (
+        "Show the computer key that plays each piano key",
+        "Mostrar la tecla del ordenador que toca cada tecla del piano",
+    ),
+    (
+        "Show fewer, larger keys; scroll or use the strip below",
+        "Mostrar menos teclas y más grandes; desplázate o usa la franja de abajo",
+    ),
+    ("Share", "Compartir"),
+    (
+        "Copy the patch as text for chat, or load one someone sent",
+        "Copiar el patch como texto para el chat o cargar uno que te hayan enviado",
+    ),
+    (
+        "Latch: released notes keep sounding until Hold is turned off",
+        "Retención: las notas soltadas siguen sonando hasta desactivar Sostener",
+    ),
+    (
+        "Soft pedal: notes play gentler and darker while it is on",
+        "Pedal suave: las notas suenan más suaves y oscuras mientras está activo",
+    ),
+    (
+        "Play together with other instances on the local network",
+        "Tocar junto con otras instancias en la red local",
+    ),
+    (
+        "Ear training: name or play back intervals and chords",
+        "Entrenamiento auditivo: nombra o repite intervalos y acordes",
+    ),
+    ("Bounce", "Exportar audio"),
+    (
+        "Render the notes you are holding to a WAV, tails included",
+        "Renderizar las notas que mantienes a un WAV, con sus colas",
+    ),
+    (
+        "Alt+1…9 sets the keyboard velocity. Hold Shift to accent a note, Ctrl to play it softly.",
+        "Alt+1…9 fija la velocidad del teclado. Mantén Mayús para acentuar una nota y Ctrl para tocarla suave.",
+    ),
+    ("Restore session?", "¿Restaurar la sesión?"),
+    (
+        "Angel didn't shut down cleanly last time.",
+        "Angel no se cerró correctamente la última vez.",
+    ),
+    ("Restore", "Restaurar"),
+    ("Discard", "Descartar"),
+    (
+        "Play with keyboard or mouse",
+        "Toca con el teclado o el ratón",
+    ),
+    ("Reset to default", "Restablecer valor predeterminado"),
+    ("Copy value", "Copiar valor"),
+    ("Paste value", "Pegar valor"),
+    ("Curve", "Curva"),
+    (
+        "Shape of the envelope stages: exponential creeps in and tails off naturally, logarithmic snaps up and holds before it falls",
+        "Forma de las etapas de la envolvente: la exponencial entra despacio y se apaga con naturalidad, la logarítmica sube de golpe y se mantiene antes de caer",
+    ),
+    ("Instrument", "Instrumento"),
+    (
+        "Character added to the waveform: Bass adds a sub-octave, Lead an overtone and drive",
+        "Carácter añadido a la forma de onda: Bass añade una suboctava, Lead un armónico y saturación",
+    ),
+    ("Voices", "Voces"),
+    (
+        "Poly plays chords; Mono plays one note at a time, restarting the envelope on each; Legato glides between overlapping notes without restarting it",
+        "Poly toca acordes; Mono toca una nota a la vez y reinicia la envolvente en cada una; Legato se desliza entre notas solapadas sin reiniciarla",
+    ),
+    (
+        "Most notes the patch sounds at once; past it, a voice is stolen",
+        "Máximo de notas que suenan a la vez; por encima, se roba una voz",
+    ),
+    (
+        "Which voice gives way to a new note once all are in use; released notes always go first",
+        "Qué voz cede ante una nota nueva cuando todas están en uso; las notas soltadas siempre van primero",
+    ),
+    (
+        "Which held key sounds: the last one pressed, the lowest or the highest",
+        "Qué tecla mantenida suena: la última pulsada, la más grave o la más aguda",
+    ),
+    ("Transpose", "Transponer"),
+    (
+        "Semitones every note sounds away from its key; -2 plays along with a B♭ instrument's part in concert pitch",
+        "Semitonos que cada nota suena lejos de su tecla; -2 acompaña la parte de un instrumento en si♭ en tono de concierto",
+    ),
+    (
+        "Reference pitch every note is tuned from: 440 is concert pitch, 415 baroque; nudge it to match a recording",
+        "Tono de referencia para afinar cada nota: 440 es el de concierto, 415 el barroco; ajústalo para igualar una grabación",
+    ),
+    (
+        "Additive plays the Harmonics card's bars instead of a waveform.",
+        "Aditivo toca las barras de la tarjeta Armónicos en lugar de una forma de onda.",
+    ),
+    ("Waveform", "Forma de onda"),
+    (
+        "Oscillator shape: sine is pure, triangle soft, square hollow, saw bright",
+        "Forma del oscilador: la senoidal es pura, la triangular suave, la cuadrada hueca y la de sierra brillante",
+    ),
+    ("Filter", "Filtro"),
+    (
+        "Low-pass darkens, high-pass thins, band-pass keeps a band around the cutoff and notch removes it; ladder is a driven four-pole low-pass that screams at high resonance",
+        "El paso bajo oscurece, el paso alto adelgaza, el paso banda conserva una banda alrededor del corte y el notch la elimina; ladder es un paso bajo de cuatro polos saturado que chilla con resonancia alta",
+    ),
+    ("Table", "Tabla"),
+    ("Built-in shapes", "Formas integradas"),
+    (
+        "Go back to the built-in shapes",
+        "Volver a las formas integradas",
+    ),
+    ("Path to a .wav", "Ruta a un .wav"),
+    ("Load", "Cargar"),
+    ("Load preset", "Cargar preset"),
+    ("Load template…", "Cargar plantilla…"),
+    (
+        "A single cycle, or a table of 2048-sample frames such as Serum writes",
+        "Un solo ciclo o una tabla de fotogramas de 2048 muestras como las que escribe Serum",
+    ),
+    ("Sample", "Muestra"),
+    ("None loaded", "Nada cargado"),
+    ("Clear", "Quitar"),
+    ("Stop playing the sample", "Dejar de tocar la muestra"),
+    (
+        "Any WAV; only the first minute is kept",
+        "Cualquier WAV; solo se conserva el primer minuto",
+    ),
+    ("Root", "Tónica"),
+    (
+        "The note the sample was recorded at; drag to retune it",
+        "La nota en la que se grabó la muestra; arrastra para reafinarla",
+    ),
+    ("Detect", "Detectar"),
+    (
+        "Go back to the root note found in the file",
+        "Volver a la tónica encontrada en el archivo",
+    ),
+    ("Loop", "Bucle"),
+    (
+        "Keep repeating between the loop points instead of stopping at the end",
+        "Repetir entre los puntos de bucle en lugar de parar al final",
+    ),
+    ("Loop start", "Inicio del bucle"),
+    ("Loop end", "Fin del bucle"),
+    (
+        "Drag to move the pad to another key",
+        "Arrastra para mover el pad a otra tecla",
+    ),
+    ("Synthesized", "Sintetizado"),
+    (
+        "Go back to the synthesized drum",
+        "Volver al tambor sintetizado",
+    ),
+    (
+        "Play the WAV in the path field below on this pad",
+        "Tocar en este pad el WAV del campo de ruta de abajo",
+    ),
+    (
+        "Path to a .wav, then Load on a pad",
+        "Ruta a un .wav y luego Cargar en un pad",
+    ),
+    (
+        "Each hit plays out in full, however long its key is held.",
+        "Cada golpe suena completo, por mucho que se mantenga la tecla.",
+    ),
+    ("Font", "Fuente"),
+    ("Stop playing the SoundFont", "Dejar de tocar el SoundFont"),
+    ("Preset", "Preset"),
+    (
+        "Bank and program number, then the preset's name",
+        "Número de banco y de programa, y luego el nombre del preset",
+    ),
+    ("Path to a .sf2 or .sfz", "Ruta a un .sf2 o .sfz"),
+    (
+        "An SFZ's samples are read from the folder it names them in",
+        "Las muestras de un SFZ se leen de la carpeta que indica",
+    ),
+    ("Unison", "Unísono"),
+    (
+        "Copies stacked on each note, detuned across the unison spread",
+        "Copias apiladas en cada nota, desafinadas a lo largo de la apertura del unísono",
+    ),
+    (
+        "Pick a scale above the piano to get progressions in its key.",
+        "Elige una escala encima del piano para obtener progresiones en su tonalidad.",
+    ),
+    (
+        "Progressions need a seven-note scale such as major or minor.",
+        "Las progresiones necesitan una escala de siete notas, como mayor o menor.",
+    ),
+    ("Suggest another", "Sugerir otra"),
+    (
+        "Global tempo; the progression plays one chord per bar",
+        "Tempo global; la progresión toca un acorde por compás",
+    ),
+    ("Morph A → B", "Morph A → B"),
+    ("Snapshot", "Instantánea"),
+    (
+        "Save the current in-between patch as a new preset",
+        "Guardar el patch intermedio actual como un preset nuevo",
+    ),
+    ("Copy patch as text", "Copiar patch como texto"),
+    ("Paste patch", "Pegar patch"),
+    (
+        "Heard with the Granular instrument.",
+        "Se oye con el instrumento Granular.",
+    ),
+    ("Use Granular", "Usar Granular"),
+    (
+        "Load a sample in the Tone card to granulate it.",
+        "Carga una muestra en la tarjeta Tono para granularla.",
+    ),
+    (
+        "Heard with the Additive instrument.",
+        "Se oye con el instrumento Aditivo.",
+    ),
+    ("Use Additive", "Usar Aditivo"),
+    (
+        "Gain reduction: how far the limiter (in the Mixer) is turning the mix down to keep it under its ceiling. A few dB on the loudest notes is fine; more means lower the gain or the part volumes.",
+        "Reducción de ganancia: cuánto baja el limitador (en el Mezclador) la mezcla para mantenerla bajo su techo. Unos pocos dB en las notas más fuertes está bien; más significa que bajes la ganancia o el volumen de las partes.",
+    ),
+    ("Reset", "Restablecer"),
+    (
+        "Restart integrated loudness",
+        "Reiniciar la sonoridad integrada",
+    ),
+    ("Session", "Sesión"),
+    ("All time", "En total"),
+    ("Time played", "Tiempo tocado"),
+    ("Notes", "Notas"),
+    ("Copy report", "Copiar informe"),
+    ("No events yet.", "Aún no hay eventos."),
+    ("Mono cancel", "Cancelación en mono"),
+    (
+        "Record the synth and the microphone into one WAV; set their levels under Take mix in Settings",
+        "Grabar el sintetizador y el micrófono en un WAV; ajusta sus niveles en Mezcla de toma en Ajustes",
+    ),
+    ("Hold for", "Mantener durante"),
+    (
+        "How long the notes are held before they are let go to ring out",
+        "Cuánto se mantienen las notas antes de soltarlas para que resuenen",
+    ),
+    ("Folder", "Carpeta"),
+    ("Bounce held notes", "Exportar notas mantenidas"),
+    ("Export PNG", "Exportar PNG"),
+    ("Export CSV", "Exportar CSV"),
+    ("Envelope", "Envolvente"),
+    (
+        "Show the loudness outline of the last few seconds",
+        "Mostrar el contorno de volumen de los últimos segundos",
+    ),
+    ("Trigger", "Disparo"),
+    (
+        "Lock the trace to rising zero crossings; free-runs when silent",
+        "Fijar el trazo a los cruces por cero ascendentes; corre libre en silencio",
+    ),
+    ("Gain", "Ganancia"),
+    (
+        "Snap to the selected scale, or to semitones without one",
+        "Ajustar a la escala elegida, o a semitonos si no hay ninguna",
+    ),
+    (
+        "Click or drag to scroll the keyboard",
+        "Haz clic o arrastra para desplazar el teclado",
+    ),
+    (
+        "Click and drag to play across keys. Click lower on a key to play louder. Ctrl+scroll to zoom.",
+        "Haz clic y arrastra para tocar varias teclas. Pulsa más abajo en una tecla para tocar más fuerte. Ctrl+rueda para hacer zoom.",
+    ),
+    ("Scale", "Escala"),
+    ("Off", "Apagado"),
+    (
+        "Tint the keys in this scale and mark its root",
+        "Colorear las teclas de esta escala y marcar su tónica",
+    ),
+    (
+        "Hold a pedal tone under whatever you play, through the main patch",
+        "Mantener una nota pedal bajo lo que toques, con el patch principal",
+    ),
+    (
+        "Drag to change the drone note",
+        "Arrastra para cambiar la nota del bordón",
+    ),
+    ("Add the fifth above", "Añadir la quinta superior"),
+    (
+        "Label every key with its interval from a root and outline a chord",
+        "Etiquetar cada tecla con su intervalo desde una tónica y perfilar un acorde",
+    ),
+    (
+        "Root the intervals are counted from",
+        "Tónica desde la que se cuentan los intervalos",
+    ),
+    ("No chord", "Sin acorde"),
+    (
+        "Outline this chord's notes on the keys",
+        "Perfilar las notas de este acorde en las teclas",
+    ),
+    (
+        "Play a second patch on the keys below the split point",
+        "Tocar un segundo patch en las teclas por debajo del punto de división",
+    ),
+    (
+        "Copy the sound being edited into the lower zone",
+        "Copiar el sonido que se edita a la zona inferior",
+    ),
+    (
+        "Stack a second patch over the whole keyboard",
+        "Apilar un segundo patch sobre todo el teclado",
+    ),
+    (
+        "Copy the sound being edited into the layer",
+        "Copiar el sonido que se edita a la capa",
+    ),
+    ("Mute", "Silenciar"),
+    (
+        "Solo: while any part is soloed, only soloed parts play",
+        "Solo: mientras alguna parte esté en solo, solo suenan las partes en solo",
+    ),
+    ("Rev", "Rev"),
+    (
+        "Send to the shared reverb",
+        "Envío a la reverberación compartida",
+    ),
+    ("Dly", "Ret"),
+    ("Send to the shared delay", "Envío al retardo compartido"),
+    (
+        "Una corda: follows the Soft pedal",
+        "Una corda: sigue al pedal Suave",
+    ),
+    (
+        "Copy the sound being edited into this part",
+        "Copiar el sonido que se edita a esta parte",
+    ),
+    ("Keys", "Teclas"),
+    (
+        "Only play this part on a range of keys",
+        "Tocar esta parte solo en un rango de teclas",
+    ),
+    ("Remove this part", "Quitar esta parte"),
+    ("Add part", "Añadir parte"),
+    (
+        "Add a part playing a copy of the sound being edited",
+        "Añadir una parte que toque una copia del sonido que se edita",
+    ),
+    ("Duck under another part", "Atenuar bajo otra parte"),
+    (
+        "Turn this part down while the chosen part plays",
+        "Bajar esta parte mientras suena la parte elegida",
+    ),
+    ("Amount", "Cantidad"),
+    ("Attack", "Ataque"),
+    ("Release", "Liberación"),
+    ("Size", "Tamaño"),
+    ("Damping", "Amortiguación"),
+    ("Higher darkens the tail", "Más alto oscurece la cola"),
+    ("Return", "Retorno"),
+    ("Time", "Tiempo"),
+    ("Feedback", "Realimentación"),
+    (
+        "Turns the whole mix down just enough to keep its peaks under the ceiling, instead of clipping at the output",
+        "Baja la mezcla entera lo justo para mantener sus picos bajo el techo, en lugar de saturar a la salida",
+    ),
+    ("Ceiling", "Techo"),
+    ("Vol", "Vol"),
+    ("Pan", "Pan"),
+    ("Double-click to centre", "Doble clic para centrar"),
+    ("Transpose in semitones", "Transposición en semitonos"),
+    (
+        "Dragging across the piano slides the pitch instead of retriggering",
+        "Arrastrar por el piano desliza el tono en lugar de redisparar",
+    ),
+    (
+        "Every new note slides in from the last one, not only dragged and legato notes",
+        "Cada nota nueva se desliza desde la anterior, no solo las arrastradas y en legato",
+    ),
+    ("Default output", "Salida predeterminada"),
+    ("Middle C", "Do central"),
+    (
+        "Octave numbering used for note names throughout the app",
+        "Numeración de octavas usada en los nombres de notas de toda la aplicación",
+    ),
+    (
+        "Keep the synth above notation software or a lesson video",
+        "Mantener el sintetizador encima del software de partituras o de un vídeo de clase",
+    ),
+    (
+        "D-pad and face buttons play a C major scale, hold LB for triads. Left stick up adds vibrato, right stick sweeps the filter, RT/LT raise and lower the master gain.",
+        "La cruceta y los botones frontales tocan una escala de do mayor; mantén LB para tríadas. El stick izquierdo hacia arriba añade vibrato, el derecho barre el filtro y RT/LT suben y bajan la ganancia general.",
+    ),
+    (
+        "Look again for controllers plugged in since launch",
+        "Buscar de nuevo mandos conectados desde el inicio",
+    ),
+    (
+        "Play from a MIDI keyboard on any channel. The sustain pedal (CC 64) latches like Hold, CC 67 works the Soft pedal, and the CC mappings below drive the sound.",
+        "Toca desde un teclado MIDI en cualquier canal. El pedal de sostenido (CC 64) retiene como Sostener, CC 67 maneja el pedal Suave y las asignaciones CC de abajo controlan el sonido.",
+    ),
+    ("Bend range", "Rango de inflexión"),
+    (
+        "Semitones the pitch bend reaches at either end",
+        "Semitonos que alcanza la inflexión de tono en cada extremo",
+    ),
+    ("Tuning", "Afinación"),
+    (
+        "Key the tuning is built up from, which keeps its equal-tempered pitch; just and meantone sound purest in keys near it",
+        "Tecla desde la que se construye la afinación, que conserva su tono temperado; la justa y la mesotónica suenan más puras en tonalidades cercanas",
+    ),
+    ("Path to a .scl", "Ruta a un .scl"),
+    (
+        "Tune every part to a Scala scale",
+        "Afinar todas las partes a una escala Scala",
+    ),
+    ("Mic mode", "Modo de micrófono"),
+    (
+        "Autotune pulls your voice onto the nearest note of the scale picked above the piano, as strongly and as fast as the Autotune and Retune time sliders say, and plays it through the EQ. Play notes turns the pitch you sing, hum or whistle into synth notes, one at a time.",
+        "Autotune lleva tu voz a la nota más cercana de la escala elegida encima del piano, con la fuerza y la rapidez que indican los controles Autotune y Tiempo de reafinación, y la reproduce por el EQ. Tocar notas convierte el tono que cantas, tarareas o silbas en notas del sintetizador, de una en una.",
+    ),
+    ("Take mix", "Mezcla de toma"),
+    ("synth", "sintetizador"),
+    ("mic", "micro"),
+    (
+        "Levels of the synth and the dry microphone in a recorded take",
+        "Niveles del sintetizador y del micrófono seco en una toma grabada",
+    ),
+    ("MIDI controls", "Controles MIDI"),
+    (
+        "Let breath pressure shape the sound, for wind controllers",
+        "Dejar que la presión del aliento moldee el sonido, para controladores de viento",
+    ),
+    ("Response", "Respuesta"),
+    ("To volume", "Al volumen"),
+    (
+        "At full, notes are silent until you blow",
+        "Al máximo, las notas callan hasta que soplas",
+    ),
+    ("To cutoff", "Al corte"),
+    (
+        "How far the filter closes as the breath eases off",
+        "Cuánto se cierra el filtro cuando el aliento afloja",
+    ),
+    ("Template", "Plantilla"),
+    (
+        "Replace the table with the stock knob and fader layout of a controller",
+        "Sustituir la tabla por la disposición de fábrica de mandos y faders de un controlador",
+    ),
+    ("No controls mapped", "Ningún control asignado"),
+    ("Remove", "Quitar"),
+    ("Add mapping", "Añadir asignación"),
+    ("Jam session", "Sesión de jam"),
+    ("Joined", "Unido"),
+    ("Waiting for other players…", "Esperando a otros músicos…"),
+    ("measuring…", "midiendo…"),
+    (
+        "One-way latency, half the measured round trip",
+        "Latencia en un sentido, la mitad del viaje de ida y vuelta medido",
+    ),
+    ("Jitter buffer (ms)", "Búfer de jitter (ms)"),
+    (
+        "Remote notes are delayed by this much so they keep their timing over a busy network",
+        "Las notas remotas se retrasan esto para mantener su ritmo en una red saturada",
+    ),
+    ("Leave", "Salir"),
+    ("Your name", "Tu nombre"),
+    ("Port", "Puerto"),
+    ("Host", "Alojar"),
+    ("Join", "Unirse"),
+    ("host or host:port", "host o host:puerto"),
+    ("Find sessions", "Buscar sesiones"),
+    ("Searching…", "Buscando…"),
+    ("Ear training", "Entrenamiento auditivo"),
+    ("Replay", "Repetir"),
+    ("What was that?", "¿Qué era eso?"),
+    (
+        "Play it back on the piano, starting from any note.",
+        "Tócalo en el piano, empezando desde cualquier nota.",
+    ),
+    ("Keymap editor", "Editor de mapas de teclas"),
+    ("New keymap", "Nuevo mapa de teclas"),
+    (
+        "Create a keymap to start assigning keys.",
+        "Crea un mapa de teclas para empezar a asignar teclas.",
+    ),
+    ("Delete", "Eliminar"),
+    (
+        "Use for the computer keyboard",
+        "Usar para el teclado del ordenador",
+    ),
+    (
+        "Click a key on the piano, then press a computer key.",
+        "Haz clic en una tecla del piano y luego pulsa una tecla del ordenador.",
+    ),
+    ("File", "Archivo"),
+    ("Export", "Exportar"),
+    ("Import", "Importar"),
+    ("Card padding (px)", "Margen de tarjeta (px)"),
+    ("Card rounding (px)", "Redondeo de tarjeta (px)"),
+    ("Scope height (px)", "Altura del osciloscopio (px)"),
+    ("Keyboard height scale", "Escala de altura del teclado"),
+    ("Touch layout", "Diseño táctil"),
+    (
+        "Taller keys and bigger controls for playing on a touch screen",
+        "Teclas más altas y controles más grandes para tocar en una pantalla táctil",
+    ),
+    ("Key colours", "Colores de teclas"),
+    ("From", "De"),
+    ("to", "a"),
+    (
+        "Drag onto another card to move this one in front of it",
+        "Arrastra sobre otra tarjeta para poner esta delante",
+    ),
+    ("Jam", "Jam"),
+    ("Record", "Grabar"),
+    ("Stop", "Parar"),
+    ("Play", "Tocar"),
+    ("MIDI learn", "Aprendizaje MIDI"),
+    ("Cancel MIDI learn", "Cancelar aprendizaje MIDI"),
+    ("Move a knob or fader…", "Mueve un mando o fader…"),
+    (
+        "Open a MIDI input in Settings first",
+        "Abre primero una entrada MIDI en Ajustes",
+    ),
+    ("Forget MIDI mapping", "Olvidar asignación MIDI"),
+    (
+        "Alt+1…9 sets the keyboard velocity. Hold Shift to accent a note.",
+        "Alt+1…9 fija la velocidad del teclado. Mantén Mayús para acentuar una nota.",
+    ),
+    (
+        "FL-style minimal layout · {down}/{up} = octave",
+        "Diseño mínimo al estilo FL · {down}/{up} = octava",
+    ),
+    (
+        "All notes off ({shortcut})",
+        "Apagar todas las notas ({shortcut})",
+    ),
+    (
+        "Use the keyboard for the controls instead of notes: Tab moves between them, arrows nudge sliders, Enter or Space presses and opens ({shortcut})",
+        "Usa el teclado para los controles en lugar de notas: Tab pasa de uno a otro, las flechas mueven los deslizadores, Intro o Espacio pulsa y abre ({shortcut})",
+    ),
+    ("Vel {velocity}", "Vel {velocity}"),
+    ("Audio: {error}", "Audio: {error}"),
+    (
+        "An autosaved session from {minutes} min ago is available.",
+        "Hay una sesión guardada automáticamente hace {minutes} min.",
+    ),
+    ("Range: {min} to {max}", "Rango: de {min} a {max}"),
+    (
+        "Another common progression in {scale}",
+        "Otra progresión habitual en {scale}",
+    ),
+    (
+        "Filter + EQ response (±{range} dB)",
+        "Respuesta de filtro + EQ (±{range} dB)",
+    ),
+    (
+        "Harmonic {number}: {level}%",
+        "Armónico {number}: {level} %",
+    ),
+    ("{count} harmonics", "{count} armónicos"),
+    (
+        "L peak {left_peak} dB, RMS {left_rms} dB\nR peak {right_peak} dB, RMS {right_rms} dB\nClick to reset the clip lamps",
+        "I pico {left_peak} dB, RMS {left_rms} dB\nD pico {right_peak} dB, RMS {right_rms} dB\nHaz clic para apagar los pilotos de saturación",
+    ),
+    (
+        "ITU-R BS.1770 loudness. Momentary {momentary} LUFS (400 ms), short-term over 3 s, integrated since the last reset.",
+        "Sonoridad ITU-R BS.1770. Momentánea {momentary} LUFS (400 ms), a corto plazo sobre 3 s, integrada desde el último reinicio.",
+    ),
+    ("{count} sessions so far", "{count} sesiones hasta ahora"),
+    ("Most played: {list}", "Más tocadas: {list}"),
+    ("Scales: {list}", "Escalas: {list}"),
+    (
+        "Xruns {count} · DSP {load}%",
+        "Xruns {count} · DSP {load} %",
+    ),
+    (
+        "{underruns} under-runs (late callbacks), {overruns} over-runs (rendering slower than real time), {errors} stream errors, {frames} frames per buffer. Details go to the log. Click to clear.",
+        "{underruns} subdesbordamientos (callbacks tardíos), {overruns} desbordamientos (cálculo más lento que el tiempo real), {errors} errores de flujo, {frames} frames por búfer. Los detalles van al registro. Haz clic para borrar.",
+    ),
+    (
+        "Phase correlation {correlation}. Below 0 the sound partly cancels in mono.",
+        "Correlación de fase {correlation}. Por debajo de 0 el sonido se cancela en parte en mono.",
+    ),
+    ("Saved {path}", "Guardado en {path}"),
+    (
+        "Failed to write {path}: {error}",
+        "No se pudo escribir {path}: {error}",
+    ),
+    (
+        "The synth is unavailable",
+        "El sintetizador no está disponible",
+    ),
+    ("Oct 0", "Oct 0"),
+    ("Oct {offset}", "Oct {offset}"),
+    (
+        "You played {notes}: {description}",
+        "Has tocado {notes}: {description}",
+    ),
+    ("Part {number}", "Parte {number}"),
+    (
+        "How much is written to {path}; older logs are kept next to it",
+        "Cuánto se escribe en {path}; los registros anteriores se guardan al lado",
+    ),
+    (
+        "Breath controller (CC {cc})",
+        "Controlador de soplido (CC {cc})",
+    ),
+    ("Hosting on port {port}", "Anfitrión en el puerto {port}"),
+    ("Join {name} ({address})", "Unirse a {name} ({address})"),
+    ("Streak {count}", "Racha {count}"),
+    ("best {count}", "mejor {count}"),
+    (
+        "{correct} of {attempts} right",
+        "{correct} de {attempts} correctas",
+    ),
+    (
+        "Press a computer key for {note}",
+        "Pulsa una tecla del ordenador para {note}",
+    ),
+    ("Exported to {path}", "Exportado a {path}"),
+    ("Imported {path}", "Importado {path}"),
+    ("Correct: {name}", "Correcto: {name}"),
+    ("It was {name}", "Era {name}"),
+    ("Octave down", "Bajar octava"),
+    ("Octave up", "Subir octava"),
+    ("Previous preset", "Preset anterior"),
+    ("Next preset", "Preset siguiente"),
+    ("Panic (all notes off)", "Pánico (apagar todas las notas)"),
+    (
+        "UI focus (navigate controls from the keyboard)",
+        "Foco de la interfaz (manejar los controles con el teclado)",
+    ),
+    ("Record a take", "Iniciar o detener una toma"),
+];
@@ -0,0 +1,483 @@
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::synth::{NoteEvent, SynthShared};
+
+pub const DEFAULT_JAM_PORT: u16 = 47_800;
+/// Remote notes are held back this long by default so network jitter
+/// doesn't smear their timing.
+pub const DEFAULT_JITTER_MS: f32 = 40.0;
+/// First word of every datagram, so stray traffic on the port is ignored.
+const PROTOCOL: &str = "ANGELJAM1";
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+const PEER_TIMEOUT: Duration = Duration::from_secs(5);
+/// Weight of each new ping when smoothing round trip and clock offset.
+const SMOOTHING: f64 = 0.2;
+/// Remote notes due further ahead than this are bogus timestamps and dropped.
+const MAX_SCHEDULE_AHEAD_MS: f64 = 2_000.0;
+
+/// Another instance in the session.
+pub struct Peer {
+    pub name: String,
+    pub addr: SocketAddr,
+    /// Smoothed round trip in milliseconds; `None` until the first pong.
+    pub rtt_ms: Option<f64>,
+    /// Their session clock minus ours, in milliseconds.
+    clock_offset_ms: Option<f64>,
+    last_seen: Instant,
+    /// Whose notes theirs are in `SynthShared`, so they can be let go of
+    /// when this peer leaves.
+    player: u32,
+}
+
+/// A guest the host relays to us. The host tags their notes with `origin`,
+/// its own player number for them.
+struct Relayed {
+    via: SocketAddr,
+    origin: u32,
+    player: u32,
+}
+
+enum Role {
+    /// Owns the port; guests talk only to the host, which relays their notes.
+    Host,
+    Guest {
+        host: SocketAddr,
+    },
+}
+
+/// A LAN jam: local note events go out over UDP with timestamps and remote
+/// ones are played back on the same timeline, delayed by a small jitter buffer.
+pub struct JamSession {
+    socket: UdpSocket,
+    role: Role,
+    name: String,
+    started: Instant,
+    pub peers: Vec<Peer>,
+    pub jitter_ms: f32,
+    /// Remote note changes waiting for their playback time, by player.
+    scheduled: Vec<(Instant, u32, NoteEvent)>,
+    last_ping: Instant,
+    relayed: Vec<Relayed>,
+    /// Players who left since the last poll; their notes still need releasing.
+    departed: Vec<u32>,
+    next_player: u32,
+}
+
+impl JamSession {
+    pub fn host(name: &str, port: u16) -> Result<Self, String> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))
+            .map_err(|err| format!("Could not open port {port}: {err}"))?;
+        Self::with_socket(socket, Role::Host, name)
+    }
+
+    /// Joins the session hosted at `address` ("host:port", or just a host for the default port).
+    pub fn join(name: &str, address: &str) -> Result<Self, String> {
+        let address = address.trim();
+        let with_port = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{address}:{DEFAULT_JAM_PORT}")
+        };
+        let host = with_port
+            .to_socket_addrs()
+            .map_err(|err| format!("Unknown address {address}: {err}"))?
+            .find(SocketAddr::is_ipv4)
+            .ok_or_else(|| format!("Unknown address {address}"))?;
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .map_err(|err| format!("Could not open a socket: {err}"))?;
+        let session = Self::with_socket(socket, Role::Guest { host }, name)?;
+        session.send(host, &format!("hello {}", session.name));
+        Ok(session)
+    }
+
+    fn with_socket(socket: UdpSocket, role: Role, name: &str) -> Result<Self, String> {
+        socket
+            .set_nonblocking(true)
+            .map_err(|err| format!("Could not configure the socket: {err}"))?;
+        let now = Instant::now();
+        Ok(Self {
+            socket,
+            role,
+            name: sanitize_name(name),
+            started: now,
+            peers: Vec::new(),
+            jitter_ms: DEFAULT_JITTER_MS,
+            scheduled: Vec::new(),
+            last_ping: now,
+            relayed: Vec::new(),
+            departed: Vec::new(),
+            next_player: 0,
+        })
+    }
+
+    pub fn is_host(&self) -> bool {
+        matches!(self.role, Role::Host)
+    }
+
+    /// Port others join on when hosting, or our own ephemeral port.
+    pub fn local_port(&self) -> Option<u16> {
+        self.socket.local_addr().ok().map(|addr| addr.port())
+    }
+
+    /// Sends local notes, handles incoming datagrams and plays remote notes
+    /// that are due. Call once per frame.
+    pub fn poll(&mut self, shared: &mut SynthShared) {
+        let now_ms = self.now_ms();
+        for event in shared.take_note_events() {
+            let message = event_message(now_ms, event, None);
+            for peer in &self.peers {
+                self.send(peer.addr, &message);
+            }
+        }
+
+        let mut buf = [0_u8; 512];
+        loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, from)) => {
+                    if let Ok(text) = std::str::from_utf8(&buf[..len]) {
+                        self.handle(text, from);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                // ICMP "port unreachable" from a peer that left shows up here on some systems.
+                Err(_) => break,
+            }
+        }
+
+        if self.last_ping.elapsed() >= PING_INTERVAL {
+            self.last_ping = Instant::now();
+            let message = format!("ping {}", self.now_ms());
+            for peer in &self.peers {
+                self.send(peer.addr, &message);
+            }
+            // Keep knocking until the host answers; the first hello may have been lost.
+            if let (Role::Guest { host }, true) = (&self.role, self.peers.is_empty()) {
+                self.send(*host, &format!("hello {}", self.name));
+            }
+            let vanished: Vec<SocketAddr> = self
+                .peers
+                .iter()
+                .filter(|peer| peer.last_seen.elapsed() >= PEER_TIMEOUT)
+                .map(|peer| peer.addr)
+                .collect();
+            for addr in vanished {
+                self.remove_peer(addr);
+            }
+        }
+
+        // Whoever left, by saying bye or by going quiet, mustn't leave notes hanging.
+        for player in self.departed.drain(..) {
+            shared.release_player_notes(player);
+            self.scheduled.retain(|(_, owner, _)| *owner != player);
+        }
+
+        let now = Instant::now();
+        self.scheduled.sort_by_key(|(due, _, _)| *due);
+        let due = self.scheduled.partition_point(|(due, _, _)| *due <= now);
+        for (_, player, event) in self.scheduled.drain(..due) {
+            match event {
+                NoteEvent::On { note, velocity } => {
+                    shared.press_remote_note(player, note, velocity);
+                }
+                NoteEvent::Off { note } => shared.release_remote_note(player, note),
+            }
+        }
+    }
+
+    /// Says goodbye so the others drop us straight away.
+    pub fn leave(self, shared: &mut SynthShared) {
+        for peer in &self.peers {
+            self.send(peer.addr, "bye");
+        }
+        shared.release_remote_notes();
+    }
+
+    fn handle(&mut self, text: &str, from: SocketAddr) {
+        let Some(body) = text.strip_prefix(PROTOCOL).map(str::trim_start) else {
+            return;
+        };
+        let (command, rest) = body.split_once(' ').unwrap_or((body, ""));
+        match command {
+            "discover" if self.is_host() => self.send(from, &format!("here {}", self.name)),
+            "hello" if self.is_host() => {
+                self.add_peer(from, rest);
+                self.send(from, &format!("welcome {}", self.name));
+            }
+            "welcome" => self.add_peer(from, rest),
+            "bye" => self.remove_peer(from),
+            "gone" => self.handle_gone(from, rest),
+            "ping" => {
+                self.touch(from);
+                self.send(from, &format!("pong {rest} {}", self.now_ms()));
+            }
+            "pong" => self.handle_pong(from, rest),
+            "on" | "off" => self.handle_note(from, body),
+            _ => {}
+        }
+    }
+
+    fn add_peer(&mut self, addr: SocketAddr, name: &str) {
+        let name = sanitize_name(name);
+        match self.peers.iter_mut().find(|peer| peer.addr == addr) {
+            Some(peer) => {
+                peer.name = name;
+                peer.last_seen = Instant::now();
+            }
+            None => {
+                let player = self.new_player();
+                self.peers.push(Peer {
+                    name,
+                    addr,
+                    rtt_ms: None,
+                    clock_offset_ms: None,
+                    last_seen: Instant::now(),
+                    player,
+                });
+            }
+        }
+    }
+
+    fn new_player(&mut self) -> u32 {
+        self.next_player = self.next_player.wrapping_add(1);
+        self.next_player
+    }
+
+    /// Drops the peer at `addr`, and any guests we heard through them, and
+    /// queues their notes for release. A host tells the remaining guests,
+    /// who only know the leaver through the host.
+    fn remove_peer(&mut self, addr: SocketAddr) {
+        let Some(index) = self.peers.iter().position(|peer| peer.addr == addr) else {
+            return;
+        };
+        let peer = self.peers.remove(index);
+        self.departed.push(peer.player);
+        self.relayed.retain(|relayed| {
+            if relayed.via != addr {
+                return true;
+            }
+            self.departed.push(relayed.player);
+            false
+        });
+        if self.is_host() {
+            let message = format!("gone {}", peer.player);
+            for other in &self.peers {
+                self.send(other.addr, &message);
+            }
+        }
+    }
+
+    /// "gone <origin>": the host lost a guest it was relaying to us.
+    fn handle_gone(&mut self, from: SocketAddr, rest: &str) {
+        let Ok(origin) = rest.trim().parse::<u32>() else {
+            return;
+        };
+        self.relayed.retain(|relayed| {
+            if relayed.via != from || relayed.origin != origin {
+                return true;
+            }
+            self.departed.push(relayed.player);
+            false
+        });
+    }
+
+    /// Our player number for the guest the host at `via` calls `origin`.
+    fn relayed_player(&mut self, via: SocketAddr, origin: u32) -> u32 {
+        if let Some(relayed) = self
+            .relayed
+            .iter()
+            .find(|relayed| relayed.via == via && relayed.origin == origin)
+        {
+            return relayed.player;
+        }
+        let player = self.new_player();
+        self.relayed.push(Relayed {
+            via,
+            origin,
+            player,
+        });
+        player
+    }
+
+    fn touch(&mut self, addr: SocketAddr) {
+        if let Some(peer) = self.peers.iter_mut().find(|peer| peer.addr == addr) {
+            peer.last_seen = Instant::now();
+        }
+    }
+
+    /// "pong <our send time> <their clock>": round trip plus an estimate of
+    /// their clock relative to ours, assuming the trip is symmetric.
+    fn handle_pong(&mut self, from: SocketAddr, rest: &str) {
+        let mut fields = rest.split(' ').map(str::parse::<f64>);
+        let (Some(Ok(sent)), Some(Ok(theirs))) = (fields.next(), fields.next()) else {
+            return;
+        };
+        if !sent.is_finite() || !theirs.is_finite() {
+            return;
+        }
+        let now = self.now_ms();
+        let Some(peer) = self.peers.iter_mut().find(|peer| peer.addr == from) else {
+            return;
+        };
+        peer.last_seen = Instant::now();
+        let rtt = (now - sent).max(0.0);
+        let offset = theirs + rtt / 2.0 - now;
+        let smooth =
+            |old: Option<f64>, new: f64| Some(old.map_or(new, |old| old + (new - old) * SMOOTHING));
+        peer.rtt_ms = smooth(peer.rtt_ms, rtt);
+        peer.clock_offset_ms = smooth(peer.clock_offset_ms, offset);
+    }
+
+    fn handle_note(&mut self, from: SocketAddr, body: &str) {
+        let Some((sent_ms, event, origin)) = parse_event(body) else {
+            return;
+        };
+        let now_ms = self.now_ms();
+        let Some(peer) = self.peers.iter_mut().find(|peer| peer.addr == from) else {
+            return;
+        };
+        peer.last_seen = Instant::now();
+        // Until the first pong the clocks can't be lined up; play on arrival.
+        let local_ms = peer
+            .clock_offset_ms
+            .map_or(now_ms, |offset| sent_ms - offset);
+        let sender = peer.player;
+        // Only the host relays, so only notes from the host carry an origin.
+        let player = match (origin, &self.role) {
+            (Some(origin), Role::Guest { host }) if *host == from => {
+                self.relayed_player(from, origin)
+            }
+            _ => sender,
+        };
+        let due_ms = local_ms + self.jitter_ms as f64;
+        // Late notes play at once; ones claiming to be far in the future are dropped.
+        if due_ms - now_ms > MAX_SCHEDULE_AHEAD_MS {
+            return;
+        }
+        let Some(due) = Duration::try_from_secs_f64(due_ms.max(0.0) / 1000.0)
+            .ok()
+            .and_then(|offset| self.started.checked_add(offset))
+        else {
+            return;
+        };
+        self.scheduled
+            .push((due.max(Instant::now()), player, event));
+
+        // The host relays guests' notes to the other guests, restamped on its
+        // own clock and tagged with who played them.
+        if self.is_host() {
+            let message = event_message(local_ms, event, Some(sender));
+            for peer in self.peers.iter().filter(|peer| peer.addr != from) {
+                self.send(peer.addr, &message);
+            }
+        }
+    }
+
+    fn send(&self, to: SocketAddr, message: &str) {
+        // UDP is best effort; a lost note-off is covered by the peer timeout.
+        let _ = self
+            .socket
+            .send_to(format!("{PROTOCOL} {message}").as_bytes(), to);
+    }
+
+    fn now_ms(&self) -> f64 {
+        self.started.elapsed().as_secs_f64() * 1000.0
+    }
+}
+
+/// Finds sessions hosted on the local network by broadcasting a discover
+/// message; hosts answer with their name.
+pub struct JamLobby {
+    socket: UdpSocket,
+    pub found: Vec<(String, SocketAddr)>,
+}
+
+impl JamLobby {
+    pub fn search(port: u16) -> Result<Self, String> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .and_then(|socket| {
+                socket.set_broadcast(true)?;
+                socket.set_nonblocking(true)?;
+                Ok(socket)
+            })
+            .map_err(|err| format!("Could not search the network: {err}"))?;
+        socket
+            .send_to(
+                format!("{PROTOCOL} discover").as_bytes(),
+                (Ipv4Addr::BROADCAST, port),
+            )
+            .map_err(|err| format!("Could not search the network: {err}"))?;
+        Ok(Self {
+            socket,
+            found: Vec::new(),
+        })
+    }
+
+    pub fn poll(&mut self) {
+        let mut buf = [0_u8; 512];
+        while let Ok((len, from)) = self.socket.recv_from(&mut buf) {
+            let Ok(text) = std::str::from_utf8(&buf[..len]) else {
+                continue;
+            };
+            let Some(name) = text
+                .strip_prefix(PROTOCOL)
+                .and_then(|body| body.trim_start().strip_prefix("here "))
+            else {
+                continue;
+            };
+            if !self.found.iter().any(|(_, addr)| *addr == from) {
+                self.found.push((sanitize_name(name), from));
+            }
+        }
+    }
+}
+
+fn event_message(time_ms: f64, event: NoteEvent, origin: Option<u32>) -> String {
+    let message = match event {
+        NoteEvent::On { note, velocity } => format!("on {time_ms:.1} {note} {velocity:.3}"),
+        NoteEvent::Off { note } => format!("off {time_ms:.1} {note}"),
+    };
+    match origin {
+        Some(origin) => format!("{message} {origin}"),
+        None => message,
+    }
+}
+
+/// Parses "on <ms> <note> <velocity>" or "off <ms> <note>", each optionally
+/// followed by the relayed player's origin. Anything a peer could use to
+/// poison the timeline (NaN, infinities) is rejected.
+fn parse_event(body: &str) -> Option<(f64, NoteEvent, Option<u32>)> {
+    let mut fields = body.split(' ');
+    let command = fields.next()?;
+    let time_ms = fields
+        .next()?
+        .parse::<f64>()
+        .ok()
+        .filter(|ms| ms.is_finite())?;
+    let note = fields.next()?.parse::<u8>().ok()?.min(127);
+    let event = match command {
+        "on" => NoteEvent::On {
+            note,
+            velocity: fields
+                .next()?
+                .parse::<f32>()
+                .ok()
+                .filter(|velocity| velocity.is_finite())?
+                .clamp(0.0, 1.0),
+        },
+        "off" => NoteEvent::Off { note },
+        _ => return None,
+    };
+    let origin = fields.next().and_then(|origin| origin.parse::<u32>().ok());
+    Some((time_ms, event, origin))
+}
+
+/// Names travel as the tail of a line, so keep them short and single-line.
+fn sanitize_name(name: &str) -> String {
+    let name: String = name.chars().filter(|c| !c.is_control()).take(32).collect();
+    match name.trim() {
+        "" => "Player".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
@@ -0,0 +1,197 @@
+use egui::{Key, Modifiers};
+
+use crate::settings::KeybindScheme;
+
+/// Something a shortcut can do besides playing a note.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    OctaveDown,
+    OctaveUp,
+    PresetPrev,
+    PresetNext,
+    Panic,
+    ToggleSettings,
+    ResetSound,
+    ToggleUiFocus,
+    ToggleRecord,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::OctaveDown,
+        Action::OctaveUp,
+        Action::PresetPrev,
+        Action::PresetNext,
+        Action::Panic,
+        Action::ToggleSettings,
+        Action::ResetSound,
+        Action::ToggleUiFocus,
+        Action::ToggleRecord,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::OctaveDown => "Octave down",
+            Action::OctaveUp => "Octave up",
+            Action::PresetPrev => "Previous preset",
+            Action::PresetNext => "Next preset",
+            Action::Panic => "Panic (all notes off)",
+            Action::ToggleSettings => "Settings",
+            Action::ResetSound => "Reset sound",
+            Action::ToggleUiFocus => "UI focus (navigate controls from the keyboard)",
+            Action::ToggleRecord => "Record a take",
+        }
+    }
+}
+
+/// A key plus the modifiers that must be held. `command` is Ctrl, or Cmd on macOS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chord {
+    pub key: Key,
+    pub command: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl Chord {
+    const fn plain(key: Key) -> Self {
+        Self {
+            key,
+            command: false,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    const fn command(key: Key) -> Self {
+        Self {
+            key,
+            command: true,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    const fn alt(key: Key) -> Self {
+        Self {
+            key,
+            command: false,
+            alt: true,
+            shift: false,
+        }
+    }
+
+    pub fn matches(&self, key: Key, modifiers: Modifiers) -> bool {
+        self.key == key
+            && self.command == modifiers.command
+            && self.alt == modifiers.alt
+            && self.shift == modifiers.shift
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        if self.command {
+            label.push_str("Ctrl+");
+        }
+        if self.alt {
+            label.push_str("Alt+");
+        }
+        if self.shift {
+            label.push_str("Shift+");
+        }
+        label.push_str(self.key.name());
+        label
+    }
+}
+
+const DEFAULT_BINDINGS: &[(Chord, Action)] = &[
+    (Chord::plain(Key::ArrowLeft), Action::OctaveDown),
+    (Chord::plain(Key::ArrowRight), Action::OctaveUp),
+    (Chord::plain(Key::PageUp), Action::PresetPrev),
+    (Chord::plain(Key::PageDown), Action::PresetNext),
+    (Chord::plain(Key::Escape), Action::Panic),
+    (Chord::plain(Key::F1), Action::ToggleSettings),
+    (Chord::plain(Key::F5), Action::ResetSound),
+    (Chord::plain(Key::F6), Action::ToggleUiFocus),
+    (Chord::plain(Key::F9), Action::ToggleRecord),
+];
+
+const VIM_BINDINGS: &[(Chord, Action)] = &[
+    (Chord::command(Key::H), Action::OctaveDown),
+    (Chord::command(Key::L), Action::OctaveUp),
+    (Chord::command(Key::K), Action::PresetPrev),
+    (Chord::command(Key::J), Action::PresetNext),
+    (Chord::plain(Key::Escape), Action::Panic),
+    (Chord::command(Key::O), Action::ToggleSettings),
+    (Chord::command(Key::U), Action::ResetSound),
+    (Chord::plain(Key::F6), Action::ToggleUiFocus),
+    (Chord::command(Key::Q), Action::ToggleRecord),
+];
+
+const EMACS_BINDINGS: &[(Chord, Action)] = &[
+    (Chord::command(Key::B), Action::OctaveDown),
+    (Chord::command(Key::F), Action::OctaveUp),
+    (Chord::command(Key::P), Action::PresetPrev),
+    (Chord::command(Key::N), Action::PresetNext),
+    (Chord::command(Key::G), Action::Panic),
+    (Chord::alt(Key::X), Action::ToggleSettings),
+    (Chord::alt(Key::R), Action::ResetSound),
+    (Chord::plain(Key::F6), Action::ToggleUiFocus),
+    (Chord::alt(Key::Q), Action::ToggleRecord),
+];
+
+const SUBLIME_BINDINGS: &[(Chord, Action)] = &[
+    (Chord::command(Key::OpenBracket), Action::OctaveDown),
+    (Chord::command(Key::CloseBracket), Action::OctaveUp),
+    (Chord::command(Key::PageUp), Action::PresetPrev),
+    (Chord::command(Key::PageDown), Action::PresetNext),
+    (Chord::plain(Key::Escape), Action::Panic),
+    (Chord::command(Key::Comma), Action::ToggleSettings),
+    (Chord::command(Key::R), Action::ResetSound),
+    (Chord::plain(Key::F6), Action::ToggleUiFocus),
+    (Chord::command(Key::Q), Action::ToggleRecord),
+];
+
+const VSCODE_BINDINGS: &[(Chord, Action)] = &[
+    (Chord::alt(Key::ArrowLeft), Action::OctaveDown),
+    (Chord::alt(Key::ArrowRight), Action::OctaveUp),
+    (Chord::command(Key::PageUp), Action::PresetPrev),
+    (Chord::command(Key::PageDown), Action::PresetNext),
+    (Chord::plain(Key::Escape), Action::Panic),
+    (Chord::command(Key::Comma), Action::ToggleSettings),
+    (Chord::command(Key::R), Action::ResetSound),
+    (Chord::plain(Key::F6), Action::ToggleUiFocus),
+    (Chord::alt(Key::R), Action::ToggleRecord),
+];
+
+pub fn bindings(scheme: KeybindScheme) -> &'static [(Chord, Action)] {
+    match scheme {
+        KeybindScheme::Default => DEFAULT_BINDINGS,
+        KeybindScheme::Vim => VIM_BINDINGS,
+        KeybindScheme::Emacs => EMACS_BINDINGS,
+        KeybindScheme::Sublime => SUBLIME_BINDINGS,
+        KeybindScheme::VSCode => VSCODE_BINDINGS,
+    }
+}
+
+pub fn action_for(scheme: KeybindScheme, key: Key, modifiers: Modifiers) -> Option<Action> {
+    bindings(scheme)
+        .iter()
+        .find(|(chord, _)| chord.matches(key, modifiers))
+        .map(|(_, action)| *action)
+}
+
+/// Whether `scheme` has shortcuts on Ctrl (Cmd on macOS). Those schemes
+/// leave Ctrl alone when playing, so it doesn't also mean a soft note.
+pub fn uses_command(scheme: KeybindScheme) -> bool {
+    bindings(scheme).iter().any(|(chord, _)| chord.command)
+}
+
+/// Human-readable shortcut for `action`, e.g. "Ctrl+H".
+pub fn chord_label(scheme: KeybindScheme, action: Action) -> String {
+    bindings(scheme)
+        .iter()
+        .find(|(_, candidate)| *candidate == action)
+        .map(|(chord, _)| chord.label())
+        .unwrap_or_default()
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The usual two-row tracker layout: Z–M play the octave from C3 with the
+/// sharps on the home row, Q–P continue from C4 with the sharps on the number row.
+const QWERTY_PIANO: &[(&str, u8)] = &[
+    ("Z", 48),
+    ("S", 49),
+    ("X", 50),
+    ("D", 51),
+    ("C", 52),
+    ("V", 53),
+    ("G", 54),
+    ("B", 55),
+    ("H", 56),
+    ("N", 57),
+    ("J", 58),
+    ("M", 59),
+    ("Q", 60),
+    ("2", 61),
+    ("W", 62),
+    ("3", 63),
+    ("E", 64),
+    ("R", 65),
+    ("5", 66),
+    ("T", 67),
+    ("6", 68),
+    ("Y", 69),
+    ("7", 70),
+    ("U", 71),
+    ("I", 72),
+    ("9", 73),
+    ("O", 74),
+    ("0", 75),
+    ("P", 76),
+];
+
+/// Note for `key` in the built-in QWERTY piano layout, before the octave offset.
+pub fn qwerty_note(key: &str) -> Option<u8> {
+    QWERTY_PIANO
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, note)| *note)
+}
+
+/// Every key of the built-in QWERTY piano layout with its note.
+pub fn qwerty_bindings() -> impl Iterator<Item = (&'static str, u8)> {
+    QWERTY_PIANO.iter().copied()
+}
+
+/// Lowest and highest note of the built-in QWERTY piano layout.
+pub fn qwerty_range() -> (u8, u8) {
+    let notes = QWERTY_PIANO.iter().map(|(_, note)| *note);
+    (notes.clone().min().unwrap_or(0), notes.max().unwrap_or(0))
+}
+
+/// A named assignment of computer keys to notes. Keys are stored by their
+/// `egui::Key::name()` so the file stays readable and layout-independent.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keymap {
+    pub name: String,
+    pub bindings: BTreeMap<String, u8>,
+}
+
+impl Keymap {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    /// A keymap seeded with the built-in QWERTY piano layout.
+    pub fn qwerty(name: impl Into<String>) -> Self {
+        let mut keymap = Self::new(name);
+        for (key, note) in QWERTY_PIANO {
+            keymap.bind(key, *note);
+        }
+        keymap
+    }
+
+    /// Note for `key` before the octave offset is applied.
+    pub fn note_for(&self, key: &str) -> Option<u8> {
+        self.bindings.get(key).copied()
+    }
+
+    pub fn bind(&mut self, key: &str, note: u8) {
+        self.bindings.insert(key.to_string(), note.min(127));
+    }
+
+    pub fn unbind(&mut self, key: &str) {
+        self.bindings.remove(key);
+    }
+
+    /// Lowest and highest bound note, or `None` for an empty keymap.
+    pub fn note_range(&self) -> Option<(u8, u8)> {
+        let notes = self.bindings.values().copied();
+        Some((notes.clone().min()?, notes.max()?))
+    }
+
+    /// Bindings ordered by note, for display.
+    pub fn sorted_by_note(&self) -> Vec<(&str, u8)> {
+        let mut entries: Vec<(&str, u8)> = self
+            .bindings
+            .iter()
+            .map(|(key, note)| (key.as_str(), *note))
+            .collect();
+        entries.sort_by_key(|(key, note)| (*note, *key));
+        entries
+    }
+}
@@ -0,0 +1,227 @@
+mod additive;
+mod audio;
+mod chords;
+mod controllers;
+mod diagnostics;
+mod drums;
+mod ear;
+mod effects;
+mod files;
+mod gamepad;
+mod granular;
+mod i18n;
+mod jam;
+mod keybinds;
+mod keymap;
+mod limiter;
+mod logging;
+mod meters;
+mod mic;
+mod midi;
+mod params;
+mod png;
+mod presets;
+mod progressions;
+mod sampler;
+mod scales;
+mod scope;
+mod session;
+mod settings;
+mod soundfont;
+mod stats;
+mod synth;
+mod theory;
+mod tuning;
+mod ui;
+mod watchdog;
+mod wav;
+mod wavetable;
+
+#[cfg(test)]
+mod golden;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use audio::SynthAudio;
+use mic::MicBuffer;
+use sampler::SampleCache;
+use scope::{SCOPE_CAPACITY, ScopeBuffer};
+use settings::{AppSettings, config_dir, default_settings_path};
+use soundfont::SoundFontCache;
+use synth::SynthShared;
+use ui::SynthApp;
+use wav::encode_stereo_pcm16;
+use wavetable::WavetableCache;
+
+/// The offline renderer, for benchmarks and scripts: a patch, timed note
+/// changes and a length in, interleaved stereo samples out, the same on
+/// every run.
+pub use synth::{NoteEvent, SynthParams, render};
+
+const RENDER_SAMPLE_RATE: u32 = 48_000;
+/// How long `--render` holds its notes before letting them ring out.
+const RENDER_HOLD_SECONDS: f32 = 1.5;
+
+/// Entry point on desktops and iOS. `Angel --render out.wav [note…]`
+/// bounces notes with the startup patch and exits; anything else opens the
+/// window.
+pub fn run() -> eframe::Result<()> {
+    let (settings_path, settings) = load_settings();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [flag, path, notes @ ..] = args.as_slice()
+        && flag == "--render"
+    {
+        if let Err(err) = render_to_file(&settings.params, Path::new(path), notes) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    start(settings_path, settings, eframe::NativeOptions::default())
+}
+
+/// Android entry point, called by the native activity once the app's
+/// process is up. Settings and logs live in the app's internal storage.
+#[cfg(target_os = "android")]
+#[unsafe(no_mangle)]
+fn android_main(app: winit::platform::android::activity::AndroidApp) {
+    use winit::platform::android::EventLoopBuilderExtAndroid;
+
+    if let Some(internal) = app.internal_data_path() {
+        settings::set_android_storage(internal, app.external_data_path());
+    }
+    let (settings_path, settings) = load_settings();
+    let options = eframe::NativeOptions {
+        event_loop_builder: Some(Box::new(move |builder| {
+            builder.with_android_app(app);
+        })),
+        ..Default::default()
+    };
+    if let Err(err) = start(settings_path, settings, options) {
+        log::error!("Angel stopped: {err}");
+    }
+}
+
+/// Loads the settings and starts logging next to them. On phones and
+/// tablets a first launch starts in the touch layout.
+fn load_settings() -> (PathBuf, AppSettings) {
+    let settings_path = default_settings_path();
+    let first_launch = !settings_path.exists();
+    let mut settings = AppSettings::load(&settings_path);
+    logging::init(&config_dir(), settings.log_level);
+    settings.params = settings.startup_params();
+    if first_launch && cfg!(any(target_os = "android", target_os = "ios")) {
+        settings.touch_layout = true;
+    }
+    (settings_path, settings)
+}
+
+/// Opens the audio output and runs the app window with `options`.
+fn start(
+    settings_path: PathBuf,
+    settings: AppSettings,
+    mut options: eframe::NativeOptions,
+) -> eframe::Result<()> {
+    let shared = Arc::new(Mutex::new(SynthShared::new_with_params(
+        settings.params.clone(),
+    )));
+    let scope = Arc::new(ScopeBuffer::new(SCOPE_CAPACITY));
+    let mic = Arc::new(Mutex::new(MicBuffer::default()));
+    let audio = SynthAudio::new_with_device(
+        Arc::clone(&shared),
+        Arc::clone(&scope),
+        Arc::clone(&mic),
+        settings.output_device.as_deref(),
+    )
+    .or_else(|_| SynthAudio::new(Arc::clone(&shared), Arc::clone(&scope), Arc::clone(&mic)))
+    .expect("Failed to initialize audio output. Is an output device available?");
+
+    if settings.always_on_top {
+        options.viewport = options.viewport.with_always_on_top();
+    }
+    eframe::run_native(
+        "Angel Piano",
+        options,
+        Box::new(move |cc| {
+            Box::new(SynthApp::new(
+                cc,
+                Arc::clone(&shared),
+                audio.clone(),
+                Arc::clone(&scope),
+                Arc::clone(&mic),
+                settings_path.clone(),
+                settings.clone(),
+            ))
+        }),
+    )
+}
+
+/// Renders MIDI `notes` (a C major triad if none are given) offline to a WAV.
+fn render_to_file(params: &SynthParams, path: &Path, notes: &[String]) -> Result<(), String> {
+    let notes: Vec<u8> = if notes.is_empty() {
+        vec![60, 64, 67]
+    } else {
+        notes
+            .iter()
+            .map(|note| {
+                note.parse::<u8>()
+                    .ok()
+                    .filter(|note| *note < 128)
+                    .ok_or_else(|| format!("Not a MIDI note number: {note}"))
+            })
+            .collect::<Result<_, _>>()?
+    };
+    let presses = notes.iter().map(|&note| {
+        let velocity = 0.8;
+        (0.0, NoteEvent::On { note, velocity })
+    });
+    let releases = notes
+        .iter()
+        .map(|&note| (RENDER_HOLD_SECONDS, NoteEvent::Off { note }));
+    let events: Vec<(f32, NoteEvent)> = presses.chain(releases).collect();
+    let length = RENDER_HOLD_SECONDS + params.release_seconds + 0.1;
+    let mut params = params.clone();
+    resolve_files(&mut params)?;
+    let frames: Vec<[f32; 2]> = render(&params, &events, RENDER_SAMPLE_RATE, length)
+        .chunks_exact(2)
+        .map(|frame| [frame[0], frame[1]])
+        .collect();
+    fs::write(path, encode_stereo_pcm16(&frames, RENDER_SAMPLE_RATE))
+        .map_err(|err| format!("Could not write {}: {err}", path.display()))
+}
+
+/// Loads the wavetable, samples and SoundFont `params` names, failing on
+/// the first that can't be read.
+fn resolve_files(params: &mut SynthParams) -> Result<(), String> {
+    let mut wavetables = WavetableCache::default();
+    let mut samples = SampleCache::default();
+    let mut soundfonts = SoundFontCache::default();
+    wavetables.resolve(params);
+    samples.resolve(params);
+    soundfonts.resolve(params);
+    // The caches read on worker threads; wait for them, then attach.
+    wavetables.wait();
+    samples.wait();
+    soundfonts.wait();
+    wavetables.resolve(params);
+    samples.resolve(params);
+    soundfonts.resolve(params);
+    let pads = params.drum_kit.0.iter().map(|pad| pad.sample.path());
+    let error = [
+        wavetables.error(params.wavetable.path()),
+        samples.error(params.sample.path()),
+        soundfonts.error(params.soundfont.path()),
+    ]
+    .into_iter()
+    .chain(pads.map(|path| samples.error(path)))
+    .flatten()
+    .next();
+    match error {
+        Some(err) => Err(err.to_string()),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,101 @@
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+/// Ceilings the limiter can be set to, in dBFS.
+pub const CEILING_RANGE_DB: RangeInclusive<f32> = -12.0..=0.0;
+/// How far ahead the limiter looks, so it is already down by the time a
+/// peak arrives instead of clipping its front.
+const LOOKAHEAD_SECONDS: f32 = 0.0015;
+/// Time the gain takes to recover by about two thirds once peaks pass.
+const RELEASE_SECONDS: f32 = 0.08;
+
+/// The brick-wall limiter at the very end of the mix, saved with the
+/// mixer.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimiterSettings {
+    pub enabled: bool,
+    /// No output sample goes above this, in dBFS.
+    pub ceiling_db: f32,
+}
+
+impl Default for LimiterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ceiling_db: -1.0,
+        }
+    }
+}
+
+/// A stereo-linked lookahead peak limiter. The output is delayed by the
+/// lookahead whether or not it is enabled, so switching it doesn't jump.
+pub struct Limiter {
+    /// The last lookahead's worth of input.
+    delay: Vec<[f32; 2]>,
+    /// Gain each of the last lookahead + 1 frames needs to stay under the
+    /// ceiling.
+    needed: Vec<f32>,
+    /// The lowest needed gain, released slowly, over the last lookahead;
+    /// their average is the gain applied.
+    held: Vec<f32>,
+    index: usize,
+    release: f32,
+    /// Lowest gain applied since `take_gain_reduction_db`.
+    deepest: f32,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: f32) -> Self {
+        let length = ((LOOKAHEAD_SECONDS * sample_rate) as usize).max(1);
+        Self {
+            delay: vec![[0.0; 2]; length],
+            needed: vec![1.0; length + 1],
+            held: vec![1.0; length],
+            index: 0,
+            release: 1.0 - (-1.0 / (RELEASE_SECONDS * sample_rate)).exp(),
+            deepest: 1.0,
+        }
+    }
+
+    /// Takes one frame in and gives back the one a lookahead earlier,
+    /// turned down as far as keeps it under the ceiling.
+    pub fn process(&mut self, frame: [f32; 2], settings: &LimiterSettings) -> [f32; 2] {
+        let peak = frame[0].abs().max(frame[1].abs());
+        let ceiling = 10_f32.powf(settings.ceiling_db.min(0.0) / 20.0);
+        let needed = if settings.enabled && peak > ceiling {
+            ceiling / peak
+        } else {
+            1.0
+        };
+        let length = self.delay.len();
+        self.needed[self.index % (length + 1)] = needed;
+        // Every frame still in the delay holds the gain down to what it
+        // needs; from there the gain only rises at the release rate.
+        let lowest = self.needed.iter().copied().fold(1.0, f32::min);
+        let previous = self.held[(self.index + length - 1) % length];
+        let held = if lowest < previous {
+            lowest
+        } else {
+            previous + (lowest - previous) * self.release
+        };
+        self.held[self.index % length] = held;
+        // Averaging over the lookahead ramps the gain down smoothly, and
+        // reaches each peak's gain just as that peak leaves the delay.
+        let gain = self.held.iter().sum::<f32>() / length as f32;
+        self.deepest = self.deepest.min(gain);
+
+        let out = self.delay[self.index % length];
+        self.delay[self.index % length] = frame;
+        self.index = (self.index + 1) % (length * (length + 1));
+        out.map(|side| side * gain)
+    }
+
+    /// Most the output was turned down since the last call, in dB (0 or
+    /// less).
+    pub fn take_gain_reduction_db(&mut self) -> f32 {
+        let deepest = std::mem::replace(&mut self.deepest, 1.0);
+        20.0 * deepest.max(1e-6).log10()
+    }
+}
@@ -0,0 +1,131 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use log::LevelFilter;
+
+use crate::settings::LogLevel;
+
+const LOG_FILE_NAME: &str = "angel.log";
+/// The log is rotated once it grows past this size.
+const MAX_LOG_BYTES: u64 = 1 << 20;
+/// Rotated logs kept next to the current one: `angel.1.log` is the newest.
+const KEPT_LOGS: usize = 3;
+
+pub fn log_path(dir: &Path) -> PathBuf {
+    dir.join(LOG_FILE_NAME)
+}
+
+/// Sends log records to stderr, as env_logger did on its own, and to a
+/// rotating file in `dir` so there is something to attach to a bug report
+/// after the console is gone. `RUST_LOG` can still narrow what is logged;
+/// `level` is the ceiling and can be changed later with `set_level`.
+pub fn init(dir: &Path, level: LogLevel) {
+    let file = match RotatingFile::open(dir) {
+        Ok(file) => Some(file),
+        Err(err) => {
+            eprintln!("Logging to stderr only: {err}");
+            None
+        }
+    };
+    let logger = env_logger::Builder::new()
+        .filter_level(LevelFilter::Trace)
+        .parse_default_env()
+        .target(env_logger::Target::Pipe(Box::new(Tee { file })))
+        .build();
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        set_level(level);
+    }
+}
+
+pub fn set_level(level: LogLevel) {
+    log::set_max_level(match level {
+        LogLevel::Errors => LevelFilter::Error,
+        LogLevel::Warnings => LevelFilter::Warn,
+        LogLevel::Info => LevelFilter::Info,
+        LogLevel::Debug => LevelFilter::Debug,
+    });
+}
+
+/// Writes every record to stderr and, when it could be opened, the log file.
+struct Tee {
+    file: Option<RotatingFile>,
+}
+
+impl Write for Tee {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A full disk shouldn't take stderr logging down with it.
+        if let Some(file) = &mut self.file {
+            let _ = file.write_all(buf);
+        }
+        io::stderr().write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            let _ = file.flush();
+        }
+        io::stderr().flush()
+    }
+}
+
+/// `angel.log`, moved to `angel.1.log` (and so on up to `KEPT_LOGS`) once it
+/// reaches `MAX_LOG_BYTES`.
+struct RotatingFile {
+    dir: PathBuf,
+    /// `None` only if reopening after a rotation failed.
+    file: Option<File>,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(dir)
+            .map_err(|err| format!("Failed to create {}: {err}", dir.display()))?;
+        let path = log_path(dir);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|err| format!("Failed to open {}: {err}", path.display()))?;
+        let written = file.metadata().map_or(0, |metadata| metadata.len());
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file: Some(file),
+            written,
+        })
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        if self.written > 0 && self.written + buf.len() as u64 > MAX_LOG_BYTES {
+            self.rotate()?;
+        }
+        let file = self
+            .file
+            .as_mut()
+            .ok_or_else(|| io::Error::other("log file closed"))?;
+        file.write_all(buf)?;
+        self.written += buf.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().map_or(Ok(()), File::flush)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated = |index: usize| self.dir.join(format!("angel.{index}.log"));
+        let _ = fs::remove_file(rotated(KEPT_LOGS));
+        for index in (1..KEPT_LOGS).rev() {
+            let _ = fs::rename(rotated(index), rotated(index + 1));
+        }
+        // Windows won't rename a file that is still open. If the rename
+        // fails anyway, starting over still keeps the log bounded.
+        self.file = None;
+        let _ = fs::rename(log_path(&self.dir), rotated(1));
+        self.file = Some(File::create(log_path(&self.dir))?);
+        self.written = 0;
+        Ok(())
+    }
+}
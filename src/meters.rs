@@ -0,0 +1,351 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// How much of each new block's reading is blended into the displayed value.
+const CORRELATION_SMOOTHING: f32 = 0.2;
+/// Peak hold falls back at this rate once the signal drops.
+const PEAK_FALLOFF_DB_PER_SECOND: f32 = 20.0;
+/// Averaging time of the RMS reading, roughly a VU meter's integration time.
+const RMS_WINDOW_SECONDS: f32 = 0.3;
+
+/// BS.1770 hop between overlapping 400 ms gating blocks.
+const LOUDNESS_HOP_SECONDS: f32 = 0.1;
+/// Hops per momentary block (400 ms) and per short-term window (3 s).
+const MOMENTARY_HOPS: usize = 4;
+const SHORT_TERM_HOPS: usize = 30;
+/// Blocks quieter than this never count towards integrated loudness.
+const ABSOLUTE_GATE_LUFS: f32 = -70.0;
+const RELATIVE_GATE_LU: f32 = -10.0;
+/// Integrated loudness keeps a histogram of block loudness in 0.1 LU bins
+/// instead of every block, so memory stays flat over long sessions.
+const HISTOGRAM_TOP_LUFS: f32 = 10.0;
+const HISTOGRAM_BINS_PER_LU: f32 = 10.0;
+/// Reported when there is nothing to measure yet.
+pub const SILENT_LUFS: f32 = f32::NEG_INFINITY;
+
+/// Output measurements written by the audio callback and read by the UI.
+/// Values are stored as `f32` bits in atomics so neither side ever blocks.
+pub struct OutputMeters {
+    sample_rate: f32,
+    correlation: AtomicU32,
+    peak: [AtomicU32; 2],
+    /// Mean square, so averaging stays linear; the UI takes the root.
+    mean_square: [AtomicU32; 2],
+    clipped: [AtomicBool; 2],
+    /// Deepest recent limiting, in dB (0 or less).
+    gain_reduction_db: AtomicU32,
+    momentary_lufs: AtomicU32,
+    short_term_lufs: AtomicU32,
+    integrated_lufs: AtomicU32,
+    loudness_reset: AtomicBool,
+}
+
+impl OutputMeters {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            correlation: AtomicU32::new(0),
+            peak: Default::default(),
+            mean_square: Default::default(),
+            clipped: Default::default(),
+            gain_reduction_db: AtomicU32::new(0),
+            momentary_lufs: AtomicU32::new(SILENT_LUFS.to_bits()),
+            short_term_lufs: AtomicU32::new(SILENT_LUFS.to_bits()),
+            integrated_lufs: AtomicU32::new(SILENT_LUFS.to_bits()),
+            loudness_reset: AtomicBool::new(false),
+        }
+    }
+
+    /// Folds one callback's worth of `[left, right]` frames into the meters.
+    pub fn record(&self, frames: &[[f32; 2]]) {
+        if frames.is_empty() {
+            return;
+        }
+        let (mut lr, mut ll, mut rr) = (0.0_f32, 0.0_f32, 0.0_f32);
+        let mut block_peak = [0.0_f32; 2];
+        for [left, right] in frames {
+            lr += left * right;
+            ll += left * left;
+            rr += right * right;
+            block_peak[0] = block_peak[0].max(left.abs());
+            block_peak[1] = block_peak[1].max(right.abs());
+        }
+        let energy = (ll * rr).sqrt();
+        // Silence says nothing about phase; hold the last reading.
+        if energy > 1e-9 {
+            let block = (lr / energy).clamp(-1.0, 1.0);
+            let previous = load(&self.correlation);
+            store(
+                &self.correlation,
+                previous + (block - previous) * CORRELATION_SMOOTHING,
+            );
+        }
+
+        let seconds = frames.len() as f32 / self.sample_rate;
+        let falloff = 10_f32.powf(-PEAK_FALLOFF_DB_PER_SECOND * seconds / 20.0);
+        let rms_blend = 1.0 - (-seconds / RMS_WINDOW_SECONDS).exp();
+        let block_mean_square = [ll, rr].map(|sum| sum / frames.len() as f32);
+        for channel in 0..2 {
+            let peak = (load(&self.peak[channel]) * falloff).max(block_peak[channel]);
+            store(&self.peak[channel], peak);
+            let previous = load(&self.mean_square[channel]);
+            let mean_square = previous + (block_mean_square[channel] - previous) * rms_blend;
+            store(&self.mean_square[channel], mean_square);
+            if block_peak[channel] >= 1.0 {
+                self.clipped[channel].store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Phase correlation between the channels: +1 mono-compatible, 0 unrelated,
+    /// -1 cancels when summed to mono.
+    pub fn correlation(&self) -> f32 {
+        load(&self.correlation)
+    }
+
+    /// Decaying peak level of `channel` (0 = left, 1 = right), linear.
+    pub fn peak(&self, channel: usize) -> f32 {
+        load(&self.peak[channel])
+    }
+
+    /// Smoothed RMS level of `channel`, linear.
+    pub fn rms(&self, channel: usize) -> f32 {
+        load(&self.mean_square[channel]).sqrt()
+    }
+
+    /// True once `channel` has hit full scale, until [`Self::reset_clip`].
+    pub fn clipped(&self, channel: usize) -> bool {
+        self.clipped[channel].load(Ordering::Relaxed)
+    }
+
+    /// How far the limiter is turning the output down, in dB (0 or less);
+    /// it recovers at the peak meters' fall-off rate.
+    pub fn gain_reduction_db(&self) -> f32 {
+        load(&self.gain_reduction_db)
+    }
+
+    pub fn reset_clip(&self) {
+        for clipped in &self.clipped {
+            clipped.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Loudness over the last 400 ms, in LUFS.
+    pub fn momentary_lufs(&self) -> f32 {
+        load(&self.momentary_lufs)
+    }
+
+    /// Loudness over the last 3 s, in LUFS.
+    pub fn short_term_lufs(&self) -> f32 {
+        load(&self.short_term_lufs)
+    }
+
+    /// Gated loudness since the stream started or the last reset, in LUFS.
+    pub fn integrated_lufs(&self) -> f32 {
+        load(&self.integrated_lufs)
+    }
+
+    /// Asks the audio thread to start integrating from scratch.
+    pub fn reset_loudness(&self) {
+        self.loudness_reset.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Audio-thread side of the meters: owns the state that can't live in
+/// atomics (K-weighting filters, loudness history) and publishes readings
+/// into the shared [`OutputMeters`].
+pub struct MeterWriter {
+    meters: Arc<OutputMeters>,
+    k_weighting: [[Biquad; 2]; 2],
+    hop_len: usize,
+    hop_frames: usize,
+    hop_energy: f32,
+    hops: VecDeque<f32>,
+    histogram: Vec<u32>,
+}
+
+impl MeterWriter {
+    pub fn new(meters: Arc<OutputMeters>) -> Self {
+        let sample_rate = meters.sample_rate;
+        let filters = [
+            Biquad::k_weighting_shelf(sample_rate),
+            Biquad::k_weighting_highpass(sample_rate),
+        ];
+        let bins = ((HISTOGRAM_TOP_LUFS - ABSOLUTE_GATE_LUFS) * HISTOGRAM_BINS_PER_LU) as usize;
+        Self {
+            meters,
+            k_weighting: [filters, filters],
+            hop_len: ((sample_rate * LOUDNESS_HOP_SECONDS) as usize).max(1),
+            hop_frames: 0,
+            hop_energy: 0.0,
+            hops: VecDeque::with_capacity(SHORT_TERM_HOPS),
+            histogram: vec![0; bins],
+        }
+    }
+
+    /// Folds in the deepest limiting over `frames` frames.
+    pub fn record_gain_reduction(&self, reduction_db: f32, frames: usize) {
+        let seconds = frames as f32 / self.meters.sample_rate;
+        let recovered = load(&self.meters.gain_reduction_db) + PEAK_FALLOFF_DB_PER_SECOND * seconds;
+        store(
+            &self.meters.gain_reduction_db,
+            recovered.min(0.0).min(reduction_db),
+        );
+    }
+
+    pub fn record(&mut self, frames: &[[f32; 2]]) {
+        self.meters.record(frames);
+        if self.meters.loudness_reset.swap(false, Ordering::Relaxed) {
+            self.hops.clear();
+            self.histogram.fill(0);
+            store(&self.meters.integrated_lufs, SILENT_LUFS);
+        }
+        for frame in frames {
+            for (channel, sample) in frame.iter().enumerate() {
+                let [shelf, highpass] = &mut self.k_weighting[channel];
+                let weighted = highpass.process(shelf.process(*sample));
+                // BS.1770 channel weight is 1.0 for left and right.
+                self.hop_energy += weighted * weighted;
+            }
+            self.hop_frames += 1;
+            if self.hop_frames == self.hop_len {
+                self.finish_hop();
+            }
+        }
+    }
+
+    fn finish_hop(&mut self) {
+        if self.hops.len() == SHORT_TERM_HOPS {
+            self.hops.pop_front();
+        }
+        self.hops.push_back(self.hop_energy / self.hop_len as f32);
+        self.hop_energy = 0.0;
+        self.hop_frames = 0;
+
+        let mean = |count: usize| {
+            let recent = self.hops.iter().rev().take(count);
+            recent.sum::<f32>() / count.min(self.hops.len()) as f32
+        };
+        let momentary = energy_to_lufs(mean(MOMENTARY_HOPS));
+        store(&self.meters.momentary_lufs, momentary);
+        store(
+            &self.meters.short_term_lufs,
+            energy_to_lufs(mean(SHORT_TERM_HOPS)),
+        );
+
+        if self.hops.len() >= MOMENTARY_HOPS && momentary > ABSOLUTE_GATE_LUFS {
+            let bin = ((momentary - ABSOLUTE_GATE_LUFS) * HISTOGRAM_BINS_PER_LU) as usize;
+            let last = self.histogram.len() - 1;
+            self.histogram[bin.min(last)] += 1;
+            store(&self.meters.integrated_lufs, self.integrated());
+        }
+    }
+
+    /// Two-pass gating over the histogram: absolute gate first, then drop
+    /// blocks more than 10 LU below the loudness of what survived.
+    fn integrated(&self) -> f32 {
+        let bin_energy = |bin: usize| {
+            lufs_to_energy(ABSOLUTE_GATE_LUFS + (bin as f32 + 0.5) / HISTOGRAM_BINS_PER_LU)
+        };
+        let gated_mean = |threshold: f32| {
+            let (mut energy, mut count) = (0.0_f64, 0_u64);
+            for (bin, blocks) in self.histogram.iter().enumerate() {
+                let lufs = ABSOLUTE_GATE_LUFS + (bin as f32 + 0.5) / HISTOGRAM_BINS_PER_LU;
+                if *blocks > 0 && lufs > threshold {
+                    energy += bin_energy(bin) as f64 * *blocks as f64;
+                    count += *blocks as u64;
+                }
+            }
+            (count > 0).then(|| (energy / count as f64) as f32)
+        };
+        let Some(ungated) = gated_mean(ABSOLUTE_GATE_LUFS) else {
+            return SILENT_LUFS;
+        };
+        let threshold = energy_to_lufs(ungated) + RELATIVE_GATE_LU;
+        gated_mean(threshold).map_or(SILENT_LUFS, energy_to_lufs)
+    }
+}
+
+fn energy_to_lufs(energy: f32) -> f32 {
+    if energy <= 0.0 {
+        SILENT_LUFS
+    } else {
+        -0.691 + 10.0 * energy.log10()
+    }
+}
+
+fn lufs_to_energy(lufs: f32) -> f32 {
+    10_f32.powf((lufs + 0.691) / 10.0)
+}
+
+/// Direct form I biquad for the K-weighting pre-filter.
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f32; 3],
+    a: [f32; 2],
+    x: [f32; 2],
+    y: [f32; 2],
+}
+
+impl Biquad {
+    /// Stage 1: the +4 dB high shelf modelling the head, from BS.1770-4.
+    fn k_weighting_shelf(sample_rate: f32) -> Self {
+        let (f0, gain_db, q) = (1_681.974_5_f32, 3.999_843_9_f32, 0.707_175_24_f32);
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10_f32.powf(gain_db / 20.0);
+        let vb = vh.powf(0.499_666_77);
+        let a0 = 1.0 + k / q + k * k;
+        Self::with_coeffs(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    /// Stage 2: the RLB high-pass around 38 Hz.
+    fn k_weighting_highpass(sample_rate: f32) -> Self {
+        let (f0, q) = (38.135_47_f32, 0.500_327_04_f32);
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Self::with_coeffs(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    fn with_coeffs(b: [f32; 3], a: [f32; 2]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// Linear amplitude to decibels full scale, floored at -120 dB.
+pub fn to_dbfs(level: f32) -> f32 {
+    20.0 * level.max(1e-6).log10()
+}
+
+fn load(value: &AtomicU32) -> f32 {
+    f32::from_bits(value.load(Ordering::Relaxed))
+}
+
+fn store(value: &AtomicU32, sample: f32) {
+    value.store(sample.to_bits(), Ordering::Relaxed);
+}
@@ -0,0 +1,525 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::scales::Scale;
+use crate::synth::{SynthParams, SynthShared, SynthSnapshot};
+use crate::wav;
+
+/// Samples of mic audio the input stream may queue up before the oldest
+/// are dropped, so a stalled output never builds up latency.
+const MIC_BUFFER_CAPACITY: usize = 4096;
+/// Samples the pitch tracker looks at; half of it is the longest period found.
+const ANALYSIS_SIZE: usize = 1024;
+/// Samples between pitch estimates.
+const ANALYSIS_HOP: usize = 512;
+/// Highest pitch the tracker reports, so harmonics aren't mistaken for the voice.
+const MAX_PITCH_HZ: f32 = 1_000.0;
+/// YIN dip that counts as a period; lower is stricter.
+const YIN_THRESHOLD: f32 = 0.15;
+/// Mean square below which the input counts as silence and isn't tracked.
+const SILENCE_POWER: f32 = 1e-5;
+/// How far, in semitones, a sung pitch may drift from the note it's playing
+/// before the note changes, so wavering on a boundary doesn't flutter.
+const NOTE_HYSTERESIS: f32 = 0.7;
+/// Input RMS that plays at full velocity in audio-to-note mode.
+const FULL_VELOCITY_LEVEL: f32 = 0.25;
+/// Length of the pitch shifter's crossfading delay taps.
+const SHIFTER_WINDOW_SECONDS: f32 = 0.03;
+const SHIFTER_BUFFER: usize = 8192;
+/// Take frames the output stream can get ahead of the take writer before
+/// new ones are dropped; about 2.7 s at 48 kHz.
+const TAKE_RING_FRAMES: usize = 1 << 17;
+/// How often the take writer collects what the output stream recorded.
+const TAKE_DRAIN_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What the microphone input is used for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MicMode {
+    #[default]
+    Off,
+    /// Pitch-correct the voice toward the nearest note and play it through the main patch's EQ.
+    Autotune,
+    /// Play the sung, hummed or whistled pitch as a synth note.
+    PlayNotes,
+}
+
+impl MicMode {
+    pub const ALL: [MicMode; 3] = [MicMode::Off, MicMode::Autotune, MicMode::PlayNotes];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MicMode::Off => "Off",
+            MicMode::Autotune => "Autotune",
+            MicMode::PlayNotes => "Play notes",
+        }
+    }
+}
+
+/// A recording of the synth and the raw mic mixed down to stereo.
+pub struct Take {
+    pub frames: Vec<[f32; 2]>,
+    pub sample_rate: f32,
+}
+
+/// Fixed storage the output stream mixes take frames into, so the audio
+/// thread never allocates; `TakeRecorder` empties it.
+struct TakeRing {
+    frames: Box<[[f32; 2]]>,
+    /// Frames written and collected since the take started.
+    written: usize,
+    read: usize,
+    sample_rate: f32,
+}
+
+impl TakeRing {
+    fn new() -> Self {
+        Self {
+            frames: vec![[0.0; 2]; TAKE_RING_FRAMES].into_boxed_slice(),
+            written: 0,
+            read: 0,
+            sample_rate: 48_000.0,
+        }
+    }
+
+    /// When full, the new frame is dropped rather than one not yet collected.
+    fn push(&mut self, frame: [f32; 2]) {
+        let len = self.frames.len();
+        if self.written - self.read < len {
+            self.frames[self.written % len] = frame;
+            self.written += 1;
+        }
+    }
+
+    fn drain_into(&mut self, out: &mut Vec<[f32; 2]>) {
+        let len = self.frames.len();
+        out.extend((self.read..self.written).map(|index| self.frames[index % len]));
+        self.read = self.written;
+    }
+}
+
+/// Records a take: opens the ring the output stream writes into and
+/// collects it on a thread of its own into the growing `Take`.
+pub struct TakeRecorder {
+    stop: Sender<()>,
+    writer: JoinHandle<Take>,
+}
+
+impl TakeRecorder {
+    pub fn start(buffer: &Arc<Mutex<MicBuffer>>) -> Self {
+        if let Ok(mut locked) = buffer.lock() {
+            locked.take = Some(TakeRing::new());
+        }
+        let buffer = Arc::clone(buffer);
+        let (stop, rx) = mpsc::channel::<()>();
+        let writer = thread::spawn(move || {
+            let mut take = Take {
+                frames: Vec::new(),
+                sample_rate: 48_000.0,
+            };
+            loop {
+                let stopping = !matches!(
+                    rx.recv_timeout(TAKE_DRAIN_INTERVAL),
+                    Err(RecvTimeoutError::Timeout)
+                );
+                // Room for a whole ring before locking, so the audio thread never
+                // waits on a reallocation.
+                take.frames.reserve(TAKE_RING_FRAMES);
+                let Ok(mut locked) = buffer.lock() else {
+                    return take;
+                };
+                if let Some(ring) = &mut locked.take {
+                    ring.drain_into(&mut take.frames);
+                    take.sample_rate = ring.sample_rate;
+                }
+                if stopping {
+                    locked.take = None;
+                    return take;
+                }
+            }
+        });
+        Self { stop, writer }
+    }
+
+    /// Stops recording and hands back everything recorded.
+    pub fn finish(self) -> Option<Take> {
+        let _ = self.stop.send(());
+        self.writer.join().ok()
+    }
+}
+
+/// Saves a take as a 16-bit stereo WAV.
+pub fn export_take(path: &Path, take: &Take) -> Result<(), String> {
+    let wav = wav::encode_stereo_pcm16(&take.frames, take.sample_rate as u32);
+    fs::write(path, wav).map_err(|err| format!("Failed to write {}: {err}", path.display()))
+}
+
+/// Mono mic samples handed from the input stream to the output stream, and
+/// the ring of the take being recorded from the output stream, if any.
+pub struct MicBuffer {
+    samples: VecDeque<f32>,
+    /// Rate of the input device, which need not match the output's.
+    pub sample_rate: f32,
+    take: Option<TakeRing>,
+    /// Levels the synth and the mic are mixed at in a take.
+    pub take_synth_level: f32,
+    pub take_mic_level: f32,
+    /// Latest pitch estimate as a fractional MIDI note, and the input RMS.
+    pitch: Option<f32>,
+    level: f32,
+}
+
+impl Default for MicBuffer {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(MIC_BUFFER_CAPACITY),
+            sample_rate: 48_000.0,
+            take: None,
+            take_synth_level: 1.0,
+            take_mic_level: 1.0,
+            pitch: None,
+            level: 0.0,
+        }
+    }
+}
+
+impl MicBuffer {
+    /// Seconds recorded so far in the current take.
+    pub fn take_seconds(&self) -> f32 {
+        self.take
+            .as_ref()
+            .map_or(0.0, |ring| ring.written as f32 / ring.sample_rate)
+    }
+
+    pub fn record(&mut self, samples: impl IntoIterator<Item = f32>) {
+        for sample in samples {
+            if self.samples.len() == MIC_BUFFER_CAPACITY {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(sample);
+        }
+    }
+}
+
+/// The output stream's side of the mic: resamples the input to the output
+/// rate and runs whatever the current mic mode does to it.
+pub struct MicReader {
+    buffer: Arc<Mutex<MicBuffer>>,
+    output_rate: f32,
+    /// Input samples consumed per output sample.
+    step: f32,
+    pending: VecDeque<f32>,
+    phase: f32,
+    previous: f32,
+    current: f32,
+    processor: MicProcessor,
+    /// Raw mic samples of the current output block, for the take.
+    dry_block: Vec<f32>,
+}
+
+impl MicReader {
+    pub fn new(buffer: Arc<Mutex<MicBuffer>>, output_rate: f32) -> Self {
+        Self {
+            buffer,
+            output_rate,
+            step: 1.0,
+            pending: VecDeque::with_capacity(MIC_BUFFER_CAPACITY),
+            phase: 0.0,
+            previous: 0.0,
+            current: 0.0,
+            processor: MicProcessor::new(output_rate),
+            dry_block: Vec::new(),
+        }
+    }
+
+    /// Takes whatever the input delivered since the last call. Call once per
+    /// output block.
+    pub fn fetch(&mut self) {
+        if let Ok(mut buffer) = self.buffer.lock() {
+            self.step = buffer.sample_rate / self.output_rate;
+            self.pending.extend(buffer.samples.drain(..));
+        }
+        let excess = self.pending.len().saturating_sub(MIC_BUFFER_CAPACITY);
+        self.pending.drain(..excess);
+    }
+
+    /// The next output sample of mic audio, already processed for the
+    /// snapshot's mic mode.
+    pub fn next_sample(&mut self, snapshot: &SynthSnapshot) -> f32 {
+        let input = self.next_input();
+        self.dry_block.push(input);
+        match snapshot.mic_mode {
+            MicMode::Off => 0.0,
+            MicMode::Autotune => match snapshot.parts.first() {
+                Some(part) => self.processor.autotune(input, snapshot.scale, &part.params),
+                None => input,
+            },
+            // Only the tracked pitch is used; `PitchFollower` plays it.
+            MicMode::PlayNotes => {
+                self.processor.tracker.push(input);
+                0.0
+            }
+        }
+    }
+
+    /// Adds the block's synth output and raw mic to the take, when one is
+    /// being recorded. Call once per output block, after rendering it.
+    pub fn finish_block(&mut self, synth: &[[f32; 2]]) {
+        if let Ok(mut buffer) = self.buffer.lock() {
+            buffer.pitch = self.processor.tracker.note();
+            buffer.level = self.processor.tracker.level;
+            let (synth_level, mic_level) = (buffer.take_synth_level, buffer.take_mic_level);
+            if let Some(ring) = &mut buffer.take {
+                ring.sample_rate = self.output_rate;
+                for ([left, right], mic) in synth.iter().zip(&self.dry_block) {
+                    ring.push([
+                        left * synth_level + mic * mic_level,
+                        right * synth_level + mic * mic_level,
+                    ]);
+                }
+            }
+        }
+        self.dry_block.clear();
+    }
+
+    /// Linearly interpolated input at the output rate; silence on underrun.
+    fn next_input(&mut self) -> f32 {
+        self.phase += self.step;
+        while self.phase >= 1.0 {
+            self.phase -= 1.0;
+            self.previous = self.current;
+            self.current = self.pending.pop_front().unwrap_or(0.0);
+        }
+        self.previous + (self.current - self.previous) * self.phase
+    }
+}
+
+/// Audio-to-note mode: holds one synth note that follows the tracked pitch,
+/// sliding between notes like a legato line. Polled from the UI thread.
+#[derive(Default)]
+pub struct PitchFollower {
+    note: Option<u8>,
+}
+
+impl PitchFollower {
+    /// Releases the note once `mode` leaves `PlayNotes` or the input goes quiet.
+    pub fn poll(&mut self, buffer: &Mutex<MicBuffer>, mode: MicMode, shared: &mut SynthShared) {
+        let (pitch, level) = match (mode, buffer.lock()) {
+            (MicMode::PlayNotes, Ok(buffer)) => (buffer.pitch, buffer.level),
+            _ => (None, 0.0),
+        };
+        let target = match (pitch, self.note) {
+            (Some(pitch), Some(note)) if (pitch - note as f32).abs() < NOTE_HYSTERESIS => {
+                Some(note)
+            }
+            (Some(pitch), _) => Some(pitch.round().clamp(0.0, 127.0) as u8),
+            (None, _) => None,
+        };
+        if target == self.note {
+            return;
+        }
+        let velocity = (level / FULL_VELOCITY_LEVEL).clamp(0.2, 1.0);
+        match (self.note, target) {
+            (Some(from), Some(to)) => shared.glide_note(from, to, velocity),
+            (Some(from), None) => shared.release_note(from),
+            (None, Some(to)) => shared.press_note(to, velocity),
+            (None, None) => {}
+        }
+        self.note = target;
+    }
+}
+
+/// Pitch tracking plus a pitch shifter, pulling the voice onto the nearest
+/// note of the scale (or semitone, without one).
+struct MicProcessor {
+    tracker: PitchTracker,
+    shifter: PitchShifter,
+    ratio: f32,
+    sample_rate: f32,
+}
+
+impl MicProcessor {
+    fn new(sample_rate: f32) -> Self {
+        Self {
+            tracker: PitchTracker::new(sample_rate),
+            shifter: PitchShifter::new(sample_rate),
+            ratio: 1.0,
+            sample_rate,
+        }
+    }
+
+    /// Follows the patch's autotune: an amount of 0 leaves the voice alone,
+    /// 1 lands it exactly on the note, reached over the retune time.
+    fn autotune(&mut self, input: f32, scale: Option<Scale>, params: &SynthParams) -> f32 {
+        self.tracker.push(input);
+        let target = match self.tracker.note() {
+            Some(note) => {
+                let nearest = scale.map_or(note.round(), |scale| scale.nearest_note(note));
+                2_f32.powf((nearest - note) * params.autotune_amount.clamp(0.0, 1.0) / 12.0)
+            }
+            None => 1.0,
+        };
+        let retune =
+            1.0 - (-1000.0 / (params.autotune_retune_ms.max(0.1) * self.sample_rate)).exp();
+        self.ratio += (target - self.ratio) * retune;
+        self.shifter.process(input, self.ratio)
+    }
+}
+
+/// Pitch of a recording as a fractional MIDI note: the middle of the
+/// estimates taken across it, or `None` when it never held a steady pitch.
+pub fn detect_note(samples: &[f32], sample_rate: f32) -> Option<f32> {
+    let mut tracker = PitchTracker::new(sample_rate);
+    let mut notes = Vec::new();
+    for &sample in samples {
+        tracker.push(sample);
+        if tracker.since_estimate == 0 {
+            notes.extend(tracker.note());
+        }
+    }
+    notes.sort_by(f32::total_cmp);
+    notes.get(notes.len() / 2).copied()
+}
+
+/// YIN pitch detector run every `ANALYSIS_HOP` samples over the last
+/// `ANALYSIS_SIZE`.
+struct PitchTracker {
+    sample_rate: f32,
+    history: Vec<f32>,
+    write: usize,
+    since_estimate: usize,
+    /// Scratch space so estimates don't allocate on the audio thread.
+    window: Vec<f32>,
+    difference: Vec<f32>,
+    pitch_hz: Option<f32>,
+    /// RMS of the last analysed window.
+    level: f32,
+}
+
+impl PitchTracker {
+    fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            history: vec![0.0; ANALYSIS_SIZE],
+            write: 0,
+            since_estimate: 0,
+            window: vec![0.0; ANALYSIS_SIZE],
+            difference: vec![0.0; ANALYSIS_SIZE / 2],
+            pitch_hz: None,
+            level: 0.0,
+        }
+    }
+
+    fn push(&mut self, sample: f32) {
+        self.history[self.write] = sample;
+        self.write = (self.write + 1) % ANALYSIS_SIZE;
+        self.since_estimate += 1;
+        if self.since_estimate >= ANALYSIS_HOP {
+            self.since_estimate = 0;
+            self.pitch_hz = self.estimate();
+        }
+    }
+
+    /// The tracked pitch as a fractional MIDI note.
+    fn note(&self) -> Option<f32> {
+        self.pitch_hz.map(|hz| 69.0 + 12.0 * (hz / 440.0).log2())
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        let (older, newer) = self.history.split_at(self.write);
+        self.window[..newer.len()].copy_from_slice(newer);
+        self.window[newer.len()..].copy_from_slice(older);
+        let power = self.window.iter().map(|x| x * x).sum::<f32>() / ANALYSIS_SIZE as f32;
+        self.level = power.sqrt();
+        if power < SILENCE_POWER {
+            return None;
+        }
+
+        let half = ANALYSIS_SIZE / 2;
+        // Cumulative mean normalized difference, as in the YIN paper.
+        let mut running = 0.0;
+        self.difference[0] = 1.0;
+        for tau in 1..half {
+            let sum: f32 = (0..half)
+                .map(|i| {
+                    let delta = self.window[i] - self.window[i + tau];
+                    delta * delta
+                })
+                .sum();
+            running += sum;
+            self.difference[tau] = if running > 0.0 {
+                sum * tau as f32 / running
+            } else {
+                1.0
+            };
+        }
+
+        let min_tau = ((self.sample_rate / MAX_PITCH_HZ) as usize).max(2);
+        let mut tau = (min_tau..half - 1).find(|&tau| self.difference[tau] < YIN_THRESHOLD)?;
+        while tau + 1 < half - 1 && self.difference[tau + 1] < self.difference[tau] {
+            tau += 1;
+        }
+        // Parabolic interpolation around the dip for a sub-sample period.
+        let (a, b, c) = (
+            self.difference[tau - 1],
+            self.difference[tau],
+            self.difference[tau + 1],
+        );
+        let curve = a - 2.0 * b + c;
+        let offset = if curve.abs() > f32::EPSILON {
+            (0.5 * (a - c) / curve).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        Some(self.sample_rate / (tau as f32 + offset))
+    }
+}
+
+/// Delay-line pitch shifter: two taps sweep through a short window at a
+/// rate set by the pitch ratio, crossfading so each wraps while silent.
+struct PitchShifter {
+    buffer: Vec<f32>,
+    write: usize,
+    window: f32,
+    phase: f32,
+}
+
+impl PitchShifter {
+    fn new(sample_rate: f32) -> Self {
+        Self {
+            buffer: vec![0.0; SHIFTER_BUFFER],
+            write: 0,
+            window: (SHIFTER_WINDOW_SECONDS * sample_rate).min(SHIFTER_BUFFER as f32 - 2.0),
+            phase: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32, ratio: f32) -> f32 {
+        self.buffer[self.write] = input;
+        // Reading faster than writing (ratio > 1) shortens the delay.
+        self.phase = (self.phase + (1.0 - ratio) / self.window).rem_euclid(1.0);
+        let second = (self.phase + 0.5) % 1.0;
+        let output = self.tap(self.phase) * (PI * self.phase).sin().powi(2)
+            + self.tap(second) * (PI * second).sin().powi(2);
+        self.write = (self.write + 1) % SHIFTER_BUFFER;
+        output
+    }
+
+    fn tap(&self, phase: f32) -> f32 {
+        let delay = phase * self.window;
+        let position = self.write as f32 - delay + SHIFTER_BUFFER as f32;
+        let index = position as usize;
+        let frac = position - index as f32;
+        let a = self.buffer[index % SHIFTER_BUFFER];
+        let b = self.buffer[(index + 1) % SHIFTER_BUFFER];
+        a + (b - a) * frac
+    }
+}
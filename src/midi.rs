@@ -0,0 +1,182 @@
+// midir has no Android backend, so there the input never opens and the
+// message handling goes unused.
+#![cfg_attr(target_os = "android", allow(dead_code))]
+
+use std::sync::{Arc, Mutex};
+
+#[cfg(not(target_os = "android"))]
+use midir::{Ignore, MidiInputConnection};
+
+use crate::controllers::{BREATH_CC, CcMapping};
+use crate::synth::SynthShared;
+
+/// Name the app goes by in the system's MIDI port lists.
+const CLIENT_NAME: &str = "Angel Synth";
+const MOD_WHEEL_CC: u8 = 1;
+const SUSTAIN_CC: u8 = 64;
+const SOFT_PEDAL_CC: u8 = 67;
+/// Channel mode message controllers send when stopped or reset.
+const ALL_NOTES_OFF_CC: u8 = 123;
+/// Switch pedals send 0 or 127; anything from here up counts as down.
+const PEDAL_DOWN: u8 = 64;
+
+#[cfg(target_os = "android")]
+pub fn list_midi_port_names() -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn list_midi_port_names() -> Vec<String> {
+    let Ok(input) = midir::MidiInput::new(CLIENT_NAME) else {
+        return Vec::new();
+    };
+    input
+        .ports()
+        .iter()
+        .filter_map(|port| input.port_name(port).ok())
+        .collect()
+}
+
+/// A connection to a hardware MIDI input. Messages are handled on midir's
+/// thread as they arrive rather than once per UI frame, so playing doesn't
+/// wait on (or stop with) the window repainting. All channels are played.
+pub struct MidiInput {
+    #[cfg(not(target_os = "android"))]
+    _connection: MidiInputConnection<()>,
+    pub port_name: String,
+    mappings: Arc<Mutex<Vec<CcMapping>>>,
+    /// Number of the last control change received, for MIDI learn.
+    last_cc: Arc<Mutex<Option<u8>>>,
+}
+
+impl MidiInput {
+    #[cfg(target_os = "android")]
+    pub fn open(
+        _port_name: Option<&str>,
+        _shared: Arc<Mutex<SynthShared>>,
+        _mappings: &[CcMapping],
+    ) -> Result<Self, String> {
+        Err("MIDI input isn't available on Android".to_string())
+    }
+
+    /// Opens `port_name`, or the first port found when `None`.
+    #[cfg(not(target_os = "android"))]
+    pub fn open(
+        port_name: Option<&str>,
+        shared: Arc<Mutex<SynthShared>>,
+        mappings: &[CcMapping],
+    ) -> Result<Self, String> {
+        let mut input = midir::MidiInput::new(CLIENT_NAME)
+            .map_err(|err| format!("MIDI input unavailable: {err}"))?;
+        // Clock, active sensing and SysEx carry nothing the synth uses.
+        input.ignore(Ignore::All);
+        let ports = input.ports();
+        let port = match port_name {
+            Some(target) => ports
+                .iter()
+                .find(|port| input.port_name(port).is_ok_and(|name| name == target))
+                .ok_or_else(|| format!("MIDI port '{target}' not found"))?,
+            None => ports
+                .first()
+                .ok_or_else(|| "No MIDI input port available".to_string())?,
+        }
+        .clone();
+        let resolved_port_name = input
+            .port_name(&port)
+            .unwrap_or_else(|_| "<unknown port>".to_string());
+
+        let mappings = Arc::new(Mutex::new(mappings.to_vec()));
+        let last_cc = Arc::new(Mutex::new(None));
+        let handler = MessageHandler {
+            shared,
+            mappings: Arc::clone(&mappings),
+            last_cc: Arc::clone(&last_cc),
+        };
+        let connection = input
+            .connect(
+                &port,
+                "input",
+                move |_, message, _| handler.handle(message),
+                (),
+            )
+            .map_err(|err| format!("Could not open MIDI port '{resolved_port_name}': {err}"))?;
+        Ok(Self {
+            _connection: connection,
+            port_name: resolved_port_name,
+            mappings,
+            last_cc,
+        })
+    }
+
+    /// The control change received since the last call, if any.
+    pub fn take_last_cc(&self) -> Option<u8> {
+        self.last_cc.lock().ok().and_then(|mut last| last.take())
+    }
+
+    /// Replaces the CC mappings applied to incoming control changes.
+    pub fn set_mappings(&self, mappings: &[CcMapping]) {
+        if let Ok(mut current) = self.mappings.lock() {
+            current.clear();
+            current.extend_from_slice(mappings);
+        }
+    }
+}
+
+/// Turns raw messages into note and controller changes on the synth.
+struct MessageHandler {
+    shared: Arc<Mutex<SynthShared>>,
+    mappings: Arc<Mutex<Vec<CcMapping>>>,
+    last_cc: Arc<Mutex<Option<u8>>>,
+}
+
+impl MessageHandler {
+    fn handle(&self, message: &[u8]) {
+        let [status, data @ ..] = message else {
+            return;
+        };
+        let Ok(mut shared) = self.shared.lock() else {
+            return;
+        };
+        match (status & 0xF0, data) {
+            // A note on at velocity 0 is how running status spells note off.
+            (0x90, &[note, velocity, ..]) if velocity > 0 => {
+                shared.press_note(note & 0x7F, velocity as f32 / 127.0);
+            }
+            (0x80 | 0x90, &[note, ..]) => shared.release_note(note & 0x7F),
+            (0xB0, &[cc, value, ..]) => self.control_change(&mut shared, cc, value),
+            _ => {}
+        }
+    }
+
+    /// Mapped controllers drive their parameters; a CC claimed by a mapping
+    /// loses its usual meaning, so a fader on CC 2 isn't also read as breath.
+    fn control_change(&self, shared: &mut SynthShared, cc: u8, value: u8) {
+        if let Ok(mut last) = self.last_cc.lock() {
+            *last = Some(cc);
+        }
+        let position = value as f32 / 127.0;
+        let mut mapped = false;
+        if let Ok(mappings) = self.mappings.lock() {
+            for mapping in mappings.iter().filter(|mapping| mapping.cc == cc) {
+                *mapping.param.get_mut(&mut shared.params) = mapping.param.denormalize(position);
+                mapped = true;
+            }
+        }
+        if mapped {
+            return;
+        }
+        match cc {
+            MOD_WHEEL_CC => shared.performance.mod_wheel = position,
+            BREATH_CC => shared.performance.breath = Some(position),
+            SUSTAIN_CC => {
+                let down = value >= PEDAL_DOWN;
+                if down != shared.is_holding() {
+                    shared.set_hold(down);
+                }
+            }
+            SOFT_PEDAL_CC => shared.soft_pedal = value >= PEDAL_DOWN,
+            ALL_NOTES_OFF_CC => shared.panic(),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth::{A4_RANGE, MAX_POLYPHONY, MAX_TRANSPOSE, MAX_UNISON_VOICES, SynthParams};
+
+/// Every continuous synth parameter, so controls, mappings and readouts can
+/// refer to one by name instead of by field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamId {
+    Gain,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+    FilterCutoff,
+    FilterResonance,
+    VibratoRate,
+    VibratoDepth,
+    Autotune,
+    UnisonSpread,
+    NoiseMix,
+    EqLowGain,
+    EqLowFreq,
+    EqMidGain,
+    EqMidFreq,
+    EqMidQ,
+    EqHighGain,
+    EqHighFreq,
+    Glide,
+    KeyTrackLevel,
+    KeyTrackTone,
+    VelocityLevel,
+    VelocityTone,
+    Pan,
+    StereoWidth,
+    WavetablePosition,
+    PulseWidth,
+    PwmDepth,
+    PwmRate,
+    FilterDrive,
+    EnvDelay,
+    Hold,
+    GrainSize,
+    GrainDensity,
+    GrainPosition,
+    GrainSpray,
+    Drift,
+    AutotuneRetune,
+    UnisonBlend,
+}
+
+/// Top-to-bottom ratio of the curve used for logarithmic ranges that start at
+/// zero, where a plain geometric mapping is undefined.
+const LOG_FROM_ZERO_RATIO: f32 = 1_000.0;
+
+/// Display and range metadata for a parameter.
+pub struct ParamInfo {
+    pub label: &'static str,
+    pub unit: &'static str,
+    pub min: f32,
+    pub max: f32,
+    /// Controls move through the range geometrically (times, frequencies).
+    pub logarithmic: bool,
+}
+
+impl ParamId {
+    pub const ALL: [ParamId; 40] = [
+        ParamId::Gain,
+        ParamId::Attack,
+        ParamId::Decay,
+        ParamId::Sustain,
+        ParamId::Release,
+        ParamId::FilterCutoff,
+        ParamId::FilterResonance,
+        ParamId::VibratoRate,
+        ParamId::VibratoDepth,
+        ParamId::Autotune,
+        ParamId::UnisonSpread,
+        ParamId::NoiseMix,
+        ParamId::EqLowGain,
+        ParamId::EqLowFreq,
+        ParamId::EqMidGain,
+        ParamId::EqMidFreq,
+        ParamId::EqMidQ,
+        ParamId::EqHighGain,
+        ParamId::EqHighFreq,
+        ParamId::Glide,
+        ParamId::KeyTrackLevel,
+        ParamId::KeyTrackTone,
+        ParamId::VelocityLevel,
+        ParamId::VelocityTone,
+        ParamId::Pan,
+        ParamId::StereoWidth,
+        ParamId::WavetablePosition,
+        ParamId::PulseWidth,
+        ParamId::PwmDepth,
+        ParamId::PwmRate,
+        ParamId::FilterDrive,
+        ParamId::EnvDelay,
+        ParamId::Hold,
+        ParamId::GrainSize,
+        ParamId::GrainDensity,
+        ParamId::GrainPosition,
+        ParamId::GrainSpray,
+        ParamId::Drift,
+        ParamId::AutotuneRetune,
+        ParamId::UnisonBlend,
+    ];
+
+    pub fn info(self) -> ParamInfo {
+        let info = |label, unit, min, max, logarithmic| ParamInfo {
+            label,
+            unit,
+            min,
+            max,
+            logarithmic,
+        };
+        match self {
+            ParamId::Gain => info("Master gain", "", 0.0, 1.0, false),
+            ParamId::Attack => info("Attack", "s", 0.001, 1.0, true),
+            ParamId::Decay => info("Decay", "s", 0.001, 1.5, true),
+            ParamId::Sustain => info("Sustain", "", 0.0, 1.0, false),
+            ParamId::Release => info("Release", "s", 0.01, 3.0, true),
+            ParamId::FilterCutoff => info("Filter cutoff", "Hz", 80.0, 16_000.0, true),
+            ParamId::FilterResonance => info("Resonance", "", 0.0, 0.95, false),
+            ParamId::VibratoRate => info("Vibrato rate", "Hz", 0.1, 12.0, true),
+            ParamId::VibratoDepth => info("Vibrato depth", "st", 0.0, 1.2, false),
+            ParamId::Autotune => info("Autotune", "", 0.0, 1.0, false),
+            ParamId::UnisonSpread => info("Unison spread", "ct", 0.0, 25.0, false),
+            ParamId::NoiseMix => info("Noise mix", "", 0.0, 0.5, false),
+            ParamId::EqLowGain => info("Low gain", "dB", -12.0, 12.0, false),
+            ParamId::EqLowFreq => info("Low cutoff", "Hz", 40.0, 400.0, true),
+            ParamId::EqMidGain => info("Mid gain", "dB", -12.0, 12.0, false),
+            ParamId::EqMidFreq => info("Mid freq", "Hz", 200.0, 4_000.0, true),
+            ParamId::EqMidQ => info("Mid Q", "", 0.3, 2.5, false),
+            ParamId::EqHighGain => info("High gain", "dB", -12.0, 12.0, false),
+            ParamId::EqHighFreq => info("High cutoff", "Hz", 2_000.0, 16_000.0, true),
+            ParamId::Glide => info("Glide", "s", 0.0, 1.0, true),
+            ParamId::KeyTrackLevel => info("Key track level", "dB/oct", -6.0, 6.0, false),
+            ParamId::KeyTrackTone => info("Key track tone", "", -1.0, 1.0, false),
+            ParamId::VelocityLevel => info("Velocity level", "", 0.0, 1.0, false),
+            ParamId::VelocityTone => info("Velocity tone", "oct", 0.0, 4.0, false),
+            ParamId::Pan => info("Pan", "", -1.0, 1.0, false),
+            ParamId::StereoWidth => info("Stereo width", "", 0.0, 1.0, false),
+            ParamId::WavetablePosition => info("Wavetable position", "", 0.0, 1.0, false),
+            ParamId::PulseWidth => info("Pulse width", "", 0.05, 0.95, false),
+            ParamId::PwmDepth => info("PWM depth", "", 0.0, 0.45, false),
+            ParamId::PwmRate => info("PWM rate", "Hz", 0.05, 10.0, true),
+            ParamId::FilterDrive => info("Drive", "dB", 0.0, 24.0, false),
+            ParamId::EnvDelay => info("Env delay", "s", 0.0, 2.0, false),
+            ParamId::Hold => info("Hold", "s", 0.0, 2.0, false),
+            ParamId::GrainSize => info("Grain size", "ms", 5.0, 500.0, true),
+            ParamId::GrainDensity => info("Grain density", "/s", 1.0, 100.0, true),
+            ParamId::GrainPosition => info("Grain position", "", 0.0, 1.0, false),
+            ParamId::GrainSpray => info("Grain spray", "", 0.0, 0.5, false),
+            ParamId::Drift => info("Drift", "", 0.0, 1.0, false),
+            ParamId::AutotuneRetune => info("Retune time", "ms", 1.0, 500.0, true),
+            ParamId::UnisonBlend => info("Unison blend", "", 0.0, 1.0, false),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        self.info().label
+    }
+
+    /// What the parameter does, for tooltips.
+    pub fn description(self) -> &'static str {
+        match self {
+            ParamId::Gain => "Overall output level.",
+            ParamId::Attack => "Time for a note to rise from silence to full level.",
+            ParamId::Decay => "Time to fall from the attack peak to the sustain level.",
+            ParamId::Sustain => "Level held while a key stays down, as a share of the peak.",
+            ParamId::Release => "Time for a note to fade out after its key is let go.",
+            ParamId::FilterCutoff => {
+                "Filter frequency: the low-pass softens harmonics above it, the high-pass thins out those below."
+            }
+            ParamId::FilterResonance => {
+                "Emphasis at the cutoff frequency; high values ring and whistle, and the ladder sings on its own."
+            }
+            ParamId::VibratoRate => "Speed of the pitch wobble.",
+            ParamId::VibratoDepth => "How far vibrato bends the pitch either side of the note.",
+            ParamId::Autotune => {
+                "Pulls the pitch onto the nearest note of the scale picked above the piano (any semitone without one), and narrows the unison detune."
+            }
+            ParamId::UnisonSpread => {
+                "Detune from the flattest unison copy to the sharpest; wider sounds thicker."
+            }
+            ParamId::NoiseMix => "Share of white noise blended into the tone for breath and grit.",
+            ParamId::EqLowGain => "Boost or cut of the low shelf.",
+            ParamId::EqLowFreq => "Frequency below which the low shelf acts.",
+            ParamId::EqMidGain => "Boost or cut of the mid band.",
+            ParamId::EqMidFreq => "Centre frequency of the mid band.",
+            ParamId::EqMidQ => "Width of the mid band; higher values are narrower.",
+            ParamId::EqHighGain => "Boost or cut of the high shelf.",
+            ParamId::EqHighFreq => "Frequency above which the high shelf acts.",
+            ParamId::Glide => "Time a glided note takes to slide to its new pitch.",
+            ParamId::KeyTrackLevel => {
+                "Level change per octave away from middle C; below zero keeps the bass from booming."
+            }
+            ParamId::KeyTrackTone => {
+                "How the cutoff follows the key: 1 moves it an octave per octave, below zero darkens high notes."
+            }
+            ParamId::VelocityLevel => {
+                "How much softer a gently struck note plays; at 0 every note plays at full level."
+            }
+            ParamId::VelocityTone => {
+                "Octaves the filter closes from the hardest strike to the softest, so soft notes sound darker."
+            }
+            ParamId::Pan => "Where the voices sit, from left to right.",
+            ParamId::StereoWidth => {
+                "Spreads notes from left (low) to right (high) and the unison copies around each note."
+            }
+            ParamId::WavetablePosition => {
+                "Which frame of the wavetable plays, blending between neighbours; sweep it to morph the sound."
+            }
+            ParamId::PulseWidth => {
+                "Share of each cycle the square wave spends high; away from 0.5 it turns thin and nasal."
+            }
+            ParamId::PwmDepth => "How far the pulse width sweeps either side of its setting.",
+            ParamId::PwmRate => {
+                "Speed of the pulse width sweep; slow sweeps give the classic PWM pad."
+            }
+            ParamId::FilterDrive => {
+                "How hard the ladder filter is driven; more drive saturates and fattens the sound."
+            }
+            ParamId::EnvDelay => "Wait after a key goes down before the attack begins.",
+            ParamId::Hold => "Time a note stays at its peak before it decays.",
+            ParamId::GrainSize => {
+                "Length of each grain; short grains buzz and blur, long ones keep the sample recognisable."
+            }
+            ParamId::GrainDensity => {
+                "Grains started per second; more overlap into a smooth cloud, fewer leave gaps."
+            }
+            ParamId::GrainPosition => {
+                "Point in the sample the grains are read from; sweep it to scan through the sound."
+            }
+            ParamId::GrainSpray => {
+                "How far grains start either side of the position at random, smearing it into a texture."
+            }
+            ParamId::Drift => {
+                "Slow random wander of each note's pitch and cutoff, like an analog synth's, so chords sound less sterile."
+            }
+            ParamId::AutotuneRetune => {
+                "How fast autotune reaches the note: short snaps for the hard robotic effect, long lets slides and vibrato through."
+            }
+            ParamId::UnisonBlend => {
+                "Level of the outer unison copies against the middle ones; low keeps the note focused, 1 plays every copy equally."
+            }
+        }
+    }
+
+    pub fn get(self, params: &SynthParams) -> f32 {
+        match self {
+            ParamId::Gain => params.gain,
+            ParamId::Attack => params.attack_seconds,
+            ParamId::Decay => params.decay_seconds,
+            ParamId::Sustain => params.sustain_level,
+            ParamId::Release => params.release_seconds,
+            ParamId::FilterCutoff => params.filter_cutoff_hz,
+            ParamId::FilterResonance => params.filter_resonance,
+            ParamId::VibratoRate => params.vibrato_rate_hz,
+            ParamId::VibratoDepth => params.vibrato_depth_semitones,
+            ParamId::Autotune => params.autotune_amount,
+            ParamId::UnisonSpread => params.unison_spread_cents,
+            ParamId::NoiseMix => params.noise_mix,
+            ParamId::EqLowGain => params.eq_low_gain_db,
+            ParamId::EqLowFreq => params.eq_low_freq_hz,
+            ParamId::EqMidGain => params.eq_mid_gain_db,
+            ParamId::EqMidFreq => params.eq_mid_freq_hz,
+            ParamId::EqMidQ => params.eq_mid_q,
+            ParamId::EqHighGain => params.eq_high_gain_db,
+            ParamId::EqHighFreq => params.eq_high_freq_hz,
+            ParamId::Glide => params.glide_seconds,
+            ParamId::KeyTrackLevel => params.key_track_level_db,
+            ParamId::KeyTrackTone => params.key_track_tone,
+            ParamId::VelocityLevel => params.velocity_level,
+            ParamId::VelocityTone => params.velocity_tone,
+            ParamId::Pan => params.pan,
+            ParamId::StereoWidth => params.stereo_width,
+            ParamId::WavetablePosition => params.wavetable_position,
+            ParamId::PulseWidth => params.pulse_width,
+            ParamId::PwmDepth => params.pwm_depth,
+            ParamId::PwmRate => params.pwm_rate_hz,
+            ParamId::FilterDrive => params.filter_drive_db,
+            ParamId::EnvDelay => params.delay_seconds,
+            ParamId::Hold => params.hold_seconds,
+            ParamId::GrainSize => params.grain_size_ms,
+            ParamId::GrainDensity => params.grain_density_hz,
+            ParamId::GrainPosition => params.grain_position,
+            ParamId::GrainSpray => params.grain_spray,
+            ParamId::Drift => params.drift_amount,
+            ParamId::AutotuneRetune => params.autotune_retune_ms,
+            ParamId::UnisonBlend => params.unison_blend,
+        }
+    }
+
+    pub fn get_mut(self, params: &mut SynthParams) -> &mut f32 {
+        match self {
+            ParamId::Gain => &mut params.gain,
+            ParamId::Attack => &mut params.attack_seconds,
+            ParamId::Decay => &mut params.decay_seconds,
+            ParamId::Sustain => &mut params.sustain_level,
+            ParamId::Release => &mut params.release_seconds,
+            ParamId::FilterCutoff => &mut params.filter_cutoff_hz,
+            ParamId::FilterResonance => &mut params.filter_resonance,
+            ParamId::VibratoRate => &mut params.vibrato_rate_hz,
+            ParamId::VibratoDepth => &mut params.vibrato_depth_semitones,
+            ParamId::Autotune => &mut params.autotune_amount,
+            ParamId::UnisonSpread => &mut params.unison_spread_cents,
+            ParamId::NoiseMix => &mut params.noise_mix,
+            ParamId::EqLowGain => &mut params.eq_low_gain_db,
+            ParamId::EqLowFreq => &mut params.eq_low_freq_hz,
+            ParamId::EqMidGain => &mut params.eq_mid_gain_db,
+            ParamId::EqMidFreq => &mut params.eq_mid_freq_hz,
+            ParamId::EqMidQ => &mut params.eq_mid_q,
+            ParamId::EqHighGain => &mut params.eq_high_gain_db,
+            ParamId::EqHighFreq => &mut params.eq_high_freq_hz,
+            ParamId::Glide => &mut params.glide_seconds,
+            ParamId::KeyTrackLevel => &mut params.key_track_level_db,
+            ParamId::KeyTrackTone => &mut params.key_track_tone,
+            ParamId::VelocityLevel => &mut params.velocity_level,
+            ParamId::VelocityTone => &mut params.velocity_tone,
+            ParamId::Pan => &mut params.pan,
+            ParamId::StereoWidth => &mut params.stereo_width,
+            ParamId::WavetablePosition => &mut params.wavetable_position,
+            ParamId::PulseWidth => &mut params.pulse_width,
+            ParamId::PwmDepth => &mut params.pwm_depth,
+            ParamId::PwmRate => &mut params.pwm_rate_hz,
+            ParamId::FilterDrive => &mut params.filter_drive_db,
+            ParamId::EnvDelay => &mut params.delay_seconds,
+            ParamId::Hold => &mut params.hold_seconds,
+            ParamId::GrainSize => &mut params.grain_size_ms,
+            ParamId::GrainDensity => &mut params.grain_density_hz,
+            ParamId::GrainPosition => &mut params.grain_position,
+            ParamId::GrainSpray => &mut params.grain_spray,
+            ParamId::Drift => &mut params.drift_amount,
+            ParamId::AutotuneRetune => &mut params.autotune_retune_ms,
+            ParamId::UnisonBlend => &mut params.unison_blend,
+        }
+    }
+
+    pub fn default_value(self) -> f32 {
+        self.get(&SynthParams::default())
+    }
+
+    /// Pulls `value` into the control range; NaN becomes the default.
+    pub fn clamp(self, value: f32) -> f32 {
+        let info = self.info();
+        if value.is_nan() {
+            self.default_value()
+        } else {
+            value.clamp(info.min, info.max)
+        }
+    }
+
+    /// Maps a value onto 0..1 across the control range, respecting log scaling.
+    pub fn normalize(self, value: f32) -> f32 {
+        let info = self.info();
+        let value = value.clamp(info.min, info.max);
+        if info.logarithmic && info.min <= 0.0 {
+            let span = info.max - info.min;
+            (1.0 + (value - info.min) / span * (LOG_FROM_ZERO_RATIO - 1.0)).ln()
+                / LOG_FROM_ZERO_RATIO.ln()
+        } else if info.logarithmic {
+            (value / info.min).ln() / (info.max / info.min).ln()
+        } else {
+            (value - info.min) / (info.max - info.min)
+        }
+    }
+
+    /// Inverse of [`ParamId::normalize`].
+    pub fn denormalize(self, position: f32) -> f32 {
+        let info = self.info();
+        let position = position.clamp(0.0, 1.0);
+        if info.logarithmic && info.min <= 0.0 {
+            let span = info.max - info.min;
+            info.min
+                + span * (LOG_FROM_ZERO_RATIO.powf(position) - 1.0) / (LOG_FROM_ZERO_RATIO - 1.0)
+        } else if info.logarithmic {
+            info.min * (info.max / info.min).powf(position)
+        } else {
+            info.min + (info.max - info.min) * position
+        }
+    }
+
+    /// Value with its unit at a precision suited to its magnitude, e.g. "1240 Hz".
+    pub fn format(self, value: f32) -> String {
+        let digits = match value.abs() {
+            v if v >= 100.0 => 0,
+            v if v >= 10.0 => 1,
+            v if v >= 1.0 => 2,
+            _ => 3,
+        };
+        let unit = self.info().unit;
+        if unit.is_empty() {
+            format!("{value:.digits$}")
+        } else {
+            format!("{value:.digits$} {unit}")
+        }
+    }
+}
+
+/// Clamps every parameter of a patch read from disk or pasted in.
+pub fn clamp_params(params: &mut SynthParams) {
+    for id in ParamId::ALL {
+        let value = id.get_mut(params);
+        *value = id.clamp(*value);
+    }
+    params.polyphony = params.polyphony.clamp(1, MAX_POLYPHONY);
+    params.unison_voices = params.unison_voices.clamp(1, MAX_UNISON_VOICES);
+    params.a4_hz = if params.a4_hz.is_nan() {
+        440.0
+    } else {
+        params.a4_hz.clamp(*A4_RANGE.start(), *A4_RANGE.end())
+    };
+    params.transpose = params.transpose.clamp(-MAX_TRANSPOSE, MAX_TRANSPOSE);
+    params.harmonics.clamp();
+    params.drum_kit.clamp();
+    let unit = |value: f32, default: f32| {
+        if value.is_nan() {
+            default
+        } else {
+            value.clamp(0.0, 1.0)
+        }
+    };
+    params.sample_root_note = if params.sample_root_note.is_nan() {
+        60.0
+    } else {
+        params.sample_root_note.clamp(0.0, 127.0)
+    };
+    params.sample_loop_start = unit(params.sample_loop_start, 0.0);
+    params.sample_loop_end = unit(params.sample_loop_end, 1.0).max(params.sample_loop_start);
+}
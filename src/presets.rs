@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+
+use crate::additive::Harmonics;
+use crate::settings::tidy_floats;
+use crate::synth::{InstrumentKind, NotePriority, SynthParams, VoiceMode, Waveform};
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub params: SynthParams,
+}
+
+impl Preset {
+    pub fn new(name: impl Into<String>, params: SynthParams) -> Self {
+        Self {
+            name: name.into(),
+            params,
+        }
+    }
+}
+
+/// Built-in patches that ship with the app. User presets are appended after these.
+pub fn factory_presets() -> Vec<Preset> {
+    vec![
+        Preset::new("Init", SynthParams::default()),
+        Preset::new(
+            "Soft Keys",
+            SynthParams {
+                instrument: InstrumentKind::Keys,
+                waveform: Waveform::Triangle,
+                attack_seconds: 0.005,
+                decay_seconds: 0.45,
+                sustain_level: 0.5,
+                release_seconds: 0.5,
+                filter_cutoff_hz: 3_000.0,
+                filter_resonance: 0.1,
+                vibrato_depth_semitones: 0.05,
+                unison_spread_cents: 4.0,
+                noise_mix: 0.01,
+                velocity_tone: 1.5,
+                stereo_width: 0.3,
+                ..SynthParams::default()
+            },
+        ),
+        Preset::new(
+            "Sub Bass",
+            SynthParams {
+                instrument: InstrumentKind::Bass,
+                waveform: Waveform::Saw,
+                attack_seconds: 0.005,
+                decay_seconds: 0.25,
+                sustain_level: 0.8,
+                release_seconds: 0.15,
+                filter_cutoff_hz: 900.0,
+                filter_resonance: 0.3,
+                vibrato_depth_semitones: 0.0,
+                unison_spread_cents: 2.0,
+                noise_mix: 0.0,
+                eq_low_gain_db: 3.0,
+                velocity_tone: 1.0,
+                voice_mode: VoiceMode::Mono,
+                note_priority: NotePriority::Low,
+                ..SynthParams::default()
+            },
+        ),
+        Preset::new(
+            "Bright Lead",
+            SynthParams {
+                instrument: InstrumentKind::Lead,
+                waveform: Waveform::Saw,
+                attack_seconds: 0.01,
+                decay_seconds: 0.3,
+                sustain_level: 0.75,
+                release_seconds: 0.25,
+                filter_cutoff_hz: 9_000.0,
+                filter_resonance: 0.35,
+                vibrato_depth_semitones: 0.25,
+                vibrato_rate_hz: 5.5,
+                unison_spread_cents: 12.0,
+                noise_mix: 0.02,
+                eq_high_gain_db: 2.0,
+                velocity_tone: 1.0,
+                stereo_width: 0.4,
+                voice_mode: VoiceMode::Legato,
+                ..SynthParams::default()
+            },
+        ),
+        Preset::new(
+            "Warm Pad",
+            SynthParams {
+                instrument: InstrumentKind::Pad,
+                waveform: Waveform::Saw,
+                attack_seconds: 0.8,
+                decay_seconds: 1.2,
+                sustain_level: 0.8,
+                release_seconds: 2.5,
+                filter_cutoff_hz: 2_200.0,
+                filter_resonance: 0.15,
+                vibrato_depth_semitones: 0.08,
+                vibrato_rate_hz: 3.0,
+                unison_spread_cents: 18.0,
+                unison_voices: 5,
+                noise_mix: 0.05,
+                drift_amount: 0.4,
+                eq_high_gain_db: -3.0,
+                velocity_level: 0.6,
+                velocity_tone: 0.5,
+                stereo_width: 0.8,
+                ..SynthParams::default()
+            },
+        ),
+        Preset::new(
+            "PWM Pad",
+            SynthParams {
+                instrument: InstrumentKind::Pad,
+                waveform: Waveform::Square,
+                pulse_width: 0.5,
+                pwm_depth: 0.35,
+                pwm_rate_hz: 0.6,
+                attack_seconds: 0.6,
+                decay_seconds: 1.0,
+                sustain_level: 0.85,
+                release_seconds: 2.0,
+                filter_cutoff_hz: 2_800.0,
+                filter_resonance: 0.1,
+                vibrato_depth_semitones: 0.0,
+                unison_spread_cents: 8.0,
+                noise_mix: 0.0,
+                velocity_level: 0.6,
+                stereo_width: 0.7,
+                ..SynthParams::default()
+            },
+        ),
+        Preset::new(
+            "Drawbar Organ",
+            SynthParams {
+                instrument: InstrumentKind::Additive,
+                harmonics: Harmonics::organ(),
+                attack_seconds: 0.005,
+                decay_seconds: 0.1,
+                sustain_level: 1.0,
+                release_seconds: 0.08,
+                filter_cutoff_hz: 9_000.0,
+                filter_resonance: 0.0,
+                vibrato_depth_semitones: 0.1,
+                vibrato_rate_hz: 6.5,
+                unison_spread_cents: 0.0,
+                noise_mix: 0.0,
+                velocity_level: 0.0,
+                ..SynthParams::default()
+            },
+        ),
+        Preset::new(
+            "Drum Kit",
+            SynthParams {
+                instrument: InstrumentKind::Drums,
+                filter_cutoff_hz: 16_000.0,
+                filter_resonance: 0.0,
+                unison_spread_cents: 0.0,
+                noise_mix: 0.0,
+                velocity_level: 0.6,
+                stereo_width: 0.3,
+                ..SynthParams::default()
+            },
+        ),
+    ]
+}
+
+/// Factory presets followed by the user's saved presets.
+pub fn all_presets(user: &[Preset]) -> Vec<Preset> {
+    let mut presets = factory_presets();
+    presets.extend(user.iter().cloned());
+    presets
+}
+
+/// Interpolates every continuous parameter between `a` and `b`.
+/// Discrete choices (instrument, waveform) flip over at the halfway point.
+pub fn morph_params(a: &SynthParams, b: &SynthParams, t: f32) -> SynthParams {
+    let t = t.clamp(0.0, 1.0);
+    let lin = |x: f32, y: f32| x + (y - x) * t;
+    // Times and frequencies are perceived logarithmically, so glide them geometrically.
+    let geo = |x: f32, y: f32| {
+        if x > 0.0 && y > 0.0 {
+            x * (y / x).powf(t)
+        } else {
+            lin(x, y)
+        }
+    };
+    let first_half = t < 0.5;
+
+    SynthParams {
+        gain: lin(a.gain, b.gain),
+        delay_seconds: lin(a.delay_seconds, b.delay_seconds),
+        attack_seconds: geo(a.attack_seconds, b.attack_seconds),
+        hold_seconds: lin(a.hold_seconds, b.hold_seconds),
+        decay_seconds: geo(a.decay_seconds, b.decay_seconds),
+        sustain_level: lin(a.sustain_level, b.sustain_level),
+        release_seconds: geo(a.release_seconds, b.release_seconds),
+        env_curve: if first_half { a.env_curve } else { b.env_curve },
+        instrument: if first_half {
+            a.instrument
+        } else {
+            b.instrument
+        },
+        waveform: if first_half { a.waveform } else { b.waveform },
+        a4_hz: lin(a.a4_hz, b.a4_hz),
+        transpose: if first_half { a.transpose } else { b.transpose },
+        wavetable_position: lin(a.wavetable_position, b.wavetable_position),
+        wavetable: if first_half {
+            a.wavetable.clone()
+        } else {
+            b.wavetable.clone()
+        },
+        harmonics: Harmonics(std::array::from_fn(|index| {
+            lin(a.harmonics.0[index], b.harmonics.0[index])
+        })),
+        sample: if first_half {
+            a.sample.clone()
+        } else {
+            b.sample.clone()
+        },
+        sample_root_note: lin(a.sample_root_note, b.sample_root_note),
+        sample_loop: if first_half {
+            a.sample_loop
+        } else {
+            b.sample_loop
+        },
+        sample_loop_start: lin(a.sample_loop_start, b.sample_loop_start),
+        sample_loop_end: lin(a.sample_loop_end, b.sample_loop_end),
+        grain_size_ms: geo(a.grain_size_ms, b.grain_size_ms),
+        grain_density_hz: geo(a.grain_density_hz, b.grain_density_hz),
+        grain_position: lin(a.grain_position, b.grain_position),
+        grain_spray: lin(a.grain_spray, b.grain_spray),
+        drum_kit: if first_half {
+            a.drum_kit.clone()
+        } else {
+            b.drum_kit.clone()
+        },
+        soundfont: if first_half {
+            a.soundfont.clone()
+        } else {
+            b.soundfont.clone()
+        },
+        soundfont_preset: if first_half {
+            a.soundfont_preset
+        } else {
+            b.soundfont_preset
+        },
+        pulse_width: lin(a.pulse_width, b.pulse_width),
+        pwm_depth: lin(a.pwm_depth, b.pwm_depth),
+        pwm_rate_hz: geo(a.pwm_rate_hz, b.pwm_rate_hz),
+        filter_mode: if first_half {
+            a.filter_mode
+        } else {
+            b.filter_mode
+        },
+        filter_cutoff_hz: geo(a.filter_cutoff_hz, b.filter_cutoff_hz),
+        filter_resonance: lin(a.filter_resonance, b.filter_resonance),
+        filter_drive_db: lin(a.filter_drive_db, b.filter_drive_db),
+        vibrato_depth_semitones: lin(a.vibrato_depth_semitones, b.vibrato_depth_semitones),
+        vibrato_rate_hz: geo(a.vibrato_rate_hz, b.vibrato_rate_hz),
+        unison_spread_cents: lin(a.unison_spread_cents, b.unison_spread_cents),
+        unison_voices: if first_half {
+            a.unison_voices
+        } else {
+            b.unison_voices
+        },
+        unison_blend: lin(a.unison_blend, b.unison_blend),
+        autotune_amount: lin(a.autotune_amount, b.autotune_amount),
+        autotune_retune_ms: geo(a.autotune_retune_ms, b.autotune_retune_ms),
+        noise_mix: lin(a.noise_mix, b.noise_mix),
+        drift_amount: lin(a.drift_amount, b.drift_amount),
+        eq_low_gain_db: lin(a.eq_low_gain_db, b.eq_low_gain_db),
+        eq_low_freq_hz: geo(a.eq_low_freq_hz, b.eq_low_freq_hz),
+        eq_mid_gain_db: lin(a.eq_mid_gain_db, b.eq_mid_gain_db),
+        eq_mid_freq_hz: geo(a.eq_mid_freq_hz, b.eq_mid_freq_hz),
+        eq_mid_q: lin(a.eq_mid_q, b.eq_mid_q),
+        eq_high_gain_db: lin(a.eq_high_gain_db, b.eq_high_gain_db),
+        eq_high_freq_hz: geo(a.eq_high_freq_hz, b.eq_high_freq_hz),
+        glide_seconds: geo(a.glide_seconds, b.glide_seconds),
+        glide_always: if first_half {
+            a.glide_always
+        } else {
+            b.glide_always
+        },
+        voice_mode: if first_half {
+            a.voice_mode
+        } else {
+            b.voice_mode
+        },
+        polyphony: if first_half { a.polyphony } else { b.polyphony },
+        voice_steal: if first_half {
+            a.voice_steal
+        } else {
+            b.voice_steal
+        },
+        note_priority: if first_half {
+            a.note_priority
+        } else {
+            b.note_priority
+        },
+        key_track_level_db: lin(a.key_track_level_db, b.key_track_level_db),
+        key_track_tone: lin(a.key_track_tone, b.key_track_tone),
+        velocity_level: lin(a.velocity_level, b.velocity_level),
+        velocity_tone: lin(a.velocity_tone, b.velocity_tone),
+        pan: lin(a.pan, b.pan),
+        stereo_width: lin(a.stereo_width, b.stereo_width),
+    }
+}
+
+/// Marks a patch code so pasting any other text fails with a clear message.
+const PATCH_CODE_PREFIX: &str = "angel:";
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes a patch as a short line of text for sharing in chat. Only the
+/// parameters that differ from the defaults are written, so codes stay small
+/// and keep loading after new parameters are added.
+pub fn patch_code(params: &SynthParams) -> String {
+    let (Ok(toml::Value::Table(mut table)), Ok(toml::Value::Table(defaults))) = (
+        toml::Value::try_from(params),
+        toml::Value::try_from(SynthParams::default()),
+    ) else {
+        return String::new();
+    };
+    table.retain(|key, value| defaults.get(key) != Some(&*value));
+    let mut value = toml::Value::Table(table);
+    tidy_floats(&mut value);
+    let text = toml::to_string(&value).unwrap_or_default();
+    format!("{PATCH_CODE_PREFIX}{}", base64_encode(text.as_bytes()))
+}
+
+pub fn parse_patch_code(code: &str) -> Result<SynthParams, String> {
+    let body = code
+        .trim()
+        .strip_prefix(PATCH_CODE_PREFIX)
+        .ok_or("Not a patch code")?;
+    let text = base64_decode(body)
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or("The patch code is damaged")?;
+    toml::from_str(&text).map_err(|err| format!("Invalid patch: {err}"))
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0_u32, |group, (i, &byte)| {
+            group | (byte as u32) << (16 - 8 * i)
+        });
+        // Padding is left off; the decoder works out the length from the digit count.
+        for i in 0..=chunk.len() {
+            out.push(BASE64_ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Ignores whitespace and padding, since chat clients like to wrap long lines.
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let digits = text
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace() && *byte != b'=')
+        .map(|byte| BASE64_ALPHABET.iter().position(|&c| c == byte))
+        .collect::<Option<Vec<_>>>()?;
+    if digits.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        let group = chunk.iter().enumerate().fold(0_u32, |group, (i, &digit)| {
+            group | (digit as u32) << (18 - 6 * i)
+        });
+        for i in 0..chunk.len() - 1 {
+            out.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,143 @@
+use std::time::Instant;
+
+use crate::chords::chord_name;
+use crate::scales::Scale;
+use crate::synth::SynthShared;
+
+/// Common progressions as scale degrees, 0 being the tonic.
+const PROGRESSIONS: &[&[usize]] = &[
+    &[0, 4, 5, 3],
+    &[0, 5, 3, 4],
+    &[5, 3, 0, 4],
+    &[1, 4, 0],
+    &[0, 5, 1, 4],
+    &[0, 3, 0, 4],
+    &[0, 3, 4, 3],
+    &[0, 6, 5, 4],
+    &[2, 5, 1, 4],
+];
+
+pub const PROGRESSION_COUNT: usize = PROGRESSIONS.len();
+
+/// Lowest root a chord is voiced on; roots above it by more than a fifth
+/// drop an octave, so every chord sits around middle C.
+const VOICING_BASE: u8 = 60;
+const BEATS_PER_BAR: f32 = 4.0;
+const CHORD_VELOCITY: f32 = 0.7;
+
+/// A triad built from the scale's own notes on one of its degrees.
+pub struct DiatonicChord {
+    /// Root position, around middle C.
+    pub notes: Vec<u8>,
+    /// Roman numeral: upper case for major, lower for minor, ° diminished, + augmented.
+    pub numeral: String,
+    pub name: String,
+}
+
+/// The triad on `degree` of `scale`, stacking every other scale note.
+/// `None` for scales without seven notes, where stacked thirds don't apply.
+pub fn diatonic_chord(scale: Scale, degree: usize) -> Option<DiatonicChord> {
+    let intervals = scale.kind.intervals();
+    if intervals.len() != 7 {
+        return None;
+    }
+    let step = |offset: usize| {
+        let interval = intervals[(degree + offset) % 7];
+        let root = intervals[degree % 7];
+        (interval + 12 - root) % 12
+    };
+    let (third, fifth) = (step(2), step(4));
+    let root_class = (scale.root + intervals[degree % 7]) % 12;
+    let mut root = VOICING_BASE + root_class;
+    if root_class > 7 {
+        root -= 12;
+    }
+    let notes = vec![root, root + third, root + fifth];
+
+    const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+    let numeral = NUMERALS[degree % 7];
+    let numeral = match (third, fifth) {
+        (4, 7) => numeral.to_string(),
+        (3, 7) => numeral.to_lowercase(),
+        (3, 6) => format!("{}°", numeral.to_lowercase()),
+        (4, 8) => format!("{numeral}+"),
+        _ => numeral.to_string(),
+    };
+    let name = chord_name(&notes).unwrap_or_else(|| numeral.clone());
+    Some(DiatonicChord {
+        notes,
+        numeral,
+        name,
+    })
+}
+
+/// Progression `index` (wrapping) in the key of `scale`.
+pub fn progression(scale: Scale, index: usize) -> Option<Vec<DiatonicChord>> {
+    PROGRESSIONS[index % PROGRESSION_COUNT]
+        .iter()
+        .map(|degree| diatonic_chord(scale, *degree))
+        .collect()
+}
+
+/// "I – V – vi – IV" for progression `index` in `scale`.
+pub fn progression_label(scale: Scale, index: usize) -> String {
+    progression(scale, index)
+        .unwrap_or_default()
+        .iter()
+        .map(|chord| chord.numeral.as_str())
+        .collect::<Vec<_>>()
+        .join(" – ")
+}
+
+/// Plays chord pads while they're held, or steps through a progression one
+/// chord per bar at the global tempo.
+#[derive(Default)]
+pub struct ChordPlayer {
+    /// Pad held down this frame, while stopped.
+    pub pad: Option<usize>,
+    /// Chord currently sounding, for highlighting its pad.
+    pub current: Option<usize>,
+    started: Option<Instant>,
+    sounding: Vec<u8>,
+}
+
+impl ChordPlayer {
+    pub fn is_playing(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn start(&mut self) {
+        self.started = Some(Instant::now());
+    }
+
+    pub fn stop(&mut self) {
+        self.started = None;
+    }
+
+    /// Sounds whichever chord is due. Call once per frame.
+    pub fn poll(&mut self, chords: &[Vec<u8>], tempo_bpm: f32, shared: &mut SynthShared) {
+        self.current = match self.started {
+            Some(_) if chords.is_empty() => None,
+            Some(started) => {
+                let bar_seconds = BEATS_PER_BAR * 60.0 / tempo_bpm.max(1.0);
+                let bar = (started.elapsed().as_secs_f32() / bar_seconds) as usize;
+                Some(bar % chords.len())
+            }
+            None => self.pad,
+        };
+        let chord = self
+            .current
+            .and_then(|index| chords.get(index))
+            .map_or(&[][..], Vec::as_slice);
+        if self.sounding == chord {
+            return;
+        }
+        for note in self.sounding.iter().filter(|note| !chord.contains(note)) {
+            shared.release_note(*note);
+        }
+        for note in chord.iter().filter(|note| !self.sounding.contains(note)) {
+            shared.press_note(*note, CHORD_VELOCITY);
+        }
+        self.sounding = chord.to_vec();
+    }
+}
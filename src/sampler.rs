@@ -0,0 +1,110 @@
+use std::fs;
+
+use crate::files::{FileCache, FileRef, Loadable};
+use crate::mic::detect_note;
+use crate::synth::SynthParams;
+use crate::wav::{decode_mono, smpl_info};
+
+/// Longest recording kept, so a stray hour-long file can't fill memory.
+const MAX_SAMPLE_SECONDS: f32 = 60.0;
+/// How much of the start the root note is detected from.
+const DETECT_SECONDS: f32 = 2.0;
+
+/// A recording for the Sampler instrument, mixed down to one channel.
+pub struct Sample {
+    samples: Vec<f32>,
+    sample_rate: f32,
+    root_note: Option<f32>,
+    loop_points: Option<(f32, f32)>,
+}
+
+impl Sample {
+    /// Reads a WAV. The root note comes from its `smpl` chunk when there is
+    /// one, and is otherwise detected from the pitch of its start.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|err| format!("Could not read {path}: {err}"))?;
+        let wav = decode_mono(&bytes).map_err(|err| format!("{path}: {err}"))?;
+        if wav.sample_rate == 0 || wav.samples.is_empty() {
+            return Err(format!("{path} has no audio"));
+        }
+        let sample_rate = wav.sample_rate as f32;
+        let mut samples = wav.samples;
+        samples.truncate((MAX_SAMPLE_SECONDS * sample_rate) as usize);
+        let (smpl_root, smpl_loop) = smpl_info(&bytes);
+        let length = samples.len() as f32;
+        let loop_points = smpl_loop.map(|(start, end)| {
+            (
+                (start as f32 / length).min(1.0),
+                (end as f32 / length).min(1.0),
+            )
+        });
+        let root_note = smpl_root.or_else(|| {
+            let start = &samples[..samples.len().min((DETECT_SECONDS * sample_rate) as usize)];
+            detect_note(start, sample_rate)
+        });
+        Ok(Self {
+            samples,
+            sample_rate,
+            root_note,
+            loop_points,
+        })
+    }
+
+    pub fn length(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn seconds(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate
+    }
+
+    /// The note it was recorded at, as a fractional MIDI note, when the
+    /// file said or its pitch could be found.
+    pub fn root_note(&self) -> Option<f32> {
+        self.root_note
+    }
+
+    /// The file's own loop, as shares (0..1) of its length.
+    pub fn loop_points(&self) -> Option<(f32, f32)> {
+        self.loop_points
+    }
+
+    /// Linear interpolation at `pos` samples in; silent past either end.
+    pub fn at(&self, pos: f64) -> f32 {
+        if pos < 0.0 {
+            return 0.0;
+        }
+        let index = pos as usize;
+        let Some(&current) = self.samples.get(index) else {
+            return 0.0;
+        };
+        let next = self.samples.get(index + 1).copied().unwrap_or(0.0);
+        current + (next - current) * pos.fract() as f32
+    }
+}
+
+impl Loadable for Sample {
+    fn load(path: &str) -> Result<Self, String> {
+        Sample::load(path)
+    }
+}
+
+/// A sample WAV named by its path, with the recording attached once loaded.
+pub type SampleFile = FileRef<Sample>;
+
+pub type SampleCache = FileCache<Sample>;
+
+impl SampleCache {
+    /// Attaches the samples `params` names, for the Sampler and the drum
+    /// kit's pads, loading each the first time.
+    pub fn resolve(&mut self, params: &mut SynthParams) {
+        self.resolve_file(&mut params.sample);
+        for pad in &mut params.drum_kit.0 {
+            self.resolve_file(&mut pad.sample);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+use crate::chords::PITCH_CLASS_NAMES;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleKind {
+    Major,
+    NaturalMinor,
+    HarmonicMinor,
+    Dorian,
+    Mixolydian,
+    MajorPentatonic,
+    MinorPentatonic,
+    Blues,
+}
+
+impl ScaleKind {
+    pub const ALL: [ScaleKind; 8] = [
+        ScaleKind::Major,
+        ScaleKind::NaturalMinor,
+        ScaleKind::HarmonicMinor,
+        ScaleKind::Dorian,
+        ScaleKind::Mixolydian,
+        ScaleKind::MajorPentatonic,
+        ScaleKind::MinorPentatonic,
+        ScaleKind::Blues,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ScaleKind::Major => "Major",
+            ScaleKind::NaturalMinor => "Minor",
+            ScaleKind::HarmonicMinor => "Harmonic minor",
+            ScaleKind::Dorian => "Dorian",
+            ScaleKind::Mixolydian => "Mixolydian",
+            ScaleKind::MajorPentatonic => "Major pentatonic",
+            ScaleKind::MinorPentatonic => "Minor pentatonic",
+            ScaleKind::Blues => "Blues",
+        }
+    }
+
+    /// Semitones above the root.
+    pub fn intervals(self) -> &'static [u8] {
+        match self {
+            ScaleKind::Major => &[0, 2, 4, 5, 7, 9, 11],
+            ScaleKind::NaturalMinor => &[0, 2, 3, 5, 7, 8, 10],
+            ScaleKind::HarmonicMinor => &[0, 2, 3, 5, 7, 8, 11],
+            ScaleKind::Dorian => &[0, 2, 3, 5, 7, 9, 10],
+            ScaleKind::Mixolydian => &[0, 2, 4, 5, 7, 9, 10],
+            ScaleKind::MajorPentatonic => &[0, 2, 4, 7, 9],
+            ScaleKind::MinorPentatonic => &[0, 3, 5, 7, 10],
+            ScaleKind::Blues => &[0, 3, 5, 6, 7, 10],
+        }
+    }
+}
+
+/// A key and scale, e.g. A minor. `root` is a pitch class, 0 = C.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scale {
+    pub root: u8,
+    pub kind: ScaleKind,
+}
+
+impl Scale {
+    pub fn label(&self) -> String {
+        format!(
+            "{} {}",
+            PITCH_CLASS_NAMES[(self.root % 12) as usize],
+            self.kind.label()
+        )
+    }
+
+    pub fn contains(&self, note: u8) -> bool {
+        let interval = (note + 12 - self.root % 12) % 12;
+        self.kind.intervals().contains(&interval)
+    }
+
+    pub fn is_root(&self, note: u8) -> bool {
+        note % 12 == self.root % 12
+    }
+
+    /// The scale note closest to `pitch`, a fractional MIDI note number.
+    pub fn nearest_note(&self, pitch: f32) -> f32 {
+        let centre = pitch.round() as i32;
+        (centre - 6..=centre + 6)
+            .filter(|note| {
+                let interval = (note - self.root as i32).rem_euclid(12) as u8;
+                self.kind.intervals().contains(&interval)
+            })
+            .map(|note| note as f32)
+            .min_by(|a, b| (a - pitch).abs().total_cmp(&(b - pitch).abs()))
+            .unwrap_or(centre as f32)
+    }
+}
@@ -1,29 +1,200 @@
-use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
 
-/// Ring buffer storing the latest waveform samples for visualization.
+use crate::png;
+
+/// Samples kept for the scope: enough for the widest time base plus room
+/// before it to search for a trigger point.
+pub const SCOPE_CAPACITY: usize = 1 << 16;
+/// Samples folded into each min/max pair of the envelope history.
+pub const ENVELOPE_DECIMATION: usize = 64;
+/// Min/max pairs kept: about 20 seconds at 48 kHz.
+const ENVELOPE_CAPACITY: usize = 1 << 14;
+/// Windows before the newest one searched for a trigger point.
+const TRIGGER_SEARCH_WINDOWS: usize = 4;
+
+/// Single-producer ring of the latest waveform samples for visualization.
+/// The audio thread writes without locking or allocating; the UI copies out
+/// what it draws. A read that races a full lap of the writer can show a few
+/// torn samples, which is harmless for a display.
 pub struct ScopeBuffer {
-    samples: VecDeque<f32>,
-    capacity: usize,
+    /// `f32` bits, so both sides can touch a slot without a lock.
+    samples: Box<[AtomicU32]>,
+    /// Samples written so far; slot `written % capacity` is the next one.
+    written: AtomicUsize,
+    /// Min/max of every `ENVELOPE_DECIMATION` samples, packed by `pack_pair`,
+    /// for the envelope view of whole phrases.
+    envelope: Box<[AtomicU64]>,
+    /// The pair still being gathered. Only the audio thread touches it.
+    bucket: AtomicU64,
 }
 
 impl ScopeBuffer {
     pub fn new(capacity: usize) -> Self {
         Self {
-            samples: VecDeque::with_capacity(capacity),
-            capacity,
+            samples: (0..capacity.max(1)).map(|_| AtomicU32::new(0)).collect(),
+            written: AtomicUsize::new(0),
+            envelope: (0..ENVELOPE_CAPACITY).map(|_| AtomicU64::new(0)).collect(),
+            bucket: AtomicU64::new(0),
         }
     }
 
-    pub fn record(&mut self, block: &[f32]) {
-        for &sample in block {
-            if self.samples.len() == self.capacity {
-                self.samples.pop_front();
-            }
-            self.samples.push_back(sample);
+    /// Appends one sample. Only the audio thread may call this.
+    pub fn push(&self, sample: f32) {
+        let written = self.written.load(Ordering::Relaxed);
+        self.samples[written % self.samples.len()].store(sample.to_bits(), Ordering::Relaxed);
+        let (low, high) = if written.is_multiple_of(ENVELOPE_DECIMATION) {
+            (sample, sample)
+        } else {
+            let (low, high) = unpack_pair(self.bucket.load(Ordering::Relaxed));
+            (low.min(sample), high.max(sample))
+        };
+        let pair = pack_pair(low, high);
+        if (written + 1).is_multiple_of(ENVELOPE_DECIMATION) {
+            let slot = written / ENVELOPE_DECIMATION % ENVELOPE_CAPACITY;
+            self.envelope[slot].store(pair, Ordering::Relaxed);
+        } else {
+            self.bucket.store(pair, Ordering::Relaxed);
         }
+        // Publishes the sample to readers that load `written` with Acquire.
+        self.written.store(written + 1, Ordering::Release);
+    }
+
+    /// The newest `window` samples, oldest first.
+    pub fn latest(&self, window: usize) -> Vec<f32> {
+        self.copy_recent(window)
+    }
+
+    /// `window` samples starting at the most recent rising zero crossing that
+    /// still has a full window after it, so a periodic wave lands at the same
+    /// phase every frame. `None` when nothing crosses (silence or DC).
+    /// Only the last few windows are searched and only the window found is
+    /// copied, so a frame doesn't copy the whole ring.
+    pub fn triggered(&self, window: usize) -> Option<Vec<f32>> {
+        let written = self.written.load(Ordering::Acquire);
+        let capacity = self.samples.len();
+        let oldest = written.saturating_sub(capacity);
+        let latest_start = written.checked_sub(window)?;
+        // The crossing needs the sample before it still in the ring.
+        let earliest_start = latest_start
+            .saturating_sub(window * TRIGGER_SEARCH_WINDOWS)
+            .max(oldest + 1);
+        let sample =
+            |index: usize| f32::from_bits(self.samples[index % capacity].load(Ordering::Relaxed));
+        let start = (earliest_start..=latest_start)
+            .rev()
+            .find(|&i| sample(i - 1) <= 0.0 && sample(i) > 0.0)?;
+        Some((start..start + window).map(sample).collect())
     }
 
-    pub fn snapshot(&self) -> Vec<f32> {
-        self.samples.iter().copied().collect()
+    /// Min/max pairs covering the newest `window` samples, merged down to at
+    /// most `columns` pairs, oldest first; one per pixel column when drawn.
+    pub fn envelope(&self, window: usize, columns: usize) -> Vec<(f32, f32)> {
+        let filled = self.written.load(Ordering::Acquire) / ENVELOPE_DECIMATION;
+        let count = (window / ENVELOPE_DECIMATION)
+            .min(filled)
+            .min(ENVELOPE_CAPACITY);
+        let pairs: Vec<(f32, f32)> = (filled - count..filled)
+            .map(|index| {
+                unpack_pair(self.envelope[index % ENVELOPE_CAPACITY].load(Ordering::Relaxed))
+            })
+            .collect();
+        let columns = columns.clamp(1, pairs.len().max(1));
+        (0..columns)
+            .filter_map(|column| {
+                let start = column * pairs.len() / columns;
+                let end = ((column + 1) * pairs.len() / columns).max(start + 1);
+                pairs
+                    .get(start..end)?
+                    .iter()
+                    .copied()
+                    .reduce(|(low, high), (min, max)| (low.min(min), high.max(max)))
+            })
+            .collect()
     }
+
+    /// Up to `count` of the newest samples, oldest first.
+    fn copy_recent(&self, count: usize) -> Vec<f32> {
+        let written = self.written.load(Ordering::Acquire);
+        let capacity = self.samples.len();
+        let count = count.min(written).min(capacity);
+        (written - count..written)
+            .map(|index| f32::from_bits(self.samples[index % capacity].load(Ordering::Relaxed)))
+            .collect()
+    }
+}
+
+/// Packs a min/max pair into one word so readers never see half of one.
+fn pack_pair(low: f32, high: f32) -> u64 {
+    (u64::from(high.to_bits()) << 32) | u64::from(low.to_bits())
+}
+
+fn unpack_pair(pair: u64) -> (f32, f32) {
+    (
+        f32::from_bits(pair as u32),
+        f32::from_bits((pair >> 32) as u32),
+    )
+}
+
+/// Writes a trace as `index,time_ms,sample` rows.
+pub fn export_csv(path: &Path, samples: &[f32], sample_rate: f32) -> Result<(), String> {
+    let mut csv = String::from("index,time_ms,sample\n");
+    for (index, sample) in samples.iter().enumerate() {
+        let time_ms = index as f32 * 1000.0 / sample_rate;
+        let _ = writeln!(csv, "{index},{time_ms:.4},{sample:.6}");
+    }
+    fs::write(path, csv).map_err(|err| format!("Failed to write {}: {err}", path.display()))
+}
+
+/// Renders a trace the way the scope card draws it and saves it as a PNG.
+pub fn export_png(path: &Path, samples: &[f32], gain: f32) -> Result<(), String> {
+    const WIDTH: usize = 1024;
+    const HEIGHT: usize = 256;
+    const BACKGROUND: [u8; 3] = [24, 24, 24];
+    const GRID: [u8; 3] = [60, 60, 60];
+    const TRACE: [u8; 3] = [255, 140, 0];
+
+    let mut pixels = [BACKGROUND].repeat(WIDTH * HEIGHT);
+    for division in 1..10 {
+        let x = division * WIDTH / 10;
+        for y in 0..HEIGHT {
+            pixels[y * WIDTH + x] = GRID;
+        }
+    }
+    for x in 0..WIDTH {
+        pixels[HEIGHT / 2 * WIDTH + x] = GRID;
+    }
+
+    let to_y = |sample: f32| {
+        let norm = ((sample * gain).clamp(-1.0, 1.0) + 1.0) * 0.5;
+        ((1.0 - norm) * (HEIGHT - 1) as f32).round() as usize
+    };
+    let last = samples.len().saturating_sub(1).max(1);
+    let mut prev: Option<(usize, usize)> = None;
+    for (index, sample) in samples.iter().enumerate() {
+        let x = index * (WIDTH - 1) / last;
+        let y = to_y(*sample);
+        let (from_x, from_y) = prev.unwrap_or((x, y));
+        // Interpolate across the columns since the previous point and fill each
+        // column's vertical span, so steep edges stay connected.
+        let mut column_y = from_y;
+        for column in from_x..=x {
+            let t = if x == from_x {
+                1.0
+            } else {
+                (column - from_x) as f32 / (x - from_x) as f32
+            };
+            let next_y = (from_y as f32 + (y as f32 - from_y as f32) * t).round() as usize;
+            for row in column_y.min(next_y)..=column_y.max(next_y) {
+                pixels[row * WIDTH + column] = TRACE;
+            }
+            column_y = next_y;
+        }
+        prev = Some((x, y));
+    }
+
+    let png = png::encode_rgb(WIDTH as u32, HEIGHT as u32, pixels.as_flattened());
+    fs::write(path, png).map_err(|err| format!("Failed to write {}: {err}", path.display()))
 }
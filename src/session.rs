@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{config_dir, to_tidy_toml, write_atomic};
+use crate::synth::SynthParams;
+
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Snapshot of the live session, written periodically while the app runs and
+/// removed on a clean exit. Finding one at startup means the last run crashed.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub saved_at_unix: u64,
+    pub params: SynthParams,
+    pub octave_offset: i32,
+    pub morph_preset_a: usize,
+    pub morph_preset_b: usize,
+    pub morph_amount: f32,
+}
+
+impl Session {
+    /// Seconds since the snapshot was written, for the restore prompt.
+    pub fn age_seconds(&self) -> u64 {
+        unix_now().saturating_sub(self.saved_at_unix)
+    }
+}
+
+pub fn recovery_path() -> PathBuf {
+    config_dir().join("recovery.toml")
+}
+
+pub fn load_recovery(path: &Path) -> Option<Session> {
+    let raw = fs::read_to_string(path).ok()?;
+    toml::from_str(&raw).ok()
+}
+
+pub fn save_recovery(path: &Path, session: &Session) {
+    let mut session = session.clone();
+    session.saved_at_unix = unix_now();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(err) = write_atomic(path, &to_tidy_toml(&session)) {
+        log::error!("Failed to autosave session to {}: {err}", path.display());
+    }
+}
+
+pub fn clear_recovery(path: &Path) {
+    let _ = fs::remove_file(path);
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -1,9 +1,33 @@
 use std::fs;
+use std::io::Write;
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
-use crate::synth::{InstrumentKind, SynthParams, Waveform};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+use crate::controllers::{BreathRouting, CcMapping};
+use crate::ear::EarStats;
+use crate::effects::{MAX_DELAY_SECONDS, SendBuses};
+use crate::i18n::Language;
+use crate::keymap::Keymap;
+use crate::limiter::{CEILING_RANGE_DB, LimiterSettings};
+use crate::mic::MicMode;
+use crate::params::{ParamId, clamp_params};
+use crate::presets::{Preset, all_presets};
+use crate::progressions::PROGRESSION_COUNT;
+use crate::scales::Scale;
+use crate::synth::{
+    Drone, InstrumentKind, KeyboardSplit, NoteSource, Part, PartMix, PatchLayer, SynthParams,
+    Waveform,
+};
+use crate::theory::TheoryOverlay;
+use crate::tuning::Temperament;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ThemeKind {
     Fl,
     Light,
@@ -20,27 +44,175 @@ pub enum ThemeKind {
     Industrial,
     Candy,
     Terminal,
+    HighContrast,
+}
+
+/// A movable section of the main window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PanelKind {
+    Scope,
+    Keyboard,
+    XyPad,
+    Tone,
+    Motion,
+    Eq,
+    Harmonics,
+    Granular,
+    Morph,
+    Progressions,
+    Mixer,
+    Stats,
+    Diagnostics,
+    Layout,
+}
+
+/// Where a panel sits: the central area, a resizable dock on one edge, or its own window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DockSlot {
+    Main,
+    Left,
+    Right,
+    Bottom,
+    Floating,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum LayoutMode {
-    Auto,
-    Stacked,
-    TwoColumn,
-    ThreeColumn,
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PanelPlacement {
+    pub panel: PanelKind,
+    pub slot: DockSlot,
+    /// Only the card's title bar is shown.
+    #[serde(default)]
+    pub collapsed: bool,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum KeybindScheme {
     Default,
     Vim,
     Emacs,
     Sublime,
+    #[serde(rename = "vscode")]
     VSCode,
 }
 
+/// Which octave number middle C (MIDI note 60) is shown with; DAWs and
+/// regions disagree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OctaveNaming {
+    C3,
+    #[default]
+    C4,
+    C5,
+}
+
+/// How much goes to the log file and stderr.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Errors,
+    Warnings,
+    #[default]
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 4] = [
+        LogLevel::Errors,
+        LogLevel::Warnings,
+        LogLevel::Info,
+        LogLevel::Debug,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LogLevel::Errors => "Errors only",
+            LogLevel::Warnings => "Warnings",
+            LogLevel::Info => "Info",
+            LogLevel::Debug => "Debug",
+        }
+    }
+}
+
+/// What the ribbon strip under the piano plays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RibbonMode {
+    #[default]
+    Off,
+    /// Continuous pitch across the piano's range.
+    Pitch,
+    /// Sweeps `ribbon_param` from its minimum to its maximum.
+    Param,
+}
+
+impl RibbonMode {
+    pub const ALL: [RibbonMode; 3] = [RibbonMode::Off, RibbonMode::Pitch, RibbonMode::Param];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RibbonMode::Off => "Off",
+            RibbonMode::Pitch => "Pitch",
+            RibbonMode::Param => "Parameter",
+        }
+    }
+}
+
+/// Which patch is loaded when the app starts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupPatch {
+    LastUsed,
+    Default,
+    Preset,
+}
+
+/// What colours a sounding piano key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyColoring {
+    /// The theme's selection colour.
+    #[default]
+    Plain,
+    /// A hue per pitch class, the same in every octave.
+    PitchClass,
+    /// Brightness follows the voice's envelope and velocity, release included.
+    Intensity,
+}
+
+impl KeyColoring {
+    pub const ALL: [KeyColoring; 3] = [
+        KeyColoring::Plain,
+        KeyColoring::PitchClass,
+        KeyColoring::Intensity,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            KeyColoring::Plain => "Plain",
+            KeyColoring::PitchClass => "By pitch class",
+            KeyColoring::Intensity => "By voice level",
+        }
+    }
+}
+
+/// Size of the on-screen piano. `Custom` uses the explicit note bounds in `AppSettings`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyboardRange {
+    Keys25,
+    Keys49,
+    Keys61,
+    Keys88,
+    Custom,
+}
+
 impl ThemeKind {
-    pub const ALL: [ThemeKind; 15] = [
+    pub const ALL: [ThemeKind; 16] = [
         ThemeKind::Fl,
         ThemeKind::Light,
         ThemeKind::Midnight,
@@ -56,6 +228,7 @@ impl ThemeKind {
         ThemeKind::Industrial,
         ThemeKind::Candy,
         ThemeKind::Terminal,
+        ThemeKind::HighContrast,
     ];
 
     pub fn label(self) -> &'static str {
@@ -75,6 +248,7 @@ impl ThemeKind {
             ThemeKind::Industrial => "Industrial",
             ThemeKind::Candy => "Candy",
             ThemeKind::Terminal => "Terminal",
+            ThemeKind::HighContrast => "High Contrast",
         }
     }
 
@@ -94,67 +268,118 @@ impl ThemeKind {
             "industrial" => ThemeKind::Industrial,
             "candy" => ThemeKind::Candy,
             "terminal" => ThemeKind::Terminal,
+            "high_contrast" | "high-contrast" => ThemeKind::HighContrast,
             _ => ThemeKind::Fl,
         }
     }
+}
 
-    pub fn as_key(self) -> &'static str {
-        match self {
-            ThemeKind::Fl => "fl",
-            ThemeKind::Light => "light",
-            ThemeKind::Midnight => "midnight",
-            ThemeKind::Sunset => "sunset",
-            ThemeKind::Neon => "neon",
-            ThemeKind::Forest => "forest",
-            ThemeKind::Ocean => "ocean",
-            ThemeKind::Vapor => "vapor",
-            ThemeKind::Mono => "mono",
-            ThemeKind::Pastel => "pastel",
-            ThemeKind::SolarizedDark => "solarized_dark",
-            ThemeKind::SolarizedLight => "solarized_light",
-            ThemeKind::Industrial => "industrial",
-            ThemeKind::Candy => "candy",
-            ThemeKind::Terminal => "terminal",
-        }
-    }
-}
-
-impl LayoutMode {
-    pub const ALL: [LayoutMode; 3] = [
-        LayoutMode::Stacked,
-        LayoutMode::TwoColumn,
-        LayoutMode::ThreeColumn,
-    ];
+impl OctaveNaming {
+    pub const ALL: [OctaveNaming; 3] = [OctaveNaming::C3, OctaveNaming::C4, OctaveNaming::C5];
 
     pub fn label(self) -> &'static str {
         match self {
-            LayoutMode::Auto => "Auto",
-            LayoutMode::Stacked => "Stacked",
-            LayoutMode::TwoColumn => "Two columns",
-            LayoutMode::ThreeColumn => "Three columns",
+            OctaveNaming::C3 => "C3 (Yamaha, Cubase, Ableton)",
+            OctaveNaming::C4 => "C4 (scientific pitch)",
+            OctaveNaming::C5 => "C5 (FL Studio)",
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
-        match s.to_ascii_lowercase().as_str() {
-            "auto" => LayoutMode::Auto,
-            "stacked" => LayoutMode::Stacked,
-            "two_column" | "two-column" => LayoutMode::TwoColumn,
-            "three_column" | "three-column" => LayoutMode::ThreeColumn,
-            _ => LayoutMode::Auto,
+    /// Octave number of MIDI notes 0–11.
+    pub fn lowest_octave(self) -> i32 {
+        match self {
+            OctaveNaming::C3 => -2,
+            OctaveNaming::C4 => -1,
+            OctaveNaming::C5 => 0,
+        }
+    }
+}
+
+impl PanelKind {
+    pub const ALL: [PanelKind; 14] = [
+        PanelKind::Scope,
+        PanelKind::Keyboard,
+        PanelKind::XyPad,
+        PanelKind::Tone,
+        PanelKind::Motion,
+        PanelKind::Eq,
+        PanelKind::Harmonics,
+        PanelKind::Granular,
+        PanelKind::Morph,
+        PanelKind::Progressions,
+        PanelKind::Mixer,
+        PanelKind::Stats,
+        PanelKind::Diagnostics,
+        PanelKind::Layout,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PanelKind::Scope => "Wave Scope",
+            PanelKind::Keyboard => "Keyboard",
+            PanelKind::XyPad => "XY Pad",
+            PanelKind::Tone => "Tone & Filter",
+            PanelKind::Motion => "Motion & Noise",
+            PanelKind::Eq => "EQ",
+            PanelKind::Harmonics => "Harmonics",
+            PanelKind::Granular => "Granular",
+            PanelKind::Morph => "Preset Morph",
+            PanelKind::Progressions => "Progressions",
+            PanelKind::Mixer => "Mixer",
+            PanelKind::Stats => "Practice Stats",
+            PanelKind::Diagnostics => "Diagnostics",
+            PanelKind::Layout => "Layout",
         }
     }
+}
 
-    pub fn as_key(self) -> &'static str {
+impl DockSlot {
+    pub const ALL: [DockSlot; 5] = [
+        DockSlot::Main,
+        DockSlot::Left,
+        DockSlot::Right,
+        DockSlot::Bottom,
+        DockSlot::Floating,
+    ];
+
+    pub fn label(self) -> &'static str {
         match self {
-            LayoutMode::Auto => "auto",
-            LayoutMode::Stacked => "stacked",
-            LayoutMode::TwoColumn => "two_column",
-            LayoutMode::ThreeColumn => "three_column",
+            DockSlot::Main => "Main",
+            DockSlot::Left => "Left dock",
+            DockSlot::Right => "Right dock",
+            DockSlot::Bottom => "Bottom dock",
+            DockSlot::Floating => "Window",
         }
     }
 }
 
+fn default_panels() -> Vec<PanelPlacement> {
+    [
+        (PanelKind::Tone, DockSlot::Left),
+        (PanelKind::Motion, DockSlot::Left),
+        (PanelKind::Harmonics, DockSlot::Left),
+        (PanelKind::Granular, DockSlot::Left),
+        (PanelKind::Scope, DockSlot::Main),
+        (PanelKind::Keyboard, DockSlot::Main),
+        (PanelKind::Eq, DockSlot::Right),
+        (PanelKind::XyPad, DockSlot::Right),
+        (PanelKind::Morph, DockSlot::Right),
+        (PanelKind::Layout, DockSlot::Right),
+        (PanelKind::Stats, DockSlot::Right),
+        (PanelKind::Progressions, DockSlot::Bottom),
+        (PanelKind::Mixer, DockSlot::Bottom),
+        (PanelKind::Diagnostics, DockSlot::Bottom),
+    ]
+    .into_iter()
+    .map(|(panel, slot)| PanelPlacement {
+        panel,
+        slot,
+        // Only needed when something goes wrong.
+        collapsed: panel == PanelKind::Diagnostics,
+    })
+    .collect()
+}
+
 impl KeybindScheme {
     pub const ALL: [KeybindScheme; 5] = [
         KeybindScheme::Default,
@@ -183,87 +408,696 @@ impl KeybindScheme {
             _ => KeybindScheme::Default,
         }
     }
+}
+
+/// Bumped whenever the on-disk layout changes; `migrate` upgrades older files.
+/// Version 0 is the pre-TOML `key=value` format.
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Furthest the piano zooms in, relative to fitting the whole range.
+pub const MAX_KEYBOARD_ZOOM: f32 = 4.0;
+
+impl StartupPatch {
+    pub const ALL: [StartupPatch; 3] = [
+        StartupPatch::LastUsed,
+        StartupPatch::Default,
+        StartupPatch::Preset,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StartupPatch::LastUsed => "Last used patch",
+            StartupPatch::Default => "Default patch",
+            StartupPatch::Preset => "Startup preset",
+        }
+    }
+}
 
-    pub fn as_key(self) -> &'static str {
+impl KeyboardRange {
+    pub const ALL: [KeyboardRange; 5] = [
+        KeyboardRange::Keys25,
+        KeyboardRange::Keys49,
+        KeyboardRange::Keys61,
+        KeyboardRange::Keys88,
+        KeyboardRange::Custom,
+    ];
+
+    pub fn label(self) -> &'static str {
         match self {
-            KeybindScheme::Default => "default",
-            KeybindScheme::Vim => "vim",
-            KeybindScheme::Emacs => "emacs",
-            KeybindScheme::Sublime => "sublime",
-            KeybindScheme::VSCode => "vscode",
+            KeyboardRange::Keys25 => "25 keys (C3–C5)",
+            KeyboardRange::Keys49 => "49 keys (C2–C6)",
+            KeyboardRange::Keys61 => "61 keys (C2–C7)",
+            KeyboardRange::Keys88 => "88 keys (A0–C8)",
+            KeyboardRange::Custom => "Custom",
+        }
+    }
+
+    /// Lowest and highest MIDI note of the preset ranges.
+    pub fn notes(self) -> Option<(u8, u8)> {
+        match self {
+            KeyboardRange::Keys25 => Some((48, 72)),
+            KeyboardRange::Keys49 => Some((36, 84)),
+            KeyboardRange::Keys61 => Some((36, 96)),
+            KeyboardRange::Keys88 => Some((21, 108)),
+            KeyboardRange::Custom => None,
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppSettings {
+    pub version: u32,
     pub theme: ThemeKind,
-    pub params: SynthParams,
+    pub language: Language,
+    pub octave_naming: OctaveNaming,
+    pub log_level: LogLevel,
+    /// Keep the main window above other applications.
+    pub always_on_top: bool,
     pub output_device: Option<String>,
-    pub layout_mode: LayoutMode,
+    /// Capture from the microphone; `input_device` of `None` is the system default.
+    pub mic_enabled: bool,
+    pub input_device: Option<String>,
+    /// Play from a MIDI port; `midi_port` of `None` is the first one found.
+    pub midi_enabled: bool,
+    pub midi_port: Option<String>,
+    /// Semitones the pitch bend reaches at either end.
+    pub bend_range: f32,
+    /// Temperament the keyboard is tuned to, unless a Scala file is set.
+    pub temperament: Temperament,
+    /// Scala file the keyboard is tuned to in place of the temperament.
+    pub tuning_file: Option<String>,
+    /// Key the tuning's first degree sits on, at its equal-tempered pitch.
+    pub tuning_root: u8,
+    pub mic_mode: MicMode,
+    /// Levels of the synth and the raw mic in a recorded take.
+    pub take_synth_level: f32,
+    pub take_mic_level: f32,
+    /// How long a bounce holds the notes before their tails ring out.
+    pub bounce_seconds: f32,
+    /// Folder bounces are written to; the home folder when empty.
+    pub bounce_dir: String,
+    /// Panel arrangement, in display order within each slot.
+    pub panels: Vec<PanelPlacement>,
+    pub dock_left_width: f32,
+    pub dock_right_width: f32,
+    pub dock_bottom_height: f32,
     pub card_padding: f32,
     pub card_rounding: f32,
     pub scope_height: f32,
+    /// Start each scope frame on a rising zero crossing instead of free-running.
+    pub scope_trigger: bool,
+    /// Scope time base; the trace spans ten divisions.
+    pub scope_ms_per_div: f32,
+    /// Vertical scope magnification.
+    pub scope_gain: f32,
+    /// Draw the min/max envelope of the last few seconds instead of the waveform.
+    pub scope_envelope: bool,
+    /// Span of the envelope view.
+    pub scope_envelope_seconds: f32,
+    /// The scope and meters live in their own window.
+    pub scope_popped_out: bool,
     pub keyboard_scale: f32,
+    /// Taller keys and finger-sized controls for touch screens.
+    pub touch_layout: bool,
+    /// Horizontal piano zoom; 1 fits the whole range in the panel.
+    pub keyboard_zoom: f32,
+    /// Overlay the mapped computer key on each piano key.
+    pub show_key_labels: bool,
+    /// Key and scale highlighted on the piano.
+    pub scale: Option<Scale>,
+    /// Interval labels and chord tones drawn over the piano, or off.
+    pub theory: Option<TheoryOverlay>,
+    /// Pedal tone held under everything played, or off.
+    pub drone: Option<Drone>,
+    pub key_coloring: KeyColoring,
+    /// Global tempo, in beats per minute.
+    pub tempo_bpm: f32,
+    /// Chosen chord progression, an index into the suggestions.
+    pub progression: usize,
+    /// Ear training streaks and totals.
+    pub ear_stats: EarStats,
+    pub keyboard_range: KeyboardRange,
+    pub custom_lowest_note: u8,
+    pub custom_highest_note: u8,
+    /// Dragging across the piano slides one note instead of retriggering each key.
+    pub mouse_glide: bool,
+    pub ribbon_mode: RibbonMode,
+    /// Parameter swept by the ribbon in `RibbonMode::Param`.
+    pub ribbon_param: ParamId,
+    /// Snap ribbon pitch to the selected scale, or to semitones without one.
+    pub ribbon_quantize: bool,
+    /// Parameters driven by the XY pad's horizontal and vertical axes.
+    pub xy_pad_x: ParamId,
+    pub xy_pad_y: ParamId,
+    /// MIDI control changes and the parameters they drive.
+    pub cc_mappings: Vec<CcMapping>,
+    /// What a breath controller (CC 2) drives.
+    pub breath: BreathRouting,
     pub keybinds: KeybindScheme,
+    pub keymaps: Vec<Keymap>,
+    pub active_keymap: Option<String>,
+    pub startup_patch: StartupPatch,
+    pub startup_preset: String,
+    pub params: SynthParams,
+    /// Lower keyboard zone and the patch it plays, when the keyboard is split.
+    pub split: Option<KeyboardSplit>,
+    /// Mixer strip of the main patch.
+    pub main_mix: PartMix,
+    /// Second patch stacked over the whole keyboard.
+    pub layer: Option<PatchLayer>,
+    /// Parts added in the mixer, each with its own patch and key range.
+    pub added_parts: Vec<Part>,
+    /// The reverb and delay the parts send to.
+    pub buses: SendBuses,
+    /// The brick-wall limiter on the final mix.
+    pub limiter: LimiterSettings,
+    pub user_presets: Vec<Preset>,
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
+            version: SETTINGS_VERSION,
             theme: ThemeKind::Fl,
-            params: SynthParams::default(),
+            language: Language::default(),
+            octave_naming: OctaveNaming::default(),
+            log_level: LogLevel::default(),
+            always_on_top: false,
             output_device: None,
-            layout_mode: LayoutMode::Auto,
+            mic_enabled: false,
+            input_device: None,
+            midi_enabled: true,
+            midi_port: None,
+            bend_range: 2.0,
+            temperament: Temperament::default(),
+            tuning_file: None,
+            tuning_root: 60,
+            mic_mode: MicMode::default(),
+            take_synth_level: 1.0,
+            take_mic_level: 1.0,
+            bounce_seconds: 2.0,
+            bounce_dir: String::new(),
+            panels: default_panels(),
+            dock_left_width: 320.0,
+            dock_right_width: 340.0,
+            dock_bottom_height: 240.0,
             card_padding: 12.0,
             card_rounding: 8.0,
             scope_height: 140.0,
+            scope_trigger: true,
+            scope_ms_per_div: 2.0,
+            scope_gain: 1.0,
+            scope_envelope: false,
+            scope_envelope_seconds: 4.0,
+            scope_popped_out: false,
             keyboard_scale: 1.0,
+            touch_layout: false,
+            keyboard_zoom: 1.0,
+            show_key_labels: false,
+            scale: None,
+            theory: None,
+            drone: None,
+            key_coloring: KeyColoring::default(),
+            tempo_bpm: 100.0,
+            progression: 0,
+            ear_stats: EarStats::default(),
+            keyboard_range: KeyboardRange::Keys49,
+            custom_lowest_note: 36,
+            custom_highest_note: 84,
+            mouse_glide: false,
+            ribbon_mode: RibbonMode::Off,
+            ribbon_param: ParamId::FilterCutoff,
+            ribbon_quantize: false,
+            xy_pad_x: ParamId::FilterCutoff,
+            xy_pad_y: ParamId::FilterResonance,
+            cc_mappings: Vec::new(),
+            breath: BreathRouting::default(),
             keybinds: KeybindScheme::Default,
+            keymaps: Vec::new(),
+            active_keymap: None,
+            startup_patch: StartupPatch::LastUsed,
+            startup_preset: String::new(),
+            params: SynthParams::default(),
+            split: None,
+            main_mix: PartMix::default(),
+            layer: None,
+            added_parts: Vec::new(),
+            buses: SendBuses::default(),
+            limiter: LimiterSettings::default(),
+            user_presets: Vec::new(),
         }
     }
 }
 
 impl AppSettings {
     pub fn load(path: &Path) -> Self {
-        let mut settings = AppSettings::default();
         if let Ok(raw) = fs::read_to_string(path) {
-            for line in raw.lines() {
-                let line = line.trim();
-                if line.is_empty() || line.starts_with('#') {
-                    continue;
-                }
-                if let Some((key, value)) = line.split_once('=') {
-                    apply_kv(key.trim(), value.trim(), &mut settings);
+            return Self::parse(&raw);
+        }
+        // First launch after the TOML switch: pick up the old `.cfg` next to it.
+        match fs::read_to_string(path.with_extension("cfg")) {
+            Ok(raw) => {
+                let settings = Self::parse(&raw);
+                settings.save(path);
+                settings
+            }
+            Err(_) => AppSettings::default(),
+        }
+    }
+
+    /// Panels placed in `slot`, in display order. Panels missing from the saved
+    /// arrangement (older files, newly added panels) land in the main area.
+    pub fn panels_in(&self, slot: DockSlot) -> Vec<PanelKind> {
+        let mut panels: Vec<PanelKind> = Vec::new();
+        for placement in &self.panels {
+            if placement.slot == slot && !panels.contains(&placement.panel) {
+                panels.push(placement.panel);
+            }
+        }
+        if slot == DockSlot::Main {
+            for panel in PanelKind::ALL {
+                if !self.panels.iter().any(|placement| placement.panel == panel) {
+                    panels.push(panel);
                 }
             }
         }
-        settings
+        panels
     }
 
-    pub fn save(&self, path: &Path) {
-        let mut buf = String::new();
-        buf.push_str(&format!("theme={}\n", self.theme.as_key()));
-        if let Some(name) = &self.output_device {
-            buf.push_str(&format!("output_device={name}\n"));
-        }
-        buf.push_str(&format!("layout_mode={}\n", self.layout_mode.as_key()));
-        buf.push_str(&format!("card_padding={}\n", self.card_padding));
-        buf.push_str(&format!("card_rounding={}\n", self.card_rounding));
-        buf.push_str(&format!("scope_height={}\n", self.scope_height));
-        buf.push_str(&format!("keyboard_scale={}\n", self.keyboard_scale));
-        buf.push_str(&format!("keybinds={}\n", self.keybinds.as_key()));
-        append_param_lines(&mut buf, &self.params);
+    pub fn panel_slot(&self, panel: PanelKind) -> DockSlot {
+        self.panels
+            .iter()
+            .find(|placement| placement.panel == panel)
+            .map_or(DockSlot::Main, |placement| placement.slot)
+    }
+
+    /// Moves `panel` to `slot`, placing it last there.
+    pub fn set_panel_slot(&mut self, panel: PanelKind, slot: DockSlot) {
+        let collapsed = self.panel_collapsed(panel);
+        self.panels.retain(|placement| placement.panel != panel);
+        self.panels.push(PanelPlacement {
+            panel,
+            slot,
+            collapsed,
+        });
+    }
+
+    /// Moves `panel` just ahead of `target`, into whichever slot `target` is in.
+    pub fn move_panel_before(&mut self, panel: PanelKind, target: PanelKind) {
+        if panel == target {
+            return;
+        }
+        let slot = self.panel_slot(target);
+        let collapsed = self.panel_collapsed(panel);
+        self.panels.retain(|placement| placement.panel != panel);
+        let index = self
+            .panels
+            .iter()
+            .position(|placement| placement.panel == target)
+            .unwrap_or(self.panels.len());
+        self.panels.insert(
+            index,
+            PanelPlacement {
+                panel,
+                slot,
+                collapsed,
+            },
+        );
+    }
+
+    pub fn panel_collapsed(&self, panel: PanelKind) -> bool {
+        self.panels
+            .iter()
+            .any(|placement| placement.panel == panel && placement.collapsed)
+    }
+
+    pub fn set_panel_collapsed(&mut self, panel: PanelKind, collapsed: bool) {
+        match self
+            .panels
+            .iter_mut()
+            .find(|placement| placement.panel == panel)
+        {
+            Some(placement) => placement.collapsed = collapsed,
+            None => self.panels.push(PanelPlacement {
+                panel,
+                slot: DockSlot::Main,
+                collapsed,
+            }),
+        }
+    }
+
+    /// Where bounces go: `bounce_dir`, or the home folder when it is empty.
+    pub fn bounce_folder(&self) -> PathBuf {
+        match self.bounce_dir.trim() {
+            "" => home_dir().unwrap_or_else(|| PathBuf::from(".")),
+            dir => PathBuf::from(dir),
+        }
+    }
+
+    /// Lowest and highest note shown on the piano. Custom bounds are kept inside the
+    /// 88-key range, at least an octave apart, and widened to start and end on white keys.
+    pub fn keyboard_notes(&self) -> (u8, u8) {
+        if let Some(notes) = self.keyboard_range.notes() {
+            return notes;
+        }
+        let mut low = self.custom_lowest_note.clamp(21, 96);
+        let mut high = self.custom_highest_note.clamp(low + 12, 108);
+        if matches!(low % 12, 1 | 3 | 6 | 8 | 10) {
+            low -= 1;
+        }
+        if matches!(high % 12, 1 | 3 | 6 | 8 | 10) {
+            high += 1;
+        }
+        (low, high)
+    }
+
+    /// The user keymap chosen for the computer keyboard, if any.
+    pub fn active_keymap(&self) -> Option<&Keymap> {
+        let name = self.active_keymap.as_deref()?;
+        self.keymaps.iter().find(|keymap| keymap.name == name)
+    }
+
+    /// The patch to start with, according to `startup_patch`. A startup preset
+    /// that no longer exists falls back to the last used patch.
+    pub fn startup_params(&self) -> SynthParams {
+        match self.startup_patch {
+            StartupPatch::LastUsed => self.params.clone(),
+            StartupPatch::Default => SynthParams::default(),
+            StartupPatch::Preset => all_presets(&self.user_presets)
+                .into_iter()
+                .find(|preset| preset.name == self.startup_preset)
+                .map(|preset| preset.params)
+                .unwrap_or_else(|| self.params.clone()),
+        }
+    }
+
+    pub fn parse(raw: &str) -> Self {
+        match raw.parse::<toml::Table>() {
+            Ok(table) => {
+                let table = migrate(table);
+                // One bad value should not cost the rest of the file.
+                decode(table.clone()).unwrap_or_else(|_| salvage(table))
+            }
+            Err(_) => parse_legacy(raw),
+        }
+    }
 
+    /// Writes the whole configuration (patch, presets, layout, bindings) to one portable file.
+    pub fn export_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("Could not create {}: {err}", parent.display()))?;
+        }
+        write_atomic(path, &self.to_toml())
+            .map_err(|err| format!("Could not write {}: {err}", path.display()))
+    }
+
+    /// Reads a file produced by `export_to`. Unlike `load`, malformed files are an error.
+    pub fn import_from(path: &Path) -> Result<Self, String> {
+        let raw = fs::read_to_string(path)
+            .map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+        let table = raw
+            .parse::<toml::Table>()
+            .map_err(|err| format!("Not a settings file: {err}"))?;
+        decode(migrate(table))
+    }
+
+    /// Pulls values a hand-edited or damaged file may hold back into the
+    /// ranges the controls allow.
+    fn clamp_ranges(&mut self) {
+        clamp_f32(&mut self.take_synth_level, 0.0..=1.5);
+        clamp_f32(&mut self.take_mic_level, 0.0..=2.0);
+        clamp_f32(&mut self.card_padding, 4.0..=24.0);
+        clamp_f32(&mut self.card_rounding, 0.0..=18.0);
+        clamp_f32(&mut self.scope_height, 80.0..=220.0);
+        clamp_f32(&mut self.scope_ms_per_div, 0.1..=20.0);
+        clamp_f32(&mut self.scope_gain, 0.25..=8.0);
+        clamp_f32(&mut self.scope_envelope_seconds, 0.5..=20.0);
+        clamp_f32(&mut self.keyboard_scale, 0.7..=1.4);
+        clamp_f32(&mut self.keyboard_zoom, 1.0..=MAX_KEYBOARD_ZOOM);
+        clamp_f32(&mut self.tempo_bpm, 40.0..=240.0);
+        clamp_f32(&mut self.bend_range, 0.0..=24.0);
+        self.tuning_root = self.tuning_root.min(127);
+        self.custom_lowest_note = self.custom_lowest_note.min(127);
+        self.custom_highest_note = self.custom_highest_note.min(127);
+        if self.progression >= PROGRESSION_COUNT {
+            self.progression = 0;
+        }
+        if let Some(scale) = &mut self.scale {
+            scale.root %= 12;
+        }
+        if let Some(theory) = &mut self.theory {
+            theory.root %= 12;
+        }
+        if let Some(drone) = &mut self.drone {
+            drone.note = drone.note.min(127);
+            clamp_f32(&mut drone.level, 0.0..=1.0);
+        }
+        clamp_params(&mut self.params);
+        for preset in &mut self.user_presets {
+            clamp_params(&mut preset.params);
+        }
+        if let Some(split) = &mut self.split {
+            split.note = split.note.min(127);
+            clamp_params(&mut split.lower);
+            clamp_mix(&mut split.mix);
+        }
+        clamp_mix(&mut self.main_mix);
+        if let Some(layer) = &mut self.layer {
+            clamp_params(&mut layer.params);
+            clamp_mix(&mut layer.mix);
+        }
+        for part in &mut self.added_parts {
+            clamp_params(&mut part.params);
+            clamp_mix(&mut part.mix);
+            if let NoteSource::Range { low, high } = &mut part.source {
+                *low = (*low).min(127);
+                *high = (*high).clamp(*low, 127);
+            }
+        }
+        clamp_f32(&mut self.bounce_seconds, 0.25..=30.0);
+        clamp_f32(&mut self.breath.amplitude, 0.0..=1.0);
+        clamp_f32(&mut self.breath.cutoff, 0.0..=1.0);
+        let buses = &mut self.buses;
+        clamp_f32(&mut buses.reverb_size, 0.0..=1.0);
+        clamp_f32(&mut buses.reverb_damping, 0.0..=1.0);
+        clamp_f32(&mut buses.reverb_return, 0.0..=1.0);
+        clamp_f32(&mut buses.delay_seconds, 0.01..=MAX_DELAY_SECONDS);
+        clamp_f32(&mut buses.delay_feedback, 0.0..=0.9);
+        clamp_f32(&mut buses.delay_return, 0.0..=1.0);
+        clamp_f32(&mut self.limiter.ceiling_db, CEILING_RANGE_DB);
+    }
+
+    pub fn to_toml(&self) -> String {
+        to_tidy_toml(self)
+    }
+
+    pub fn save(&self, path: &Path) {
         if let Some(parent) = path.parent() {
             let _ = fs::create_dir_all(parent);
         }
-        let _ = fs::write(path, buf);
+        if let Err(err) = write_atomic(path, &self.to_toml()) {
+            log::error!("Failed to save settings to {}: {err}", path.display());
+        }
+    }
+}
+
+/// How long the saver waits for further changes before touching the disk.
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(750);
+
+/// Saves settings on a background thread. Bursts of changes (e.g. a slider drag)
+/// collapse into a single write once things settle; dropping the saver flushes.
+pub struct SettingsSaver {
+    tx: Option<Sender<AppSettings>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl SettingsSaver {
+    pub fn new(path: PathBuf) -> Self {
+        let (tx, rx) = mpsc::channel::<AppSettings>();
+        let worker = thread::spawn(move || {
+            while let Ok(mut pending) = rx.recv() {
+                loop {
+                    match rx.recv_timeout(SAVE_DEBOUNCE) {
+                        Ok(newer) => pending = newer,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => {
+                            pending.save(&path);
+                            return;
+                        }
+                    }
+                }
+                pending.save(&path);
+            }
+        });
+        Self {
+            tx: Some(tx),
+            worker: Some(worker),
+        }
+    }
+
+    pub fn save(&self, settings: &AppSettings) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(settings.clone());
+        }
     }
 }
 
+impl Drop for SettingsSaver {
+    fn drop(&mut self) {
+        self.tx.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Writes to a sibling temp file and renames it over `path`, so a crash mid-write
+/// never leaves a truncated file behind.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)
+}
+
 pub fn default_settings_path() -> PathBuf {
-    config_dir().join("angel_settings.cfg")
+    config_dir().join("angel_settings.toml")
+}
+
+pub fn default_export_path() -> PathBuf {
+    home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("angel_export.toml")
+}
+
+/// Brings an older table up to the current schema. Files without a `version` predate it.
+fn migrate(mut table: toml::Table) -> toml::Table {
+    let version = table
+        .get("version")
+        .and_then(toml::Value::as_integer)
+        .unwrap_or(0);
+    // Future schema changes rewrite `table` here, one version step at a time.
+    if version < SETTINGS_VERSION as i64 {
+        table.insert(
+            "version".to_string(),
+            toml::Value::Integer(SETTINGS_VERSION as i64),
+        );
+    }
+    table
+}
+
+fn decode(table: toml::Table) -> Result<AppSettings, String> {
+    let mut settings: AppSettings = toml::Value::Table(table)
+        .try_into()
+        .map_err(|err| format!("Invalid settings: {err}"))?;
+    settings.clamp_ranges();
+    Ok(settings)
+}
+
+/// Starts from the defaults and takes over every field of `table` that decodes,
+/// descending into sections so one bad value only resets itself.
+fn salvage(table: toml::Table) -> AppSettings {
+    let Ok(toml::Value::Table(mut kept)) = toml::Value::try_from(AppSettings::default()) else {
+        return AppSettings::default();
+    };
+    merge_valid(&mut kept, &[], table);
+    decode(kept).unwrap_or_default()
+}
+
+fn merge_valid(kept: &mut toml::Table, path: &[String], incoming: toml::Table) {
+    for (key, value) in incoming {
+        let mut trial = kept.clone();
+        if let Some(section) = section_mut(&mut trial, path) {
+            section.insert(key.clone(), value.clone());
+            if decode(trial.clone()).is_ok() {
+                *kept = trial;
+                continue;
+            }
+        }
+        let has_section = section_mut(kept, path)
+            .and_then(|section| section.get(&key))
+            .is_some_and(toml::Value::is_table);
+        if let (toml::Value::Table(inner), true) = (value, has_section) {
+            let mut inner_path = path.to_vec();
+            inner_path.push(key);
+            merge_valid(kept, &inner_path, inner);
+        }
+    }
+}
+
+fn section_mut<'a>(table: &'a mut toml::Table, path: &[String]) -> Option<&'a mut toml::Table> {
+    path.iter().try_fold(table, |table, key| {
+        table.get_mut(key).and_then(toml::Value::as_table_mut)
+    })
+}
+
+/// NaN, which TOML can spell, falls to the bottom of the range.
+pub(crate) fn clamp_f32(value: &mut f32, range: RangeInclusive<f32>) {
+    *value = if value.is_nan() {
+        *range.start()
+    } else {
+        value.clamp(*range.start(), *range.end())
+    };
+}
+
+fn clamp_mix(mix: &mut PartMix) {
+    clamp_f32(&mut mix.volume, 0.0..=1.0);
+    clamp_f32(&mut mix.pan, -1.0..=1.0);
+    mix.transpose = mix.transpose.clamp(-24, 24);
+    clamp_f32(&mut mix.reverb_send, 0.0..=1.0);
+    clamp_f32(&mut mix.delay_send, 0.0..=1.0);
+    if let Some(ducking) = &mut mix.ducking {
+        clamp_f32(&mut ducking.amount, 0.0..=1.0);
+        clamp_f32(&mut ducking.attack_ms, 0.1..=100.0);
+        clamp_f32(&mut ducking.release_ms, 10.0..=2000.0);
+    }
+}
+
+pub(crate) fn to_tidy_toml<T: Serialize>(data: &T) -> String {
+    let mut value = match toml::Value::try_from(data) {
+        Ok(value) => value,
+        Err(_) => return String::new(),
+    };
+    tidy_floats(&mut value);
+    toml::to_string_pretty(&value).unwrap_or_default()
+}
+
+/// Serde writes `f32` through `f64`, turning 0.65 into 0.6499999761581421.
+/// Round-trip each float through its shortest `f32` spelling to keep the file readable.
+pub(crate) fn tidy_floats(value: &mut toml::Value) {
+    match value {
+        toml::Value::Float(f) => {
+            if let Ok(short) = (*f as f32).to_string().parse::<f64>() {
+                *f = short;
+            }
+        }
+        toml::Value::Array(items) => items.iter_mut().for_each(tidy_floats),
+        toml::Value::Table(table) => table.iter_mut().for_each(|(_, v)| tidy_floats(v)),
+        _ => {}
+    }
+}
+
+/// Reads the pre-TOML `key=value` format (schema version 0).
+fn parse_legacy(raw: &str) -> AppSettings {
+    let mut settings = AppSettings::default();
+    for line in raw.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            apply_kv(key.trim(), value.trim(), &mut settings);
+        }
+    }
+    settings.clamp_ranges();
+    settings
 }
 
 fn apply_kv(key: &str, value: &str, settings: &mut AppSettings) {
@@ -276,39 +1110,55 @@ fn apply_kv(key: &str, value: &str, settings: &mut AppSettings) {
                 Some(value.to_string())
             }
         }
-        "layout_mode" => settings.layout_mode = LayoutMode::from_str(value),
         "card_padding" => parse_f32(value, &mut settings.card_padding),
         "card_rounding" => parse_f32(value, &mut settings.card_rounding),
         "scope_height" => parse_f32(value, &mut settings.scope_height),
         "keyboard_scale" => parse_f32(value, &mut settings.keyboard_scale),
         "keybinds" => settings.keybinds = KeybindScheme::from_str(value),
-        "gain" => parse_f32(value, &mut settings.params.gain),
-        "attack_seconds" => parse_f32(value, &mut settings.params.attack_seconds),
-        "decay_seconds" => parse_f32(value, &mut settings.params.decay_seconds),
-        "sustain_level" => parse_f32(value, &mut settings.params.sustain_level),
-        "release_seconds" => parse_f32(value, &mut settings.params.release_seconds),
+        _ => {
+            if let Some(name) = key.strip_prefix("preset.") {
+                if !name.is_empty() {
+                    settings
+                        .user_presets
+                        .push(Preset::new(name, decode_preset_params(value)));
+                }
+            } else {
+                apply_param_kv(key, value, &mut settings.params);
+            }
+        }
+    }
+}
+
+fn apply_param_kv(key: &str, value: &str, params: &mut SynthParams) {
+    match key {
+        "gain" => parse_f32(value, &mut params.gain),
+        "attack_seconds" => parse_f32(value, &mut params.attack_seconds),
+        "decay_seconds" => parse_f32(value, &mut params.decay_seconds),
+        "sustain_level" => parse_f32(value, &mut params.sustain_level),
+        "release_seconds" => parse_f32(value, &mut params.release_seconds),
         "waveform" => {
             if let Some(wf) = parse_waveform(value) {
-                settings.params.waveform = wf;
+                params.waveform = wf;
             }
         }
-        "filter_cutoff_hz" => parse_f32(value, &mut settings.params.filter_cutoff_hz),
-        "filter_resonance" => parse_f32(value, &mut settings.params.filter_resonance),
-        "vibrato_depth_semitones" => parse_f32(value, &mut settings.params.vibrato_depth_semitones),
-        "vibrato_rate_hz" => parse_f32(value, &mut settings.params.vibrato_rate_hz),
-        "unison_spread_cents" => parse_f32(value, &mut settings.params.unison_spread_cents),
-        "autotune_amount" => parse_f32(value, &mut settings.params.autotune_amount),
-        "noise_mix" => parse_f32(value, &mut settings.params.noise_mix),
-        "eq_low_gain_db" => parse_f32(value, &mut settings.params.eq_low_gain_db),
-        "eq_low_freq_hz" => parse_f32(value, &mut settings.params.eq_low_freq_hz),
-        "eq_mid_gain_db" => parse_f32(value, &mut settings.params.eq_mid_gain_db),
-        "eq_mid_freq_hz" => parse_f32(value, &mut settings.params.eq_mid_freq_hz),
-        "eq_mid_q" => parse_f32(value, &mut settings.params.eq_mid_q),
-        "eq_high_gain_db" => parse_f32(value, &mut settings.params.eq_high_gain_db),
-        "eq_high_freq_hz" => parse_f32(value, &mut settings.params.eq_high_freq_hz),
+        "filter_cutoff_hz" => parse_f32(value, &mut params.filter_cutoff_hz),
+        "filter_resonance" => parse_f32(value, &mut params.filter_resonance),
+        "vibrato_depth_semitones" => parse_f32(value, &mut params.vibrato_depth_semitones),
+        "vibrato_rate_hz" => parse_f32(value, &mut params.vibrato_rate_hz),
+        "unison_spread_cents" => parse_f32(value, &mut params.unison_spread_cents),
+        "autotune_amount" => parse_f32(value, &mut params.autotune_amount),
+        "noise_mix" => parse_f32(value, &mut params.noise_mix),
+        "eq_low_gain_db" => parse_f32(value, &mut params.eq_low_gain_db),
+        "eq_low_freq_hz" => parse_f32(value, &mut params.eq_low_freq_hz),
+        "eq_mid_gain_db" => parse_f32(value, &mut params.eq_mid_gain_db),
+        "eq_mid_freq_hz" => parse_f32(value, &mut params.eq_mid_freq_hz),
+        "eq_mid_q" => parse_f32(value, &mut params.eq_mid_q),
+        "eq_high_gain_db" => parse_f32(value, &mut params.eq_high_gain_db),
+        "eq_high_freq_hz" => parse_f32(value, &mut params.eq_high_freq_hz),
+        "glide_seconds" => parse_f32(value, &mut params.glide_seconds),
         "instrument" => {
             if let Some(inst) = parse_instrument(value) {
-                settings.params.instrument = inst;
+                params.instrument = inst;
             }
         }
         _ => {}
@@ -331,55 +1181,15 @@ fn parse_waveform(value: &str) -> Option<Waveform> {
     }
 }
 
-fn append_param_lines(buf: &mut String, params: &SynthParams) {
-    buf.push_str(&format!("gain={}\n", params.gain));
-    buf.push_str(&format!("attack_seconds={}\n", params.attack_seconds));
-    buf.push_str(&format!("decay_seconds={}\n", params.decay_seconds));
-    buf.push_str(&format!("sustain_level={}\n", params.sustain_level));
-    buf.push_str(&format!("release_seconds={}\n", params.release_seconds));
-    buf.push_str(&format!("waveform={}\n", waveform_key(params.waveform)));
-    buf.push_str(&format!("filter_cutoff_hz={}\n", params.filter_cutoff_hz));
-    buf.push_str(&format!("filter_resonance={}\n", params.filter_resonance));
-    buf.push_str(&format!(
-        "vibrato_depth_semitones={}\n",
-        params.vibrato_depth_semitones
-    ));
-    buf.push_str(&format!("vibrato_rate_hz={}\n", params.vibrato_rate_hz));
-    buf.push_str(&format!(
-        "unison_spread_cents={}\n",
-        params.unison_spread_cents
-    ));
-    buf.push_str(&format!("autotune_amount={}\n", params.autotune_amount));
-    buf.push_str(&format!("noise_mix={}\n", params.noise_mix));
-    buf.push_str(&format!("eq_low_gain_db={}\n", params.eq_low_gain_db));
-    buf.push_str(&format!("eq_low_freq_hz={}\n", params.eq_low_freq_hz));
-    buf.push_str(&format!("eq_mid_gain_db={}\n", params.eq_mid_gain_db));
-    buf.push_str(&format!("eq_mid_freq_hz={}\n", params.eq_mid_freq_hz));
-    buf.push_str(&format!("eq_mid_q={}\n", params.eq_mid_q));
-    buf.push_str(&format!("eq_high_gain_db={}\n", params.eq_high_gain_db));
-    buf.push_str(&format!("eq_high_freq_hz={}\n", params.eq_high_freq_hz));
-    buf.push_str(&format!(
-        "instrument={}\n",
-        instrument_key(params.instrument)
-    ));
-}
-
-fn waveform_key(waveform: Waveform) -> &'static str {
-    match waveform {
-        Waveform::Sine => "sine",
-        Waveform::Square => "square",
-        Waveform::Saw => "saw",
-        Waveform::Triangle => "triangle",
-    }
-}
-
-fn instrument_key(inst: InstrumentKind) -> &'static str {
-    match inst {
-        InstrumentKind::Keys => "keys",
-        InstrumentKind::Bass => "bass",
-        InstrumentKind::Lead => "lead",
-        InstrumentKind::Pad => "pad",
+// Legacy user presets live on a single line: `preset.<name>=gain:0.65,attack_seconds:0.01,...`
+fn decode_preset_params(value: &str) -> SynthParams {
+    let mut params = SynthParams::default();
+    for pair in value.split(',') {
+        if let Some((key, value)) = pair.split_once(':') {
+            apply_param_kv(key.trim(), value.trim(), &mut params);
+        }
     }
+    params
 }
 
 fn parse_instrument(value: &str) -> Option<InstrumentKind> {
@@ -392,7 +1202,26 @@ fn parse_instrument(value: &str) -> Option<InstrumentKind> {
     }
 }
 
-fn config_dir() -> PathBuf {
+/// Folders Android hands the app at startup: private internal storage for
+/// settings and logs, and the app's external folder for exports, which a
+/// file manager can reach. There is no HOME to fall back on there.
+#[cfg(target_os = "android")]
+static ANDROID_STORAGE: std::sync::OnceLock<(PathBuf, Option<PathBuf>)> =
+    std::sync::OnceLock::new();
+
+#[cfg(target_os = "android")]
+pub fn set_android_storage(internal: PathBuf, external: Option<PathBuf>) {
+    let _ = ANDROID_STORAGE.set((internal, external));
+}
+
+pub fn config_dir() -> PathBuf {
+    #[cfg(target_os = "android")]
+    {
+        if let Some((internal, _)) = ANDROID_STORAGE.get() {
+            return internal.clone();
+        }
+    }
+
     #[cfg(target_os = "windows")]
     {
         if let Ok(roaming) = std::env::var("APPDATA") {
@@ -403,7 +1232,8 @@ fn config_dir() -> PathBuf {
         }
     }
 
-    #[cfg(target_os = "macos")]
+    // iOS apps get a sandboxed home with the same Library layout as macOS.
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
     {
         if let Some(home) = home_dir() {
             return home
@@ -425,6 +1255,12 @@ fn config_dir() -> PathBuf {
 }
 
 fn home_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "android")]
+    {
+        if let Some((internal, external)) = ANDROID_STORAGE.get() {
+            return Some(external.clone().unwrap_or_else(|| internal.clone()));
+        }
+    }
     std::env::var("HOME").ok().map(PathBuf::from).or_else(|| {
         #[cfg(target_os = "windows")]
         {
@@ -436,3 +1272,371 @@ fn home_dir() -> Option<PathBuf> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::panic::catch_unwind;
+
+    use super::*;
+    use crate::controllers::{BreathCurve, ControllerTemplate};
+    use crate::scales::ScaleKind;
+    use crate::synth::Ducking;
+    use crate::theory::ChordKind;
+
+    /// Fixed-seed xorshift, so a failing case reproduces on every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: usize) -> usize {
+            (self.next() % bound.max(1) as u64) as usize
+        }
+
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[self.below(items.len())]
+        }
+    }
+
+    /// Fragments that steer random text towards almost-valid TOML.
+    const SNIPPETS: &[&str] = &[
+        "[",
+        "]",
+        "[[panels]]\n",
+        "[params]\n",
+        " = ",
+        "\"",
+        "'",
+        "\n",
+        "#",
+        "=",
+        ",",
+        "{",
+        "}",
+        "nan",
+        "-inf",
+        "1e40",
+        "-0.0",
+        "99999999999999999999",
+        "-1",
+        "true",
+        "\"\u{1F3B9}\"",
+        "gain",
+        "version",
+        "preset.x=gain:5",
+        "theme=dark\n",
+    ];
+
+    /// Enum spellings from across the file, so a string can turn into the wrong kind of variant.
+    const VARIANTS: &[&str] = &[
+        "sine",
+        "wavetable",
+        "midnight",
+        "high_contrast",
+        "vim",
+        "vscode",
+        "c3",
+        "floating",
+        "bass",
+        "natural_minor",
+        "minor7",
+        "hard",
+    ];
+
+    /// Every optional part filled in, so round trips cover all of it.
+    fn rich_settings() -> AppSettings {
+        AppSettings {
+            output_device: Some("Speakers".to_string()),
+            input_device: Some("USB Mic".to_string()),
+            midi_port: Some("Launchkey MK3 MIDI 1".to_string()),
+            scale: Some(Scale {
+                root: 9,
+                kind: ScaleKind::NaturalMinor,
+            }),
+            theory: Some(TheoryOverlay {
+                root: 2,
+                chord: Some(ChordKind::Minor7),
+            }),
+            drone: Some(Drone {
+                note: 45,
+                fifth: true,
+                level: 0.3,
+            }),
+            theme: ThemeKind::Midnight,
+            cc_mappings: ControllerTemplate::LaunchkeyMk3.mappings(),
+            breath: BreathRouting {
+                curve: BreathCurve::Hard,
+                cutoff: 0.8,
+                ..BreathRouting::default()
+            },
+            keymaps: vec![Keymap::qwerty("Laptop")],
+            active_keymap: Some("Laptop".to_string()),
+            user_presets: vec![Preset::new(
+                "Bright",
+                SynthParams {
+                    filter_cutoff_hz: 9_000.0,
+                    ..SynthParams::default()
+                },
+            )],
+            split: Some(KeyboardSplit {
+                note: 55,
+                preset: "Bass".to_string(),
+                lower: SynthParams {
+                    instrument: InstrumentKind::Bass,
+                    ..SynthParams::default()
+                },
+                mix: PartMix {
+                    mute: true,
+                    ..PartMix::default()
+                },
+            }),
+            layer: Some(PatchLayer {
+                preset: "Pad".to_string(),
+                params: SynthParams {
+                    instrument: InstrumentKind::Pad,
+                    ..SynthParams::default()
+                },
+                mix: PartMix {
+                    volume: 0.5,
+                    pan: -0.3,
+                    transpose: 12,
+                    solo: true,
+                    follows_soft_pedal: false,
+                    ..PartMix::default()
+                },
+            }),
+            added_parts: vec![Part {
+                id: 3,
+                name: "Strings".to_string(),
+                params: SynthParams {
+                    waveform: Waveform::Triangle,
+                    ..SynthParams::default()
+                },
+                source: NoteSource::Range { low: 60, high: 84 },
+                mix: PartMix {
+                    pan: 0.4,
+                    reverb_send: 0.5,
+                    delay_send: 0.25,
+                    ducking: Some(Ducking {
+                        source: 0,
+                        amount: 0.8,
+                        attack_ms: 2.0,
+                        release_ms: 350.0,
+                    }),
+                    ..PartMix::default()
+                },
+            }],
+            buses: SendBuses {
+                reverb_size: 0.9,
+                delay_seconds: 0.5,
+                ..SendBuses::default()
+            },
+            limiter: LimiterSettings {
+                enabled: false,
+                ceiling_db: -3.0,
+            },
+            ear_stats: EarStats {
+                streak: 3,
+                best_streak: 7,
+                correct: 20,
+                attempts: 31,
+            },
+            ..AppSettings::default()
+        }
+    }
+
+    fn parse_without_panic(raw: &str) -> AppSettings {
+        catch_unwind(|| AppSettings::parse(raw))
+            .unwrap_or_else(|_| panic!("parse panicked on:\n{raw:?}"))
+    }
+
+    /// Randomizes every leaf of a settings table, keeping its types.
+    fn scramble(value: &mut toml::Value, rng: &mut Rng) {
+        match value {
+            toml::Value::Float(f) => {
+                *f = match rng.below(6) {
+                    0 => f64::NAN,
+                    1 => f64::INFINITY,
+                    2 => -1e40,
+                    3 => 0.0,
+                    _ => (rng.below(2_000_001) as f64 - 1_000_000.0) / 100.0,
+                }
+            }
+            toml::Value::Integer(i) => *i = rng.below(128) as i64,
+            toml::Value::Boolean(b) => *b = rng.below(2) == 0,
+            toml::Value::String(s) => match rng.below(6) {
+                0 => s.clear(),
+                1 => *s = "nope".to_string(),
+                2 => *s = rng.pick(VARIANTS).to_string(),
+                3 => s.push('\u{1F3B9}'),
+                _ => {}
+            },
+            toml::Value::Array(items) => items.iter_mut().for_each(|item| scramble(item, rng)),
+            toml::Value::Table(table) => {
+                // Missing keys must fall back to their defaults.
+                if !table.is_empty() && rng.below(50) == 0 {
+                    let key = table.keys().nth(rng.below(table.len())).cloned();
+                    if let Some(key) = key {
+                        table.remove(&key);
+                    }
+                }
+                table.iter_mut().for_each(|(_, item)| scramble(item, rng));
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn parse_never_panics_on_arbitrary_input() {
+        let mut rng = Rng(0x5EED_0001);
+        for _ in 0..2_000 {
+            let mut raw = String::new();
+            for _ in 0..rng.below(60) {
+                if rng.below(3) == 0 {
+                    let bytes: Vec<u8> = (0..rng.below(8)).map(|_| rng.next() as u8).collect();
+                    raw.push_str(&String::from_utf8_lossy(&bytes));
+                } else {
+                    raw.push_str(rng.pick(SNIPPETS));
+                }
+            }
+            parse_without_panic(&raw);
+        }
+    }
+
+    #[test]
+    fn load_never_panics_on_damaged_files() {
+        let dir = std::env::temp_dir().join(format!("angel_settings_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create temp dir");
+        let path = dir.join("angel_settings.toml");
+        let saved = rich_settings().to_toml();
+        let mut rng = Rng(0x5EED_0002);
+        for round in 0..1_000 {
+            let mut raw = saved.clone();
+            for _ in 0..=rng.below(4) {
+                let at = raw.floor_char_boundary(rng.below(raw.len() + 1));
+                match rng.below(4) {
+                    0 => raw.truncate(at),
+                    1 => {
+                        let end = raw.floor_char_boundary(at + rng.below(40));
+                        raw.replace_range(at..end, "");
+                    }
+                    2 => raw.insert_str(at, rng.pick(SNIPPETS)),
+                    _ => {
+                        let end = raw.floor_char_boundary(at + rng.below(80));
+                        let copy = raw[at..end].to_string();
+                        raw.insert_str(end, &copy);
+                    }
+                }
+            }
+            parse_without_panic(&raw);
+            // Through the file system now and then, covering `load` itself.
+            if round % 20 == 0 {
+                fs::write(&path, &raw).expect("write temp settings");
+                let loaded = catch_unwind(|| AppSettings::load(&path));
+                assert!(loaded.is_ok(), "load panicked on:\n{raw:?}");
+            }
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn save_then_load_round_trips_every_field() {
+        let rich = rich_settings();
+        let saved = rich.to_toml();
+        let loaded = AppSettings::parse(&saved);
+        assert_eq!(loaded.to_toml(), saved);
+        assert!(loaded.split.is_some() && loaded.layer.is_some() && loaded.theory.is_some());
+
+        let mut rng = Rng(0x5EED_0003);
+        let template = toml::Value::Table(saved.parse().expect("valid TOML"));
+        for _ in 0..300 {
+            let mut value = template.clone();
+            scramble(&mut value, &mut rng);
+            let raw = toml::to_string(&value).expect("serialize scrambled settings");
+            // The first load clamps; after that, save→load must change nothing.
+            let first = parse_without_panic(&raw).to_toml();
+            let second = AppSettings::parse(&first).to_toml();
+            assert_eq!(
+                second, first,
+                "round trip changed settings loaded from:\n{raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn bad_field_only_resets_itself() {
+        let rich = rich_settings();
+        let saved = rich.to_toml();
+        for (good, bad) in [
+            ("theme = \"midnight\"", "theme = \"nope\""),
+            ("waveform = \"triangle\"", "waveform = 7"),
+            ("curve = \"hard\"", "curve = \"sine\""),
+        ] {
+            assert!(saved.contains(good), "fixture lost {good}");
+            let loaded = AppSettings::parse(&saved.replacen(good, bad, 1));
+            assert_eq!(loaded.params.gain, rich.params.gain, "{bad}");
+            assert_eq!(loaded.keymaps.len(), 1, "{bad}");
+            assert_eq!(loaded.active_keymap, rich.active_keymap, "{bad}");
+            assert_eq!(loaded.user_presets.len(), 1, "{bad}");
+            assert_eq!(loaded.cc_mappings, rich.cc_mappings, "{bad}");
+            assert_eq!(loaded.limiter.ceiling_db, -3.0, "{bad}");
+            assert_eq!(loaded.breath.cutoff, 0.8, "{bad}");
+            assert!(loaded.split.is_some() && loaded.layer.is_some(), "{bad}");
+        }
+        let loaded =
+            AppSettings::parse(&saved.replacen("theme = \"midnight\"", "theme = \"nope\"", 1));
+        assert_eq!(loaded.theme, ThemeKind::Fl);
+    }
+
+    #[test]
+    fn missing_version_reads_as_the_oldest_schema() {
+        let table: toml::Table = "gain = 1".parse().expect("valid TOML");
+        let migrated = migrate(table);
+        assert_eq!(
+            migrated.get("version").and_then(toml::Value::as_integer),
+            Some(SETTINGS_VERSION as i64)
+        );
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped() {
+        let settings = AppSettings::parse(
+            "tempo_bpm = 5000.0\n\
+             keyboard_zoom = -2.0\n\
+             scope_gain = nan\n\
+             progression = 99\n\
+             [scale]\n\
+             root = 30\n\
+             kind = \"major\"\n\
+             [params]\n\
+             gain = 40.0\n\
+             filter_cutoff_hz = 1.0\n\
+             eq_mid_q = nan\n\
+             [main_mix]\n\
+             volume = 3.0\n\
+             pan = -7.0\n\
+             transpose = 100\n",
+        );
+        assert_eq!(settings.tempo_bpm, 240.0);
+        assert_eq!(settings.keyboard_zoom, 1.0);
+        assert_eq!(settings.scope_gain, 0.25);
+        assert_eq!(settings.progression, 0);
+        assert_eq!(settings.scale.map(|scale| scale.root), Some(6));
+        assert_eq!(settings.params.gain, 1.0);
+        assert_eq!(settings.params.filter_cutoff_hz, 80.0);
+        assert_eq!(settings.params.eq_mid_q, ParamId::EqMidQ.default_value());
+        assert_eq!(settings.main_mix.volume, 1.0);
+        assert_eq!(settings.main_mix.pan, -1.0);
+        assert_eq!(settings.main_mix.transpose, 24);
+
+        // The pre-TOML format is clamped the same way.
+        let legacy = AppSettings::parse("theme=dark\ngain=9\nkeyboard_scale=0.1\n");
+        assert_eq!(legacy.params.gain, 1.0);
+        assert_eq!(legacy.keyboard_scale, 0.7);
+    }
+}
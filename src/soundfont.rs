@@ -0,0 +1,576 @@
+use std::collections::HashMap;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use crate::files::{FileCache, FileRef, Loadable};
+use crate::synth::SynthParams;
+use crate::wav::{decode_mono, smpl_info};
+
+/// SF2 generator numbers read from zones; the rest (envelopes, filters,
+/// modulators) are left to the patch.
+const GEN_START_OFFSET: usize = 0;
+const GEN_END_OFFSET: usize = 1;
+const GEN_LOOP_START_OFFSET: usize = 2;
+const GEN_LOOP_END_OFFSET: usize = 3;
+const GEN_START_COARSE_OFFSET: usize = 4;
+const GEN_END_COARSE_OFFSET: usize = 12;
+const GEN_INSTRUMENT: usize = 41;
+const GEN_KEY_RANGE: usize = 43;
+const GEN_VELOCITY_RANGE: usize = 44;
+const GEN_LOOP_START_COARSE_OFFSET: usize = 45;
+const GEN_ATTENUATION: usize = 48;
+const GEN_LOOP_END_COARSE_OFFSET: usize = 50;
+const GEN_COARSE_TUNE: usize = 51;
+const GEN_FINE_TUNE: usize = 52;
+const GEN_SAMPLE_ID: usize = 53;
+const GEN_SAMPLE_MODES: usize = 54;
+const GEN_ROOT_KEY: usize = 58;
+const GEN_COUNT: usize = 61;
+
+/// One sample of a preset, played for the keys and velocities it covers.
+pub struct Region {
+    keys: RangeInclusive<u8>,
+    velocities: RangeInclusive<u8>,
+    /// Where its audio sits in the font's pool, end exclusive.
+    start: usize,
+    end: usize,
+    /// Loop in pool positions, end exclusive.
+    loop_points: Option<(usize, usize)>,
+    sample_rate: f32,
+    /// The note that plays the audio back at its own speed, tuning included.
+    pub root_note: f32,
+    pub gain: f32,
+}
+
+impl Region {
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// The loop in samples from the region's start.
+    pub fn loop_points(&self) -> Option<(usize, usize)> {
+        self.loop_points
+            .map(|(start, end)| (start - self.start, end - self.start))
+    }
+}
+
+/// A playable patch of a font: an SF2 preset, or a whole SFZ file.
+pub struct SoundFontPreset {
+    pub name: String,
+    regions: Vec<Region>,
+}
+
+/// The audio and presets of an SF2 or SFZ file.
+pub struct SoundFont {
+    /// Every sample back to back, mono.
+    pool: Vec<f32>,
+    presets: Vec<SoundFontPreset>,
+}
+
+impl SoundFont {
+    /// Reads an `.sfz` (with the WAVs it names) or otherwise an `.sf2`.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let font = if path.to_ascii_lowercase().ends_with(".sfz") {
+            let text =
+                fs::read_to_string(path).map_err(|err| format!("Could not read {path}: {err}"))?;
+            parse_sfz(&text, Path::new(path))
+        } else {
+            let bytes = fs::read(path).map_err(|err| format!("Could not read {path}: {err}"))?;
+            parse_sf2(&bytes)
+        }
+        .map_err(|err| format!("{path}: {err}"))?;
+        if font.presets.iter().all(|preset| preset.regions.is_empty()) {
+            return Err(format!("{path} has nothing to play"));
+        }
+        Ok(font)
+    }
+
+    pub fn presets(&self) -> &[SoundFontPreset] {
+        &self.presets
+    }
+
+    /// Index of the region of `preset` that plays `note` at `velocity` (0..1).
+    pub fn find_region(&self, preset: usize, note: u8, velocity: f32) -> Option<usize> {
+        let velocity = (velocity.clamp(0.0, 1.0) * 127.0).round() as u8;
+        self.presets
+            .get(preset)?
+            .regions
+            .iter()
+            .position(|region| region.keys.contains(&note) && region.velocities.contains(&velocity))
+    }
+
+    pub fn region(&self, preset: usize, index: usize) -> Option<&Region> {
+        self.presets.get(preset)?.regions.get(index)
+    }
+
+    /// Linear interpolation at `pos` samples into `region`; silent past
+    /// either end.
+    pub fn region_sample(&self, region: &Region, pos: f64) -> f32 {
+        if pos < 0.0 {
+            return 0.0;
+        }
+        let index = region.start + pos as usize;
+        if index >= region.end {
+            return 0.0;
+        }
+        let next = if index + 1 < region.end {
+            self.pool[index + 1]
+        } else {
+            0.0
+        };
+        self.pool[index] + (next - self.pool[index]) * pos.fract() as f32
+    }
+}
+
+impl Loadable for SoundFont {
+    fn load(path: &str) -> Result<Self, String> {
+        SoundFont::load(path)
+    }
+}
+
+/// A SoundFont named by its path, with the font attached once loaded.
+pub type SoundFontFile = FileRef<SoundFont>;
+
+pub type SoundFontCache = FileCache<SoundFont>;
+
+impl SoundFontCache {
+    /// Attaches the font `params` names, loading it the first time.
+    pub fn resolve(&mut self, params: &mut SynthParams) {
+        self.resolve_file(&mut params.soundfont);
+    }
+}
+
+/// The chunks of a RIFF body, as (id, body) pairs.
+fn riff_chunks(mut data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut chunks = Vec::new();
+    while data.len() >= 8 {
+        let id = [data[0], data[1], data[2], data[3]];
+        let len = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        // A length past the end (or past `usize` on 32-bit targets) keeps
+        // what is there and ends the list.
+        let end = 8_usize.checked_add(len);
+        chunks.push((
+            id,
+            &data[8..end.map_or(data.len(), |end| end.min(data.len()))],
+        ));
+        // Chunks are padded to an even length.
+        let Some(next) = end.and_then(|end| end.checked_add(len % 2)) else {
+            break;
+        };
+        if next >= data.len() {
+            break;
+        }
+        data = &data[next..];
+    }
+    chunks
+}
+
+/// The chunks inside the `LIST` chunk of type `kind`.
+fn list<'a>(chunks: &[([u8; 4], &'a [u8])], kind: &[u8; 4]) -> Vec<([u8; 4], &'a [u8])> {
+    chunks
+        .iter()
+        .find(|(id, body)| id == b"LIST" && body.get(..4) == Some(kind.as_slice()))
+        .map_or_else(Vec::new, |(_, body)| riff_chunks(&body[4..]))
+}
+
+fn chunk<'a>(chunks: &[([u8; 4], &'a [u8])], id: &[u8; 4]) -> &'a [u8] {
+    chunks
+        .iter()
+        .find(|(chunk, _)| chunk == id)
+        .map_or(&[], |(_, body)| body)
+}
+
+/// Fixed-size records of the chunk `id`.
+fn records<'a>(chunks: &[([u8; 4], &'a [u8])], id: &[u8; 4], size: usize) -> Vec<&'a [u8]> {
+    chunk(chunks, id).chunks_exact(size).collect()
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// A zone's generators by number, as their raw two bytes.
+type Generators = [Option<[u8; 2]>; GEN_COUNT];
+
+fn unsigned(generators: &Generators, generator: usize) -> usize {
+    generators[generator].map_or(0, |amount| u16::from_le_bytes(amount) as usize)
+}
+
+fn signed(generators: &Generators, generator: usize) -> i32 {
+    generators[generator].map_or(0, |amount| i16::from_le_bytes(amount) as i32)
+}
+
+fn range(generators: &Generators, generator: usize) -> RangeInclusive<u8> {
+    generators[generator].map_or(0..=127, |[low, high]| low..=high)
+}
+
+fn intersect(a: RangeInclusive<u8>, b: RangeInclusive<u8>) -> RangeInclusive<u8> {
+    *a.start().max(b.start())..=*a.end().min(b.end())
+}
+
+/// The generator sets of each zone of one preset or instrument, the global
+/// zone (a first zone without the terminal generator) folded into the rest.
+fn zones(
+    bags: &[&[u8]],
+    generators: &[&[u8]],
+    bag_range: std::ops::Range<usize>,
+    terminal: usize,
+) -> Vec<Generators> {
+    let mut global = [None; GEN_COUNT];
+    let mut zones = Vec::new();
+    for bag in bag_range {
+        let (Some(this), Some(next)) = (bags.get(bag), bags.get(bag + 1)) else {
+            break;
+        };
+        let mut zone = global;
+        for generator in generators
+            .get(u16_at(this, 0) as usize..u16_at(next, 0) as usize)
+            .unwrap_or_default()
+        {
+            let number = u16_at(generator, 0) as usize;
+            if number < GEN_COUNT {
+                zone[number] = Some([generator[2], generator[3]]);
+            }
+        }
+        if zone[terminal].is_some() {
+            zones.push(zone);
+        } else if zones.is_empty() {
+            global = zone;
+        }
+    }
+    zones
+}
+
+fn parse_sf2(bytes: &[u8]) -> Result<SoundFont, String> {
+    if bytes.get(..4) != Some(b"RIFF".as_slice()) || bytes.get(8..12) != Some(b"sfbk".as_slice()) {
+        return Err("Not a SoundFont".to_string());
+    }
+    let top = riff_chunks(&bytes[12..]);
+    let sdta = list(&top, b"sdta");
+    let pdta = list(&top, b"pdta");
+    let pool: Vec<f32> = chunk(&sdta, b"smpl")
+        .chunks_exact(2)
+        .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32_768.0)
+        .collect();
+    if pool.is_empty() {
+        return Err("The SoundFont has no samples".to_string());
+    }
+    let preset_headers = records(&pdta, b"phdr", 38);
+    let preset_bags = records(&pdta, b"pbag", 4);
+    let preset_generators = records(&pdta, b"pgen", 4);
+    let instruments = records(&pdta, b"inst", 22);
+    let instrument_bags = records(&pdta, b"ibag", 4);
+    let instrument_generators = records(&pdta, b"igen", 4);
+    let sample_headers = records(&pdta, b"shdr", 46);
+    let name = |record: &[u8]| {
+        let raw = &record[..20];
+        let len = raw.iter().position(|&byte| byte == 0).unwrap_or(raw.len());
+        String::from_utf8_lossy(&raw[..len]).trim().to_string()
+    };
+
+    let mut presets = Vec::new();
+    // The last header only marks where the previous preset's zones end.
+    for pair in preset_headers.windows(2) {
+        let (header, next) = (pair[0], pair[1]);
+        let (program, bank) = (u16_at(header, 20), u16_at(header, 22));
+        let bag_range = u16_at(header, 24) as usize..u16_at(next, 24) as usize;
+        let mut regions = Vec::new();
+        for preset_zone in zones(&preset_bags, &preset_generators, bag_range, GEN_INSTRUMENT) {
+            let instrument = unsigned(&preset_zone, GEN_INSTRUMENT);
+            let (Some(header), Some(next)) =
+                (instruments.get(instrument), instruments.get(instrument + 1))
+            else {
+                continue;
+            };
+            let bag_range = u16_at(header, 20) as usize..u16_at(next, 20) as usize;
+            for zone in zones(
+                &instrument_bags,
+                &instrument_generators,
+                bag_range,
+                GEN_SAMPLE_ID,
+            ) {
+                let sample = unsigned(&zone, GEN_SAMPLE_ID);
+                let Some(header) = sample_headers.get(sample) else {
+                    continue;
+                };
+                // ROM samples live in hardware that isn't here.
+                if u16_at(header, 44) & 0x8000 != 0 {
+                    continue;
+                }
+                let offset = |fine: usize, coarse: usize| {
+                    signed(&zone, fine) as i64 + signed(&zone, coarse) as i64 * 32_768
+                };
+                let at = |base: u32, offset: i64| {
+                    (base as i64 + offset).clamp(0, pool.len() as i64) as usize
+                };
+                let start = at(
+                    u32_at(header, 20),
+                    offset(GEN_START_OFFSET, GEN_START_COARSE_OFFSET),
+                );
+                let end = at(
+                    u32_at(header, 24),
+                    offset(GEN_END_OFFSET, GEN_END_COARSE_OFFSET),
+                );
+                let loop_start = at(
+                    u32_at(header, 28),
+                    offset(GEN_LOOP_START_OFFSET, GEN_LOOP_START_COARSE_OFFSET),
+                );
+                let loop_end = at(
+                    u32_at(header, 32),
+                    offset(GEN_LOOP_END_OFFSET, GEN_LOOP_END_COARSE_OFFSET),
+                );
+                let sample_rate = u32_at(header, 36) as f32;
+                if start >= end || sample_rate <= 0.0 {
+                    continue;
+                }
+                let loops = signed(&zone, GEN_SAMPLE_MODES) & 1 == 1
+                    && start <= loop_start
+                    && loop_start < loop_end
+                    && loop_end <= end;
+                let root = match signed(&zone, GEN_ROOT_KEY) {
+                    key @ 0..=127 if zone[GEN_ROOT_KEY].is_some() => key as f32,
+                    _ => match header[40] {
+                        key @ 0..=127 => key as f32,
+                        _ => 60.0,
+                    },
+                };
+                let tune = (signed(&zone, GEN_COARSE_TUNE) + signed(&preset_zone, GEN_COARSE_TUNE))
+                    as f32
+                    + (signed(&zone, GEN_FINE_TUNE)
+                        + signed(&preset_zone, GEN_FINE_TUNE)
+                        + header[41] as i8 as i32) as f32
+                        / 100.0;
+                let attenuation_cb =
+                    signed(&zone, GEN_ATTENUATION) + signed(&preset_zone, GEN_ATTENUATION);
+                regions.push(Region {
+                    keys: intersect(
+                        range(&zone, GEN_KEY_RANGE),
+                        range(&preset_zone, GEN_KEY_RANGE),
+                    ),
+                    velocities: intersect(
+                        range(&zone, GEN_VELOCITY_RANGE),
+                        range(&preset_zone, GEN_VELOCITY_RANGE),
+                    ),
+                    start,
+                    end,
+                    loop_points: loops.then_some((loop_start, loop_end)),
+                    sample_rate,
+                    root_note: root - tune,
+                    gain: 10_f32.powf(-attenuation_cb.max(0) as f32 / 200.0),
+                });
+            }
+        }
+        presets.push((
+            bank,
+            program,
+            SoundFontPreset {
+                name: format!("{bank:03}:{program:03} {}", name(header)),
+                regions,
+            },
+        ));
+    }
+    presets.sort_by_key(|(bank, program, _)| (*bank, *program));
+    Ok(SoundFont {
+        pool,
+        presets: presets.into_iter().map(|(_, _, preset)| preset).collect(),
+    })
+}
+
+/// SFZ note value: a MIDI number or a name such as `c4` or `f#3`, where
+/// `c4` is 60.
+fn sfz_note(value: &str) -> Option<u8> {
+    if let Ok(note) = value.parse::<i32>() {
+        return u8::try_from(note).ok().filter(|note| *note <= 127);
+    }
+    let value = value.to_ascii_lowercase();
+    let mut chars = value.chars();
+    let pitch_class = match chars.next()? {
+        'c' => 0,
+        'd' => 2,
+        'e' => 4,
+        'f' => 5,
+        'g' => 7,
+        'a' => 9,
+        'b' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = match rest.chars().next()? {
+        '#' => (1, &rest[1..]),
+        'b' if rest.len() > 1 => (-1, &rest[1..]),
+        _ => (0, rest),
+    };
+    let note = (octave.parse::<i32>().ok()? + 1) * 12 + pitch_class + accidental;
+    u8::try_from(note).ok().filter(|note| *note <= 127)
+}
+
+/// Splits an SFZ file into headers and their opcodes. Values may contain
+/// spaces (sample paths often do), so a word without `=` belongs to the
+/// value before it.
+fn sfz_sections(text: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    for line in text.lines() {
+        let mut rest = line.split("//").next().unwrap_or_default().trim();
+        while !rest.is_empty() {
+            if let Some(header) = rest.strip_prefix('<') {
+                let Some(end) = header.find('>') else {
+                    break;
+                };
+                sections.push((header[..end].trim().to_string(), Vec::new()));
+                rest = header[end + 1..].trim();
+                continue;
+            }
+            let end = rest.find('<').unwrap_or(rest.len());
+            if let Some((_, opcodes)) = sections.last_mut() {
+                for word in rest[..end].split_whitespace() {
+                    match word.split_once('=') {
+                        Some((key, value)) => opcodes.push((key.to_string(), value.to_string())),
+                        None => {
+                            if let Some((_, value)) = opcodes.last_mut() {
+                                value.push(' ');
+                                value.push_str(word);
+                            }
+                        }
+                    }
+                }
+            }
+            rest = rest[end..].trim();
+        }
+    }
+    sections
+}
+
+/// Where an SFZ's WAV landed in the sample pool.
+#[derive(Clone, Copy)]
+struct PooledWav {
+    offset: usize,
+    length: usize,
+    sample_rate: f32,
+    smpl_loop: Option<(usize, usize)>,
+}
+
+fn parse_sfz(text: &str, path: &Path) -> Result<SoundFont, String> {
+    let folder = path.parent().unwrap_or(Path::new(""));
+    let mut pool = Vec::new();
+    let mut loaded: HashMap<String, PooledWav> = HashMap::new();
+    let mut control: HashMap<String, String> = HashMap::new();
+    let mut global: HashMap<String, String> = HashMap::new();
+    let mut group: HashMap<String, String> = HashMap::new();
+    let mut regions = Vec::new();
+    for (header, opcodes) in sfz_sections(text) {
+        match header.as_str() {
+            "control" => control.extend(opcodes),
+            "global" => {
+                global = opcodes.into_iter().collect();
+                group.clear();
+            }
+            "group" => group = opcodes.into_iter().collect(),
+            "region" => {
+                let mut region = global.clone();
+                region.extend(group.clone());
+                region.extend(opcodes);
+                let Some(sample) = region.get("sample") else {
+                    continue;
+                };
+                let relative = format!(
+                    "{}{}",
+                    control.get("default_path").map_or("", String::as_str),
+                    sample
+                )
+                .replace('\\', "/");
+                let file = folder.join(relative).to_string_lossy().into_owned();
+                if !loaded.contains_key(&file) {
+                    let bytes =
+                        fs::read(&file).map_err(|err| format!("Could not read {file}: {err}"))?;
+                    let wav = decode_mono(&bytes).map_err(|err| format!("{file}: {err}"))?;
+                    let (_, smpl_loop) = smpl_info(&bytes);
+                    loaded.insert(
+                        file.clone(),
+                        PooledWav {
+                            offset: pool.len(),
+                            length: wav.samples.len(),
+                            sample_rate: wav.sample_rate as f32,
+                            smpl_loop,
+                        },
+                    );
+                    pool.extend(wav.samples);
+                }
+                let PooledWav {
+                    offset,
+                    length,
+                    sample_rate,
+                    smpl_loop,
+                } = loaded[&file];
+                let number =
+                    |key: &str| region.get(key).and_then(|value| value.parse::<f32>().ok());
+                let note = |key: &str| region.get(key).and_then(|value| sfz_note(value));
+                let frame =
+                    |key: &str| number(key).map(|value| (value.max(0.0) as usize).min(length));
+                let velocity =
+                    |key: &str| region.get(key).and_then(|value| value.parse::<u8>().ok());
+                let key = note("key");
+                let root = note("pitch_keycenter").or(key).unwrap_or(60) as f32;
+                let loops = match region.get("loop_mode").map(String::as_str) {
+                    Some("loop_continuous" | "loop_sustain") => true,
+                    Some(_) => false,
+                    None => smpl_loop.is_some(),
+                };
+                // SFZ loop and sample ends are the last sample played.
+                let start = frame("offset").unwrap_or(0);
+                let end = frame("end").map_or(length, |end| (end + 1).min(length));
+                if start >= end || sample_rate <= 0.0 {
+                    continue;
+                }
+                let loop_points = match (frame("loop_start"), frame("loop_end")) {
+                    (Some(start), Some(end)) => Some((start, (end + 1).min(length))),
+                    _ => smpl_loop.map(|(start, end)| (start.min(length), end.min(length))),
+                }
+                .filter(|(loop_start, loop_end)| {
+                    loops && start <= *loop_start && loop_start < loop_end && *loop_end <= end
+                });
+                regions.push(Region {
+                    keys: note("lokey").or(key).unwrap_or(0)..=note("hikey").or(key).unwrap_or(127),
+                    velocities: velocity("lovel").unwrap_or(0)..=velocity("hivel").unwrap_or(127),
+                    start: offset + start,
+                    end: offset + end,
+                    loop_points: loop_points.map(|(start, end)| (offset + start, offset + end)),
+                    sample_rate,
+                    root_note: root
+                        - number("transpose").unwrap_or(0.0)
+                        - number("tune").unwrap_or(0.0) / 100.0,
+                    gain: 10_f32.powf(number("volume").unwrap_or(0.0) / 20.0),
+                });
+            }
+            _ => {}
+        }
+    }
+    let name = path
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    Ok(SoundFont {
+        pool,
+        presets: vec![SoundFontPreset { name, regions }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn riff_chunks_survive_damaged_headers() {
+        assert!(riff_chunks(b"LIST\x04\x00").is_empty());
+        let oversized = b"smpl\xff\xff\xff\xff\x01\x02\x03";
+        assert_eq!(riff_chunks(oversized), vec![(*b"smpl", &[1, 2, 3][..])]);
+        let truncated = b"ifil\x02\x00\x00\x00\x02\x00INAM\x09\x00\x00\x00ab";
+        assert_eq!(
+            riff_chunks(truncated),
+            vec![(*b"ifil", &[2, 0][..]), (*b"INAM", &b"ab"[..])]
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chords::PITCH_CLASS_NAMES;
+use crate::scales::Scale;
+use crate::settings::{config_dir, to_tidy_toml, write_atomic};
+
+pub const STATS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+/// Practice time keeps counting this long after the last key goes up, so
+/// the pauses between phrases still count.
+const IDLE_GRACE_SECS: f32 = 10.0;
+/// Longest frame counted; a stalled or hidden window shouldn't add time.
+const MAX_FRAME_SECS: f32 = 0.25;
+/// Listed against the time played without a scale selected.
+const NO_SCALE: &str = "No scale";
+
+/// What was played over some stretch of practice.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PracticeTotals {
+    pub seconds_played: f64,
+    pub notes_played: u64,
+    /// Notes played per pitch class, C first.
+    pub pitch_classes: [u64; 12],
+    /// Seconds played with each scale selected, by its label.
+    pub scales: BTreeMap<String, f64>,
+}
+
+impl PracticeTotals {
+    fn add_time(&mut self, seconds: f64, scale: Option<&Scale>) {
+        self.seconds_played += seconds;
+        let label = scale.map_or_else(|| NO_SCALE.to_string(), Scale::label);
+        *self.scales.entry(label).or_default() += seconds;
+    }
+
+    fn add_note(&mut self, note: u8) {
+        self.notes_played += 1;
+        self.pitch_classes[(note % 12) as usize] += 1;
+    }
+
+    /// The `count` most played pitch classes, most played first.
+    pub fn top_pitch_classes(&self, count: usize) -> Vec<(&'static str, u64)> {
+        let mut played: Vec<(&'static str, u64)> = PITCH_CLASS_NAMES
+            .iter()
+            .copied()
+            .zip(self.pitch_classes)
+            .filter(|(_, notes)| *notes > 0)
+            .collect();
+        played.sort_by_key(|entry| std::cmp::Reverse(entry.1));
+        played.truncate(count);
+        played
+    }
+
+    /// The `count` scales practised longest, longest first.
+    pub fn top_scales(&self, count: usize) -> Vec<(&str, f64)> {
+        let mut scales: Vec<(&str, f64)> = self
+            .scales
+            .iter()
+            .map(|(label, seconds)| (label.as_str(), *seconds))
+            .collect();
+        scales.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scales.truncate(count);
+        scales
+    }
+}
+
+/// Cumulative practice, kept in its own file in the config directory.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PracticeStats {
+    /// Runs of the app in which anything was played.
+    pub sessions: u32,
+    pub total: PracticeTotals,
+}
+
+/// Counts notes and practice time as they are played, for this session and
+/// on top of the saved totals.
+pub struct PracticeTracker {
+    pub session: PracticeTotals,
+    pub stats: PracticeStats,
+    previous: Vec<u8>,
+    /// Seconds since a key was last down.
+    idle: f32,
+    unsaved: bool,
+}
+
+impl PracticeTracker {
+    pub fn new(stats: PracticeStats) -> Self {
+        Self {
+            session: PracticeTotals::default(),
+            stats,
+            previous: Vec::new(),
+            idle: f32::INFINITY,
+            unsaved: false,
+        }
+    }
+
+    /// Call once per frame with the sounding notes, the selected scale and
+    /// the frame time in seconds.
+    pub fn update(&mut self, held: &[u8], scale: Option<&Scale>, dt: f32) {
+        for note in held {
+            if self.previous.contains(note) {
+                continue;
+            }
+            if self.session.notes_played == 0 {
+                self.stats.sessions += 1;
+            }
+            self.session.add_note(*note);
+            self.stats.total.add_note(*note);
+            self.unsaved = true;
+        }
+        self.previous.clear();
+        self.previous.extend_from_slice(held);
+
+        self.idle = if held.is_empty() { self.idle + dt } else { 0.0 };
+        if self.idle <= IDLE_GRACE_SECS {
+            let seconds = dt.clamp(0.0, MAX_FRAME_SECS) as f64;
+            self.session.add_time(seconds, scale);
+            self.stats.total.add_time(seconds, scale);
+            self.unsaved = true;
+        }
+    }
+
+    /// The totals to write, if anything changed since the last call.
+    pub fn take_unsaved(&mut self) -> Option<&PracticeStats> {
+        std::mem::take(&mut self.unsaved).then_some(&self.stats)
+    }
+}
+
+pub fn stats_path() -> PathBuf {
+    config_dir().join("angel_stats.toml")
+}
+
+pub fn load_stats(path: &Path) -> PracticeStats {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| toml::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_stats(path: &Path, stats: &PracticeStats) {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(err) = write_atomic(path, &to_tidy_toml(stats)) {
+        log::error!("Failed to save practice stats to {}: {err}", path.display());
+    }
+}
+
+/// "1 h 05 min", or "12 min" under an hour.
+pub fn format_practice_time(seconds: f64) -> String {
+    let minutes = (seconds / 60.0) as u64;
+    match minutes / 60 {
+        0 => format!("{minutes} min"),
+        hours => format!("{hours} h {:02} min", minutes % 60),
+    }
+}
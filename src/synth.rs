@@ -1,22 +1,98 @@
-use std::collections::BTreeSet;
-use std::f32::consts::{SQRT_2, TAU};
+use std::collections::BTreeMap;
+use std::f32::consts::{PI, SQRT_2, TAU};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
 
-#[derive(Clone, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use crate::additive::Harmonics;
+use crate::controllers::BreathRouting;
+use crate::drums::{DrumHit, DrumKit};
+use crate::effects::{BusEffects, PedalResonance, SendBuses};
+use crate::granular::GrainCloud;
+use crate::limiter::{Limiter, LimiterSettings};
+use crate::mic::MicMode;
+use crate::sampler::SampleFile;
+use crate::scales::Scale;
+use crate::soundfont::SoundFontFile;
+use crate::tuning::{EqualTemperament, Tuning};
+use crate::wavetable::{WavetableFile, wavetable_sample};
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SynthParams {
     pub gain: f32,
+    /// Wait between the key going down and the attack starting.
+    pub delay_seconds: f32,
     pub attack_seconds: f32,
+    /// Time the envelope stays at its peak before the decay.
+    pub hold_seconds: f32,
     pub decay_seconds: f32,
     pub sustain_level: f32,
     pub release_seconds: f32,
+    pub env_curve: EnvCurve,
     pub instrument: InstrumentKind,
     pub waveform: Waveform,
+    /// Pitch of A4, which every note is scaled from: 415 for baroque pitch,
+    /// 442 or 443 to match many orchestras.
+    pub a4_hz: f32,
+    /// Semitones every note sounds away from its key, as for a transposing
+    /// instrument.
+    pub transpose: i8,
+    /// 0..1 sweep through the frames of the wavetable.
+    pub wavetable_position: f32,
+    /// WAV the `Wavetable` waveform plays; the built-in shapes when empty.
+    pub wavetable: WavetableFile,
+    /// What the `Additive` instrument sums in place of the waveform.
+    pub harmonics: Harmonics,
+    /// WAV the `Sampler` instrument plays; silent when empty.
+    pub sample: SampleFile,
+    /// Note the sample was recorded at, as a fractional MIDI note; playing
+    /// it gives the recording back at its own speed.
+    pub sample_root_note: f32,
+    /// Loop between the loop points, as shares (0..1) of the sample, once
+    /// playback reaches the end one, rather than stopping at the end.
+    pub sample_loop: bool,
+    pub sample_loop_start: f32,
+    pub sample_loop_end: f32,
+    /// Length of each grain the `Granular` instrument plays.
+    pub grain_size_ms: f32,
+    /// Grains started per second.
+    pub grain_density_hz: f32,
+    /// 0..1 point in the sample the grains are read from.
+    pub grain_position: f32,
+    /// How far, as a share of the sample, grains start either side of the
+    /// grain position at random.
+    pub grain_spray: f32,
+    /// The keys the `Drums` instrument plays and their drums.
+    pub drum_kit: DrumKit,
+    /// SF2 or SFZ file the `SoundFont` instrument plays; silent when empty.
+    pub soundfont: SoundFontFile,
+    /// Index into the font's presets, in bank and program order.
+    pub soundfont_preset: u32,
+    /// Share of each cycle the `Square` wave spends high; 0.5 is square.
+    pub pulse_width: f32,
+    /// How far the PWM LFO moves the pulse width either side of it.
+    pub pwm_depth: f32,
+    pub pwm_rate_hz: f32,
+    pub filter_mode: FilterMode,
     pub filter_cutoff_hz: f32,
     pub filter_resonance: f32,
+    /// Gain into the ladder filter's saturation, in dB.
+    pub filter_drive_db: f32,
     pub vibrato_depth_semitones: f32,
     pub vibrato_rate_hz: f32,
     pub unison_spread_cents: f32,
+    /// Detuned copies stacked on each note; 1 plays it alone.
+    pub unison_voices: u8,
+    /// 0..1 level of the outer unison copies against the middle ones.
+    pub unison_blend: f32,
     pub autotune_amount: f32,
+    /// Time autotune takes to pull the pitch onto a note.
+    pub autotune_retune_ms: f32,
     pub noise_mix: f32,
+    /// 0..1 slow random wander of each voice's pitch and cutoff.
+    pub drift_amount: f32,
     pub eq_low_gain_db: f32,
     pub eq_low_freq_hz: f32,
     pub eq_mid_gain_db: f32,
@@ -24,25 +100,77 @@ pub struct SynthParams {
     pub eq_mid_q: f32,
     pub eq_high_gain_db: f32,
     pub eq_high_freq_hz: f32,
+    /// Portamento time when a held note slides to a new one.
+    pub glide_seconds: f32,
+    /// Slide into every new note from the one before, not only legato and
+    /// dragged notes.
+    pub glide_always: bool,
+    pub voice_mode: VoiceMode,
+    /// Most voices a poly patch sounds at once before it steals one.
+    pub polyphony: u32,
+    pub voice_steal: VoiceSteal,
+    /// Which held key a mono or legato patch plays.
+    pub note_priority: NotePriority,
+    /// Level change per octave from `KEY_TRACK_CENTRE`, in dB.
+    pub key_track_level_db: f32,
+    /// Octaves the filter cutoff moves per octave from `KEY_TRACK_CENTRE`.
+    pub key_track_tone: f32,
+    /// 0..1 share of the level set by velocity; 1 scales it all the way down.
+    pub velocity_level: f32,
+    /// Octaves the filter cutoff closes from full velocity down to none.
+    pub velocity_tone: f32,
+    /// -1 (left) to 1 (right): where the patch's voices are centred.
+    pub pan: f32,
+    /// 0..1 spread of voices by key and of unison copies around them.
+    pub stereo_width: f32,
 }
 
 impl Default for SynthParams {
     fn default() -> Self {
         Self {
             gain: 0.65,
+            delay_seconds: 0.0,
             attack_seconds: 0.01,
+            hold_seconds: 0.0,
             decay_seconds: 0.2,
             sustain_level: 0.7,
             release_seconds: 0.35,
+            env_curve: EnvCurve::Linear,
             instrument: InstrumentKind::Keys,
             waveform: Waveform::Saw,
+            a4_hz: 440.0,
+            transpose: 0,
+            wavetable_position: 0.0,
+            wavetable: WavetableFile::default(),
+            harmonics: Harmonics::default(),
+            sample: SampleFile::default(),
+            sample_root_note: 60.0,
+            sample_loop: false,
+            sample_loop_start: 0.0,
+            sample_loop_end: 1.0,
+            grain_size_ms: 80.0,
+            grain_density_hz: 20.0,
+            grain_position: 0.25,
+            grain_spray: 0.05,
+            drum_kit: DrumKit::default(),
+            soundfont: SoundFontFile::default(),
+            soundfont_preset: 0,
+            pulse_width: 0.5,
+            pwm_depth: 0.0,
+            pwm_rate_hz: 0.8,
+            filter_mode: FilterMode::LowPass,
             filter_cutoff_hz: 4_000.0,
             filter_resonance: 0.2,
+            filter_drive_db: 0.0,
             vibrato_depth_semitones: 0.15,
             vibrato_rate_hz: 4.0,
             unison_spread_cents: 6.0,
+            unison_voices: 3,
+            unison_blend: 0.7,
             autotune_amount: 0.0,
+            autotune_retune_ms: 15.0,
             noise_mix: 0.03,
+            drift_amount: 0.0,
             eq_low_gain_db: 0.0,
             eq_low_freq_hz: 120.0,
             eq_mid_gain_db: 0.0,
@@ -50,21 +178,388 @@ impl Default for SynthParams {
             eq_mid_q: 0.8,
             eq_high_gain_db: 0.0,
             eq_high_freq_hz: 6_000.0,
+            glide_seconds: 0.12,
+            glide_always: false,
+            voice_mode: VoiceMode::Poly,
+            polyphony: 32,
+            voice_steal: VoiceSteal::Oldest,
+            note_priority: NotePriority::Last,
+            key_track_level_db: 0.0,
+            key_track_tone: 0.0,
+            velocity_level: 1.0,
+            velocity_tone: 0.0,
+            pan: 0.0,
+            stereo_width: 0.0,
+        }
+    }
+}
+
+/// Live controller state layered over the patch at playback time, so moving a
+/// stick or wheel never edits the saved sound.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Performance {
+    /// 0..1, adds up to half a semitone of vibrato like a mod wheel.
+    pub mod_wheel: f32,
+    /// -1..1, sweeps the filter cutoff up to three octaves down or up.
+    pub cutoff_sweep: f32,
+    /// Latest breath controller pressure, 0..1, once any has arrived.
+    pub breath: Option<f32>,
+    pub breath_routing: BreathRouting,
+    /// -1..1, the pitch wheel's position.
+    pub pitch_bend: f32,
+    /// Semitones the pitch bend reaches at either end.
+    pub bend_range: f32,
+}
+
+impl Performance {
+    fn apply(&self, params: &SynthParams) -> SynthParams {
+        let mut params = params.clone();
+        params.vibrato_depth_semitones += self.mod_wheel.clamp(0.0, 1.0) * 0.5;
+        params.filter_cutoff_hz *= 2_f32.powf(self.cutoff_sweep.clamp(-1.0, 1.0) * 3.0);
+        if let (Some(breath), true) = (self.breath, self.breath_routing.enabled) {
+            self.breath_routing.apply(breath, &mut params);
+        }
+        params
+    }
+}
+
+/// A held key: how hard it was struck and, for a slide, the note it came from.
+#[derive(Clone, Copy, PartialEq)]
+pub struct HeldNote {
+    pub velocity: f32,
+    pub glide_from: Option<u8>,
+    /// Semitones the note is bent away from its key, as by the ribbon.
+    pub bend: f32,
+    /// Counts up with every press, so mono patches can follow the latest key.
+    pub order: u32,
+}
+
+/// Note at which key tracking leaves level and tone alone: middle C.
+const KEY_TRACK_CENTRE: f32 = 60.0;
+/// Share of the stereo width that places voices by key, low notes left; the
+/// rest spreads each voice's unison copies, flat left and sharp right.
+const VOICE_SPREAD: f32 = 0.5;
+/// Notes this far from middle C sit at the edge of the voice spread.
+const VOICE_SPREAD_SEMITONES: f32 = 24.0;
+/// Narrowest pulse the PWM sweep reaches, so deep modulation thins the
+/// wave rather than cutting it out.
+const MIN_PULSE_WIDTH: f32 = 0.02;
+/// Bend of the curved envelope shapes: an exponential release spends its
+/// last half below -20 dB.
+const ENV_CURVE_STEEPNESS: f32 = 5.0;
+/// Fade given to a stolen voice: long enough not to click, short enough
+/// that the polyphony barely overshoots.
+const STEAL_FADE_SECONDS: f32 = 0.005;
+/// Ladder feedback at full resonance. It rings from 4, so the top fifth of
+/// the resonance range self-oscillates.
+const LADDER_MAX_FEEDBACK: f32 = 5.0;
+/// Share of the input added back against the feedback, so the ladder's bass
+/// doesn't vanish as resonance rises.
+const LADDER_BASS_COMPENSATION: f32 = 0.5;
+
+/// Time a voice takes to follow a change of bend, so bends arriving once per
+/// UI frame still sound continuous.
+const BEND_SMOOTHING_SECONDS: f32 = 0.015;
+
+/// How often drift picks a new heading, and how long it takes to get there.
+const DRIFT_STEP_SECONDS: f32 = 0.8;
+/// Widest wander at full drift: of the pitch, in semitones, and of the
+/// cutoff, in octaves.
+const DRIFT_PITCH_SEMITONES: f32 = 0.15;
+const DRIFT_CUTOFF_OCTAVES: f32 = 0.5;
+
+/// Most voices a patch can be set to sound at once.
+pub const MAX_POLYPHONY: u32 = 64;
+/// Most unison copies a note can stack.
+pub const MAX_UNISON_VOICES: u8 = 8;
+/// Step between the unison copies' starting phases, in cycles: the golden
+/// ratio, so no two copies start together however many there are.
+const UNISON_PHASE_STEP: f32 = 0.618_034;
+/// Range of the A4 reference pitch, from baroque pitch to a semitone above
+/// concert pitch.
+pub const A4_RANGE: RangeInclusive<f32> = 415.0..=466.0;
+/// Furthest a patch can be transposed either way, in semitones.
+pub const MAX_TRANSPOSE: i8 = 24;
+
+/// Engine ids of the parts the main patch, split and layer turn into.
+/// Added parts are numbered from `FIRST_ADDED_PART` up.
+pub const MAIN_PART: u32 = 0;
+pub const LOWER_PART: u32 = 1;
+pub const LAYER_PART: u32 = 2;
+pub const FIRST_ADDED_PART: u32 = 3;
+/// Engine id of the drone, out of the way of added parts.
+pub const DRONE_PART: u32 = u32::MAX;
+
+/// Splits the keyboard in two: notes below `note` play the `lower` patch,
+/// the rest play the main one.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardSplit {
+    pub note: u8,
+    /// Preset the lower patch was loaded from, for display.
+    pub preset: String,
+    pub lower: SynthParams,
+    #[serde(default)]
+    pub mix: PartMix,
+}
+
+/// Level, stereo position, pitch offset and mixer switches of one part.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PartMix {
+    /// 0..1 linear gain applied after the patch's own gain.
+    pub volume: f32,
+    /// -1 (left) to 1 (right).
+    pub pan: f32,
+    /// Semitones added to every note the part plays.
+    pub transpose: i8,
+    pub mute: bool,
+    /// While any part is soloed, only soloed parts are heard.
+    pub solo: bool,
+    /// 0..1 sent to the shared reverb, after the volume fader.
+    pub reverb_send: f32,
+    /// 0..1 sent to the shared delay, after the volume fader.
+    pub delay_send: f32,
+    /// Another part that pushes this one down while it plays.
+    pub ducking: Option<Ducking>,
+    /// The soft pedal softens and darkens this part while it is down.
+    pub follows_soft_pedal: bool,
+}
+
+/// Sidechain ducking: the level of the `source` part, followed with the
+/// attack and release times, turns this part down by up to `amount`.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ducking {
+    /// Id of the part that does the ducking.
+    pub source: u32,
+    /// 0..1 gain reduction once the source is at full level.
+    pub amount: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+}
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Self {
+            source: MAIN_PART,
+            amount: 0.6,
+            attack_ms: 5.0,
+            release_ms: 200.0,
+        }
+    }
+}
+
+/// Una corda: struck velocities are squeezed into this range and the
+/// cutoff drops by this factor while the soft pedal is down.
+const SOFT_PEDAL_VELOCITY: RangeInclusive<f32> = 0.2..=0.65;
+const SOFT_PEDAL_CUTOFF: f32 = 0.5;
+
+/// Longest tail `bounce_held` waits for after the notes are let go.
+const MAX_BOUNCE_TAIL_SECONDS: f32 = 12.0;
+/// A bounce ends once the output has stayed below this level for
+/// `BOUNCE_SILENCE_SECONDS`.
+const BOUNCE_SILENCE_LEVEL: f32 = 1.0e-4;
+const BOUNCE_SILENCE_SECONDS: f32 = 0.25;
+
+/// Keys the audio thread reports voice levels for: the MIDI range.
+const VOICE_LEVEL_KEYS: usize = 128;
+
+/// Followed source level at which ducking reaches its full amount; about
+/// one firmly played note.
+const DUCK_FULL_LEVEL: f32 = 0.25;
+
+/// Corner of the high-pass on the engine output, below hearing, which
+/// takes out DC the filters' feedback and asymmetric waves leave behind.
+const DC_BLOCK_HZ: f32 = 5.0;
+/// Filter memory smaller than this is zeroed, so a fading tail never
+/// reaches the denormal numbers that take the CPU far longer to work on.
+const DENORMAL_LIMIT: f32 = 1.0e-20;
+
+impl Default for PartMix {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pan: 0.0,
+            transpose: 0,
+            mute: false,
+            solo: false,
+            reverb_send: 0.0,
+            delay_send: 0.0,
+            ducking: None,
+            follows_soft_pedal: true,
+        }
+    }
+}
+
+impl PartMix {
+    /// Balance-style panning: the centre leaves both sides at full level so a
+    /// single centred part sounds exactly as it did before layering.
+    fn gains(&self, audible: bool) -> [f32; 2] {
+        if !audible {
+            return [0.0; 2];
+        }
+        let volume = self.volume.clamp(0.0, 1.0);
+        balance(self.pan).map(|gain| volume * gain)
+    }
+
+    /// Reverb and delay send levels, following the fader and the mute.
+    fn sends(&self, audible: bool) -> [f32; 2] {
+        if !audible {
+            return [0.0; 2];
+        }
+        let volume = self.volume.clamp(0.0, 1.0);
+        [self.reverb_send, self.delay_send].map(|send| send.clamp(0.0, 1.0) * volume)
+    }
+
+    fn transpose_note(&self, note: u8) -> u8 {
+        (note as i16 + self.transpose as i16).clamp(0, 127) as u8
+    }
+}
+
+/// A second patch stacked on the main one across the whole keyboard.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatchLayer {
+    /// Preset the layer was loaded from, for display.
+    pub preset: String,
+    pub params: SynthParams,
+    pub mix: PartMix,
+}
+
+/// The keys a part answers to.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteSource {
+    All,
+    /// `low` to `high`, both included.
+    Range {
+        low: u8,
+        high: u8,
+    },
+}
+
+impl NoteSource {
+    pub fn plays(&self, note: u8) -> bool {
+        match self {
+            NoteSource::All => true,
+            NoteSource::Range { low, high } => (*low..=*high).contains(&note),
         }
     }
 }
 
+/// One patch in the multi-part engine: its sound, the keys it plays and its
+/// mixer strip. The main patch, the split's lower zone and the layer are
+/// parts too; `SynthShared::parts` lists them all.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Part {
+    /// Stable while parts come and go, so the engine never hands one
+    /// part's ringing voices to another.
+    pub id: u32,
+    pub name: String,
+    pub params: SynthParams,
+    pub source: NoteSource,
+    #[serde(default)]
+    pub mix: PartMix,
+}
+
+/// A note (and optionally its fifth) held indefinitely through the main
+/// patch underneath whatever is played, for practising against a pedal tone.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Drone {
+    pub note: u8,
+    /// Also sound the fifth above.
+    pub fifth: bool,
+    /// 0..1, independent of the main part's volume.
+    pub level: f32,
+}
+
+impl Default for Drone {
+    fn default() -> Self {
+        Self {
+            note: 36,
+            fifth: false,
+            level: 0.5,
+        }
+    }
+}
+
+impl Drone {
+    fn notes(&self) -> impl Iterator<Item = u8> {
+        let fifth = self.fifth.then(|| self.note.saturating_add(7).min(127));
+        std::iter::once(self.note.min(127)).chain(fifth)
+    }
+}
+
+/// A locally played note change, queued for listeners such as a jam session.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoteEvent {
+    On { note: u8, velocity: f32 },
+    Off { note: u8 },
+}
+
 #[derive(Clone)]
 pub struct SynthShared {
     pub params: SynthParams,
-    pressed_notes: BTreeSet<u8>,
+    pub performance: Performance,
+    pub split: Option<KeyboardSplit>,
+    /// Mixer strip of the main patch.
+    pub main_mix: PartMix,
+    pub layer: Option<PatchLayer>,
+    /// Parts added in the mixer, on top of the main patch, split and layer.
+    pub added_parts: Vec<Part>,
+    pub buses: SendBuses,
+    pub limiter: LimiterSettings,
+    /// Una corda: parts that follow it play softer and darker.
+    pub soft_pedal: bool,
+    pub drone: Option<Drone>,
+    pub mic_mode: MicMode,
+    /// Key autotune corrects voices and the mic toward; chromatic when unset.
+    pub scale: Option<Scale>,
+    /// Pitch of each key, for every part.
+    pub tuning: Arc<dyn Tuning>,
+    pressed_notes: BTreeMap<u8, HeldNote>,
+    /// Notes released while hold is on; they keep sounding until hold is turned off.
+    latched_notes: BTreeMap<u8, HeldNote>,
+    /// Notes played by other players in a jam session, keyed by player and
+    /// note so two players on one key don't cut each other off; never
+    /// recorded as events.
+    remote_notes: BTreeMap<(u32, u8), HeldNote>,
+    /// Local note changes since the last `take_note_events`, while capturing.
+    note_events: Option<Vec<NoteEvent>>,
+    /// Loudest envelope × velocity on each key, reported back by the audio
+    /// thread. A fixed array, so reporting never allocates.
+    voice_levels: [f32; VOICE_LEVEL_KEYS],
+    active_voices: usize,
+    hold: bool,
+    panic_count: u32,
+    press_count: u32,
 }
 
 impl Default for SynthShared {
     fn default() -> Self {
         Self {
             params: SynthParams::default(),
-            pressed_notes: BTreeSet::new(),
+            performance: Performance::default(),
+            split: None,
+            main_mix: PartMix::default(),
+            layer: None,
+            added_parts: Vec::new(),
+            buses: SendBuses::default(),
+            limiter: LimiterSettings::default(),
+            soft_pedal: false,
+            drone: None,
+            mic_mode: MicMode::Off,
+            scale: None,
+            tuning: Arc::new(EqualTemperament),
+            pressed_notes: BTreeMap::new(),
+            latched_notes: BTreeMap::new(),
+            remote_notes: BTreeMap::new(),
+            note_events: None,
+            voice_levels: [0.0; VOICE_LEVEL_KEYS],
+            active_voices: 0,
+            hold: false,
+            panic_count: 0,
+            press_count: 0,
         }
     }
 }
@@ -73,50 +568,516 @@ impl SynthShared {
     pub fn new_with_params(params: SynthParams) -> Self {
         Self {
             params,
-            pressed_notes: BTreeSet::new(),
+            performance: Performance::default(),
+            split: None,
+            main_mix: PartMix::default(),
+            layer: None,
+            added_parts: Vec::new(),
+            buses: SendBuses::default(),
+            limiter: LimiterSettings::default(),
+            soft_pedal: false,
+            drone: None,
+            mic_mode: MicMode::Off,
+            scale: None,
+            tuning: Arc::new(EqualTemperament),
+            pressed_notes: BTreeMap::new(),
+            latched_notes: BTreeMap::new(),
+            remote_notes: BTreeMap::new(),
+            note_events: None,
+            voice_levels: [0.0; VOICE_LEVEL_KEYS],
+            active_voices: 0,
+            hold: false,
+            panic_count: 0,
+            press_count: 0,
         }
     }
 
-    pub fn press_note(&mut self, note: u8) {
-        self.pressed_notes.insert(note);
+    /// Holds `note` down. `velocity` runs from 0 (silent) to 1 (full).
+    pub fn press_note(&mut self, note: u8, velocity: f32) {
+        let held = HeldNote {
+            velocity: velocity.clamp(0.0, 1.0),
+            glide_from: None,
+            bend: 0.0,
+            order: self.next_press(),
+        };
+        self.latched_notes.remove(&note);
+        self.pressed_notes.insert(note, held);
+        self.record(NoteEvent::On {
+            note,
+            velocity: held.velocity,
+        });
+    }
+
+    /// Moves a held `from` note to `to` without retriggering: the sounding
+    /// voice bends over to the new pitch in `glide_seconds`.
+    pub fn glide_note(&mut self, from: u8, to: u8, velocity: f32) {
+        self.pressed_notes.remove(&from);
+        let held = HeldNote {
+            velocity: velocity.clamp(0.0, 1.0),
+            glide_from: Some(from),
+            bend: 0.0,
+            order: self.next_press(),
+        };
+        self.latched_notes.remove(&to);
+        self.pressed_notes.insert(to, held);
+        self.record(NoteEvent::Off { note: from });
+        self.record(NoteEvent::On {
+            note: to,
+            velocity: held.velocity,
+        });
+    }
+
+    /// Bends a held `note` by `semitones`, leaving it held on its own key.
+    pub fn bend_note(&mut self, note: u8, semitones: f32) {
+        if let Some(held) = self.pressed_notes.get_mut(&note) {
+            held.bend = semitones;
+        }
     }
 
     pub fn release_note(&mut self, note: u8) {
-        self.pressed_notes.remove(&note);
+        if let Some(held) = self.pressed_notes.remove(&note) {
+            if self.hold {
+                self.latched_notes.insert(note, held);
+            } else {
+                self.record(NoteEvent::Off { note });
+            }
+        }
+    }
+
+    /// Starts or stops queueing local note changes for `take_note_events`.
+    pub fn set_note_capture(&mut self, capture: bool) {
+        if capture != self.note_events.is_some() {
+            self.note_events = capture.then(Vec::new);
+        }
+    }
+
+    pub fn take_note_events(&mut self) -> Vec<NoteEvent> {
+        self.note_events
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn next_press(&mut self) -> u32 {
+        self.press_count = self.press_count.wrapping_add(1);
+        self.press_count
+    }
+
+    fn record(&mut self, event: NoteEvent) {
+        if let Some(events) = &mut self.note_events {
+            events.push(event);
+        }
+    }
+
+    /// Plays a note for another `player`; it sounds and shows like a local
+    /// one but is not captured as a local event.
+    pub fn press_remote_note(&mut self, player: u32, note: u8, velocity: f32) {
+        let held = HeldNote {
+            velocity: velocity.clamp(0.0, 1.0),
+            glide_from: None,
+            bend: 0.0,
+            order: self.next_press(),
+        };
+        self.remote_notes.insert((player, note), held);
+    }
+
+    pub fn release_remote_note(&mut self, player: u32, note: u8) {
+        self.remote_notes.remove(&(player, note));
+    }
+
+    /// Lets go of everything `player` was holding, e.g. when they leave.
+    pub fn release_player_notes(&mut self, player: u32) {
+        self.remote_notes.retain(|(owner, _), _| *owner != player);
+    }
+
+    pub fn release_remote_notes(&mut self) {
+        self.remote_notes.clear();
+    }
+
+    pub fn is_holding(&self) -> bool {
+        self.hold
+    }
+
+    /// Sustain/latch: while on, released notes keep sounding. Turning it off
+    /// releases everything that was only being held by the latch.
+    pub fn set_hold(&mut self, hold: bool) {
+        self.hold = hold;
+        if !hold {
+            let latched = std::mem::take(&mut self.latched_notes);
+            for note in latched.into_keys() {
+                self.record(NoteEvent::Off { note });
+            }
+        }
+    }
+
+    /// Releases every note and tells the audio thread to silence all voices,
+    /// including ones still ringing out their release.
+    pub fn panic(&mut self) {
+        let local: Vec<u8> = self
+            .pressed_notes
+            .keys()
+            .chain(self.latched_notes.keys())
+            .copied()
+            .collect();
+        for note in local {
+            self.record(NoteEvent::Off { note });
+        }
+        self.pressed_notes.clear();
+        self.latched_notes.clear();
+        self.remote_notes.clear();
+        self.hold = false;
+        self.panic_count = self.panic_count.wrapping_add(1);
     }
 
     pub fn is_pressed(&self, note: u8) -> bool {
-        self.pressed_notes.contains(&note)
+        self.pressed_notes.contains_key(&note)
+            || self.latched_notes.contains_key(&note)
+            || self.remote_notes.keys().any(|(_, remote)| *remote == note)
+    }
+
+    pub fn set_voice_levels(&mut self, levels: impl IntoIterator<Item = (u8, f32)>) {
+        self.voice_levels = [0.0; VOICE_LEVEL_KEYS];
+        self.active_voices = 0;
+        for (note, level) in levels {
+            self.active_voices += 1;
+            if let Some(key) = self.voice_levels.get_mut(note as usize) {
+                *key = key.max(level);
+            }
+        }
+    }
+
+    /// Voices the engine is rendering, release tails included.
+    pub fn active_voices(&self) -> usize {
+        self.active_voices
+    }
+
+    /// How loud `note` currently is, 0..1, including its release tail.
+    pub fn voice_level(&self, note: u8) -> f32 {
+        self.voice_levels.get(note as usize).copied().unwrap_or(0.0)
+    }
+
+    /// Every sounding note, held, latched or remote, lowest first.
+    pub fn held_notes(&self) -> Vec<u8> {
+        let mut notes: Vec<u8> = self
+            .pressed_notes
+            .keys()
+            .chain(self.latched_notes.keys())
+            .chain(self.remote_notes.keys().map(|(_, note)| note))
+            .copied()
+            .collect();
+        notes.sort_unstable();
+        notes.dedup();
+        notes
+    }
+
+    /// Every part in mixer order: the main patch, the split's lower zone and
+    /// the layer when they are on, then the added parts.
+    pub fn parts(&self) -> Vec<Part> {
+        self.part_views()
+            .map(|(id, params, source, mix)| {
+                let name = match id {
+                    MAIN_PART => "Main".to_string(),
+                    LOWER_PART => format!(
+                        "Lower: {}",
+                        self.split.as_ref().map_or("", |split| &split.preset)
+                    ),
+                    LAYER_PART => format!(
+                        "Layer: {}",
+                        self.layer.as_ref().map_or("", |layer| &layer.preset)
+                    ),
+                    _ => self
+                        .added_parts
+                        .iter()
+                        .find(|part| part.id == id)
+                        .map(|part| part.name.clone())
+                        .unwrap_or_default(),
+                };
+                Part {
+                    id,
+                    name,
+                    params: params.clone(),
+                    source,
+                    mix,
+                }
+            })
+            .collect()
+    }
+
+    /// The id, patch, keys and mixer strip of every part, in mixer order.
+    fn part_views(&self) -> impl Iterator<Item = (u32, &SynthParams, NoteSource, PartMix)> {
+        let main_source = match &self.split {
+            Some(split) => NoteSource::Range {
+                low: split.note,
+                high: 127,
+            },
+            None => NoteSource::All,
+        };
+        let main = (MAIN_PART, &self.params, main_source, self.main_mix);
+        let lower = self.split.as_ref().map(|split| {
+            let source = NoteSource::Range {
+                low: 0,
+                high: split.note.saturating_sub(1),
+            };
+            (LOWER_PART, &split.lower, source, split.mix)
+        });
+        let layer = self
+            .layer
+            .as_ref()
+            .map(|layer| (LAYER_PART, &layer.params, NoteSource::All, layer.mix));
+        let added = self
+            .added_parts
+            .iter()
+            .map(|part| (part.id, &part.params, part.source, part.mix));
+        std::iter::once(main).chain(lower).chain(layer).chain(added)
+    }
+
+    /// The mixer strip of the part with `id`.
+    pub fn part_mix_mut(&mut self, id: u32) -> Option<&mut PartMix> {
+        match id {
+            MAIN_PART => Some(&mut self.main_mix),
+            LOWER_PART => self.split.as_mut().map(|split| &mut split.mix),
+            LAYER_PART => self.layer.as_mut().map(|layer| &mut layer.mix),
+            _ => self
+                .added_parts
+                .iter_mut()
+                .find(|part| part.id == id)
+                .map(|part| &mut part.mix),
+        }
+    }
+
+    /// Adds a part playing `params` on every key and returns its id.
+    pub fn add_part(&mut self, name: String, params: SynthParams) -> u32 {
+        let id = self
+            .added_parts
+            .iter()
+            .map(|part| part.id + 1)
+            .max()
+            .unwrap_or(FIRST_ADDED_PART);
+        self.added_parts.push(Part {
+            id,
+            name,
+            params,
+            source: NoteSource::All,
+            mix: PartMix::default(),
+        });
+        id
     }
 
     pub fn snapshot(&self) -> SynthSnapshot {
+        self.snapshot_with(Vec::new())
+    }
+
+    /// Refreshes `snapshot` in place, reusing its part and note lists so the
+    /// audio thread only allocates when a part or a held note is added.
+    pub fn snapshot_into(&self, snapshot: &mut SynthSnapshot) {
+        let parts = std::mem::take(&mut snapshot.parts);
+        *snapshot = self.snapshot_with(parts);
+    }
+
+    fn snapshot_with(&self, mut parts: Vec<PartSnapshot>) -> SynthSnapshot {
+        let soloing = self.part_views().any(|(_, _, _, mix)| mix.solo);
+        let mut count = 0;
+        for (id, params, source, mix) in self.part_views() {
+            let audible = !mix.mute && (!soloing || mix.solo);
+            let soft = self.soft_pedal && mix.follows_soft_pedal;
+            let mut params = self.performance.apply(params);
+            if soft {
+                params.filter_cutoff_hz *= SOFT_PEDAL_CUTOFF;
+            }
+            let part = PartSnapshot {
+                id,
+                params,
+                pressed_notes: Vec::new(),
+                gains: mix.gains(audible),
+                sends: mix.sends(audible),
+                ducking: mix.ducking,
+            };
+            let notes = self
+                .sounding_notes()
+                .filter(|(note, _)| source.plays(*note))
+                .map(|(note, held)| {
+                    let glide_from = held.glide_from.map(|from| mix.transpose_note(from));
+                    let velocity = if soft {
+                        let (low, high) = SOFT_PEDAL_VELOCITY.into_inner();
+                        low + held.velocity * (high - low)
+                    } else {
+                        held.velocity
+                    };
+                    (
+                        mix.transpose_note(note),
+                        HeldNote {
+                            velocity,
+                            glide_from,
+                            ..held
+                        },
+                    )
+                });
+            put_part(&mut parts, count, part).extend(notes);
+            count += 1;
+        }
+        if let Some(drone) = &self.drone {
+            let level = drone.level.clamp(0.0, 1.0);
+            let part = PartSnapshot {
+                id: DRONE_PART,
+                params: self.performance.apply(&self.params),
+                pressed_notes: Vec::new(),
+                gains: [level; 2],
+                sends: [0.0; 2],
+                ducking: None,
+            };
+            let notes = drone.notes().map(|note| {
+                let held = HeldNote {
+                    velocity: 1.0,
+                    glide_from: None,
+                    bend: 0.0,
+                    order: 0,
+                };
+                (note, held)
+            });
+            put_part(&mut parts, count, part).extend(notes);
+            count += 1;
+        }
+        parts.truncate(count);
         SynthSnapshot {
-            params: self.params.clone(),
-            pressed_notes: self.pressed_notes.iter().copied().collect(),
+            parts,
+            buses: self.buses,
+            limiter: self.limiter,
+            sustain: self.hold,
+            panic_count: self.panic_count,
+            mic_mode: self.mic_mode,
+            scale: self.scale,
+            pitch_bend: self.performance.pitch_bend.clamp(-1.0, 1.0) * self.performance.bend_range,
+            tuning: Arc::clone(&self.tuning),
         }
     }
+
+    /// Latched, remote and held notes, in the order parts hear them.
+    fn sounding_notes(&self) -> impl Iterator<Item = (u8, HeldNote)> + '_ {
+        let remote = self
+            .remote_notes
+            .iter()
+            .map(|((_, note), held)| (note, held));
+        self.latched_notes
+            .iter()
+            .chain(remote)
+            .chain(&self.pressed_notes)
+            .map(|(note, held)| (*note, *held))
+    }
+
+    /// Renders the notes sounding now through a copy of every part, the
+    /// mixer and the send effects: held for `hold_seconds`, then released
+    /// and left to ring out until the release and effect tails fall silent.
+    /// The drone is left out. Returns left/right frames.
+    pub fn bounce_held(&self, hold_seconds: f32, sample_rate: u32) -> Vec<[f32; 2]> {
+        let mut copy = self.clone();
+        copy.drone = None;
+        copy.note_events = None;
+        let held = copy.snapshot();
+        copy.pressed_notes.clear();
+        copy.latched_notes.clear();
+        copy.remote_notes.clear();
+        copy.hold = false;
+        let released = copy.snapshot();
+
+        let rate = sample_rate as f32;
+        let mut engine = SynthEngine::new(rate);
+        let hold_frames = (hold_seconds.max(0.0) * rate) as usize;
+        let max_frames = hold_frames + (MAX_BOUNCE_TAIL_SECONDS * rate) as usize;
+        let silent_frames = (BOUNCE_SILENCE_SECONDS * rate) as usize;
+        let mut frames = Vec::with_capacity(hold_frames * 2);
+        let mut quiet = 0;
+        while frames.len() < max_frames {
+            let releasing = frames.len() >= hold_frames;
+            let snapshot = if releasing { &released } else { &held };
+            let frame = engine.next_frame(snapshot, 0.0);
+            frames.push(frame);
+            if frame
+                .iter()
+                .all(|sample| sample.abs() < BOUNCE_SILENCE_LEVEL)
+            {
+                quiet += 1;
+            } else {
+                quiet = 0;
+            }
+            if releasing && quiet >= silent_frames {
+                frames.truncate(frames.len() - quiet);
+                break;
+            }
+        }
+        frames
+    }
 }
 
+/// Stores `part` at `index` of a snapshot being refilled, keeping the note
+/// list already there, and returns that list emptied.
+fn put_part(
+    parts: &mut Vec<PartSnapshot>,
+    index: usize,
+    part: PartSnapshot,
+) -> &mut Vec<(u8, HeldNote)> {
+    match parts.get_mut(index) {
+        Some(slot) => {
+            let pressed_notes = std::mem::take(&mut slot.pressed_notes);
+            *slot = PartSnapshot {
+                pressed_notes,
+                ..part
+            };
+        }
+        None => parts.push(part),
+    }
+    let notes = &mut parts[index].pressed_notes;
+    notes.clear();
+    notes
+}
+
+/// One part's patch and the notes it should be playing.
 #[derive(Clone)]
-pub struct SynthSnapshot {
+pub struct PartSnapshot {
+    pub id: u32,
     pub params: SynthParams,
-    pub pressed_notes: Vec<u8>,
+    /// Already filtered by the part's note source and transposed.
+    pub pressed_notes: Vec<(u8, HeldNote)>,
+    /// Left/right gain from volume and pan; zero while muted or soloed out.
+    pub gains: [f32; 2],
+    /// Reverb and delay send levels, zero while muted or soloed out.
+    pub sends: [f32; 2],
+    pub ducking: Option<Ducking>,
+}
+
+#[derive(Clone)]
+pub struct SynthSnapshot {
+    /// In mixer order; the main part is always first and the drone, when
+    /// on, last.
+    pub parts: Vec<PartSnapshot>,
+    pub buses: SendBuses,
+    pub limiter: LimiterSettings,
+    /// The hold (sustain pedal) is down.
+    pub sustain: bool,
+    pub panic_count: u32,
+    pub mic_mode: MicMode,
+    pub scale: Option<Scale>,
+    /// Semitones every part but the drone is bent by.
+    pub pitch_bend: f32,
+    pub tuning: Arc<dyn Tuning>,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Waveform {
     Sine,
     Square,
     Saw,
     Triangle,
+    Wavetable,
 }
 
 impl Waveform {
-    pub const ALL: [Waveform; 4] = [
+    pub const ALL: [Waveform; 5] = [
         Waveform::Sine,
         Waveform::Square,
         Waveform::Saw,
         Waveform::Triangle,
+        Waveform::Wavetable,
     ];
 
     pub fn label(&self) -> &'static str {
@@ -125,12 +1086,15 @@ impl Waveform {
             Waveform::Square => "Square",
             Waveform::Saw => "Saw",
             Waveform::Triangle => "Triangle",
+            Waveform::Wavetable => "Wavetable",
         }
     }
 
+    /// `Wavetable` needs the patch's table and position (see
+    /// `wavetable_sample`); on its own it plays its first built-in shape.
     pub fn sample(&self, phase: f32) -> f32 {
         match self {
-            Waveform::Sine => (TAU * phase).sin(),
+            Waveform::Sine | Waveform::Wavetable => (TAU * phase).sin(),
             Waveform::Square => {
                 if phase < 0.5 {
                     1.0
@@ -144,20 +1108,313 @@ impl Waveform {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterMode {
+    #[default]
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+    /// Four-pole Moog-style low-pass that self-oscillates at high resonance.
+    Ladder,
+}
+
+impl FilterMode {
+    pub const ALL: [FilterMode; 5] = [
+        FilterMode::LowPass,
+        FilterMode::HighPass,
+        FilterMode::BandPass,
+        FilterMode::Notch,
+        FilterMode::Ladder,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FilterMode::LowPass => "Low-pass",
+            FilterMode::HighPass => "High-pass",
+            FilterMode::BandPass => "Band-pass",
+            FilterMode::Notch => "Notch",
+            FilterMode::Ladder => "Ladder",
+        }
+    }
+}
+
+/// A voice's filter for one sample, from its patch's `FilterMode`.
+enum VoiceFilter {
+    Svf(Svf),
+    Ladder(Ladder),
+}
+
+impl VoiceFilter {
+    /// Filters one sample through `state`, the filter memory of one side.
+    fn process(&self, state: &mut [f32; 4], input: f32) -> f32 {
+        let output = match self {
+            VoiceFilter::Svf(svf) => svf.process(state, input),
+            VoiceFilter::Ladder(ladder) => ladder.process(state, input),
+        };
+        *state = state.map(flush_denormal);
+        output
+    }
+
+    fn magnitude(&self, w: f32) -> f32 {
+        match self {
+            VoiceFilter::Svf(svf) => svf.magnitude(w),
+            VoiceFilter::Ladder(ladder) => ladder.magnitude(w),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum SvfOutput {
+    Low,
+    High,
+    Band,
+    Notch,
+}
+
+/// Coefficients of the trapezoidal state-variable filter (Andrew Simper's
+/// form), which stays stable while the cutoff is swept every sample.
+struct Svf {
+    output: SvfOutput,
+    /// Prewarped cutoff, `tan(π·fc/fs)`.
+    g: f32,
+    /// Damping: 2 at no resonance, falling towards self-oscillation.
+    k: f32,
+    a1: f32,
+    a2: f32,
+    a3: f32,
+}
+
+impl Svf {
+    fn new(output: SvfOutput, cutoff_hz: f32, resonance: f32, sample_rate: f32) -> Self {
+        let g = (PI * cutoff_hz / sample_rate).tan();
+        let k = 2.0 * (1.0 - resonance.clamp(0.0, 0.95));
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        Self {
+            output,
+            g,
+            k,
+            a1,
+            a2,
+            a3: g * a2,
+        }
+    }
+
+    /// Only the first two slots of `state` are used, as the integrators.
+    fn process(&self, state: &mut [f32; 4], input: f32) -> f32 {
+        let v3 = input - state[1];
+        let band = self.a1 * state[0] + self.a2 * v3;
+        let low = state[1] + self.a2 * state[0] + self.a3 * v3;
+        state[0] = 2.0 * band - state[0];
+        state[1] = 2.0 * low - state[1];
+        match self.output {
+            SvfOutput::Low => low,
+            SvfOutput::High => input - self.k * band - low,
+            SvfOutput::Band => band,
+            SvfOutput::Notch => input - self.k * band,
+        }
+    }
+
+    /// Gain at angular frequency `w`, from the analogue prototype
+    /// `1 / (s² + ks + 1)` the filter maps onto.
+    fn magnitude(&self, w: f32) -> f32 {
+        let omega = (w * 0.5).tan() / self.g;
+        let numerator = match self.output {
+            SvfOutput::Low => 1.0,
+            SvfOutput::High => omega * omega,
+            SvfOutput::Band => omega,
+            SvfOutput::Notch => (1.0 - omega * omega).abs(),
+        };
+        numerator / (1.0 - omega * omega).hypot(self.k * omega)
+    }
+}
+
+/// Four one-pole stages with the last fed back to the first through a tanh,
+/// in the zero-delay form: each sample the feedback loop is solved rather
+/// than taken a sample late, so the ladder rings from a feedback of 4 at any
+/// cutoff, like the circuit.
+struct Ladder {
+    /// Prewarped cutoff, `tan(π·fc/fs)`.
+    g: f32,
+    /// Feedback: the ladder rings on its own from 4.
+    k: f32,
+    drive: f32,
+}
+
+impl Ladder {
+    fn new(cutoff_hz: f32, resonance: f32, drive_db: f32, sample_rate: f32) -> Self {
+        Self {
+            g: (PI * cutoff_hz / sample_rate).tan(),
+            k: LADDER_MAX_FEEDBACK * resonance.clamp(0.0, 0.95) / 0.95,
+            drive: 10_f32.powf(drive_db / 20.0),
+        }
+    }
+
+    /// `state` holds the four stages' integrators.
+    fn process(&self, state: &mut [f32; 4], input: f32) -> f32 {
+        let stage_gain = self.g / (1.0 + self.g);
+        // What the stored state alone would put out of the last stage.
+        let held = state
+            .iter()
+            .fold(0.0, |held, &pole| held * stage_gain + pole / (1.0 + self.g));
+        let driven = self.drive * input;
+        let ladder_gain = stage_gain.powi(4);
+        let mut stage = ((driven * (1.0 + self.k * LADDER_BASS_COMPENSATION) - self.k * held)
+            / (1.0 + self.k * ladder_gain))
+            .tanh();
+        for pole in state.iter_mut() {
+            let v = (stage - *pole) * stage_gain;
+            stage = v + *pole;
+            *pole = stage + v;
+        }
+        stage
+    }
+
+    /// Small-signal gain at angular frequency `w`, below the saturation.
+    fn magnitude(&self, w: f32) -> f32 {
+        // Four `1 / (1 + s)` poles with `k` of the output fed back.
+        let omega = (w * 0.5).tan() / self.g;
+        let pole = complex_div((1.0, 0.0), (1.0, omega));
+        let squared = complex_mul(pole, pole);
+        let four = complex_mul(squared, squared);
+        let response = complex_div(four, (1.0 + self.k * four.0, self.k * four.1));
+        self.drive * (1.0 + self.k * LADDER_BASS_COMPENSATION) * response.0.hypot(response.1)
+    }
+}
+
+fn complex_mul(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+fn complex_div(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    let norm = b.0 * b.0 + b.1 * b.1;
+    (
+        (a.0 * b.0 + a.1 * b.1) / norm,
+        (a.1 * b.0 - a.0 * b.1) / norm,
+    )
+}
+
+/// A pulse high for `width` of the cycle, shifted so it has no DC offset
+/// and scaled to a peak of 1; at a width of 0.5 it is the plain square.
+fn pulse(phase: f32, width: f32) -> f32 {
+    let width = width.clamp(MIN_PULSE_WIDTH, 1.0 - MIN_PULSE_WIDTH);
+    let sample = if phase < width { 1.0 - width } else { -width };
+    sample / width.max(1.0 - width)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoiceMode {
+    /// Every key gets its own voice.
+    #[default]
+    Poly,
+    /// One voice that restarts its envelope on every new note.
+    Mono,
+    /// One voice; overlapping notes glide over without restarting the
+    /// envelope, as on a monosynth played legato.
+    Legato,
+}
+
+impl VoiceMode {
+    pub const ALL: [VoiceMode; 3] = [VoiceMode::Poly, VoiceMode::Mono, VoiceMode::Legato];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VoiceMode::Poly => "Poly",
+            VoiceMode::Mono => "Mono",
+            VoiceMode::Legato => "Legato",
+        }
+    }
+}
+
+/// Which voice a poly patch at its polyphony gives up for a new note.
+/// Voices already released go first whatever the policy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoiceSteal {
+    #[default]
+    Oldest,
+    Quietest,
+    Lowest,
+}
+
+impl VoiceSteal {
+    pub const ALL: [VoiceSteal; 3] = [VoiceSteal::Oldest, VoiceSteal::Quietest, VoiceSteal::Lowest];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VoiceSteal::Oldest => "Oldest",
+            VoiceSteal::Quietest => "Quietest",
+            VoiceSteal::Lowest => "Lowest",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotePriority {
+    /// The most recently pressed key.
+    #[default]
+    Last,
+    Low,
+    High,
+}
+
+impl NotePriority {
+    pub const ALL: [NotePriority; 3] = [NotePriority::Last, NotePriority::Low, NotePriority::High];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotePriority::Last => "Last",
+            NotePriority::Low => "Low",
+            NotePriority::High => "High",
+        }
+    }
+
+    /// The key among `pressed` a mono patch plays.
+    fn pick(self, pressed: &[(u8, HeldNote)]) -> Option<(u8, HeldNote)> {
+        let notes = pressed.iter().copied();
+        match self {
+            NotePriority::Last => notes.max_by_key(|(_, held)| held.order),
+            NotePriority::Low => notes.min_by_key(|(note, _)| *note),
+            NotePriority::High => notes.max_by_key(|(note, _)| *note),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum InstrumentKind {
     Keys,
     Bass,
     Lead,
     Pad,
+    /// Sums the patch's harmonics instead of playing the waveform.
+    Additive,
+    /// Plays the patch's sample, pitched from its root note.
+    Sampler,
+    /// Plays a preset of the patch's SF2 or SFZ file.
+    SoundFont,
+    /// Plays a cloud of short grains read from the patch's sample.
+    Granular,
+    /// Each key of the patch's drum kit plays its drum in full, however
+    /// long it is held.
+    Drums,
 }
 
 impl InstrumentKind {
-    pub const ALL: [InstrumentKind; 4] = [
+    pub const ALL: [InstrumentKind; 9] = [
         InstrumentKind::Keys,
         InstrumentKind::Bass,
         InstrumentKind::Lead,
         InstrumentKind::Pad,
+        InstrumentKind::Additive,
+        InstrumentKind::Sampler,
+        InstrumentKind::SoundFont,
+        InstrumentKind::Granular,
+        InstrumentKind::Drums,
     ];
 
     pub fn label(&self) -> &'static str {
@@ -166,6 +1423,55 @@ impl InstrumentKind {
             InstrumentKind::Bass => "Bass",
             InstrumentKind::Lead => "Lead",
             InstrumentKind::Pad => "Pad",
+            InstrumentKind::Additive => "Additive",
+            InstrumentKind::Sampler => "Sampler",
+            InstrumentKind::SoundFont => "SoundFont",
+            InstrumentKind::Granular => "Granular",
+            InstrumentKind::Drums => "Drums",
+        }
+    }
+}
+
+/// How the envelope's stages travel between their levels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvCurve {
+    #[default]
+    Linear,
+    /// Attack creeps in and sweeps up; decay and release drop fast and tail
+    /// off, evenly in decibels.
+    Exponential,
+    /// Attack leaps up and eases into the peak; decay and release hang on,
+    /// then fall away.
+    Logarithmic,
+}
+
+impl EnvCurve {
+    pub const ALL: [EnvCurve; 3] = [
+        EnvCurve::Linear,
+        EnvCurve::Exponential,
+        EnvCurve::Logarithmic,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EnvCurve::Linear => "Linear",
+            EnvCurve::Exponential => "Exponential",
+            EnvCurve::Logarithmic => "Logarithmic",
+        }
+    }
+
+    /// Share of the way from a stage's start level to its end after
+    /// `progress` (0..1) of its time; `rising` for the attack.
+    pub fn shape(self, progress: f32, rising: bool) -> f32 {
+        let progress = progress.clamp(0.0, 1.0);
+        let slow_start = |p: f32| (ENV_CURVE_STEEPNESS * p).exp_m1() / ENV_CURVE_STEEPNESS.exp_m1();
+        match (self, rising) {
+            (EnvCurve::Linear, _) => progress,
+            (EnvCurve::Exponential, true) | (EnvCurve::Logarithmic, false) => slow_start(progress),
+            (EnvCurve::Exponential, false) | (EnvCurve::Logarithmic, true) => {
+                1.0 - slow_start(1.0 - progress)
+            }
         }
     }
 }
@@ -173,7 +1479,9 @@ impl InstrumentKind {
 #[derive(Clone, Copy)]
 enum EnvStage {
     Idle,
+    Delay,
     Attack,
+    Hold,
     Decay,
     Sustain,
     Release,
@@ -181,49 +1489,141 @@ enum EnvStage {
 
 struct VoiceState {
     note: u8,
+    /// Sounding pitch in semitones; trails `note` while gliding.
+    pitch: f32,
+    /// Smoothed bend in semitones, heading for `bend_target`.
+    bend: f32,
+    bend_target: f32,
+    velocity: f32,
     phase: f32,
     env_level: f32,
     stage: EnvStage,
+    /// Level the current stage set out from, and how far through its time
+    /// it is (0..1), so the envelope curve can shape it.
+    stage_start: f32,
+    stage_progress: f32,
     gate: bool,
-    filter_state: f32,
+    /// Gain left while a stolen voice fades out; `None` until it is stolen.
+    steal_fade: Option<f32>,
+    /// Filter memory, left and right.
+    filter_state: [[f32; 4]; 2],
     lfo_phase: f32,
+    pwm_phase: f32,
+    /// Playback position in the sample, in its own samples.
+    sample_pos: f64,
+    /// The SoundFont region playing: `None` until picked at the first
+    /// frame, `Some(None)` when no region covers the note.
+    region: Option<Option<usize>>,
+    grains: GrainCloud,
+    drum: DrumHit,
+    /// Analog drift of the pitch and the cutoff, each -1..1.
+    pitch_drift: RandomWalk,
+    cutoff_drift: RandomWalk,
+    /// Semitones autotune is moving the pitch by, on its way to the
+    /// nearest note.
+    autotune_shift: f32,
+    /// Each unison copy's own phase, as they run at their own detunes.
+    unison_phases: [f32; MAX_UNISON_VOICES as usize],
     noise_seed: u32,
 }
 
 impl VoiceState {
-    fn new(note: u8) -> Self {
+    fn new(note: u8, velocity: f32) -> Self {
         Self {
             note,
+            pitch: note as f32,
+            bend: 0.0,
+            bend_target: 0.0,
+            velocity,
             phase: 0.0,
             env_level: 0.0,
             stage: EnvStage::Idle,
+            stage_start: 0.0,
+            stage_progress: 0.0,
             gate: false,
-            filter_state: 0.0,
+            steal_fade: None,
+            filter_state: [[0.0; 4]; 2],
             lfo_phase: 0.0,
+            pwm_phase: 0.0,
+            sample_pos: 0.0,
+            region: None,
+            grains: GrainCloud::new((note as u32).wrapping_mul(2_654_435_761)),
+            drum: DrumHit::default(),
+            pitch_drift: RandomWalk::new((note as u32).wrapping_mul(747_796_405)),
+            cutoff_drift: RandomWalk::new((note as u32).wrapping_mul(2_891_336_453) ^ 1),
+            autotune_shift: 0.0,
+            unison_phases: std::array::from_fn(|copy| (copy as f32 * UNISON_PHASE_STEP).fract()),
             noise_seed: (note as u32).wrapping_mul(1_104_607),
         }
     }
 
     fn set_gate(&mut self, gate: bool) {
         if gate && !self.gate {
-            self.stage = EnvStage::Attack;
+            self.enter_stage(EnvStage::Delay);
+            self.sample_pos = 0.0;
+            self.region = None;
+            self.grains.reset();
+            self.drum = DrumHit::default();
         } else if !gate && self.gate {
             if !matches!(self.stage, EnvStage::Idle) {
-                self.stage = EnvStage::Release;
+                self.enter_stage(EnvStage::Release);
             }
         }
         self.gate = gate;
     }
 
-    fn next_sample(&mut self, params: &SynthParams, sample_rate: f32) -> f32 {
-        self.advance_envelope(params, sample_rate);
+    fn enter_stage(&mut self, stage: EnvStage) {
+        self.stage = stage;
+        self.stage_start = self.env_level;
+        self.stage_progress = 0.0;
+    }
+
+    /// Pulls `note` toward the nearest note of `scale` (semitone, without
+    /// one) as far as the patch's autotune amount says. The pull moves at
+    /// the retune time, so a slow retune lets slides and vibrato through
+    /// and a fast one snaps from note to note.
+    fn autotune(
+        &mut self,
+        note: f32,
+        params: &SynthParams,
+        scale: Option<Scale>,
+        sample_rate: f32,
+    ) -> f32 {
+        if params.autotune_amount <= 0.0 {
+            self.autotune_shift = 0.0;
+            return note;
+        }
+        let nearest = scale.map_or(note.round(), |scale| scale.nearest_note(note));
+        let coeff = 1.0 - (-1000.0 / (params.autotune_retune_ms.max(0.1) * sample_rate)).exp();
+        self.autotune_shift += (nearest - note - self.autotune_shift) * coeff;
+        note + self.autotune_shift * params.autotune_amount
+    }
+
+    fn next_frame(
+        &mut self,
+        params: &SynthParams,
+        sample_rate: f32,
+        pitch_bend: f32,
+        tuning: &dyn Tuning,
+        scale: Option<Scale>,
+    ) -> [f32; 2] {
+        let drums = params.instrument == InstrumentKind::Drums;
+        if drums {
+            // A drum hit plays out in full; `next_drum_sample` ends it.
+            self.env_level = if matches!(self.stage, EnvStage::Idle) {
+                0.0
+            } else {
+                1.0
+            };
+        } else {
+            self.advance_envelope(params, sample_rate);
+        }
         if matches!(self.stage, EnvStage::Idle) {
-            return 0.0;
+            return [0.0; 2];
         }
 
-        let vibrato_depth = params.vibrato_depth_semitones * (1.0 - params.autotune_amount);
-        let vibrato = if vibrato_depth > 0.0 {
-            (TAU * self.lfo_phase).sin() * vibrato_depth
+        let vibrato = if params.vibrato_depth_semitones > 0.0 {
+            (TAU * self.lfo_phase).sin() * params.vibrato_depth_semitones
         } else {
             0.0
         };
@@ -232,22 +1632,106 @@ impl VoiceState {
             self.lfo_phase -= 1.0;
         }
 
-        let freq = midi_to_freq(self.note) * 2_f32.powf(vibrato / 12.0);
+        let (pitch_drift, cutoff_drift) = if params.drift_amount > 0.0 {
+            (
+                self.pitch_drift.next(sample_rate) * params.drift_amount,
+                self.cutoff_drift.next(sample_rate) * params.drift_amount,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+
+        self.advance_glide(params, sample_rate);
+        if self.bend != self.bend_target {
+            let coeff = 1.0 - (-1.0 / (BEND_SMOOTHING_SECONDS * sample_rate)).exp();
+            self.bend += (self.bend_target - self.bend) * coeff;
+            if (self.bend_target - self.bend).abs() < 0.0001 {
+                self.bend = self.bend_target;
+            }
+        }
+        let drift = pitch_drift * DRIFT_PITCH_SEMITONES;
+        let note = self.pitch + params.transpose as f32 + self.bend + pitch_bend + vibrato + drift;
+        let note = self.autotune(note, params, scale, sample_rate);
+        // Tunings are set out from A4 at 440 Hz.
+        let freq = tuning.freq(note) * params.a4_hz / 440.0;
         self.phase += freq / sample_rate;
         if self.phase >= 1.0 {
             self.phase -= 1.0;
         }
         let base_phase = self.phase;
+        let audible_harmonics = (sample_rate * 0.5 / freq) as usize;
 
-        let mut sample = self.unison_sample(params, base_phase);
-        sample = VoiceState::apply_instrument_color(sample, base_phase, params.instrument);
+        let pulse_width = params.pulse_width + params.pwm_depth * (TAU * self.pwm_phase).sin();
+        self.pwm_phase += params.pwm_rate_hz / sample_rate;
+        if self.pwm_phase >= 1.0 {
+            self.pwm_phase -= 1.0;
+        }
+
+        let played = match params.instrument {
+            InstrumentKind::Sampler => Some(self.next_sample(params, freq, sample_rate)),
+            InstrumentKind::SoundFont => {
+                Some(self.next_soundfont_sample(params, freq, sample_rate))
+            }
+            InstrumentKind::Granular => Some(self.next_grains(params, freq, sample_rate)),
+            InstrumentKind::Drums => Some(self.next_drum_sample(params, sample_rate)),
+            _ => None,
+        };
+        let mut frame = match played {
+            Some(sample) => balance(self.position(params)).map(|gain| sample * gain),
+            None => self
+                .unison_frame(params, freq, sample_rate, pulse_width, audible_harmonics)
+                .map(|sample| {
+                    VoiceState::apply_instrument_color(sample, base_phase, params.instrument)
+                }),
+        };
         if params.noise_mix > 0.0 {
             let noise = self.next_noise();
-            sample = sample * (1.0 - params.noise_mix) + noise * params.noise_mix;
+            frame =
+                frame.map(|sample| sample * (1.0 - params.noise_mix) + noise * params.noise_mix);
         }
 
-        let filtered = self.apply_filter(sample, params, sample_rate);
-        filtered * self.env_level * params.gain
+        // Drum keys pick a drum rather than a pitch, so nothing tracks them.
+        let octaves = if drums {
+            0.0
+        } else {
+            (self.pitch - KEY_TRACK_CENTRE) / 12.0
+        };
+        let track = |amount: f32, base: f32| {
+            if amount == 0.0 {
+                1.0
+            } else {
+                base.powf(octaves * amount)
+            }
+        };
+        // Full velocity plays the patch as set; softer strikes are quieter
+        // and, with velocity tone, darker.
+        let softness = 1.0 - self.velocity;
+        let cutoff_scale = track(params.key_track_tone, 2.0)
+            * 2_f32.powf(cutoff_drift * DRIFT_CUTOFF_OCTAVES - params.velocity_tone * softness);
+        let level = 1.0 - params.velocity_level * softness;
+        let key_gain = track(params.key_track_level_db / 20.0, 10.0);
+        let filter = VoiceState::filter(params, cutoff_scale, sample_rate);
+        let filtered = [0, 1].map(|side| filter.process(&mut self.filter_state[side], frame[side]));
+        let fade = match &mut self.steal_fade {
+            Some(gain) => {
+                let now = gain.max(0.0);
+                *gain -= 1.0 / (STEAL_FADE_SECONDS * sample_rate);
+                now
+            }
+            None => 1.0,
+        };
+        filtered.map(|sample| sample * self.env_level * level * params.gain * key_gain * fade)
+    }
+
+    fn advance_glide(&mut self, params: &SynthParams, sample_rate: f32) {
+        let target = self.note as f32;
+        if params.glide_seconds <= 0.0 || (target - self.pitch).abs() < 0.001 {
+            self.pitch = target;
+            return;
+        }
+        // One-pole slide: covers ~99% of the interval in glide_seconds.
+        let coeff = 1.0 - (-4.6 / (params.glide_seconds * sample_rate)).exp();
+        self.pitch += (target - self.pitch) * coeff;
     }
 
     fn apply_instrument_color(sample: f32, base_phase: f32, instrument: InstrumentKind) -> f32 {
@@ -262,34 +1746,197 @@ impl VoiceState {
                 (overtone * 1.2).tanh()
             }
             InstrumentKind::Pad => sample * 0.9,
+            InstrumentKind::Additive
+            | InstrumentKind::Sampler
+            | InstrumentKind::SoundFont
+            | InstrumentKind::Granular
+            | InstrumentKind::Drums => sample,
         }
     }
 
-    fn unison_sample(&self, params: &SynthParams, base_phase: f32) -> f32 {
-        let detune =
-            ((params.unison_spread_cents * (1.0 - params.autotune_amount)) / 1200.0).min(0.2);
-        let offsets: &[f32] = if detune > 0.0 {
-            &[-detune, 0.0, detune]
+    /// The unison copies at `freq`, detuned evenly across the unison spread
+    /// and panned with it within the stereo width around the voice's own
+    /// position, flat left and sharp right. `audible_harmonics` is how many
+    /// of the Additive instrument's harmonics fit below Nyquist.
+    fn unison_frame(
+        &mut self,
+        params: &SynthParams,
+        freq: f32,
+        sample_rate: f32,
+        pulse_width: f32,
+        audible_harmonics: usize,
+    ) -> [f32; 2] {
+        let cents = params.unison_spread_cents * (1.0 - params.autotune_amount);
+        let copies = if cents > 0.0 {
+            params.unison_voices.clamp(1, MAX_UNISON_VOICES) as usize
         } else {
-            &[0.0]
+            1
         };
-        let mut acc = 0.0;
-        for offset in offsets {
-            let phase = (base_phase + offset).fract();
-            acc += params.waveform.sample(phase);
+        // Distance from the middle of the spread of the innermost copies,
+        // which the blend leaves at full level.
+        let inner = if copies % 2 == 0 {
+            1.0 / (copies - 1) as f32
+        } else {
+            0.0
+        };
+        let width = params.stereo_width.clamp(0.0, 1.0);
+        let position = self.position(params);
+        let mut acc = [0.0; 2];
+        let mut power = 0.0;
+        for (copy, phase) in self.unison_phases.iter_mut().take(copies).enumerate() {
+            // -1 for the flattest copy to 1 for the sharpest.
+            let spread = if copies > 1 {
+                copy as f32 / (copies - 1) as f32 * 2.0 - 1.0
+            } else {
+                0.0
+            };
+            let outer = if copies > 2 {
+                (spread.abs() - inner) / (1.0 - inner)
+            } else {
+                0.0
+            };
+            let level = 1.0 + (params.unison_blend.clamp(0.0, 1.0) - 1.0) * outer;
+            let sample = match params.waveform {
+                _ if params.instrument == InstrumentKind::Additive => {
+                    params.harmonics.sample(*phase, audible_harmonics)
+                }
+                Waveform::Wavetable => wavetable_sample(params, *phase),
+                Waveform::Square => pulse(*phase, pulse_width),
+                waveform => waveform.sample(*phase),
+            };
+            let gains = balance(position + spread * width * (1.0 - VOICE_SPREAD));
+            acc[0] += sample * level * gains[0];
+            acc[1] += sample * level * gains[1];
+            power += level * level;
+            *phase += freq * 2_f32.powf(spread * cents / 1200.0) / sample_rate;
+            *phase -= phase.floor();
         }
-        acc / offsets.len() as f32
+        // The copies drift in and out of phase, so they add up in power:
+        // scaling by its root keeps the loudness steady whatever the count.
+        acc.map(|side| side / power.sqrt())
     }
 
-    fn apply_filter(&mut self, input: f32, params: &SynthParams, sample_rate: f32) -> f32 {
-        let cutoff = params
-            .filter_cutoff_hz
-            .clamp(60.0, sample_rate.min(48_000.0) * 0.45);
-        let x = (TAU * cutoff / sample_rate).min(0.99);
-        let alpha = x / (1.0 + x);
-        self.filter_state += alpha * (input - self.filter_state);
-        let resonance = params.filter_resonance.clamp(0.0, 0.95);
-        self.filter_state + resonance * (self.filter_state - input)
+    /// Where the voice sits, -1 (left) to 1 (right): the patch's pan, spread
+    /// by key across the stereo width.
+    fn position(&self, params: &SynthParams) -> f32 {
+        let width = params.stereo_width.clamp(0.0, 1.0);
+        let key = ((self.note as f32 - KEY_TRACK_CENTRE) / VOICE_SPREAD_SEMITONES).clamp(-1.0, 1.0);
+        params.pan + width * VOICE_SPREAD * key
+    }
+
+    /// The Sampler instrument: the sample at the playback position, which
+    /// moves on at `freq` relative to the root note's pitch. Root notes name
+    /// the pitch a recording was made at, so they stay equal-tempered
+    /// whatever the tuning.
+    fn next_sample(&mut self, params: &SynthParams, freq: f32, sample_rate: f32) -> f32 {
+        let Some(sample) = params.sample.loaded() else {
+            return 0.0;
+        };
+        let value = sample.at(self.sample_pos);
+        let speed = freq / EqualTemperament.freq(params.sample_root_note) * sample.sample_rate()
+            / sample_rate;
+        let length = sample.length() as f64;
+        let loop_points = params.sample_loop.then_some((
+            params.sample_loop_start as f64 * length,
+            params.sample_loop_end as f64 * length,
+        ));
+        self.advance_sample(speed, loop_points);
+        value
+    }
+
+    /// The SoundFont instrument: the region picked for the note when it
+    /// started, played like `next_sample`.
+    fn next_soundfont_sample(&mut self, params: &SynthParams, freq: f32, sample_rate: f32) -> f32 {
+        let Some(font) = params.soundfont.loaded() else {
+            return 0.0;
+        };
+        let preset = params.soundfont_preset as usize;
+        let (note, velocity) = (self.note, self.velocity);
+        let region = *self
+            .region
+            .get_or_insert_with(|| font.find_region(preset, note, velocity));
+        let Some(region) = region.and_then(|index| font.region(preset, index)) else {
+            return 0.0;
+        };
+        let value = font.region_sample(region, self.sample_pos) * region.gain;
+        let speed =
+            freq / EqualTemperament.freq(region.root_note) * region.sample_rate() / sample_rate;
+        let loop_points = region
+            .loop_points()
+            .map(|(start, end)| (start as f64, end as f64));
+        self.advance_sample(speed, loop_points);
+        value
+    }
+
+    /// The Granular instrument: the voice's grain cloud over the sample,
+    /// each grain pitched like `next_sample`.
+    fn next_grains(&mut self, params: &SynthParams, freq: f32, sample_rate: f32) -> f32 {
+        let Some(sample) = params.sample.loaded() else {
+            return 0.0;
+        };
+        let speed = freq / EqualTemperament.freq(params.sample_root_note) * sample.sample_rate()
+            / sample_rate;
+        self.grains.next(sample, params, speed, sample_rate)
+    }
+
+    /// The Drums instrument: the pad on the voice's key, playing its sample
+    /// when one is loaded and its drum otherwise. Ends the voice once the
+    /// hit has died away, whether or not the key is still down.
+    fn next_drum_sample(&mut self, params: &SynthParams, sample_rate: f32) -> f32 {
+        let value = match params.drum_kit.pad(self.note) {
+            Some(pad) => match pad.sample.loaded() {
+                Some(sample) if self.sample_pos < sample.length() as f64 => {
+                    let value = sample.at(self.sample_pos);
+                    self.advance_sample(sample.sample_rate() / sample_rate, None);
+                    Some(value)
+                }
+                Some(_) => None,
+                None => {
+                    let noise = self.next_noise();
+                    self.drum.next(pad.sound, noise, sample_rate)
+                }
+            },
+            None => None,
+        };
+        value.unwrap_or_else(|| {
+            self.enter_stage(EnvStage::Idle);
+            0.0
+        })
+    }
+
+    /// Moves the sample playback on by `speed` samples, wrapping back to the
+    /// loop start on passing the loop end.
+    fn advance_sample(&mut self, speed: f32, loop_points: Option<(f64, f64)>) {
+        self.sample_pos += speed as f64;
+        if let Some((start, end)) = loop_points
+            && end - start >= 1.0
+            && self.sample_pos >= end
+        {
+            self.sample_pos = start + (self.sample_pos - end) % (end - start);
+        }
+    }
+
+    /// The voice's filter for this sample. `cutoff_scale` is the key and
+    /// velocity tracking's share of the cutoff.
+    fn filter(params: &SynthParams, cutoff_scale: f32, sample_rate: f32) -> VoiceFilter {
+        let cutoff =
+            (params.filter_cutoff_hz * cutoff_scale).clamp(20.0, sample_rate.min(48_000.0) * 0.45);
+        let resonance = params.filter_resonance;
+        let output = match params.filter_mode {
+            FilterMode::Ladder => {
+                return VoiceFilter::Ladder(Ladder::new(
+                    cutoff,
+                    resonance,
+                    params.filter_drive_db,
+                    sample_rate,
+                ));
+            }
+            FilterMode::LowPass => SvfOutput::Low,
+            FilterMode::HighPass => SvfOutput::High,
+            FilterMode::BandPass => SvfOutput::Band,
+            FilterMode::Notch => SvfOutput::Notch,
+        };
+        VoiceFilter::Svf(Svf::new(output, cutoff, resonance, sample_rate))
     }
 
     fn next_noise(&mut self) -> f32 {
@@ -302,103 +1949,576 @@ impl VoiceState {
         value * 2.0 - 1.0
     }
 
+    /// Stages take their full time to cross the whole range, so a release
+    /// from half level takes half the release time. Delay and hold keep the
+    /// level still, and are skipped when set to zero.
     fn advance_envelope(&mut self, params: &SynthParams, sample_rate: f32) {
+        let step = |seconds: f32| {
+            if seconds <= 0.0 {
+                1.0
+            } else {
+                1.0 / (seconds * sample_rate)
+            }
+        };
+        let curve = params.env_curve;
         match self.stage {
             EnvStage::Idle => {
                 self.env_level = 0.0;
             }
+            EnvStage::Delay => {
+                if params.delay_seconds <= 0.0 {
+                    self.enter_stage(EnvStage::Attack);
+                    return self.advance_envelope(params, sample_rate);
+                }
+                self.stage_progress += step(params.delay_seconds);
+                if self.stage_progress >= 1.0 {
+                    self.enter_stage(EnvStage::Attack);
+                }
+            }
             EnvStage::Attack => {
-                let step = if params.attack_seconds <= 0.0 {
-                    1.0
-                } else {
-                    1.0 / (params.attack_seconds * sample_rate)
-                };
-                self.env_level += step;
-                if self.env_level >= 1.0 {
+                let distance = 1.0 - self.stage_start;
+                if distance > 0.0 {
+                    self.stage_progress += step(params.attack_seconds) / distance;
+                    self.env_level =
+                        self.stage_start + distance * curve.shape(self.stage_progress, true);
+                }
+                if distance <= 0.0 || self.stage_progress >= 1.0 {
                     self.env_level = 1.0;
-                    self.stage = EnvStage::Decay;
+                    self.enter_stage(if params.hold_seconds > 0.0 {
+                        EnvStage::Hold
+                    } else {
+                        EnvStage::Decay
+                    });
+                }
+            }
+            EnvStage::Hold => {
+                self.stage_progress += step(params.hold_seconds);
+                if self.stage_progress >= 1.0 {
+                    self.enter_stage(EnvStage::Decay);
                 }
             }
             EnvStage::Decay => {
                 let target = params.sustain_level.clamp(0.0, 1.0);
-                let step = if params.decay_seconds <= 0.0 {
-                    1.0
-                } else {
-                    1.0 / (params.decay_seconds * sample_rate)
-                };
-                self.env_level -= step * (1.0 - target);
-                if self.env_level <= target {
+                self.stage_progress += step(params.decay_seconds);
+                self.env_level = 1.0 - (1.0 - target) * curve.shape(self.stage_progress, false);
+                if self.stage_progress >= 1.0 {
                     self.env_level = target;
-                    self.stage = EnvStage::Sustain;
+                    self.enter_stage(EnvStage::Sustain);
                 }
             }
             EnvStage::Sustain => self.env_level = params.sustain_level.clamp(0.0, 1.0),
             EnvStage::Release => {
-                let step = if params.release_seconds <= 0.0 {
-                    1.0
-                } else {
-                    1.0 / (params.release_seconds * sample_rate)
-                };
-                self.env_level -= step;
-                if self.env_level <= 0.0 {
+                if self.stage_start > 0.0 {
+                    self.stage_progress += step(params.release_seconds) / self.stage_start;
+                    self.env_level =
+                        self.stage_start * (1.0 - curve.shape(self.stage_progress, false));
+                }
+                if self.stage_start <= 0.0 || self.stage_progress >= 1.0 {
                     self.env_level = 0.0;
-                    self.stage = EnvStage::Idle;
+                    self.enter_stage(EnvStage::Idle);
                 }
             }
         }
     }
 
     fn is_finished(&self) -> bool {
-        matches!(self.stage, EnvStage::Idle) && !self.gate
+        (matches!(self.stage, EnvStage::Idle) && !self.gate)
+            || self.steal_fade.is_some_and(|gain| gain <= 0.0)
+    }
+
+    fn is_stolen(&self) -> bool {
+        self.steal_fade.is_some()
+    }
+}
+
+/// A slow random walk between -1 and 1: heads for a new random point every
+/// `DRIFT_STEP_SECONDS` and glides there, so it wanders without jumping.
+#[derive(Clone, Copy)]
+struct RandomWalk {
+    value: f32,
+    target: f32,
+    /// Seconds until the next heading.
+    countdown: f32,
+    seed: u32,
+}
+
+impl RandomWalk {
+    /// Starts somewhere random, so the notes of a chord drift apart at once.
+    fn new(seed: u32) -> Self {
+        let mut walk = Self {
+            value: 0.0,
+            target: 0.0,
+            countdown: DRIFT_STEP_SECONDS,
+            seed,
+        };
+        walk.value = walk.next_random();
+        walk.target = walk.next_random();
+        walk
+    }
+
+    fn next(&mut self, sample_rate: f32) -> f32 {
+        self.countdown -= 1.0 / sample_rate;
+        if self.countdown <= 0.0 {
+            self.countdown += DRIFT_STEP_SECONDS;
+            self.target = self.next_random();
+        }
+        let coeff = 1.0 - (-1.0 / (DRIFT_STEP_SECONDS * sample_rate)).exp();
+        self.value += (self.target - self.value) * coeff;
+        self.value
+    }
+
+    /// The same LCG as the voices' noise, mapped to -1..1.
+    fn next_random(&mut self) -> f32 {
+        self.seed = self
+            .seed
+            .wrapping_mul(1_664_525)
+            .wrapping_add(1_013_904_223);
+        let value = ((self.seed >> 9) & 0x7FFFFF) as f32 / 0x7FFFFF as f32;
+        value * 2.0 - 1.0
     }
 }
 
 pub struct SynthEngine {
-    voices: Vec<VoiceState>,
+    parts: Vec<PartVoices>,
+    buses: BusEffects,
     sample_rate: f32,
-    eq_chain: EqChain,
+    panic_count: u32,
+    /// Smoothed toward the snapshot's pitch bend.
+    pitch_bend: f32,
+    /// Left and right.
+    dc_blockers: [DcBlocker; 2],
+    limiter: Limiter,
 }
 
-impl SynthEngine {
-    pub fn new(sample_rate: f32) -> Self {
+/// Voices and EQ for one part. Kept after its part leaves the snapshot until
+/// the notes it was playing have rung out.
+struct PartVoices {
+    id: u32,
+    params: SynthParams,
+    gains: [f32; 2],
+    sends: [f32; 2],
+    ducking: Option<Ducking>,
+    /// Followed level of the ducking source.
+    duck_level: f32,
+    /// The latest post-EQ frame, before gain; what other parts duck from.
+    output: [f32; 2],
+    voices: Vec<VoiceState>,
+    /// Latest note started, which `glide_always` slides on from once its
+    /// voice has gone.
+    last_note: Option<u8>,
+    /// Held notes whose voices were stolen; they stay silent until released
+    /// rather than stealing a voice back.
+    stolen_notes: Vec<u8>,
+    /// Only sounds for the Keys instrument.
+    resonance: PedalResonance,
+    /// Left and right.
+    eq_chains: [EqChain; 2],
+}
+
+impl PartVoices {
+    fn new(part: &PartSnapshot, sample_rate: f32) -> Self {
+        let eq_chains = [0, 1].map(|_| {
+            let mut eq_chain = EqChain::new(sample_rate);
+            eq_chain.update(&part.params);
+            eq_chain
+        });
         Self {
+            id: part.id,
+            params: part.params.clone(),
+            gains: part.gains,
+            sends: part.sends,
+            ducking: part.ducking,
+            duck_level: 0.0,
+            output: [0.0; 2],
             voices: Vec::new(),
-            sample_rate,
-            eq_chain: EqChain::new(sample_rate),
+            last_note: None,
+            stolen_notes: Vec::new(),
+            resonance: PedalResonance::new(sample_rate),
+            eq_chains,
         }
     }
 
-    fn sync_voices(&mut self, pressed: &[u8]) {
-        for voice in &mut self.voices {
-            let gate = pressed.contains(&voice.note);
-            voice.set_gate(gate);
+    fn sync_voices(&mut self, pressed: &[(u8, HeldNote)]) {
+        if self.params.voice_mode != VoiceMode::Poly {
+            return self.sync_mono_voice(pressed);
+        }
+        self.stolen_notes
+            .retain(|stolen| pressed.iter().any(|(note, _)| note == stolen));
+        // Slides hand the still-gated voice of the old note over to the new one.
+        for &(note, held) in pressed {
+            let Some(from) = held.glide_from else {
+                continue;
+            };
+            if self.voices.iter().any(|voice| voice.note == note) {
+                continue;
+            }
+            if let Some(voice) = self
+                .voices
+                .iter_mut()
+                .find(|voice| voice.note == from && voice.gate && !voice.is_stolen())
+            {
+                voice.note = note;
+            }
         }
-        for &note in pressed {
-            if !self.voices.iter().any(|voice| voice.note == note) {
-                let mut voice = VoiceState::new(note);
+        for voice in self.voices.iter_mut().filter(|voice| !voice.is_stolen()) {
+            let held = pressed.iter().find(|(note, _)| *note == voice.note);
+            if let (Some((_, held)), false) = (held, voice.gate) {
+                // A retriggered voice takes the velocity of the new strike.
+                voice.velocity = held.velocity;
+            }
+            if let Some((_, held)) = held {
+                voice.bend_target = held.bend;
+            }
+            voice.set_gate(held.is_some());
+        }
+        for &(note, held) in pressed {
+            let sounding = self
+                .voices
+                .iter()
+                .any(|voice| voice.note == note && !voice.is_stolen());
+            if !sounding && !self.stolen_notes.contains(&note) {
+                self.make_room();
+                let mut voice = VoiceState::new(note, held.velocity);
+                if let Some(from) = held.glide_from {
+                    voice.pitch = from as f32;
+                } else if let Some(pitch) = self.glide_start() {
+                    voice.pitch = pitch;
+                }
+                self.last_note = Some(note);
+                voice.bend = held.bend;
+                voice.bend_target = held.bend;
                 voice.set_gate(true);
                 self.voices.push(voice);
             }
         }
     }
 
-    pub fn next_sample(&mut self, snapshot: &SynthSnapshot) -> f32 {
-        self.sync_voices(&snapshot.pressed_notes);
-        let mut mix = 0.0;
+    /// Steals a voice, by the patch's policy, if the part is already
+    /// sounding as many as its polyphony allows.
+    fn make_room(&mut self) {
+        let limit = self.params.polyphony.max(1) as usize;
+        let playing = self.voices.iter().filter(|voice| !voice.is_stolen());
+        if playing.count() < limit {
+            return;
+        }
+        let policy = self.params.voice_steal;
+        let victim = self
+            .voices
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, voice)| !voice.is_stolen())
+            .min_by(|(a_age, a), (b_age, b)| {
+                let rank = |age: usize, voice: &VoiceState| match policy {
+                    VoiceSteal::Oldest => age as f32,
+                    VoiceSteal::Quietest => voice.env_level * voice.velocity,
+                    VoiceSteal::Lowest => voice.note as f32,
+                };
+                a.gate
+                    .cmp(&b.gate)
+                    .then(rank(*a_age, a).total_cmp(&rank(*b_age, b)))
+            })
+            .map(|(_, voice)| voice);
+        if let Some(voice) = victim {
+            if voice.gate {
+                self.stolen_notes.push(voice.note);
+            }
+            voice.steal_fade = Some(1.0);
+        }
+    }
+
+    /// Mono and legato: the newest voice follows the key the note priority
+    /// picks, and any others (left from poly playing) are released.
+    fn sync_mono_voice(&mut self, pressed: &[(u8, HeldNote)]) {
+        let target = self.params.note_priority.pick(pressed);
+        let newest = self.voices.len().checked_sub(1);
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            if target.is_none() || Some(index) != newest {
+                voice.set_gate(false);
+            }
+        }
+        let Some((note, held)) = target else {
+            return;
+        };
+        let Some(voice) = self.voices.last_mut() else {
+            let mut voice = VoiceState::new(note, held.velocity);
+            if let Some(pitch) = self.glide_start() {
+                voice.pitch = pitch;
+            }
+            self.last_note = Some(note);
+            voice.bend = held.bend;
+            voice.bend_target = held.bend;
+            voice.set_gate(true);
+            self.voices.push(voice);
+            return;
+        };
+        voice.bend_target = held.bend;
+        if voice.note == note && voice.gate {
+            return;
+        }
+        // Legato glides a held voice over to the new key; otherwise the
+        // voice starts afresh there, from wherever its envelope has got to.
+        let legato = self.params.voice_mode == VoiceMode::Legato && voice.gate;
+        voice.note = note;
+        self.last_note = Some(note);
+        if !legato {
+            if held.glide_from.is_none() && !self.params.glide_always {
+                voice.pitch = note as f32;
+            }
+            voice.velocity = held.velocity;
+            voice.gate = true;
+            voice.enter_stage(EnvStage::Delay);
+        }
+    }
+
+    /// Pitch a new voice slides in from when the patch glides every note:
+    /// where the newest voice is now, or else the last note played.
+    fn glide_start(&self) -> Option<f32> {
+        if !self.params.glide_always {
+            return None;
+        }
+        self.voices
+            .last()
+            .map(|voice| voice.pitch)
+            .or(self.last_note.map(f32::from))
+    }
+
+    /// Renders the next frame into `output`. `input` is external audio
+    /// mixed into both sides ahead of the EQ; `sustain` lets the Keys strings
+    /// resonate; `pitch_bend` (semitones) shifts every voice, `tuning` gives
+    /// each its pitch and `scale` is the key autotune pulls them into.
+    fn render(
+        &mut self,
+        sample_rate: f32,
+        input: f32,
+        sustain: bool,
+        pitch_bend: f32,
+        tuning: &dyn Tuning,
+        scale: Option<Scale>,
+    ) {
+        let mut mix = [0.0; 2];
         for voice in &mut self.voices {
-            mix += voice.next_sample(&snapshot.params, self.sample_rate);
+            let frame = voice.next_frame(&self.params, sample_rate, pitch_bend, tuning, scale);
+            mix[0] += frame[0];
+            mix[1] += frame[1];
         }
         self.voices.retain(|voice| !voice.is_finished());
-        self.eq_chain.process(mix)
+        if self.params.instrument == InstrumentKind::Keys {
+            let resonance = self.resonance.process((mix[0] + mix[1]) * 0.5, sustain);
+            mix = mix.map(|side| side + resonance);
+        }
+        self.output = [0, 1].map(|side| self.eq_chains[side].process(mix[side] + input));
+    }
+
+    /// Level of the latest frame, for parts ducking from this one.
+    fn level(&self) -> f32 {
+        self.output[0].abs().max(self.output[1].abs())
     }
 
-    pub fn update_eq(&mut self, params: &SynthParams) {
-        self.eq_chain.update(params);
+    /// Follows `source` (the ducking part's latest sample) and returns the
+    /// gain left after ducking.
+    fn duck_gain(&mut self, source: f32, sample_rate: f32) -> f32 {
+        let Some(ducking) = self.ducking else {
+            self.duck_level = 0.0;
+            return 1.0;
+        };
+        let level = source.abs();
+        let ms = if level > self.duck_level {
+            ducking.attack_ms
+        } else {
+            ducking.release_ms
+        };
+        let coeff = 1.0 - (-1000.0 / (ms.max(0.1) * sample_rate)).exp();
+        self.duck_level += (level - self.duck_level) * coeff;
+        1.0 - ducking.amount.clamp(0.0, 1.0) * (self.duck_level / DUCK_FULL_LEVEL).min(1.0)
     }
 }
 
-fn midi_to_freq(note: u8) -> f32 {
-    440.0 * 2_f32.powf((note as f32 - 69.0) / 12.0)
+impl SynthEngine {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            parts: Vec::new(),
+            buses: BusEffects::new(sample_rate),
+            sample_rate,
+            panic_count: 0,
+            pitch_bend: 0.0,
+            dc_blockers: [0, 1].map(|_| DcBlocker::new(sample_rate)),
+            limiter: Limiter::new(sample_rate),
+        }
+    }
+
+    /// Renders one left/right frame of every part mixed together. `input`
+    /// (the processed mic) joins the main part before its EQ and mix. The
+    /// parts' sends run through the shared reverb and delay, then the whole
+    /// mix through a DC blocker and the limiter.
+    pub fn next_frame(&mut self, snapshot: &SynthSnapshot, input: f32) -> [f32; 2] {
+        if snapshot.panic_count != self.panic_count {
+            self.panic_count = snapshot.panic_count;
+            for part in &mut self.parts {
+                part.voices.clear();
+                part.resonance.clear();
+            }
+            self.buses.clear();
+        }
+        if self.pitch_bend != snapshot.pitch_bend {
+            let coeff = 1.0 - (-1.0 / (BEND_SMOOTHING_SECONDS * self.sample_rate)).exp();
+            self.pitch_bend += (snapshot.pitch_bend - self.pitch_bend) * coeff;
+            if (snapshot.pitch_bend - self.pitch_bend).abs() < 0.0001 {
+                self.pitch_bend = snapshot.pitch_bend;
+            }
+        }
+        for part in &snapshot.parts {
+            if !self.parts.iter().any(|voices| voices.id == part.id) {
+                self.parts.push(PartVoices::new(part, self.sample_rate));
+            }
+        }
+        for part in &mut self.parts {
+            match snapshot
+                .parts
+                .iter()
+                .find(|snapshot| snapshot.id == part.id)
+            {
+                Some(snapshot) => {
+                    part.params.clone_from(&snapshot.params);
+                    part.gains = snapshot.gains;
+                    part.sends = snapshot.sends;
+                    part.ducking = snapshot.ducking;
+                    part.sync_voices(&snapshot.pressed_notes);
+                }
+                None => part.sync_voices(&[]),
+            }
+            let part_input = if part.id == MAIN_PART { input } else { 0.0 };
+            let pitch_bend = if part.id == DRONE_PART {
+                0.0
+            } else {
+                self.pitch_bend
+            };
+            part.render(
+                self.sample_rate,
+                part_input,
+                snapshot.sustain,
+                pitch_bend,
+                &*snapshot.tuning,
+                snapshot.scale,
+            );
+        }
+        // Mixed only once every part has rendered, so a part can duck from
+        // one later in the list.
+        let mut mix = [0.0; 2];
+        let mut sends = [0.0; 2];
+        for index in 0..self.parts.len() {
+            let source = self.parts[index]
+                .ducking
+                .and_then(|ducking| self.parts.iter().find(|part| part.id == ducking.source))
+                .map_or(0.0, PartVoices::level);
+            let part = &mut self.parts[index];
+            let duck = part.duck_gain(source, self.sample_rate);
+            let [left, right] = part.output.map(|side| side * duck);
+            mix[0] += left * part.gains[0];
+            mix[1] += right * part.gains[1];
+            // The buses take a mono feed and spread it themselves.
+            let send = (left + right) * 0.5;
+            sends[0] += send * part.sends[0];
+            sends[1] += send * part.sends[1];
+        }
+        let [left, right] = self.buses.next_frame(&snapshot.buses, sends[0], sends[1]);
+        mix[0] += left;
+        mix[1] += right;
+        // Parts that were removed go once their release tails are done.
+        self.parts.retain(|part| {
+            !part.voices.is_empty() || snapshot.parts.iter().any(|snapshot| snapshot.id == part.id)
+        });
+        let mix = [0, 1].map(|side| self.dc_blockers[side].process(mix[side]));
+        self.limiter.process(mix, &snapshot.limiter)
+    }
+
+    /// Most the limiter turned the output down since the last call, in dB.
+    pub fn take_gain_reduction_db(&mut self) -> f32 {
+        self.limiter.take_gain_reduction_db()
+    }
+
+    /// (note, envelope × velocity) for every voice of every part.
+    pub fn voice_levels(&self) -> impl Iterator<Item = (u8, f32)> + '_ {
+        self.parts
+            .iter()
+            .flat_map(|part| &part.voices)
+            .map(|voice| (voice.note, voice.env_level * voice.velocity))
+    }
+
+    pub fn update_eq(&mut self, snapshot: &SynthSnapshot) {
+        for part in &mut self.parts {
+            if let Some(snapshot) = snapshot
+                .parts
+                .iter()
+                .find(|snapshot| snapshot.id == part.id)
+            {
+                for eq_chain in &mut part.eq_chains {
+                    eq_chain.update(&snapshot.params);
+                }
+            }
+        }
+    }
+}
+
+/// Renders `events` (seconds from the start, note change) through a fresh
+/// engine offline and returns interleaved left/right samples. Nothing reads
+/// the wall clock and noise is seeded per voice from its note, so the same
+/// input always renders the same output.
+pub fn render(
+    params: &SynthParams,
+    events: &[(f32, NoteEvent)],
+    sample_rate: u32,
+    length_seconds: f32,
+) -> Vec<f32> {
+    let mut events = events.to_vec();
+    events.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut pending = events.into_iter().peekable();
+    let mut shared = SynthShared::new_with_params(params.clone());
+    let mut engine = SynthEngine::new(sample_rate as f32);
+    let mut snapshot = shared.snapshot();
+    let frames = (length_seconds.max(0.0) * sample_rate as f32) as usize;
+    let mut samples = Vec::with_capacity(frames * 2);
+    for frame in 0..frames {
+        let time = frame as f32 / sample_rate as f32;
+        let mut changed = false;
+        while let Some((_, event)) = pending.next_if(|(at, _)| *at <= time) {
+            match event {
+                NoteEvent::On { note, velocity } => shared.press_note(note, velocity),
+                NoteEvent::Off { note } => shared.release_note(note),
+            }
+            changed = true;
+        }
+        if changed {
+            snapshot = shared.snapshot();
+        }
+        samples.extend(engine.next_frame(&snapshot, 0.0));
+    }
+    samples
+}
+
+/// Combined magnitude response of the voice filter and the EQ chain at
+/// `freq`, in dB, for drawing the response curve.
+pub fn response_db(params: &SynthParams, sample_rate: f32, freq: f32) -> f32 {
+    let w = TAU * freq / sample_rate;
+    let filter = VoiceState::filter(params, 1.0, sample_rate).magnitude(w);
+
+    let eq = low_shelf_coeffs(sample_rate, params.eq_low_freq_hz, params.eq_low_gain_db)
+        .magnitude(w)
+        * peaking_coeffs(
+            sample_rate,
+            params.eq_mid_freq_hz,
+            params.eq_mid_q,
+            params.eq_mid_gain_db,
+        )
+        .magnitude(w)
+        * high_shelf_coeffs(sample_rate, params.eq_high_freq_hz, params.eq_high_gain_db)
+            .magnitude(w);
+    20.0 * (filter * eq).max(1e-6).log10()
+}
+
+/// Left/right gains for a -1..1 position, by the law `PartMix::gains` describes.
+fn balance(pan: f32) -> [f32; 2] {
+    let pan = pan.clamp(-1.0, 1.0);
+    [(1.0 - pan).min(1.0), (1.0 + pan).min(1.0)]
 }
 
 struct EqChain {
@@ -468,6 +2588,21 @@ impl BiquadCoeffs {
         }
     }
 
+    /// Linear gain at angular frequency `w` (radians per sample).
+    fn magnitude(&self, w: f32) -> f32 {
+        let (c1, s1) = (w.cos(), -w.sin());
+        let (c2, s2) = ((2.0 * w).cos(), -(2.0 * w).sin());
+        let num = (
+            self.b0 + self.b1 * c1 + self.b2 * c2,
+            self.b1 * s1 + self.b2 * s2,
+        );
+        let den = (
+            1.0 + self.a1 * c1 + self.a2 * c2,
+            self.a1 * s1 + self.a2 * s2,
+        );
+        num.0.hypot(num.1) / den.0.hypot(den.1).max(f32::EPSILON)
+    }
+
     fn from_raw(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
         let inv_a0 = if a0.abs() < f32::EPSILON {
             1.0
@@ -505,12 +2640,45 @@ impl BiquadState {
 
     fn process(&mut self, input: f32) -> f32 {
         let y = self.coeffs.b0 * input + self.z1;
-        self.z1 = self.coeffs.b1 * input - self.coeffs.a1 * y + self.z2;
-        self.z2 = self.coeffs.b2 * input - self.coeffs.a2 * y;
+        self.z1 = flush_denormal(self.coeffs.b1 * input - self.coeffs.a1 * y + self.z2);
+        self.z2 = flush_denormal(self.coeffs.b2 * input - self.coeffs.a2 * y);
         y
     }
 }
 
+/// A one-pole high-pass at `DC_BLOCK_HZ` for one side of the output.
+struct DcBlocker {
+    coeff: f32,
+    last_input: f32,
+    last_output: f32,
+}
+
+impl DcBlocker {
+    fn new(sample_rate: f32) -> Self {
+        Self {
+            coeff: (-TAU * DC_BLOCK_HZ / sample_rate).exp(),
+            last_input: 0.0,
+            last_output: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let output = input - self.last_input + self.coeff * self.last_output;
+        self.last_input = input;
+        self.last_output = flush_denormal(output);
+        self.last_output
+    }
+}
+
+/// `value`, or zero once it is small enough to go denormal.
+pub fn flush_denormal(value: f32) -> f32 {
+    if value.abs() < DENORMAL_LIMIT {
+        0.0
+    } else {
+        value
+    }
+}
+
 fn low_shelf_coeffs(sample_rate: f32, freq: f32, gain_db: f32) -> BiquadCoeffs {
     let freq = freq.clamp(10.0, sample_rate * 0.45);
     let a = 10_f32.powf(gain_db / 40.0);
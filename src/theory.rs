@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+
+use crate::chords::{PITCH_CLASS_NAMES, chord_name};
+
+/// Short interval names by semitones above the root, as printed on the keys.
+const INTERVAL_SHORT: [&str; 12] = [
+    "R", "m2", "M2", "m3", "M3", "P4", "TT", "P5", "m6", "M6", "m7", "M7",
+];
+
+const INTERVAL_NAMES: [&str; 12] = [
+    "unison",
+    "minor second",
+    "major second",
+    "minor third",
+    "major third",
+    "perfect fourth",
+    "tritone",
+    "perfect fifth",
+    "minor sixth",
+    "major sixth",
+    "minor seventh",
+    "major seventh",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChordKind {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Sus2,
+    Sus4,
+    Dominant7,
+    Major7,
+    Minor7,
+}
+
+impl ChordKind {
+    pub const ALL: [ChordKind; 9] = [
+        ChordKind::Major,
+        ChordKind::Minor,
+        ChordKind::Diminished,
+        ChordKind::Augmented,
+        ChordKind::Sus2,
+        ChordKind::Sus4,
+        ChordKind::Dominant7,
+        ChordKind::Major7,
+        ChordKind::Minor7,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChordKind::Major => "Major",
+            ChordKind::Minor => "Minor",
+            ChordKind::Diminished => "Diminished",
+            ChordKind::Augmented => "Augmented",
+            ChordKind::Sus2 => "Sus2",
+            ChordKind::Sus4 => "Sus4",
+            ChordKind::Dominant7 => "Dominant 7th",
+            ChordKind::Major7 => "Major 7th",
+            ChordKind::Minor7 => "Minor 7th",
+        }
+    }
+
+    /// Semitones above the root.
+    pub fn intervals(self) -> &'static [u8] {
+        match self {
+            ChordKind::Major => &[0, 4, 7],
+            ChordKind::Minor => &[0, 3, 7],
+            ChordKind::Diminished => &[0, 3, 6],
+            ChordKind::Augmented => &[0, 4, 8],
+            ChordKind::Sus2 => &[0, 2, 7],
+            ChordKind::Sus4 => &[0, 5, 7],
+            ChordKind::Dominant7 => &[0, 4, 7, 10],
+            ChordKind::Major7 => &[0, 4, 7, 11],
+            ChordKind::Minor7 => &[0, 3, 7, 10],
+        }
+    }
+}
+
+/// The learning overlay: every key is labelled with its interval from `root`
+/// (a pitch class, 0 = C) and the tones of `chord`, if any, are outlined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TheoryOverlay {
+    pub root: u8,
+    pub chord: Option<ChordKind>,
+}
+
+impl TheoryOverlay {
+    fn interval(&self, note: u8) -> usize {
+        ((note + 12 - self.root % 12) % 12) as usize
+    }
+
+    pub fn interval_label(&self, note: u8) -> &'static str {
+        INTERVAL_SHORT[self.interval(note)]
+    }
+
+    pub fn in_chord(&self, note: u8) -> bool {
+        self.chord
+            .is_some_and(|chord| chord.intervals().contains(&(self.interval(note) as u8)))
+    }
+
+    /// Names `notes` for a learner: the interval from the root for a single
+    /// note, the interval between two, or the chord with each tone's interval
+    /// from the root, e.g. "C major (R M3 P5)".
+    pub fn describe(&self, notes: &[u8]) -> Option<String> {
+        let root_name = PITCH_CLASS_NAMES[(self.root % 12) as usize];
+        match notes {
+            [] => None,
+            [note] if self.interval(*note) == 0 => Some(format!("the root, {root_name}")),
+            [note] => Some(format!(
+                "a {} above {root_name}",
+                INTERVAL_NAMES[self.interval(*note)]
+            )),
+            [low, high] => Some(interval_name(high.abs_diff(*low))),
+            _ => {
+                let intervals: Vec<&str> = notes
+                    .iter()
+                    .map(|note| self.interval_label(*note))
+                    .collect();
+                let chord = chord_name(notes).unwrap_or_else(|| "No known chord".to_string());
+                Some(format!("{chord} ({})", intervals.join(" ")))
+            }
+        }
+    }
+}
+
+/// "perfect fifth", or "major third plus an octave" past the octave.
+fn interval_name(semitones: u8) -> String {
+    let name = INTERVAL_NAMES[(semitones % 12) as usize];
+    match semitones / 12 {
+        0 => name.to_string(),
+        1 if semitones == 12 => "octave".to_string(),
+        1 => format!("{name} plus an octave"),
+        octaves if semitones.is_multiple_of(12) => format!("{octaves} octaves"),
+        octaves => format!("{name} plus {octaves} octaves"),
+    }
+}
+
+/// The notes of the last gesture: everything pressed from the first key down
+/// until every key is up again, so a rolled chord still reads as a chord.
+#[derive(Default)]
+pub struct PlayedNotes {
+    notes: Vec<u8>,
+    holding: bool,
+}
+
+impl PlayedNotes {
+    /// Call once per frame with the sounding notes.
+    pub fn update(&mut self, held: &[u8]) {
+        if held.is_empty() {
+            self.holding = false;
+            return;
+        }
+        if !self.holding {
+            self.notes.clear();
+            self.holding = true;
+        }
+        for note in held {
+            if !self.notes.contains(note) {
+                self.notes.push(*note);
+            }
+        }
+        self.notes.sort_unstable();
+    }
+
+    pub fn notes(&self) -> &[u8] {
+        &self.notes
+    }
+}
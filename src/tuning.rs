@@ -0,0 +1,272 @@
+use std::fs;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// How notes map to pitches. Notes are fractional MIDI notes, so glides,
+/// bends and vibrato fall between keys.
+pub trait Tuning: Send + Sync {
+    fn freq(&self, note: f32) -> f32;
+
+    /// What the tuning is called, for the settings.
+    fn description(&self) -> &str;
+}
+
+/// Twelve-tone equal temperament with A4 at 440 Hz.
+pub struct EqualTemperament;
+
+impl Tuning for EqualTemperament {
+    fn freq(&self, note: f32) -> f32 {
+        440.0 * 2_f32.powf((note - 69.0) / 12.0)
+    }
+
+    fn description(&self) -> &str {
+        "12-tone equal temperament"
+    }
+}
+
+/// A historical tuning of the twelve keys of the octave.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Temperament {
+    #[default]
+    Equal,
+    /// 5-limit just intonation: pure thirds and fifths above the root.
+    Just,
+    /// Pure fifths, stacked from the root.
+    Pythagorean,
+    /// Fifths narrowed by a quarter of the syntonic comma, for pure major
+    /// thirds; the standard Renaissance keyboard tuning.
+    Meantone,
+}
+
+impl Temperament {
+    pub const ALL: [Temperament; 4] = [
+        Temperament::Equal,
+        Temperament::Just,
+        Temperament::Pythagorean,
+        Temperament::Meantone,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Temperament::Equal => "12-tone equal temperament",
+            Temperament::Just => "Just intonation",
+            Temperament::Pythagorean => "Pythagorean",
+            Temperament::Meantone => "Quarter-comma meantone",
+        }
+    }
+
+    /// Cents of each of the twelve keys above the root.
+    fn cents(self) -> [f32; 12] {
+        match self {
+            Temperament::Equal => std::array::from_fn(|key| key as f32 * 100.0),
+            Temperament::Just => [
+                (1, 1),
+                (16, 15),
+                (9, 8),
+                (6, 5),
+                (5, 4),
+                (4, 3),
+                (45, 32),
+                (3, 2),
+                (8, 5),
+                (5, 3),
+                (16, 9),
+                (15, 8),
+            ]
+            .map(|(numerator, denominator)| {
+                1200.0 * (numerator as f32 / denominator as f32).log2()
+            }),
+            // The leftover "wolf" fifth falls between the chain's ends: from
+            // a root of C, F♯–D♭ for Pythagorean and G♯–E♭ for meantone.
+            Temperament::Pythagorean => chain_of_fifths(1200.0 * 1.5_f32.log2(), -5),
+            Temperament::Meantone => chain_of_fifths(300.0 * 5_f32.log2(), -3),
+        }
+    }
+
+    /// The temperament laid across the keyboard from `root_note`.
+    pub fn tuning(self, root_note: u8) -> Arc<dyn Tuning> {
+        if self == Temperament::Equal {
+            return Arc::new(EqualTemperament);
+        }
+        let cents = self.cents();
+        let scale = ScalaScale {
+            description: self.label().to_string(),
+            degrees: cents[1..].iter().copied().chain([1200.0]).collect(),
+        };
+        Arc::new(ScalaTuning::new(scale, root_note))
+    }
+}
+
+/// Cents above the root of the twelve keys reached by stacking `fifth`
+/// from `lowest` fifths below the root to eleven above that, folded into
+/// one octave.
+fn chain_of_fifths(fifth: f32, lowest: i32) -> [f32; 12] {
+    let mut cents = [0.0; 12];
+    for fifths in lowest..lowest + 12 {
+        let key = (fifths * 7).rem_euclid(12) as usize;
+        cents[key] = (fifths as f32 * fifth).rem_euclid(1200.0);
+    }
+    cents
+}
+
+/// A scale read from a Scala (`.scl`) file: its degrees in cents above the
+/// first, ending with the period it repeats at (usually the octave).
+pub struct ScalaScale {
+    description: String,
+    degrees: Vec<f32>,
+}
+
+impl ScalaScale {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text =
+            fs::read_to_string(path).map_err(|err| format!("Could not read {path}: {err}"))?;
+        Self::parse(&text).map_err(|err| format!("{path}: {err}"))
+    }
+
+    /// Reads the `.scl` format: after `!` comment lines, a description, the
+    /// number of degrees, then each degree as cents (with a `.`), a ratio
+    /// (`3/2`) or a whole number (`2`). Text after a value is ignored.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().filter(|line| !line.starts_with('!'));
+        let description = lines.next().ok_or("The file is empty")?.trim().to_string();
+        let count = lines
+            .next()
+            .and_then(|line| line.split_whitespace().next())
+            .and_then(|count| count.parse::<usize>().ok())
+            .ok_or("No number of notes after the description")?;
+        if count == 0 {
+            return Err("The scale has no notes".to_string());
+        }
+        let degrees = lines
+            .filter_map(|line| line.split_whitespace().next())
+            .take(count)
+            .map(parse_pitch)
+            .collect::<Result<Vec<_>, _>>()?;
+        if degrees.len() < count {
+            return Err(format!("{count} notes promised, {} found", degrees.len()));
+        }
+        let period = degrees[count - 1];
+        if !(period.is_finite() && period > 0.0) {
+            return Err("The scale must repeat above its first note".to_string());
+        }
+        Ok(Self {
+            description: if description.is_empty() {
+                format!("{count}-note scale")
+            } else {
+                description
+            },
+            degrees,
+        })
+    }
+
+    /// Degrees per period.
+    fn len(&self) -> usize {
+        self.degrees.len()
+    }
+}
+
+/// One degree of a `.scl` file, in cents. `NaN`, `inf` and values too
+/// big for an `f32` parse as numbers, so anything not finite is refused.
+fn parse_pitch(value: &str) -> Result<f32, String> {
+    let invalid = || format!("{value:?} is not a pitch");
+    let cents = if value.contains('.') {
+        value.parse::<f32>().map_err(|_| invalid())?
+    } else {
+        let (numerator, denominator) = value.split_once('/').unwrap_or((value, "1"));
+        let numerator = numerator.parse::<f64>().map_err(|_| invalid())?;
+        let denominator = denominator.parse::<f64>().map_err(|_| invalid())?;
+        let ratio = numerator / denominator;
+        if !(ratio.is_finite() && ratio > 0.0) {
+            return Err(invalid());
+        }
+        (1200.0 * ratio.log2()) as f32
+    };
+    if cents.is_finite() {
+        Ok(cents)
+    } else {
+        Err(invalid())
+    }
+}
+
+/// A Scala scale laid across the keyboard: its first degree on `root_note`,
+/// at that key's equal-tempered pitch, and the rest on the keys above and
+/// below in turn, a period apart every `len` keys.
+pub struct ScalaTuning {
+    scale: ScalaScale,
+    root_note: u8,
+    root_freq: f32,
+}
+
+impl ScalaTuning {
+    pub fn new(scale: ScalaScale, root_note: u8) -> Self {
+        Self {
+            root_freq: EqualTemperament.freq(root_note as f32),
+            scale,
+            root_note,
+        }
+    }
+
+    /// Cents from the root to `key`.
+    fn cents(&self, key: i32) -> f32 {
+        let len = self.scale.len() as i32;
+        let steps = key - self.root_note as i32;
+        let period = self.scale.degrees[self.scale.len() - 1];
+        let degree = steps.rem_euclid(len) as usize;
+        let above = if degree == 0 {
+            0.0
+        } else {
+            self.scale.degrees[degree - 1]
+        };
+        steps.div_euclid(len) as f32 * period + above
+    }
+}
+
+impl Tuning for ScalaTuning {
+    /// Notes between keys glide evenly in cents from one key's pitch to the next.
+    fn freq(&self, note: f32) -> f32 {
+        let key = note.floor();
+        let below = self.cents(key as i32);
+        let above = self.cents(key as i32 + 1);
+        let cents = below + (above - below) * (note - key);
+        self.root_freq * 2_f32.powf(cents / 1200.0)
+    }
+
+    fn description(&self) -> &str {
+        &self.scale.description
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pitch_reads_cents_and_ratios() {
+        assert_eq!(parse_pitch("701.955"), Ok(701.955));
+        assert_eq!(parse_pitch("-50.0"), Ok(-50.0));
+        assert!((parse_pitch("3/2").unwrap() - 701.955).abs() < 0.001);
+        assert!((parse_pitch("2").unwrap() - 1200.0).abs() < 0.001);
+        assert!(parse_pitch("0/1").is_err());
+        assert!(parse_pitch("1/0").is_err());
+        assert!(parse_pitch("-3/2").is_err());
+    }
+
+    #[test]
+    fn parse_pitch_refuses_values_that_are_not_finite() {
+        for value in [
+            "NaN", "NaN.", "inf", "inf.", "-inf.", "1e400", "1e39.", "1e400/1",
+        ] {
+            assert!(parse_pitch(value).is_err(), "{value} was accepted");
+        }
+    }
+
+    #[test]
+    fn scale_needs_a_finite_rising_period() {
+        assert!(ScalaScale::parse("Fifths\n2\n3/2\n2/1\n").is_ok());
+        assert!(ScalaScale::parse("Flat\n1\n0.0\n").is_err());
+        assert!(ScalaScale::parse("Broken\n2\n100.0\nNaN.\n").is_err());
+        assert!(ScalaScale::parse("Broken\n1\ninf\n").is_err());
+    }
+}
@@ -1,33 +1,228 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use egui::{self, Align2, Color32, ComboBox, FontId, Id, Layout, Rounding, Stroke};
 
-use crate::audio::{SynthAudio, list_output_device_names};
-use crate::scope::ScopeBuffer;
-use crate::settings::{AppSettings, KeybindScheme, LayoutMode, ThemeKind};
-use crate::synth::{InstrumentKind, SynthParams, SynthShared, Waveform};
+use crate::additive::{HARMONIC_COUNT, Harmonics};
+use crate::audio::{MicInput, SynthAudio, list_input_device_names, list_output_device_names};
+use crate::chords::{PITCH_CLASS_NAMES, chord_name};
+use crate::controllers::{BREATH_CC, BreathCurve, BreathRouting, CcMapping, ControllerTemplate};
+use crate::diagnostics::{Diagnostics, EngineStats, EventKind, format_uptime};
+use crate::drums::DrumSound;
+use crate::ear::{AnswerMode, EarStats, EarTrainer, Exercise};
+use crate::effects::{MAX_DELAY_SECONDS, SendBuses};
+use crate::gamepad::GamepadInput;
+use crate::i18n::{Language, set_language, tr, tr_fmt};
+use crate::jam::{DEFAULT_JAM_PORT, JamLobby, JamSession};
+use crate::keybinds::{Action, action_for, chord_label, uses_command};
+use crate::keymap::{Keymap, qwerty_bindings, qwerty_note, qwerty_range};
+use crate::limiter::{CEILING_RANGE_DB, LimiterSettings};
+use crate::logging;
+use crate::meters::{OutputMeters, to_dbfs};
+use crate::mic::{MicBuffer, MicMode, PitchFollower, TakeRecorder, export_take};
+use crate::midi::{MidiInput, list_midi_port_names};
+use crate::params::{ParamId, clamp_params};
+use crate::presets::{Preset, all_presets, morph_params, parse_patch_code, patch_code};
+use crate::progressions::{ChordPlayer, PROGRESSION_COUNT, progression, progression_label};
+use crate::sampler::{SampleCache, SampleFile};
+use crate::scales::{Scale, ScaleKind};
+use crate::scope::{self, SCOPE_CAPACITY, ScopeBuffer};
+use crate::session::{
+    AUTOSAVE_INTERVAL, Session, clear_recovery, load_recovery, recovery_path, save_recovery,
+};
+use crate::settings::{
+    AppSettings, DockSlot, KeyColoring, KeybindScheme, KeyboardRange, LogLevel, MAX_KEYBOARD_ZOOM,
+    OctaveNaming, PanelKind, RibbonMode, SettingsSaver, StartupPatch, ThemeKind, clamp_f32,
+    config_dir, default_export_path,
+};
+use crate::soundfont::{SoundFontCache, SoundFontFile};
+use crate::stats::{
+    PracticeTracker, STATS_SAVE_INTERVAL, format_practice_time, load_stats, save_stats, stats_path,
+};
+use crate::synth::{
+    A4_RANGE, Drone, Ducking, EnvCurve, FilterMode, InstrumentKind, KeyboardSplit, MAIN_PART,
+    MAX_POLYPHONY, MAX_TRANSPOSE, MAX_UNISON_VOICES, NotePriority, NoteSource, Part, PartMix,
+    PatchLayer, SynthParams, SynthShared, VoiceMode, VoiceSteal, Waveform, response_db,
+};
+use crate::theory::{ChordKind, PlayedNotes, TheoryOverlay};
+use crate::tuning::{ScalaScale, ScalaTuning, Temperament, Tuning};
+use crate::watchdog::{AudioWatchdog, XrunCounts};
+use crate::wav::encode_stereo_pcm16;
+use crate::wavetable::{WavetableCache, WavetableFile};
 
-const LOWEST_NOTE: u8 = 36; // C2
-const HIGHEST_NOTE: u8 = 84; // C6
 const BASE_WHITE_KEY_WIDTH: f32 = 36.0;
 const BASE_WHITE_KEY_HEIGHT: f32 = 200.0;
 const BLACK_KEY_WIDTH_RATIO: f32 = 0.62;
 const BLACK_KEY_HEIGHT_RATIO: f32 = 0.62;
 const ACCENT: Color32 = Color32::from_rgb(255, 140, 0);
+const SCOPE_DIVISIONS: usize = 10;
+
+/// How many octaves the computer keyboard can be shifted either way.
+const MAX_OCTAVE_SHIFT: i32 = 2;
+/// Velocity of computer keyboard notes until the player picks another preset.
+const DEFAULT_KEY_VELOCITY: f32 = 0.8;
+/// Height of the ribbon strip under the piano, before the touch layout.
+const RIBBON_HEIGHT: f32 = 28.0;
+/// Fill blended into keys that belong to the selected scale.
+const SCALE_TINT: Color32 = Color32::from_rgb(90, 170, 255);
+/// Dot marking the selected scale's root.
+const SCALE_ROOT: Color32 = Color32::from_rgb(20, 90, 200);
+/// Outline of the keys in the theory overlay's chord.
+const CHORD_TONE: Color32 = Color32::from_rgb(60, 190, 110);
+/// Where a new keyboard split starts: the lower zone ends just below middle C.
+const DEFAULT_SPLIT_NOTE: u8 = 60;
+/// Height of the miniature keyboard under a zoomed piano.
+const OVERVIEW_HEIGHT: f32 = 16.0;
+/// Piano keys are this much taller in the touch layout, to fit a fingertip.
+const TOUCH_KEY_HEIGHT: f32 = 1.5;
+/// Velocity of a click at the very top of a piano key; the bottom edge plays at full velocity.
+const MIN_CLICK_VELOCITY: f32 = 0.2;
+
+/// A key press or release with the modifiers held at the time.
+type KeyEvent = (egui::Key, bool, egui::Modifiers);
 
 pub struct SynthApp {
     shared: Arc<Mutex<SynthShared>>,
     _audio: SynthAudio,
     mouse_note: Option<u8>,
-    scope: Arc<Mutex<ScopeBuffer>>,
-    last_key: Option<egui::Key>,
+    /// Note held down by the ribbon strip in pitch mode.
+    ribbon_note: Option<u8>,
+    /// Left edge of the zoomed piano view, as a fraction of the keyboard width.
+    piano_scroll: f32,
+    scope: Arc<ScopeBuffer>,
+    mic: Arc<Mutex<MicBuffer>>,
+    /// Open capture stream while the microphone is enabled.
+    mic_input: Option<MicInput>,
+    mic_error: Option<String>,
+    midi_input: Option<MidiInput>,
+    midi_error: Option<String>,
+    midi_ports: Vec<String>,
+    pitch_follower: PitchFollower,
+    chord_player: ChordPlayer,
+    played: PlayedNotes,
+    input_devices: Vec<String>,
     octave_offset: i32,
-    settings_path: PathBuf,
+    saver: SettingsSaver,
     settings: AppSettings,
     output_devices: Vec<String>,
     audio_error: Option<String>,
     settings_open: bool,
+    /// Keys navigate and adjust controls instead of playing notes.
+    ui_focus: bool,
+    morph: MorphState,
+    transfer_path: String,
+    transfer_status: Option<String>,
+    recovery_path: PathBuf,
+    pending_recovery: Option<Session>,
+    last_autosave: Instant,
+    practice: PracticeTracker,
+    last_stats_save: Instant,
+    keymap_editor: KeymapEditor,
+    jam: JamState,
+    ear: EarTrainer,
+    ear_open: bool,
+    current_preset: Option<usize>,
+    /// Text typed or pasted into the header's Paste patch field.
+    patch_code_input: String,
+    patch_code_error: Option<String>,
+    key_velocity: f32,
+    gamepad: GamepadInput,
+    wavetables: WavetableCache,
+    /// Text typed into the Tone panel's wavetable path field.
+    wavetable_input: String,
+    samples: SampleCache,
+    /// Text typed into the Tone panel's sample path field, for the Sampler
+    /// or a drum pad.
+    sample_input: String,
+    soundfonts: SoundFontCache,
+    /// Text typed into the Tone panel's SoundFont path field.
+    soundfont_input: String,
+    /// Text typed into the settings' tuning path field.
+    tuning_input: String,
+    /// What the keyboard is tuned to, and why the chosen file isn't used.
+    tuning_name: String,
+    tuning_error: Option<String>,
+    scope_frozen: Option<Vec<f32>>,
+    envelope_frozen: Option<Vec<(f32, f32)>>,
+    scope_status: Option<String>,
+    /// Xruns already written to the log.
+    xruns_logged: XrunCounts,
+    diagnostics: Diagnostics,
+    take_recorder: Option<TakeRecorder>,
+    /// Where the last take was saved, or why it wasn't.
+    take_status: Option<String>,
+    bounce_status: Option<String>,
+}
+
+/// The running jam session, if any, plus the lobby window's inputs.
+struct JamState {
+    open: bool,
+    name: String,
+    port: u16,
+    address: String,
+    error: Option<String>,
+    session: Option<JamSession>,
+    lobby: Option<JamLobby>,
+}
+
+impl Default for JamState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            name: "Player".to_string(),
+            port: DEFAULT_JAM_PORT,
+            address: String::new(),
+            error: None,
+            session: None,
+            lobby: None,
+        }
+    }
+}
+
+/// State of the keymap editor window: which keymap is being edited and the
+/// note picked on the piano that the next key press will be bound to.
+#[derive(Default)]
+struct KeymapEditor {
+    open: bool,
+    editing: usize,
+    learn_note: Option<u8>,
+    new_name: String,
+}
+
+impl KeymapEditor {
+    /// Turns the first key press after a piano click into a binding instead of a note.
+    fn capture(&mut self, events: &mut Vec<KeyEvent>, keymaps: &mut [Keymap]) -> bool {
+        let (Some(note), Some(keymap)) = (self.learn_note, keymaps.get_mut(self.editing)) else {
+            return false;
+        };
+        let Some(index) = events.iter().position(|(_, pressed, _)| *pressed) else {
+            return false;
+        };
+        let (key, _, _) = events.remove(index);
+        events.retain(|(other, _, _)| *other != key);
+        keymap.bind(key.name(), note);
+        self.learn_note = None;
+        true
+    }
+}
+
+/// Which two presets the morph slider blends between, and where it sits.
+struct MorphState {
+    preset_a: usize,
+    preset_b: usize,
+    amount: f32,
+}
+
+impl Default for MorphState {
+    fn default() -> Self {
+        Self {
+            preset_a: 0,
+            preset_b: 1,
+            amount: 0.0,
+        }
+    }
 }
 
 impl SynthApp {
@@ -35,30 +230,452 @@ impl SynthApp {
         cc: &eframe::CreationContext<'_>,
         shared: Arc<Mutex<SynthShared>>,
         audio: SynthAudio,
-        scope: Arc<Mutex<ScopeBuffer>>,
+        scope: Arc<ScopeBuffer>,
+        mic: Arc<Mutex<MicBuffer>>,
         settings_path: PathBuf,
         mut settings: AppSettings,
     ) -> Self {
         apply_theme(&cc.egui_ctx, settings.theme);
+        apply_touch_layout(&cc.egui_ctx, settings.touch_layout);
+        set_language(settings.language);
         if let Ok(mut guard) = shared.lock() {
             guard.params = settings.params.clone();
+            guard.split = settings.split.clone();
+            guard.main_mix = settings.main_mix;
+            guard.layer = settings.layer.clone();
+            guard.added_parts = settings.added_parts.clone();
+            guard.buses = settings.buses;
+            guard.limiter = settings.limiter;
         }
         let devices = list_output_device_names();
+        let recovery_path = recovery_path();
         if settings.output_device.is_none() {
             settings.output_device = Some(audio.device_name.clone());
         }
-        Self {
+        let mut app = Self {
             shared,
             _audio: audio,
             mouse_note: None,
+            ribbon_note: None,
+            piano_scroll: 0.0,
             scope,
-            last_key: None,
+            mic,
+            mic_input: None,
+            mic_error: None,
+            midi_input: None,
+            midi_error: None,
+            midi_ports: list_midi_port_names(),
+            pitch_follower: PitchFollower::default(),
+            chord_player: ChordPlayer::default(),
+            played: PlayedNotes::default(),
+            input_devices: list_input_device_names(),
             octave_offset: 0,
-            settings_path,
+            saver: SettingsSaver::new(settings_path),
             settings,
             output_devices: devices,
             audio_error: None,
             settings_open: false,
+            ui_focus: false,
+            morph: MorphState::default(),
+            transfer_path: default_export_path().display().to_string(),
+            transfer_status: None,
+            pending_recovery: load_recovery(&recovery_path),
+            recovery_path,
+            last_autosave: Instant::now(),
+            practice: PracticeTracker::new(load_stats(&stats_path())),
+            last_stats_save: Instant::now(),
+            keymap_editor: KeymapEditor::default(),
+            jam: JamState::default(),
+            ear: EarTrainer::default(),
+            ear_open: false,
+            current_preset: None,
+            patch_code_input: String::new(),
+            patch_code_error: None,
+            key_velocity: DEFAULT_KEY_VELOCITY,
+            gamepad: GamepadInput::default(),
+            wavetables: WavetableCache::default(),
+            wavetable_input: String::new(),
+            samples: SampleCache::default(),
+            sample_input: String::new(),
+            soundfonts: SoundFontCache::default(),
+            soundfont_input: String::new(),
+            tuning_input: String::new(),
+            tuning_name: String::new(),
+            tuning_error: None,
+            scope_frozen: None,
+            envelope_frozen: None,
+            scope_status: None,
+            xruns_logged: XrunCounts::default(),
+            diagnostics: Diagnostics::default(),
+            take_recorder: None,
+            take_status: None,
+            bounce_status: None,
+        };
+        let opened = format!(
+            "Output {} at {} Hz",
+            app._audio.device_name, app._audio.sample_rate
+        );
+        app.diagnostics.record(EventKind::Device, opened);
+        app.switch_input_device();
+        app.switch_midi_input();
+        app.apply_tuning();
+        app
+    }
+
+    fn session_snapshot(&self) -> Session {
+        Session {
+            saved_at_unix: 0,
+            params: self.settings.params.clone(),
+            octave_offset: self.octave_offset,
+            morph_preset_a: self.morph.preset_a,
+            morph_preset_b: self.morph.preset_b,
+            morph_amount: self.morph.amount,
+        }
+    }
+
+    fn restore_session(&mut self, mut session: Session) {
+        // A session file is read back like the settings, so it gets the
+        // same range checks before anything reaches the synth.
+        clamp_params(&mut session.params);
+        clamp_f32(&mut session.morph_amount, 0.0..=1.0);
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.params = session.params.clone();
+        }
+        self.settings.params = session.params;
+        self.octave_offset = session
+            .octave_offset
+            .clamp(-MAX_OCTAVE_SHIFT, MAX_OCTAVE_SHIFT);
+        self.morph.preset_a = session.morph_preset_a;
+        self.morph.preset_b = session.morph_preset_b;
+        self.morph.amount = session.morph_amount;
+        self.saver.save(&self.settings);
+    }
+
+    fn autosave_session(&mut self) {
+        // Don't clobber the crash file until the user has decided what to do with it.
+        if self.pending_recovery.is_none() && self.last_autosave.elapsed() >= AUTOSAVE_INTERVAL {
+            save_recovery(&self.recovery_path, &self.session_snapshot());
+            self.last_autosave = Instant::now();
+        }
+        if self.last_stats_save.elapsed() >= STATS_SAVE_INTERVAL {
+            if let Some(stats) = self.practice.take_unsaved() {
+                save_stats(&stats_path(), stats);
+            }
+            self.last_stats_save = Instant::now();
+        }
+    }
+
+    /// Draws the panels placed in `slot` as stacked cards. Dropping a card's
+    /// title onto another card moves it in front of that one, across docks too.
+    /// Returns true when a setting changed.
+    fn dock_contents(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        slot: DockSlot,
+        shared: &mut SynthShared,
+    ) -> bool {
+        let (padding, rounding) = (self.settings.card_padding, self.settings.card_rounding);
+        let mut changed = false;
+        egui::ScrollArea::vertical()
+            .id_source(("dock", slot))
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for panel in self.settings.panels_in(slot) {
+                    let was_collapsed = self.settings.panel_collapsed(panel);
+                    let mut collapsed = was_collapsed;
+                    let card = fl_card(ui, panel, padding, rounding, &mut collapsed, |ui| {
+                        changed |= self.panel_contents(ui, ctx, panel, shared);
+                    });
+                    if collapsed != was_collapsed {
+                        self.settings.set_panel_collapsed(panel, collapsed);
+                        changed = true;
+                    }
+                    if let Some(dragged) = card.dnd_release_payload::<PanelKind>() {
+                        self.settings.move_panel_before(*dragged, panel);
+                        changed = true;
+                    }
+                    ui.add_space(6.0);
+                }
+            });
+        changed
+    }
+
+    fn scope_contents(&mut self, ui: &mut egui::Ui, height: f32) -> bool {
+        let sample_rate = self._audio.sample_rate;
+        if self.settings.scope_envelope {
+            let envelope = match &self.envelope_frozen {
+                Some(frozen) => frozen.clone(),
+                None => {
+                    let window = self.settings.scope_envelope_seconds * sample_rate;
+                    let columns = ui.available_width().max(200.0) as usize;
+                    self.scope.envelope(window as usize, columns)
+                }
+            };
+            let changed = scope_controls(ui, &mut self.settings);
+            let mut freeze = self.envelope_frozen.is_some();
+            if ui.toggle_value(&mut freeze, tr("Freeze")).changed() {
+                self.envelope_frozen = freeze.then(|| envelope.clone());
+            }
+            draw_envelope(ui, &self.settings, height, &envelope);
+            return changed;
+        }
+        let trace = match &self.scope_frozen {
+            Some(frozen) => frozen.clone(),
+            None => scope_trace(&self.settings, sample_rate, &self.scope),
+        };
+        let changed = scope_controls(ui, &mut self.settings);
+        scope_snapshot_controls(
+            ui,
+            &mut self.scope_frozen,
+            &mut self.scope_status,
+            &trace,
+            sample_rate,
+            self.settings.scope_gain,
+        );
+        draw_scope(ui, &self.settings, height, &trace);
+        changed
+    }
+
+    /// Scope plus the output meters in a separate OS window, so they can live
+    /// on another monitor. Backends without multi-window support get an egui
+    /// window instead. Returns true when a setting changed, including the
+    /// window being closed, which docks the scope again.
+    fn popped_out_scope(&mut self, ctx: &egui::Context) -> bool {
+        let viewport = egui::ViewportId::from_hash_of("scope_viewport");
+        let builder = egui::ViewportBuilder::default()
+            .with_title("Angel Synth · Scope")
+            .with_inner_size([760.0, 420.0]);
+        let (changed, closed) = ctx.show_viewport_immediate(viewport, builder, |ctx, class| {
+            let mut changed = false;
+            let body = |ui: &mut egui::Ui| {
+                ui.horizontal(|ui| {
+                    level_meter(ui, &self._audio.meters);
+                    gain_reduction_readout(ui, &self._audio.meters);
+                    loudness_readout(ui, &self._audio.meters);
+                    correlation_meter(ui, self._audio.meters.correlation());
+                });
+                ui.separator();
+                let height = (ui.available_height() - 60.0).max(self.settings.scope_height);
+                changed = self.scope_contents(ui, height);
+            };
+            if class == egui::ViewportClass::Embedded {
+                let mut open = true;
+                egui::Window::new(tr("Scope"))
+                    .open(&mut open)
+                    .resizable(true)
+                    .show(ctx, body);
+                (changed, !open)
+            } else {
+                egui::CentralPanel::default().show(ctx, body);
+                (
+                    changed,
+                    ctx.input(|input| input.viewport().close_requested()),
+                )
+            }
+        });
+        if closed {
+            self.settings.scope_popped_out = false;
+        }
+        changed || closed
+    }
+
+    /// The body of one panel, wherever it is placed. Returns true when a
+    /// setting changed.
+    fn panel_contents(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        panel: PanelKind,
+        shared: &mut SynthShared,
+    ) -> bool {
+        match panel {
+            PanelKind::Scope if self.settings.scope_popped_out => {
+                ui.label(tr("The scope is in its own window."));
+                if ui.button(tr("Pop back in")).clicked() {
+                    self.settings.scope_popped_out = false;
+                    return true;
+                }
+                false
+            }
+            PanelKind::Scope => {
+                let popped = ui
+                    .small_button(tr("Pop out"))
+                    .on_hover_text(tr("Move the scope and meters into a separate window"))
+                    .clicked();
+                if popped {
+                    self.settings.scope_popped_out = true;
+                }
+                let height = self.settings.scope_height;
+                self.scope_contents(ui, height) || popped
+            }
+            PanelKind::Keyboard => {
+                if self.settings_open {
+                    ui.label(tr("Keyboard disabled while settings are open."));
+                    return false;
+                }
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    octave_controls(ui, &mut self.octave_offset);
+                    ui.separator();
+                    changed = mouse_glide_controls(
+                        ui,
+                        &mut self.settings.mouse_glide,
+                        &mut shared.params,
+                    );
+                    ui.separator();
+                    changed |= ui
+                        .checkbox(&mut self.settings.show_key_labels, tr("Key labels"))
+                        .on_hover_text(tr("Show the computer key that plays each piano key"))
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::Slider::new(
+                                &mut self.settings.keyboard_zoom,
+                                1.0..=MAX_KEYBOARD_ZOOM,
+                            )
+                            .text(tr("Zoom")),
+                        )
+                        .on_hover_text(tr("Show fewer, larger keys; scroll or use the strip below"))
+                        .changed();
+                });
+                changed |= scale_controls(ui, &mut self.settings.scale);
+                changed |= drone_controls(
+                    ui,
+                    &mut self.settings.drone,
+                    self.settings.scale,
+                    self.settings.octave_naming,
+                );
+                changed |= theory_controls(
+                    ui,
+                    &mut self.settings.theory,
+                    self.settings.scale,
+                    self.played.notes(),
+                    self.settings.octave_naming,
+                );
+                let presets = all_presets(&self.settings.user_presets);
+                split_controls(ui, shared, &presets, self.settings.octave_naming);
+                layer_controls(ui, shared, &presets);
+                let reach = KeyboardReach::new(
+                    self.settings.active_keymap(),
+                    self.octave_offset,
+                    self.settings.show_key_labels,
+                );
+                let piano = draw_piano(
+                    ui,
+                    ctx,
+                    shared,
+                    &mut self.mouse_note,
+                    &self.settings,
+                    &reach,
+                    &mut self.piano_scroll,
+                );
+                changed |= ribbon(ui, shared, &mut self.settings, &mut self.ribbon_note);
+                let zoom = ui.input(|i| i.zoom_delta());
+                if piano.hovered() && zoom != 1.0 {
+                    self.settings.keyboard_zoom =
+                        (self.settings.keyboard_zoom * zoom).clamp(1.0, MAX_KEYBOARD_ZOOM);
+                    changed = true;
+                }
+                if self.keymap_editor.open
+                    && let Some(note) = self.mouse_note
+                {
+                    self.keymap_editor.learn_note = Some(note);
+                }
+                changed
+            }
+            PanelKind::XyPad => xy_pad(ui, &mut self.settings, &mut shared.params),
+            PanelKind::Tone => {
+                tone_controls(ui, shared);
+                if matches!(
+                    shared.params.instrument,
+                    InstrumentKind::Sampler | InstrumentKind::Granular
+                ) {
+                    sampler_controls(
+                        ui,
+                        &mut shared.params,
+                        &mut self.samples,
+                        &mut self.sample_input,
+                        self.settings.octave_naming,
+                    );
+                } else if shared.params.instrument == InstrumentKind::Drums {
+                    drum_kit_controls(
+                        ui,
+                        &mut shared.params,
+                        &mut self.samples,
+                        &mut self.sample_input,
+                        self.settings.octave_naming,
+                    );
+                } else if shared.params.instrument == InstrumentKind::SoundFont {
+                    soundfont_controls(
+                        ui,
+                        &mut shared.params,
+                        &mut self.soundfonts,
+                        &mut self.soundfont_input,
+                    );
+                } else if shared.params.waveform == Waveform::Wavetable
+                    && shared.params.instrument != InstrumentKind::Additive
+                {
+                    wavetable_controls(
+                        ui,
+                        &mut shared.params,
+                        &mut self.wavetables,
+                        &mut self.wavetable_input,
+                    );
+                }
+                false
+            }
+            PanelKind::Motion => {
+                modulation_controls(ui, shared);
+                false
+            }
+            PanelKind::Eq => {
+                eq_controls(ui, shared);
+                false
+            }
+            PanelKind::Harmonics => {
+                harmonics_controls(ui, &mut shared.params);
+                false
+            }
+            PanelKind::Granular => {
+                granular_controls(ui, &mut shared.params);
+                false
+            }
+            PanelKind::Morph => morph_controls(ui, shared, &mut self.settings, &mut self.morph),
+            PanelKind::Progressions => {
+                progression_controls(ui, &mut self.settings, &mut self.chord_player)
+            }
+            PanelKind::Mixer => {
+                let presets = all_presets(&self.settings.user_presets);
+                mixer_controls(ui, shared, &presets, self.settings.octave_naming);
+                false
+            }
+            PanelKind::Stats => {
+                practice_stats(ui, &self.practice);
+                false
+            }
+            PanelKind::Diagnostics => {
+                let stats = self.engine_stats(shared);
+                diagnostics_controls(ui, &mut self.diagnostics, &stats);
+                false
+            }
+            PanelKind::Layout => layout_controls(ui, &mut self.settings),
+        }
+    }
+
+    fn engine_stats(&self, shared: &SynthShared) -> EngineStats {
+        let watchdog = &self._audio.watchdog;
+        EngineStats {
+            output_device: self._audio.device_name.clone(),
+            input_device: self
+                .mic_input
+                .as_ref()
+                .map(|input| input.device_name.clone()),
+            sample_rate: self._audio.sample_rate,
+            buffer_frames: watchdog.buffer_frames(),
+            active_voices: shared.active_voices(),
+            dsp_load: watchdog.load(),
+            xruns: watchdog.counts(),
         }
     }
 
@@ -67,730 +684,4910 @@ impl SynthApp {
         let audio = SynthAudio::new_with_device(
             Arc::clone(&self.shared),
             Arc::clone(&self.scope),
+            Arc::clone(&self.mic),
             target.as_deref(),
         )?;
         self.output_devices = list_output_device_names();
         self.settings.output_device = Some(audio.device_name.clone());
+        self.diagnostics.record(
+            EventKind::Device,
+            format!(
+                "Output switched to {} at {} Hz",
+                audio.device_name, audio.sample_rate
+            ),
+        );
         self._audio = audio;
+        self.xruns_logged = XrunCounts::default();
         self.audio_error = None;
         Ok(())
     }
+
+    /// Tunes the keyboard to the settings' Scala file, or to their
+    /// temperament when there is none or it can't be read.
+    fn apply_tuning(&mut self) {
+        self.tuning_error = None;
+        let root = self.settings.tuning_root;
+        let temperament = self.settings.temperament.tuning(root);
+        let tuning: Arc<dyn Tuning> = match self.settings.tuning_file.as_deref() {
+            None => temperament,
+            Some(path) => match ScalaScale::load(path) {
+                Ok(scale) => Arc::new(ScalaTuning::new(scale, root)),
+                Err(err) => {
+                    self.tuning_error = Some(err);
+                    temperament
+                }
+            },
+        };
+        self.tuning_name = tuning.description().to_string();
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.tuning = tuning;
+        }
+    }
+
+    /// Opens, reopens or closes the microphone to match the settings.
+    fn switch_input_device(&mut self) {
+        // Close the old stream first so the device is free to reopen.
+        self.mic_input = None;
+        self.mic_error = None;
+        if !self.settings.mic_enabled {
+            return;
+        }
+        match MicInput::open(self.settings.input_device.as_deref(), Arc::clone(&self.mic)) {
+            Ok(input) => {
+                let opened = format!("Input {}", input.device_name);
+                self.diagnostics.record(EventKind::Device, opened);
+                self.mic_input = Some(input);
+            }
+            Err(err) => {
+                self.diagnostics.record(EventKind::AudioError, err.clone());
+                self.mic_error = Some(err);
+            }
+        }
+    }
+
+    /// Carries out what parameter menus asked of MIDI learn: maps the next
+    /// control change onto the armed parameter, or forgets a parameter's CCs.
+    fn update_midi_learn(&mut self, ctx: &egui::Context) {
+        let mut learn = ctx
+            .data(|data| data.get_temp::<MidiLearn>(midi_learn_id()))
+            .unwrap_or_default();
+        let mut changed = false;
+        if let Some(param) = learn.forget.take() {
+            self.settings
+                .cc_mappings
+                .retain(|mapping| mapping.param != param);
+            changed = true;
+        }
+        match (&self.midi_input, learn.armed) {
+            (Some(midi), Some(param)) => {
+                let cc = midi.take_last_cc();
+                // A control moved before learning started doesn't count.
+                if learn.just_armed {
+                    learn.just_armed = false;
+                } else if let Some(cc) = cc {
+                    let mappings = &mut self.settings.cc_mappings;
+                    mappings.retain(|mapping| mapping.cc != cc && mapping.param != param);
+                    mappings.push(CcMapping { cc, param });
+                    learn.armed = None;
+                    changed = true;
+                }
+            }
+            (None, Some(_)) => learn.armed = None,
+            _ => {}
+        }
+        if changed {
+            if let Some(midi) = &self.midi_input {
+                midi.set_mappings(&self.settings.cc_mappings);
+            }
+            self.saver.save(&self.settings);
+        }
+        learn.mappings.clone_from(&self.settings.cc_mappings);
+        learn.connected = self.midi_input.is_some();
+        ctx.data_mut(|data| data.insert_temp(midi_learn_id(), learn));
+    }
+
+    /// Connects, reconnects or disconnects MIDI input to match the settings.
+    /// Without a chosen port and with none plugged in there is nothing to
+    /// report; most players don't own a controller.
+    fn switch_midi_input(&mut self) {
+        self.midi_input = None;
+        self.midi_error = None;
+        let port = self.settings.midi_port.as_deref();
+        if !self.settings.midi_enabled || (port.is_none() && self.midi_ports.is_empty()) {
+            return;
+        }
+        let shared = Arc::clone(&self.shared);
+        match MidiInput::open(port, shared, &self.settings.cc_mappings) {
+            Ok(input) => {
+                let opened = format!("MIDI input {}", input.port_name);
+                self.diagnostics.record(EventKind::Device, opened);
+                self.midi_input = Some(input);
+            }
+            Err(err) => {
+                self.diagnostics.record(EventKind::Device, err.clone());
+                self.midi_error = Some(err);
+            }
+        }
+    }
 }
 
 impl eframe::App for SynthApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         ctx.request_repaint_after(std::time::Duration::from_millis(16));
-        let keyboard_events = collect_keyboard_events(ctx);
-
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.add_space(4.0);
-            let mut theme_changed = false;
-            let mut reset_requested = false;
-            let mut device_changed = false;
-            let mut layout_changed = false;
-            let mut keybinds_changed = false;
-
-            egui::ScrollArea::vertical()
-                .auto_shrink([false; 2])
-                .show(ui, |ui| {
-                    ui.with_layout(Layout::top_down(egui::Align::LEFT), |ui| {
-                        ui.horizontal_wrapped(|ui| {
-                            ui.strong("Angel Synth");
-                            ui.label("FL-style minimal layout · Left/Right = octave");
-                            ui.separator();
-                            if ui.button("Settings").clicked() {
-                                self.settings_open = true;
-                            }
-                            ui.separator();
-                            theme_changed = theme_selector(ui, ctx, &mut self.settings);
-                            if ui.button("Reset sound").clicked() {
-                                reset_requested = true;
-                            }
-                            ui.separator();
-                            device_changed = output_selector(
-                                ui,
-                                &self.output_devices,
-                                &mut self.settings.output_device,
-                                &mut self.audio_error,
-                            );
-                            ui.separator();
-                            keybinds_changed = keybind_selector(ui, &mut self.settings);
-                            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
-                                if let Some(key) = self.last_key {
-                                    ui.label(format!("Last key: {:?}", key));
-                                } else {
-                                    ui.label("Play with keyboard or mouse");
-                                }
-                            });
-                        });
-                    });
-
-                    ui.add_space(6.0);
-
-                    let mut shared = self.shared.lock().expect("Synth parameters poisoned");
-                    if reset_requested {
-                        shared.params = SynthParams::default();
-                    }
+        for line in self._audio.watchdog.describe_new(&mut self.xruns_logged) {
+            self.diagnostics.record(EventKind::Xrun, line);
+        }
+        let stream_errors = self._audio.watchdog.take_errors().into_iter();
+        let input_errors = self.mic_input.iter().flat_map(MicInput::take_errors);
+        for err in stream_errors.chain(input_errors) {
+            self.diagnostics.record(EventKind::AudioError, err);
+        }
+        let scheme = self.settings.keybinds;
+        let mut keyboard_events = collect_keyboard_events(ctx);
+        if self.ui_focus {
+            // Presses belong to the focused widget now; releases still pass so
+            // notes held when the mode was switched on don't hang.
+            keyboard_events.retain(|(key, pressed, modifiers)| {
+                !pressed || action_for(scheme, *key, *modifiers) == Some(Action::ToggleUiFocus)
+            });
+        } else if !ctx.wants_keyboard_input() {
+            // In play mode keys are notes and shortcuts, so a clicked slider
+            // must not also react to the arrows.
+            ctx.memory_mut(|memory| {
+                if let Some(id) = memory.focused() {
+                    memory.surrender_focus(id);
+                }
+            });
+        }
+        if self
+            .keymap_editor
+            .capture(&mut keyboard_events, &mut self.settings.keymaps)
+        {
+            self.saver.save(&self.settings);
+        }
+        self.update_midi_learn(ctx);
+        let dt = ctx.input(|input| input.stable_dt);
+        if let Ok(mut shared) = self.shared.lock() {
+            self.gamepad
+                .poll(&mut shared, self.octave_offset, self.key_velocity, dt);
+            // Patches from presets, sessions and pasted codes arrive with
+            // only the paths of their wavetable, sample and SoundFont. The
+            // caches read those on their workers, so this only attaches
+            // files already read and never holds the lock over the disk.
+            let shared = &mut *shared;
+            let mut patches = vec![&mut shared.params];
+            patches.extend(shared.split.as_mut().map(|split| &mut split.lower));
+            patches.extend(shared.layer.as_mut().map(|layer| &mut layer.params));
+            patches.extend(shared.added_parts.iter_mut().map(|part| &mut part.params));
+            for params in patches {
+                self.wavetables.resolve(params);
+                self.samples.resolve(params);
+                self.soundfonts.resolve(params);
+            }
+        }
+        let presets = all_presets(&self.settings.user_presets);
+        let (was_holding, was_soft, held_notes) = self
+            .shared
+            .lock()
+            .map(|shared| (shared.is_holding(), shared.soft_pedal, shared.held_notes()))
+            .unwrap_or_default();
+        self.played.update(&held_notes);
+        self.practice
+            .update(&held_notes, self.settings.scale.as_ref(), dt);
 
-                    handle_keyboard_events(
-                        &keyboard_events,
-                        &mut shared,
-                        &mut self.last_key,
-                        &mut self.octave_offset,
-                    );
+        let mut preset_chosen = false;
+        let mut pasted_patch = None;
+        let mut theme_changed = false;
+        let mut reset_requested = false;
+        let mut panic_requested = false;
+        let mut hold = was_holding;
+        let mut soft = was_soft;
+        let mut device_changed = false;
+        let mut keybinds_changed = false;
+        let mut bounce_changed = false;
+        let mut panels_changed = false;
 
-                    fl_card(
+        egui::TopBottomPanel::top("header").show(ctx, |ui| {
+            ui.add_space(4.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.strong("Angel Synth");
+                ui.label(tr_fmt(
+                    "FL-style minimal layout · {down}/{up} = octave",
+                    &[
+                        ("down", &chord_label(scheme, Action::OctaveDown)),
+                        ("up", &chord_label(scheme, Action::OctaveUp)),
+                    ],
+                ));
+                ui.separator();
+                if ui.button(tr("Settings")).clicked() {
+                    self.settings_open = true;
+                }
+                ui.separator();
+                preset_chosen = preset_browser(ui, &presets, &mut self.current_preset);
+                ui.menu_button(tr("Share"), |ui| {
+                    pasted_patch = patch_share_menu(
                         ui,
-                        "Wave Scope",
-                        self.settings.card_padding,
-                        self.settings.card_rounding,
-                        |ui| draw_scope(ui, self.settings.scope_height, &self.scope),
+                        &self.settings.params,
+                        &mut self.patch_code_input,
+                        &mut self.patch_code_error,
                     );
-                    ui.add_space(8.0);
-                    fl_card(
+                })
+                .response
+                .on_hover_text(tr(
+                    "Copy the patch as text for chat, or load one someone sent",
+                ));
+                ui.separator();
+                theme_changed = theme_selector(ui, ctx, &mut self.settings);
+                if ui.button(tr("Reset sound")).clicked() {
+                    reset_requested = true;
+                }
+                if ui
+                    .button(tr("Panic"))
+                    .on_hover_text(tr_fmt(
+                        "All notes off ({shortcut})",
+                        &[("shortcut", &chord_label(scheme, Action::Panic))],
+                    ))
+                    .clicked()
+                {
+                    panic_requested = true;
+                }
+                ui.toggle_value(&mut hold, tr("Hold")).on_hover_text(tr(
+                    "Latch: released notes keep sounding until Hold is turned off",
+                ));
+                ui.toggle_value(&mut soft, tr("Soft")).on_hover_text(tr(
+                    "Soft pedal: notes play gentler and darker while it is on",
+                ));
+                let jam_label = match &self.jam.session {
+                    Some(session) => format!("{} ({})", tr("Jam"), session.peers.len() + 1),
+                    None => tr("Jam").to_string(),
+                };
+                ui.toggle_value(&mut self.jam.open, jam_label)
+                    .on_hover_text(tr(
+                        "Play together with other instances on the local network",
+                    ));
+                ui.toggle_value(&mut self.ear_open, tr("Practice"))
+                    .on_hover_text(tr("Ear training: name or play back intervals and chords"));
+                take_controls(
+                    ui,
+                    &self.mic,
+                    self.mic_input.is_some(),
+                    &self.settings,
+                    &mut self.take_recorder,
+                    &mut self.take_status,
+                );
+                ui.menu_button(tr("Bounce"), |ui| {
+                    bounce_changed = bounce_controls(
                         ui,
-                        "Keyboard",
-                        self.settings.card_padding,
-                        self.settings.card_rounding,
-                        |ui| {
-                            if self.settings_open {
-                                ui.label("Keyboard disabled while settings are open.");
-                            } else {
-                                draw_piano(
-                                    ui,
-                                    ctx,
-                                    &mut shared,
-                                    &mut self.mouse_note,
-                                    self.settings.keyboard_scale,
-                                )
-                            }
-                        },
+                        &self.shared,
+                        &mut self.settings,
+                        &mut self.bounce_status,
                     );
-                    ui.add_space(10.0);
-
-                    layout_changed |= layout_grid(ui, &mut shared, &mut self.settings);
-
-                    let new_params = shared.params.clone();
-                    let params_changed = new_params != self.settings.params;
-                    drop(shared);
+                })
+                .response
+                .on_hover_text(tr(
+                    "Render the notes you are holding to a WAV, tails included",
+                ));
+                ui.toggle_value(&mut self.ui_focus, tr("UI focus"))
+                    .on_hover_text(tr_fmt(
+                        "Use the keyboard for the controls instead of notes: Tab moves between \
+                         them, arrows nudge sliders, Enter or Space presses and opens ({shortcut})",
+                        &[("shortcut", &chord_label(scheme, Action::ToggleUiFocus))],
+                    ));
+                ui.separator();
+                device_changed = output_selector(
+                    ui,
+                    &self.output_devices,
+                    &mut self.settings.output_device,
+                    &mut self.audio_error,
+                );
+                ui.separator();
+                keybinds_changed = keybind_selector(ui, &mut self.settings);
+                ui.separator();
+                level_meter(ui, &self._audio.meters);
+                gain_reduction_readout(ui, &self._audio.meters);
+                loudness_readout(ui, &self._audio.meters);
+                correlation_meter(ui, self._audio.meters.correlation());
+                xrun_readout(ui, &self._audio.watchdog);
+                ui.separator();
+                let velocity_help = if uses_command(scheme) {
+                    tr("Alt+1…9 sets the keyboard velocity. Hold Shift to accent a note.")
+                } else {
+                    tr(
+                        "Alt+1…9 sets the keyboard velocity. Hold Shift to accent a note, Ctrl \
+                        to play it softly.",
+                    )
+                };
+                ui.label(tr_fmt(
+                    "Vel {velocity}",
+                    &[("velocity", &format!("{:.0}", self.key_velocity * 127.0))],
+                ))
+                .on_hover_text(velocity_help);
+                held_notes_readout(ui, &held_notes, self.settings.octave_naming);
+                ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                    param_readout(ui, &self.settings.params);
+                });
+            });
+            if let Some(err) = &self.audio_error {
+                ui.colored_label(Color32::RED, tr_fmt("Audio: {error}", &[("error", err)]));
+            }
+            ui.add_space(4.0);
+        });
 
-                    if device_changed {
-                        if let Err(err) = self.switch_output_device() {
-                            self.audio_error = Some(err);
-                        }
-                    }
+        let shared_handle = Arc::clone(&self.shared);
+        let mut shared = shared_handle.lock().expect("Synth parameters poisoned");
+        shared.set_note_capture(self.jam.session.is_some());
+        shared.scale = self.settings.scale;
+        shared.mic_mode = if self.mic_input.is_some() {
+            self.settings.mic_mode
+        } else {
+            MicMode::Off
+        };
+        let mic_mode = shared.mic_mode;
+        self.pitch_follower.poll(&self.mic, mic_mode, &mut shared);
+        let chords: Vec<Vec<u8>> = self
+            .settings
+            .scale
+            .and_then(|scale| progression(scale, self.settings.progression))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|chord| chord.notes)
+            .collect();
+        self.chord_player
+            .poll(&chords, self.settings.tempo_bpm, &mut shared);
+        let practice_changed = self.ear.poll(&mut shared, &mut self.settings.ear_stats);
+        if let Some(session) = &mut self.jam.session {
+            session.poll(&mut shared);
+        }
+        if reset_requested {
+            shared.params = SynthParams::default();
+        }
+        if hold != was_holding {
+            shared.set_hold(hold);
+        }
+        if soft != was_soft {
+            shared.soft_pedal = soft;
+        }
+        if panic_requested {
+            shared.panic();
+            self.mouse_note = None;
+            self.ribbon_note = None;
+        }
+        if let (true, Some(index)) = (preset_chosen, self.current_preset) {
+            shared.params = presets[index].params.clone();
+        }
+        if let Some(params) = pasted_patch {
+            shared.params = params;
+            self.current_preset = None;
+        }
 
-                    if params_changed
-                        || theme_changed
-                        || device_changed
-                        || layout_changed
-                        || keybinds_changed
-                    {
-                        self.settings.params = new_params;
-                        self.settings.output_device = Some(self._audio.device_name.clone());
-                        self.settings.save(&self.settings_path);
-                    }
+        let actions = handle_keyboard_events(
+            &keyboard_events,
+            scheme,
+            &mut shared,
+            self.octave_offset,
+            &mut self.key_velocity,
+            self.settings.active_keymap(),
+        );
+        for action in actions {
+            match action {
+                Action::OctaveDown => shift_octave(&mut self.octave_offset, -1),
+                Action::OctaveUp => shift_octave(&mut self.octave_offset, 1),
+                Action::PresetPrev => {
+                    step_preset(&mut shared, &presets, &mut self.current_preset, -1);
+                }
+                Action::PresetNext => {
+                    step_preset(&mut shared, &presets, &mut self.current_preset, 1);
+                }
+                Action::Panic => {
+                    shared.panic();
+                    self.mouse_note = None;
+                    self.ribbon_note = None;
+                }
+                Action::ToggleSettings => self.settings_open = !self.settings_open,
+                Action::ResetSound => shared.params = SynthParams::default(),
+                Action::ToggleUiFocus => self.ui_focus = !self.ui_focus,
+                Action::ToggleRecord => toggle_take(
+                    &self.mic,
+                    self.mic_input.is_some(),
+                    &mut self.take_recorder,
+                    &mut self.take_status,
+                ),
+            }
+        }
 
-                    if let Some(err) = &self.audio_error {
-                        ui.colored_label(Color32::RED, format!("Audio: {err}"));
-                    }
+        // Edge docks must be added before the central panel claims the rest.
+        if !self.settings.panels_in(DockSlot::Bottom).is_empty() {
+            let dock = egui::TopBottomPanel::bottom("dock_bottom")
+                .resizable(true)
+                .default_height(self.settings.dock_bottom_height)
+                .show(ctx, |ui| {
+                    panels_changed |= self.dock_contents(ui, ctx, DockSlot::Bottom, &mut shared);
+                });
+            panels_changed |= remember_size(
+                &mut self.settings.dock_bottom_height,
+                dock.response.rect.height(),
+            );
+        }
+        if !self.settings.panels_in(DockSlot::Left).is_empty() {
+            let dock = egui::SidePanel::left("dock_left")
+                .resizable(true)
+                .default_width(self.settings.dock_left_width)
+                .show(ctx, |ui| {
+                    panels_changed |= self.dock_contents(ui, ctx, DockSlot::Left, &mut shared);
                 });
+            panels_changed |= remember_size(
+                &mut self.settings.dock_left_width,
+                dock.response.rect.width(),
+            );
+        }
+        if !self.settings.panels_in(DockSlot::Right).is_empty() {
+            let dock = egui::SidePanel::right("dock_right")
+                .resizable(true)
+                .default_width(self.settings.dock_right_width)
+                .show(ctx, |ui| {
+                    panels_changed |= self.dock_contents(ui, ctx, DockSlot::Right, &mut shared);
+                });
+            panels_changed |= remember_size(
+                &mut self.settings.dock_right_width,
+                dock.response.rect.width(),
+            );
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            panels_changed |= self.dock_contents(ui, ctx, DockSlot::Main, &mut shared);
         });
+        for panel in self.settings.panels_in(DockSlot::Floating) {
+            let mut open = true;
+            egui::Window::new(tr(panel.label()))
+                .id(Id::new(("panel_window", panel)))
+                .open(&mut open)
+                .resizable(true)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        panels_changed |= self.panel_contents(ui, ctx, panel, &mut shared);
+                    });
+                });
+            // Closing a floating panel docks it back into the main area.
+            if !open {
+                self.settings.set_panel_slot(panel, DockSlot::Main);
+                panels_changed = true;
+            }
+        }
 
-        settings_popup(ctx, self);
-    }
-}
+        if self.settings.scope_popped_out {
+            panels_changed |= self.popped_out_scope(ctx);
+        }
 
-fn tone_controls(ui: &mut egui::Ui, shared: &mut SynthShared) {
-    ui.add(egui::Slider::new(&mut shared.params.gain, 0.0..=1.0).text("Master gain"));
-    ui.add(
-        egui::Slider::new(&mut shared.params.attack_seconds, 0.001..=1.0)
-            .logarithmic(true)
-            .text("Attack (s)"),
-    );
-    ui.add(
-        egui::Slider::new(&mut shared.params.decay_seconds, 0.001..=1.5)
-            .logarithmic(true)
-            .text("Decay (s)"),
-    );
-    ui.add(egui::Slider::new(&mut shared.params.sustain_level, 0.0..=1.0).text("Sustain"));
-    ui.add(
-        egui::Slider::new(&mut shared.params.release_seconds, 0.01..=3.0)
-            .logarithmic(true)
-            .text("Release (s)"),
-    );
+        let new_params = shared.params.clone();
+        let params_changed = new_params != self.settings.params;
+        let split_changed = shared.split != self.settings.split;
+        if split_changed {
+            self.settings.split = shared.split.clone();
+        }
+        shared.performance.breath_routing = self.settings.breath;
+        shared.performance.bend_range = self.settings.bend_range;
+        shared.drone = self.settings.drone;
+        let layer_changed = shared.main_mix != self.settings.main_mix
+            || shared.layer != self.settings.layer
+            || shared.added_parts != self.settings.added_parts
+            || shared.buses != self.settings.buses
+            || shared.limiter != self.settings.limiter;
+        if layer_changed {
+            self.settings.main_mix = shared.main_mix;
+            self.settings.layer = shared.layer.clone();
+            self.settings.added_parts = shared.added_parts.clone();
+            self.settings.buses = shared.buses;
+            self.settings.limiter = shared.limiter;
+        }
+        drop(shared);
 
-    ui.horizontal(|ui| {
-        ui.label("Instrument");
-        ComboBox::from_id_source("instrument")
-            .selected_text(shared.params.instrument.label())
-            .show_ui(ui, |ui| {
-                for instrument in InstrumentKind::ALL {
-                    ui.selectable_value(
-                        &mut shared.params.instrument,
-                        instrument,
-                        instrument.label(),
-                    );
-                }
-            });
-    });
+        if device_changed && let Err(err) = self.switch_output_device() {
+            self.diagnostics.record(EventKind::AudioError, err.clone());
+            self.audio_error = Some(err);
+        }
 
-    ui.horizontal(|ui| {
-        ui.label("Waveform");
-        ComboBox::from_id_source("waveform")
-            .selected_text(shared.params.waveform.label())
-            .show_ui(ui, |ui| {
-                for waveform in Waveform::ALL {
-                    ui.selectable_value(&mut shared.params.waveform, waveform, waveform.label());
-                }
-            });
-    });
+        if params_changed
+            || split_changed
+            || layer_changed
+            || theme_changed
+            || device_changed
+            || keybinds_changed
+            || bounce_changed
+            || panels_changed
+            || practice_changed
+        {
+            self.settings.params = new_params;
+            self.settings.output_device = Some(self._audio.device_name.clone());
+            self.saver.save(&self.settings);
+        }
 
-    ui.add(
-        egui::Slider::new(&mut shared.params.filter_cutoff_hz, 80.0..=16_000.0)
-            .logarithmic(true)
-            .text("Filter cutoff (Hz)"),
-    );
-    ui.add(egui::Slider::new(&mut shared.params.filter_resonance, 0.0..=0.95).text("Resonance"));
+        settings_popup(ctx, self);
+        keymap_window(ctx, self);
+        jam_window(ctx, self);
+        ear_window(ctx, self);
+        recovery_prompt(ctx, self);
+        self.autosave_session();
+    }
 }
 
-fn modulation_controls(ui: &mut egui::Ui, shared: &mut SynthShared) {
-    ui.add(
-        egui::Slider::new(&mut shared.params.vibrato_rate_hz, 0.1..=12.0)
-            .logarithmic(true)
-            .text("Vibrato rate (Hz)"),
-    );
-    ui.add(
-        egui::Slider::new(&mut shared.params.vibrato_depth_semitones, 0.0..=1.2)
-            .text("Vibrato depth (semitones)"),
-    );
-    ui.add(
-        egui::Slider::new(&mut shared.params.autotune_amount, 0.0..=1.0)
-            .text("Autotune (0=free,1=hard)"),
-    );
-    ui.add(
-        egui::Slider::new(&mut shared.params.unison_spread_cents, 0.0..=25.0)
-            .text("Unison spread (cents)"),
-    );
-    ui.add(egui::Slider::new(&mut shared.params.noise_mix, 0.0..=0.5).text("Noise mix"));
+impl Drop for SynthApp {
+    fn drop(&mut self) {
+        // A panic unwinds through here too; keep the file so the next launch can
+        // recover. Keep it as well while "Restore session?" is unanswered.
+        if !std::thread::panicking() && self.pending_recovery.is_none() {
+            clear_recovery(&self.recovery_path);
+        }
+        if let Some(stats) = self.practice.take_unsaved() {
+            save_stats(&stats_path(), stats);
+        }
+    }
 }
 
-fn eq_controls(ui: &mut egui::Ui, shared: &mut SynthShared) {
-    ui.columns(2, |columns| {
-        columns[0].add(
-            egui::Slider::new(&mut shared.params.eq_low_gain_db, -12.0..=12.0)
-                .text("Low gain (dB)"),
-        );
-        columns[0].add(
-            egui::Slider::new(&mut shared.params.eq_low_freq_hz, 40.0..=400.0)
-                .logarithmic(true)
-                .text("Low cutoff (Hz)"),
-        );
+fn recovery_prompt(ctx: &egui::Context, app: &mut SynthApp) {
+    let Some(session) = &app.pending_recovery else {
+        return;
+    };
+    let mut restore = false;
+    let mut discard = false;
+    egui::Window::new(tr("Restore session?"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label(tr("Angel didn't shut down cleanly last time."));
+            ui.label(tr_fmt(
+                "An autosaved session from {minutes} min ago is available.",
+                &[("minutes", &(session.age_seconds() / 60))],
+            ));
+            ui.horizontal(|ui| {
+                restore = ui.button(tr("Restore")).clicked();
+                discard = ui.button(tr("Discard")).clicked();
+            });
+        });
+    if restore {
+        if let Some(session) = app.pending_recovery.take() {
+            app.restore_session(session);
+        }
+    } else if discard {
+        app.pending_recovery = None;
+        clear_recovery(&app.recovery_path);
+    }
+}
 
-        columns[1].add(
-            egui::Slider::new(&mut shared.params.eq_high_gain_db, -12.0..=12.0)
-                .text("High gain (dB)"),
-        );
-        columns[1].add(
-            egui::Slider::new(&mut shared.params.eq_high_freq_hz, 2_000.0..=16_000.0)
-                .logarithmic(true)
-                .text("High cutoff (Hz)"),
-        );
+/// Share of the editor width the attack, decay and release can each stretch
+/// across.
+const ENVELOPE_STAGE_WIDTH: f32 = 0.24;
+/// Share of the editor width the delay and hold can each stretch across.
+const ENVELOPE_PAUSE_WIDTH: f32 = 0.1;
+/// Fixed-width plateau standing in for the held sustain.
+const ENVELOPE_SUSTAIN_WIDTH: f32 = 0.08;
+/// Line segments drawn per stage, enough for the curved shapes to look smooth.
+const ENVELOPE_CURVE_POINTS: usize = 16;
+/// Share of the normal slider travel a Shift-drag covers.
+const FINE_DRAG_SCALE: f32 = 0.1;
+/// How far a finger slides up or down to turn a control through its whole range.
+const KNOB_DRAG_DISTANCE: f32 = 240.0;
+
+/// Slider for a synth parameter with the shared control ergonomics: Shift-drag
+/// for fine adjustment, double-click to reset to the default, and the value
+/// box takes typed input. Under a finger it works like a knob instead: slide
+/// up or down from anywhere on it to turn it, without it jumping to where
+/// the finger landed.
+fn param_slider(ui: &mut egui::Ui, params: &mut SynthParams, id: ParamId) -> egui::Response {
+    let info = id.info();
+    let before = id.get(params);
+    let mut slider = egui::Slider::new(id.get_mut(params), info.min..=info.max)
+        .logarithmic(info.logarithmic)
+        .text(info.label);
+    if !info.unit.is_empty() {
+        slider = slider.suffix(format!(" {}", info.unit));
+    }
+    let mut response = ui.add(slider).on_hover_text(param_help(id));
+    let value = id.get_mut(params);
+    let knob_start = response.id.with("knob_start");
+    if response.drag_started() && ui.input(|input| input.any_touches()) {
+        ui.data_mut(|data| data.insert_temp(knob_start, before));
+    }
+    let knob = response
+        .dragged()
+        .then(|| ui.data(|data| data.get_temp::<f32>(knob_start)))
+        .flatten();
+    if let Some(start) = knob {
+        let rise = ui.input(|input| {
+            let origin = input.pointer.press_origin();
+            origin
+                .zip(input.pointer.interact_pos())
+                .map_or(0.0, |(origin, pos)| origin.y - pos.y)
+        });
+        *value = id.denormalize(id.normalize(start) + rise / KNOB_DRAG_DISTANCE);
+        response.mark_changed();
+    } else if response.dragged() && ui.input(|input| input.modifiers.shift) {
+        let travel = response.drag_delta().x / ui.spacing().slider_width;
+        *value = id.denormalize(id.normalize(before) + travel * FINE_DRAG_SCALE);
+        response.mark_changed();
+    }
+    if response.drag_stopped() {
+        ui.data_mut(|data| data.remove::<f32>(knob_start));
+    }
+    if response.double_clicked() {
+        *value = id.default_value();
+        response.mark_changed();
+    }
+    if param_context_menu(&response, params, id) {
+        response.mark_changed();
+    }
+    if response.changed() {
+        touch_param(ui, id);
+    }
+    response
+}
+
+/// Tooltip text: what the parameter does and the range its control covers.
+fn param_help(id: ParamId) -> String {
+    let info = id.info();
+    let range = tr_fmt(
+        "Range: {min} to {max}",
+        &[("min", &id.format(info.min)), ("max", &id.format(info.max))],
+    );
+    format!("{}\n{range}", id.description())
+}
+
+fn last_touched_id() -> Id {
+    Id::new("last_touched_param")
+}
+
+/// MIDI learn as the parameter menus see it, kept in egui's temp storage
+/// like the parameter clipboard; `SynthApp::update_midi_learn` acts on it.
+#[derive(Clone, Default)]
+struct MidiLearn {
+    /// Parameter the next control change gets mapped onto.
+    armed: Option<ParamId>,
+    just_armed: bool,
+    /// Parameter whose CC mappings are to be dropped.
+    forget: Option<ParamId>,
+    /// Copies of the settings' mappings and whether MIDI input is open.
+    mappings: Vec<CcMapping>,
+    connected: bool,
+}
+
+fn midi_learn_id() -> Id {
+    Id::new("midi_learn")
+}
+
+/// Records `id` as the parameter shown in the header readout.
+fn touch_param(ui: &egui::Ui, id: ParamId) {
+    ui.data_mut(|data| data.insert_temp(last_touched_id(), id));
+}
+
+/// Name and exact value of the last parameter moved from any control, with
+/// the MIDI CCs mapped onto it, and the parameter MIDI learn is waiting on.
+fn param_readout(ui: &mut egui::Ui, params: &SynthParams) {
+    let learn = ui
+        .data(|data| data.get_temp::<MidiLearn>(midi_learn_id()))
+        .unwrap_or_default();
+    if let Some(armed) = learn.armed {
+        ui.weak(format!("{} · {}", tr("MIDI learn"), armed.label()));
+    }
+    match ui.data(|data| data.get_temp::<ParamId>(last_touched_id())) {
+        Some(id) => {
+            let mut text = format!("{}: {}", id.label(), id.format(id.get(params)));
+            for mapping in learn.mappings.iter().filter(|mapping| mapping.param == id) {
+                text.push_str(&format!(" · CC {}", mapping.cc));
+            }
+            ui.label(text);
+        }
+        None => {
+            ui.label(tr("Play with keyboard or mouse"));
+        }
+    }
+}
+
+/// Right-click menu shared by parameter controls. Copied values live in egui's
+/// temp storage so they can be pasted onto any parameter (clamped to its
+/// range), and also go to the system clipboard as text. MIDI learn maps the
+/// next knob or fader moved onto the parameter. Returns true when the value
+/// changed.
+fn param_context_menu(response: &egui::Response, params: &mut SynthParams, id: ParamId) -> bool {
+    let clipboard = Id::new("param_clipboard");
+    let mut changed = false;
+    response.context_menu(|ui| {
+        ui.label(id.label());
+        ui.separator();
+        if ui.button(tr("Reset to default")).clicked() {
+            *id.get_mut(params) = id.default_value();
+            changed = true;
+            ui.close_menu();
+        }
+        if ui.button(tr("Copy value")).clicked() {
+            let value = id.get(params);
+            ui.data_mut(|data| data.insert_temp(clipboard, value));
+            ui.output_mut(|output| output.copied_text = value.to_string());
+            ui.close_menu();
+        }
+        let copied = ui.data(|data| data.get_temp::<f32>(clipboard));
+        if ui
+            .add_enabled(copied.is_some(), egui::Button::new(tr("Paste value")))
+            .clicked()
+        {
+            if let Some(value) = copied {
+                let info = id.info();
+                *id.get_mut(params) = value.clamp(info.min, info.max);
+                changed = true;
+            }
+            ui.close_menu();
+        }
+        ui.separator();
+        midi_learn_menu(ui, id);
     });
+    changed
+}
 
-    ui.add(
-        egui::Slider::new(&mut shared.params.eq_mid_gain_db, -12.0..=12.0).text("Mid gain (dB)"),
+fn midi_learn_menu(ui: &mut egui::Ui, id: ParamId) {
+    let mut learn = ui
+        .data(|data| data.get_temp::<MidiLearn>(midi_learn_id()))
+        .unwrap_or_default();
+    if learn.armed == Some(id) {
+        ui.weak(tr("Move a knob or fader…"));
+        if ui.button(tr("Cancel MIDI learn")).clicked() {
+            learn.armed = None;
+            ui.close_menu();
+        }
+    } else if ui
+        .add_enabled(learn.connected, egui::Button::new(tr("MIDI learn")))
+        .on_disabled_hover_text(tr("Open a MIDI input in Settings first"))
+        .clicked()
+    {
+        learn.armed = Some(id);
+        learn.just_armed = true;
+        ui.close_menu();
+    }
+    let mapped: Vec<String> = learn
+        .mappings
+        .iter()
+        .filter(|mapping| mapping.param == id)
+        .map(|mapping| format!("CC {}", mapping.cc))
+        .collect();
+    if !mapped.is_empty()
+        && ui
+            .button(format!(
+                "{} ({})",
+                tr("Forget MIDI mapping"),
+                mapped.join(", ")
+            ))
+            .clicked()
+    {
+        learn.forget = Some(id);
+        ui.close_menu();
+    }
+    ui.data_mut(|data| data.insert_temp(midi_learn_id(), learn));
+}
+
+/// Draggable envelope graph: the attack peak, the decay/sustain corner and the
+/// release end can be dragged directly, over the same ranges as the sliders.
+/// Delay and hold show as flat stretches before and after the attack.
+fn envelope_editor(ui: &mut egui::Ui, params: &mut SynthParams) {
+    let desired = egui::vec2(ui.available_width().max(200.0), 80.0);
+    let (rect, response) = ui.allocate_exact_size(desired, egui::Sense::hover());
+    describe(&response, egui::WidgetType::Other, || {
+        format!(
+            "Envelope: delay {}, attack {}, hold {}, decay {}, sustain {}, release {}",
+            ParamId::EnvDelay.format(params.delay_seconds),
+            ParamId::Attack.format(params.attack_seconds),
+            ParamId::Hold.format(params.hold_seconds),
+            ParamId::Decay.format(params.decay_seconds),
+            ParamId::Sustain.format(params.sustain_level),
+            ParamId::Release.format(params.release_seconds),
+        )
+    });
+    let zone = rect.width() * ENVELOPE_STAGE_WIDTH;
+    let pause_zone = rect.width() * ENVELOPE_PAUSE_WIDTH;
+    let y_for = |level: f32| egui::lerp(rect.y_range(), 1.0 - level.clamp(0.0, 1.0));
+
+    let delay_x = rect.min.x + ParamId::EnvDelay.normalize(params.delay_seconds) * pause_zone;
+    let attack_x = delay_x + ParamId::Attack.normalize(params.attack_seconds) * zone;
+    let hold_x = attack_x + ParamId::Hold.normalize(params.hold_seconds) * pause_zone;
+    let decay_x = hold_x + ParamId::Decay.normalize(params.decay_seconds) * zone;
+    let sustain_end_x = decay_x + rect.width() * ENVELOPE_SUSTAIN_WIDTH;
+    let release_x = sustain_end_x + ParamId::Release.normalize(params.release_seconds) * zone;
+
+    let handle = |id: &str, center: egui::Pos2| {
+        let hit = egui::Rect::from_center_size(center, egui::vec2(14.0, 14.0));
+        let response = ui.interact(hit, ui.id().with(("envelope", id)), egui::Sense::drag());
+        describe(&response, egui::WidgetType::Slider, || {
+            format!("Envelope {id} handle")
+        });
+        response
+    };
+    let attack = handle("attack", egui::pos2(attack_x, y_for(1.0)));
+    let decay = handle("decay", egui::pos2(decay_x, y_for(params.sustain_level)));
+    let release = handle("release", egui::pos2(release_x, y_for(0.0)));
+
+    if let Some(pos) = attack.interact_pointer_pos().filter(|_| attack.dragged()) {
+        params.attack_seconds = ParamId::Attack.denormalize((pos.x - delay_x) / zone);
+        touch_param(ui, ParamId::Attack);
+    }
+    if let Some(pos) = decay.interact_pointer_pos().filter(|_| decay.dragged()) {
+        params.decay_seconds = ParamId::Decay.denormalize((pos.x - hold_x) / zone);
+        touch_param(ui, ParamId::Decay);
+        params.sustain_level = (1.0 - (pos.y - rect.min.y) / rect.height()).clamp(0.0, 1.0);
+    }
+    if let Some(pos) = release.interact_pointer_pos().filter(|_| release.dragged()) {
+        params.release_seconds = ParamId::Release.denormalize((pos.x - sustain_end_x) / zone);
+        touch_param(ui, ParamId::Release);
+    }
+
+    let painter = ui.painter_at(rect.expand(6.0));
+    painter.rect_filled(rect, Rounding::same(4.0), ui.visuals().extreme_bg_color);
+    let sustain_y = y_for(params.sustain_level);
+    let curve = params.env_curve;
+    let mut outline = Vec::new();
+    let mut stage = |from: egui::Pos2, to: egui::Pos2, rising: bool| {
+        outline.extend((0..=ENVELOPE_CURVE_POINTS).map(|point| {
+            let progress = point as f32 / ENVELOPE_CURVE_POINTS as f32;
+            egui::pos2(
+                egui::lerp(from.x..=to.x, progress),
+                egui::lerp(from.y..=to.y, curve.shape(progress, rising)),
+            )
+        }));
+    };
+    stage(
+        egui::pos2(rect.min.x, y_for(0.0)),
+        egui::pos2(delay_x, y_for(0.0)),
+        true,
     );
-    ui.add(
-        egui::Slider::new(&mut shared.params.eq_mid_freq_hz, 200.0..=4_000.0)
-            .logarithmic(true)
-            .text("Mid freq (Hz)"),
+    stage(
+        egui::pos2(delay_x, y_for(0.0)),
+        egui::pos2(attack_x, y_for(1.0)),
+        true,
+    );
+    stage(
+        egui::pos2(attack_x, y_for(1.0)),
+        egui::pos2(hold_x, y_for(1.0)),
+        false,
+    );
+    stage(
+        egui::pos2(hold_x, y_for(1.0)),
+        egui::pos2(decay_x, sustain_y),
+        false,
     );
-    ui.add(egui::Slider::new(&mut shared.params.eq_mid_q, 0.3..=2.5).text("Mid Q"));
+    stage(
+        egui::pos2(sustain_end_x, sustain_y),
+        egui::pos2(release_x, y_for(0.0)),
+        false,
+    );
+    painter.add(egui::Shape::line(outline, Stroke::new(2.0, ACCENT)));
+    for (response, center) in [
+        (&attack, egui::pos2(attack_x, y_for(1.0))),
+        (&decay, egui::pos2(decay_x, sustain_y)),
+        (&release, egui::pos2(release_x, y_for(0.0))),
+    ] {
+        let radius = if response.hovered() || response.dragged() {
+            6.0
+        } else {
+            4.0
+        };
+        painter.circle_filled(center, radius, ui.visuals().strong_text_color());
+    }
 }
 
-fn draw_scope(ui: &mut egui::Ui, height: f32, scope: &Arc<Mutex<ScopeBuffer>>) {
-    let desired = egui::vec2(ui.available_width().max(200.0), height);
-    let (rect, _) = ui.allocate_exact_size(desired, egui::Sense::hover());
-    let painter = ui.painter_at(rect);
-    painter.rect(
-        rect,
-        Rounding::same(6.0),
-        ui.visuals().faint_bg_color,
-        Stroke::new(1.0, ui.visuals().weak_text_color()),
-    );
+fn tone_controls(ui: &mut egui::Ui, shared: &mut SynthShared) {
+    param_slider(ui, &mut shared.params, ParamId::Gain);
+    envelope_editor(ui, &mut shared.params);
+    ui.horizontal(|ui| {
+        ui.label(tr("Curve"));
+        ComboBox::from_id_source("env_curve")
+            .selected_text(shared.params.env_curve.label())
+            .show_ui(ui, |ui| {
+                for curve in EnvCurve::ALL {
+                    ui.selectable_value(&mut shared.params.env_curve, curve, curve.label());
+                }
+            })
+            .response
+            .on_hover_text(
+                tr("Shape of the envelope stages: exponential creeps in and tails off naturally, logarithmic snaps up and holds before it falls"),
+            );
+    });
+    param_slider(ui, &mut shared.params, ParamId::EnvDelay);
+    param_slider(ui, &mut shared.params, ParamId::Attack);
+    param_slider(ui, &mut shared.params, ParamId::Hold);
+    param_slider(ui, &mut shared.params, ParamId::Decay);
+    param_slider(ui, &mut shared.params, ParamId::Sustain);
+    param_slider(ui, &mut shared.params, ParamId::Release);
+
+    ui.horizontal(|ui| {
+        ui.label(tr("Instrument"));
+        ComboBox::from_id_source("instrument")
+            .selected_text(shared.params.instrument.label())
+            .show_ui(ui, |ui| {
+                for instrument in InstrumentKind::ALL {
+                    ui.selectable_value(
+                        &mut shared.params.instrument,
+                        instrument,
+                        instrument.label(),
+                    );
+                }
+            })
+            .response
+            .on_hover_text(tr("Character added to the waveform: Bass adds a sub-octave, Lead an overtone and drive"));
+    });
 
-    match scope.lock() {
-        Ok(buffer) => {
-            let data = buffer.snapshot();
-            if data.len() >= 2 {
-                let len = data.len().saturating_sub(1).max(1);
-                let mut points = Vec::with_capacity(data.len());
-                for (i, sample) in data.iter().enumerate() {
-                    let t = i as f32 / len as f32;
-                    let x = egui::lerp(rect.x_range(), t);
-                    let norm = (*sample + 1.0) * 0.5;
-                    let y = egui::lerp(rect.y_range(), 1.0 - norm);
-                    points.push(egui::pos2(x, y));
+    ui.horizontal(|ui| {
+        ui.label(tr("Voices"));
+        ComboBox::from_id_source("voice_mode")
+            .selected_text(shared.params.voice_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in VoiceMode::ALL {
+                    ui.selectable_value(&mut shared.params.voice_mode, mode, mode.label());
                 }
-                painter.add(egui::Shape::line(
-                    points,
-                    Stroke::new(2.0, ui.visuals().selection.bg_fill),
-                ));
-            } else {
-                painter.text(
-                    rect.center(),
-                    Align2::CENTER_CENTER,
-                    "Scope warming up...",
-                    FontId::proportional(14.0),
-                    ui.visuals().weak_text_color(),
+            })
+            .response
+            .on_hover_text(
+                tr("Poly plays chords; Mono plays one note at a time, restarting the envelope on each; Legato glides between overlapping notes without restarting it"),
+            );
+        if shared.params.voice_mode == VoiceMode::Poly {
+            ui.add(
+                egui::DragValue::new(&mut shared.params.polyphony)
+                    .clamp_range(1..=MAX_POLYPHONY)
+                    .suffix(" voices"),
+            )
+            .on_hover_text(tr("Most notes the patch sounds at once; past it, a voice is stolen"));
+            ComboBox::from_id_source("voice_steal")
+                .selected_text(shared.params.voice_steal.label())
+                .show_ui(ui, |ui| {
+                    for steal in VoiceSteal::ALL {
+                        ui.selectable_value(&mut shared.params.voice_steal, steal, steal.label());
+                    }
+                })
+                .response
+                .on_hover_text(
+                    tr("Which voice gives way to a new note once all are in use; released notes always go first"),
+                );
+        } else {
+            ComboBox::from_id_source("note_priority")
+                .selected_text(shared.params.note_priority.label())
+                .show_ui(ui, |ui| {
+                    for priority in NotePriority::ALL {
+                        ui.selectable_value(
+                            &mut shared.params.note_priority,
+                            priority,
+                            priority.label(),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text(
+                    tr("Which held key sounds: the last one pressed, the lowest or the highest"),
                 );
-            }
         }
-        Err(_) => {
-            painter.text(
-                rect.center(),
-                Align2::CENTER_CENTER,
-                "Scope busy...",
-                FontId::proportional(14.0),
-                ui.visuals().weak_text_color(),
-            );
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(tr("Transpose"));
+        ui.add(
+            egui::DragValue::new(&mut shared.params.transpose)
+                .clamp_range(-MAX_TRANSPOSE..=MAX_TRANSPOSE)
+                .suffix(" st"),
+        )
+        .on_hover_text(
+            tr("Semitones every note sounds away from its key; -2 plays along with a B♭ instrument's part in concert pitch"),
+        );
+        ui.label("A4");
+        ui.add(
+            egui::DragValue::new(&mut shared.params.a4_hz)
+                .clamp_range(A4_RANGE)
+                .speed(0.1)
+                .suffix(" Hz"),
+        )
+        .on_hover_text(
+            tr("Reference pitch every note is tuned from: 440 is concert pitch, 415 baroque; nudge it to match a recording"),
+        );
+    });
+
+    if shared.params.instrument == InstrumentKind::Additive {
+        ui.weak(tr(
+            "Additive plays the Harmonics card's bars instead of a waveform.",
+        ));
+    } else if !matches!(
+        shared.params.instrument,
+        InstrumentKind::Sampler
+            | InstrumentKind::SoundFont
+            | InstrumentKind::Granular
+            | InstrumentKind::Drums
+    ) {
+        ui.horizontal(|ui| {
+            ui.label(tr("Waveform"));
+            ComboBox::from_id_source("waveform")
+                .selected_text(shared.params.waveform.label())
+                .show_ui(ui, |ui| {
+                    for waveform in Waveform::ALL {
+                        ui.selectable_value(
+                            &mut shared.params.waveform,
+                            waveform,
+                            waveform.label(),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text(tr(
+                    "Oscillator shape: sine is pure, triangle soft, square hollow, saw bright",
+                ));
+        });
+        if shared.params.waveform == Waveform::Square {
+            param_slider(ui, &mut shared.params, ParamId::PulseWidth);
+            param_slider(ui, &mut shared.params, ParamId::PwmDepth);
+            param_slider(ui, &mut shared.params, ParamId::PwmRate);
         }
     }
+
+    ui.horizontal(|ui| {
+        ui.label(tr("Filter"));
+        ComboBox::from_id_source("filter_mode")
+            .selected_text(shared.params.filter_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in FilterMode::ALL {
+                    ui.selectable_value(&mut shared.params.filter_mode, mode, mode.label());
+                }
+            })
+            .response
+            .on_hover_text(
+                tr("Low-pass darkens, high-pass thins, band-pass keeps a band around the cutoff and notch removes it; ladder is a driven four-pole low-pass that screams at high resonance"),
+            );
+    });
+    param_slider(ui, &mut shared.params, ParamId::FilterCutoff);
+    param_slider(ui, &mut shared.params, ParamId::FilterResonance);
+    if shared.params.filter_mode == FilterMode::Ladder {
+        param_slider(ui, &mut shared.params, ParamId::FilterDrive);
+    }
+    param_slider(ui, &mut shared.params, ParamId::KeyTrackLevel);
+    param_slider(ui, &mut shared.params, ParamId::KeyTrackTone);
+    param_slider(ui, &mut shared.params, ParamId::VelocityLevel);
+    param_slider(ui, &mut shared.params, ParamId::VelocityTone);
 }
 
-fn handle_keyboard_events(
-    events: &[(egui::Key, bool)],
-    shared: &mut SynthShared,
-    last_key: &mut Option<egui::Key>,
-    octave_offset: &mut i32,
+/// Position slider for the Wavetable waveform, the table it plays and a
+/// path field to load another WAV.
+fn wavetable_controls(
+    ui: &mut egui::Ui,
+    params: &mut SynthParams,
+    cache: &mut WavetableCache,
+    input: &mut String,
 ) {
-    for (key, pressed) in events.iter().copied() {
-        *last_key = Some(key);
-        match key {
-            egui::Key::ArrowLeft if pressed => {
-                *octave_offset = (*octave_offset - 1).clamp(-2, 2);
-            }
-            egui::Key::ArrowRight if pressed => {
-                *octave_offset = (*octave_offset + 1).clamp(-2, 2);
-            }
-            _ => {
-                let note = map_key_to_note(key, *octave_offset);
-                if pressed {
-                    shared.press_note(note);
-                } else {
-                    shared.release_note(note);
-                }
-            }
+    param_slider(ui, params, ParamId::WavetablePosition);
+    ui.horizontal(|ui| {
+        ui.label(tr("Table"));
+        let path = params.wavetable.path();
+        let name = Path::new(path)
+            .file_name()
+            .map_or(path.into(), |name| name.to_string_lossy());
+        match (path, params.wavetable.loaded()) {
+            ("", _) => ui.label(tr("Built-in shapes")),
+            (_, Some(table)) => ui.label(format!("{name} ({} frames)", table.frames())),
+            (_, None) if cache.is_loading(path) => ui.weak(format!("{name} (loading…)")),
+            (_, None) => ui.weak(format!("{name} (not loaded)")),
+        }
+        .on_hover_text(if path.is_empty() {
+            "Sine, triangle, saw and square, from one end of the position to the other"
+        } else {
+            path
+        });
+        if !path.is_empty()
+            && ui
+                .small_button(tr("Built-in"))
+                .on_hover_text(tr("Go back to the built-in shapes"))
+                .clicked()
+        {
+            params.wavetable = WavetableFile::default();
+        }
+    });
+    ui.horizontal(|ui| {
+        let field = ui.add(
+            egui::TextEdit::singleline(input)
+                .hint_text(tr("Path to a .wav"))
+                .desired_width(200.0),
+        );
+        let entered = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        let load = ui
+            .button(tr("Load"))
+            .on_hover_text(tr(
+                "A single cycle, or a table of 2048-sample frames such as Serum writes",
+            ))
+            .clicked();
+        if (load || entered) && !input.trim().is_empty() {
+            // Reading it again picks up a file that was fixed or re-exported.
+            cache.forget(input.trim());
+            params.wavetable = WavetableFile::new(input);
+            cache.resolve(params);
         }
+    });
+    if let Some(err) = cache.error(params.wavetable.path()) {
+        ui.colored_label(Color32::RED, err);
     }
 }
 
-fn collect_keyboard_events(ctx: &egui::Context) -> Vec<(egui::Key, bool)> {
-    let mut events = Vec::new();
-    ctx.input(|input| {
-        for event in &input.events {
-            if let egui::Event::Key { key, pressed, .. } = event {
-                events.push((*key, *pressed));
+/// The sample whose root note and loop should be taken once it's read.
+fn sample_settings_id() -> Id {
+    Id::new("sample_settings_pending")
+}
+
+/// The Sampler instrument's WAV, its root note and loop. Loading a file
+/// takes the root note and loop it carries, or the detected pitch.
+fn sampler_controls(
+    ui: &mut egui::Ui,
+    params: &mut SynthParams,
+    cache: &mut SampleCache,
+    input: &mut String,
+    naming: OctaveNaming,
+) {
+    ui.horizontal(|ui| {
+        ui.label(tr("Sample"));
+        let path = params.sample.path();
+        let name = Path::new(path)
+            .file_name()
+            .map_or(path.into(), |name| name.to_string_lossy());
+        match (path, params.sample.loaded()) {
+            ("", _) => ui.weak(tr("None loaded")),
+            (_, Some(sample)) => ui.label(format!("{name} ({:.1} s)", sample.seconds())),
+            (_, None) if cache.is_loading(path) => ui.weak(format!("{name} (loading…)")),
+            (_, None) => ui.weak(format!("{name} (not loaded)")),
+        }
+        .on_hover_text(if path.is_empty() {
+            "Load a WAV below to play it across the keyboard"
+        } else {
+            path
+        });
+        if !path.is_empty()
+            && ui
+                .small_button(tr("Clear"))
+                .on_hover_text(tr("Stop playing the sample"))
+                .clicked()
+        {
+            params.sample = SampleFile::default();
+        }
+    });
+    ui.horizontal(|ui| {
+        let field = ui.add(
+            egui::TextEdit::singleline(input)
+                .hint_text(tr("Path to a .wav"))
+                .desired_width(200.0),
+        );
+        let entered = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        let load = ui
+            .button(tr("Load"))
+            .on_hover_text(tr("Any WAV; only the first minute is kept"))
+            .clicked();
+        if (load || entered) && !input.trim().is_empty() {
+            cache.forget(input.trim());
+            params.sample = SampleFile::new(input);
+            cache.resolve(params);
+            let path = params.sample.path().to_string();
+            ui.data_mut(|data| data.insert_temp(sample_settings_id(), path));
+        }
+    });
+    // The file is read on a worker; its root note and loop are taken once
+    // it arrives, unless another file was picked in the meantime.
+    let waiting = ui.data(|data| data.get_temp::<String>(sample_settings_id()));
+    if let Some(path) = waiting {
+        if path != params.sample.path() || cache.error(&path).is_some() {
+            ui.data_mut(|data| data.remove::<String>(sample_settings_id()));
+        } else if let Some(sample) = params.sample.loaded() {
+            if let Some(root) = sample.root_note() {
+                params.sample_root_note = root;
             }
+            let loop_points = sample.loop_points();
+            params.sample_loop = loop_points.is_some();
+            (params.sample_loop_start, params.sample_loop_end) = loop_points.unwrap_or((0.0, 1.0));
+            ui.data_mut(|data| data.remove::<String>(sample_settings_id()));
+        }
+    }
+    if let Some(err) = cache.error(params.sample.path()) {
+        ui.colored_label(Color32::RED, err);
+    }
+    ui.horizontal(|ui| {
+        ui.label(tr("Root"));
+        ui.add(
+            egui::DragValue::new(&mut params.sample_root_note)
+                .clamp_range(0.0..=127.0)
+                .speed(0.05)
+                .custom_formatter(|note, _| {
+                    let nearest = note.round();
+                    let cents = ((note - nearest) * 100.0).round();
+                    if cents == 0.0 {
+                        note_label(nearest as u8, naming)
+                    } else {
+                        format!("{} {cents:+.0}¢", note_label(nearest as u8, naming))
+                    }
+                }),
+        )
+        .on_hover_text(tr("The note the sample was recorded at; drag to retune it"));
+        if let Some(root) = params.sample.loaded().and_then(|sample| sample.root_note())
+            && ui
+                .small_button(tr("Detect"))
+                .on_hover_text(tr("Go back to the root note found in the file"))
+                .clicked()
+        {
+            params.sample_root_note = root;
         }
     });
-    events
+    // Grains never reach the end, so the loop only matters to the Sampler.
+    if params.instrument == InstrumentKind::Granular {
+        return;
+    }
+    ui.checkbox(&mut params.sample_loop, tr("Loop"))
+        .on_hover_text(tr(
+            "Keep repeating between the loop points instead of stopping at the end",
+        ));
+    if params.sample_loop {
+        ui.add(egui::Slider::new(&mut params.sample_loop_start, 0.0..=1.0).text(tr("Loop start")));
+        ui.add(egui::Slider::new(&mut params.sample_loop_end, 0.0..=1.0).text(tr("Loop end")));
+        params.sample_loop_end = params.sample_loop_end.max(params.sample_loop_start);
+    }
 }
 
-fn map_key_to_note(key: egui::Key, octave_offset: i32) -> u8 {
-    let span = (HIGHEST_NOTE - LOWEST_NOTE + 1) as u8;
-    let idx = egui::Key::ALL
-        .iter()
-        .position(|candidate| *candidate == key)
-        .unwrap_or(0) as u8;
-    let mut note = LOWEST_NOTE + (idx % span);
-    let shift = (octave_offset * 12) as i32;
-    note = (note as i32 + shift).clamp(LOWEST_NOTE as i32, HIGHEST_NOTE as i32) as u8;
-    note
+/// The Drums instrument's kit: the key of each pad, the drum it plays, and
+/// a sample to play in its place.
+fn drum_kit_controls(
+    ui: &mut egui::Ui,
+    params: &mut SynthParams,
+    cache: &mut SampleCache,
+    input: &mut String,
+    naming: OctaveNaming,
+) {
+    egui::Grid::new("drum_kit")
+        .num_columns(3)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            for (index, pad) in params.drum_kit.0.iter_mut().enumerate() {
+                ui.add(
+                    egui::DragValue::new(&mut pad.note)
+                        .clamp_range(0..=127)
+                        .custom_formatter(|n, _| note_label(n as u8, naming)),
+                )
+                .on_hover_text(tr("Drag to move the pad to another key"));
+                ComboBox::from_id_source(("drum_sound", index))
+                    .selected_text(pad.sound.label())
+                    .show_ui(ui, |ui| {
+                        for sound in DrumSound::ALL {
+                            ui.selectable_value(&mut pad.sound, sound, sound.label());
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    let path = pad.sample.path();
+                    if path.is_empty() {
+                        ui.weak(tr("Synthesized"));
+                    } else {
+                        let name = Path::new(path)
+                            .file_name()
+                            .map_or(path.into(), |name| name.to_string_lossy());
+                        match cache.error(path) {
+                            Some(err) => ui.colored_label(Color32::RED, &*name).on_hover_text(err),
+                            None => ui.label(&*name).on_hover_text(path),
+                        };
+                        if ui
+                            .small_button(tr("Clear"))
+                            .on_hover_text(tr("Go back to the synthesized drum"))
+                            .clicked()
+                        {
+                            pad.sample = SampleFile::default();
+                        }
+                    }
+                    if ui
+                        .small_button(tr("Load"))
+                        .on_hover_text(tr("Play the WAV in the path field below on this pad"))
+                        .clicked()
+                        && !input.trim().is_empty()
+                    {
+                        cache.forget(input.trim());
+                        pad.sample = SampleFile::new(input);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+    ui.add(
+        egui::TextEdit::singleline(input)
+            .hint_text(tr("Path to a .wav, then Load on a pad"))
+            .desired_width(200.0),
+    );
+    cache.resolve(params);
+    ui.weak(tr(
+        "Each hit plays out in full, however long its key is held.",
+    ));
 }
 
-fn draw_piano(
+/// The SoundFont instrument's SF2 or SFZ file and which of its presets
+/// plays.
+fn soundfont_controls(
     ui: &mut egui::Ui,
-    ctx: &egui::Context,
-    shared: &mut SynthShared,
-    mouse_note: &mut Option<u8>,
-    scale: f32,
+    params: &mut SynthParams,
+    cache: &mut SoundFontCache,
+    input: &mut String,
 ) {
-    let white_key_count = (LOWEST_NOTE..=HIGHEST_NOTE)
-        .filter(|n| !is_black(*n))
-        .count();
-    let aspect = BASE_WHITE_KEY_HEIGHT / BASE_WHITE_KEY_WIDTH;
-    let available_width = ui.available_width().max(white_key_count as f32 * 12.0);
-    let white_key_width =
-        (available_width / white_key_count as f32).clamp(16.0, 80.0) * scale.clamp(0.7, 1.4);
-    let white_key_height = white_key_width * aspect * scale.clamp(0.7, 1.4);
-    let black_key_width = white_key_width * BLACK_KEY_WIDTH_RATIO;
-    let black_key_height = white_key_height * BLACK_KEY_HEIGHT_RATIO;
-    let desired_size = egui::vec2(white_key_count as f32 * white_key_width, white_key_height);
-    let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click_and_drag());
-    let painter = ui.painter_at(rect);
-
-    let pointer_down = ui.input(|i| i.pointer.primary_down());
-    let pointer_pos = ui.input(|i| i.pointer.interact_pos());
-
-    let mut white_layout = Vec::new();
-    let mut black_layout = Vec::new();
-    let mut white_index = 0usize;
-
-    for note in LOWEST_NOTE..=HIGHEST_NOTE {
-        if !is_black(note) {
-            let x = rect.min.x + white_index as f32 * white_key_width;
-            let key_rect = egui::Rect::from_min_size(
-                egui::pos2(x, rect.min.y),
-                egui::vec2(white_key_width - 1.0, white_key_height),
-            );
-            white_layout.push((note, key_rect));
-            white_index += 1;
+    ui.horizontal(|ui| {
+        ui.label(tr("Font"));
+        let path = params.soundfont.path();
+        let name = Path::new(path)
+            .file_name()
+            .map_or(path.into(), |name| name.to_string_lossy());
+        match (path, params.soundfont.loaded()) {
+            ("", _) => ui.weak(tr("None loaded")),
+            (_, Some(_)) => ui.label(&*name),
+            (_, None) if cache.is_loading(path) => ui.weak(format!("{name} (loading…)")),
+            (_, None) => ui.weak(format!("{name} (not loaded)")),
+        }
+        .on_hover_text(if path.is_empty() {
+            "Load an .sf2 or .sfz below, such as a General MIDI piano"
         } else {
-            let preceding_white = white_index.saturating_sub(1) as f32;
-            let base = rect.min.x + preceding_white * white_key_width;
-            let x = base + white_key_width * 0.7 - black_key_width / 2.0;
-            let key_rect = egui::Rect::from_min_size(
-                egui::pos2(x, rect.min.y),
-                egui::vec2(black_key_width, black_key_height),
-            );
-            black_layout.push((note, key_rect));
+            path
+        });
+        if !path.is_empty()
+            && ui
+                .small_button(tr("Clear"))
+                .on_hover_text(tr("Stop playing the SoundFont"))
+                .clicked()
+        {
+            params.soundfont = SoundFontFile::default();
+        }
+    });
+    if let Some(font) = params.soundfont.loaded() {
+        let presets = font.presets();
+        let selected = presets
+            .get(params.soundfont_preset as usize)
+            .map_or("None", |preset| preset.name.as_str());
+        ui.horizontal(|ui| {
+            ui.label(tr("Preset"));
+            ComboBox::from_id_source("soundfont_preset")
+                .selected_text(selected)
+                .width(200.0)
+                .show_ui(ui, |ui| {
+                    for (index, preset) in presets.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut params.soundfont_preset,
+                            index as u32,
+                            preset.name.as_str(),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text(tr("Bank and program number, then the preset's name"));
+        });
+    }
+    ui.horizontal(|ui| {
+        let field = ui.add(
+            egui::TextEdit::singleline(input)
+                .hint_text(tr("Path to a .sf2 or .sfz"))
+                .desired_width(200.0),
+        );
+        let entered = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        let load = ui
+            .button(tr("Load"))
+            .on_hover_text(tr(
+                "An SFZ's samples are read from the folder it names them in",
+            ))
+            .clicked();
+        if (load || entered) && !input.trim().is_empty() {
+            cache.forget(input.trim());
+            params.soundfont = SoundFontFile::new(input);
+            params.soundfont_preset = 0;
+            cache.resolve(params);
         }
+    });
+    if let Some(err) = cache.error(params.soundfont.path()) {
+        ui.colored_label(Color32::RED, err);
     }
+}
 
-    let mut pointer_note = None;
-    if let (Some(pos), true) = (pointer_pos, pointer_down) {
-        if rect.contains(pos) {
-            for (note, key_rect) in &black_layout {
-                if key_rect.contains(pos) {
-                    pointer_note = Some(*note);
-                    break;
+fn modulation_controls(ui: &mut egui::Ui, shared: &mut SynthShared) {
+    param_slider(ui, &mut shared.params, ParamId::VibratoRate);
+    param_slider(ui, &mut shared.params, ParamId::VibratoDepth);
+    param_slider(ui, &mut shared.params, ParamId::Autotune);
+    param_slider(ui, &mut shared.params, ParamId::AutotuneRetune);
+    ui.horizontal(|ui| {
+        ui.label(tr("Unison"));
+        ui.add(
+            egui::DragValue::new(&mut shared.params.unison_voices)
+                .clamp_range(1..=MAX_UNISON_VOICES)
+                .suffix(" voices"),
+        )
+        .on_hover_text(tr(
+            "Copies stacked on each note, detuned across the unison spread",
+        ));
+    });
+    param_slider(ui, &mut shared.params, ParamId::UnisonSpread);
+    param_slider(ui, &mut shared.params, ParamId::UnisonBlend);
+    param_slider(ui, &mut shared.params, ParamId::StereoWidth);
+    param_slider(ui, &mut shared.params, ParamId::Pan);
+    param_slider(ui, &mut shared.params, ParamId::NoiseMix);
+    param_slider(ui, &mut shared.params, ParamId::Drift);
+}
+
+/// Suggested progressions in the selected key as chord pads; hold a pad to
+/// play its chord, or Play to loop the progression a chord per bar.
+fn progression_controls(
+    ui: &mut egui::Ui,
+    settings: &mut AppSettings,
+    player: &mut ChordPlayer,
+) -> bool {
+    let Some(scale) = settings.scale else {
+        player.stop();
+        ui.weak(tr(
+            "Pick a scale above the piano to get progressions in its key.",
+        ));
+        return false;
+    };
+    let Some(chords) = progression(scale, settings.progression) else {
+        player.stop();
+        ui.weak(tr(
+            "Progressions need a seven-note scale such as major or minor.",
+        ));
+        return false;
+    };
+    let before = (settings.progression, settings.tempo_bpm);
+
+    ui.horizontal(|ui| {
+        ComboBox::from_id_source("progression")
+            .selected_text(progression_label(scale, settings.progression))
+            .show_ui(ui, |ui| {
+                for index in 0..PROGRESSION_COUNT {
+                    ui.selectable_value(
+                        &mut settings.progression,
+                        index,
+                        progression_label(scale, index),
+                    );
                 }
-            }
-            if pointer_note.is_none() {
-                for (note, key_rect) in &white_layout {
-                    if key_rect.contains(pos) {
-                        pointer_note = Some(*note);
-                        break;
+            });
+        if ui
+            .button(tr("Suggest another"))
+            .on_hover_text(tr_fmt(
+                "Another common progression in {scale}",
+                &[("scale", &scale.label())],
+            ))
+            .clicked()
+        {
+            settings.progression = (settings.progression + 1) % PROGRESSION_COUNT;
+        }
+    });
+
+    let mut held = None;
+    ui.horizontal_wrapped(|ui| {
+        for (index, chord) in chords.iter().enumerate() {
+            let pad = ui.add(
+                egui::Button::new(format!("{}\n{}", chord.numeral, chord.name))
+                    .min_size(egui::vec2(84.0, 56.0))
+                    .selected(player.current == Some(index)),
+            );
+            if pad.is_pointer_button_down_on() {
+                held = Some(index);
+            }
+        }
+    });
+    player.pad = held;
+
+    ui.horizontal(|ui| {
+        let mut playing = player.is_playing();
+        let label = if playing {
+            format!("■ {}", tr("Stop"))
+        } else {
+            format!("▶ {}", tr("Play"))
+        };
+        if ui.toggle_value(&mut playing, label).changed() {
+            if playing {
+                player.start();
+            } else {
+                player.stop();
+            }
+        }
+        ui.add(
+            egui::DragValue::new(&mut settings.tempo_bpm)
+                .clamp_range(40.0..=240.0)
+                .speed(0.5)
+                .fixed_decimals(0)
+                .suffix(" BPM"),
+        )
+        .on_hover_text(tr("Global tempo; the progression plays one chord per bar"));
+    });
+    before != (settings.progression, settings.tempo_bpm)
+}
+
+/// Returns true when a snapshot was added to the user presets.
+fn morph_controls(
+    ui: &mut egui::Ui,
+    shared: &mut SynthShared,
+    settings: &mut AppSettings,
+    morph: &mut MorphState,
+) -> bool {
+    let presets = all_presets(&settings.user_presets);
+    morph.preset_a = morph.preset_a.min(presets.len() - 1);
+    morph.preset_b = morph.preset_b.min(presets.len() - 1);
+    let mut selection_changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label("A");
+        selection_changed |= preset_selector(ui, "morph_preset_a", &presets, &mut morph.preset_a);
+        ui.label("B");
+        selection_changed |= preset_selector(ui, "morph_preset_b", &presets, &mut morph.preset_b);
+    });
+
+    let slider = ui.add(egui::Slider::new(&mut morph.amount, 0.0..=1.0).text(tr("Morph A → B")));
+    if slider.changed() || selection_changed {
+        shared.params = morph_params(
+            &presets[morph.preset_a].params,
+            &presets[morph.preset_b].params,
+            morph.amount,
+        );
+    }
+
+    if ui
+        .button(tr("Snapshot"))
+        .on_hover_text(tr("Save the current in-between patch as a new preset"))
+        .clicked()
+    {
+        let name = format!(
+            "{} → {} {:.0}%",
+            presets[morph.preset_a].name,
+            presets[morph.preset_b].name,
+            morph.amount * 100.0
+        );
+        settings
+            .user_presets
+            .push(Preset::new(name, shared.params.clone()));
+        return true;
+    }
+    false
+}
+
+/// Header combo showing the last loaded preset. Returns true when one was picked.
+fn preset_browser(ui: &mut egui::Ui, presets: &[Preset], current: &mut Option<usize>) -> bool {
+    let before = *current;
+    let selected = current
+        .and_then(|index| presets.get(index))
+        .map_or("Custom patch", |preset| preset.name.as_str());
+    ComboBox::from_id_source("preset_browser")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for (index, preset) in presets.iter().enumerate() {
+                ui.selectable_value(current, Some(index), preset.name.as_str());
+            }
+        });
+    *current != before
+}
+
+/// Copy/paste of patch codes. Returns the pasted patch once one loads.
+fn patch_share_menu(
+    ui: &mut egui::Ui,
+    params: &SynthParams,
+    input: &mut String,
+    error: &mut Option<String>,
+) -> Option<SynthParams> {
+    if ui.button(tr("Copy patch as text")).clicked() {
+        let code = patch_code(params);
+        ui.ctx().output_mut(|output| output.copied_text = code);
+        ui.close_menu();
+    }
+    ui.separator();
+    ui.label(tr("Paste patch"));
+    let mut loaded = None;
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(input)
+                .hint_text("angel:…")
+                .desired_width(180.0),
+        );
+        if ui.button(tr("Load")).clicked() {
+            match parse_patch_code(input) {
+                Ok(params) => {
+                    loaded = Some(params);
+                    input.clear();
+                    *error = None;
+                    ui.close_menu();
+                }
+                Err(err) => *error = Some(err),
+            }
+        }
+    });
+    if let Some(err) = error {
+        ui.colored_label(Color32::RED, err.as_str());
+    }
+    loaded
+}
+
+fn step_preset(
+    shared: &mut SynthShared,
+    presets: &[Preset],
+    current: &mut Option<usize>,
+    delta: i32,
+) {
+    let count = presets.len() as i32;
+    let index = match *current {
+        Some(index) => (index as i32 + delta).rem_euclid(count),
+        None if delta < 0 => count - 1,
+        None => 0,
+    } as usize;
+    *current = Some(index);
+    shared.params = presets[index].params.clone();
+}
+
+fn preset_selector(ui: &mut egui::Ui, id: &str, presets: &[Preset], selected: &mut usize) -> bool {
+    let before = *selected;
+    ComboBox::from_id_source(id)
+        .selected_text(presets[*selected].name.as_str())
+        .show_ui(ui, |ui| {
+            for (index, preset) in presets.iter().enumerate() {
+                ui.selectable_value(selected, index, preset.name.as_str());
+            }
+        });
+    *selected != before
+}
+
+/// Sample rate the response curve is evaluated at; the shape only differs
+/// from the device rate right up near Nyquist.
+const RESPONSE_SAMPLE_RATE: f32 = 48_000.0;
+const RESPONSE_RANGE_DB: f32 = 24.0;
+
+/// Filter + EQ magnitude response on a log frequency axis, redrawn live from
+/// the current parameters.
+fn response_curve(ui: &mut egui::Ui, params: &SynthParams) {
+    let desired = egui::vec2(ui.available_width().max(200.0), 90.0);
+    let (rect, response) = ui.allocate_exact_size(desired, egui::Sense::hover());
+    describe(&response, egui::WidgetType::Other, || {
+        "Filter and EQ response curve".to_string()
+    });
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, Rounding::same(4.0), ui.visuals().extreme_bg_color);
+
+    let (low_hz, high_hz) = (20_f32, 20_000_f32);
+    let x_for = |freq: f32| {
+        let t = (freq / low_hz).ln() / (high_hz / low_hz).ln();
+        egui::lerp(rect.x_range(), t)
+    };
+    let y_for = |db: f32| {
+        let t = (db / RESPONSE_RANGE_DB).clamp(-1.0, 1.0) * 0.5 + 0.5;
+        egui::lerp(rect.y_range(), 1.0 - t)
+    };
+    let grid = Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color);
+    for freq in [100.0, 1_000.0, 10_000.0] {
+        painter.vline(x_for(freq), rect.y_range(), grid);
+    }
+    painter.hline(rect.x_range(), y_for(0.0), grid);
+
+    let steps = rect.width().max(2.0) as usize;
+    let points = (0..=steps)
+        .map(|step| {
+            let t = step as f32 / steps as f32;
+            let freq = low_hz * (high_hz / low_hz).powf(t);
+            let db = response_db(params, RESPONSE_SAMPLE_RATE, freq);
+            egui::pos2(egui::lerp(rect.x_range(), t), y_for(db))
+        })
+        .collect();
+    painter.add(egui::Shape::line(points, Stroke::new(2.0, ACCENT)));
+    painter.text(
+        rect.left_top() + egui::vec2(6.0, 4.0),
+        Align2::LEFT_TOP,
+        tr_fmt(
+            "Filter + EQ response (±{range} dB)",
+            &[("range", &format!("{RESPONSE_RANGE_DB:.0}"))],
+        ),
+        FontId::proportional(11.0),
+        ui.visuals().weak_text_color(),
+    );
+}
+
+fn eq_controls(ui: &mut egui::Ui, shared: &mut SynthShared) {
+    response_curve(ui, &shared.params);
+    ui.add_space(4.0);
+    ui.columns(2, |columns| {
+        param_slider(&mut columns[0], &mut shared.params, ParamId::EqLowGain);
+        param_slider(&mut columns[0], &mut shared.params, ParamId::EqLowFreq);
+        param_slider(&mut columns[1], &mut shared.params, ParamId::EqHighGain);
+        param_slider(&mut columns[1], &mut shared.params, ParamId::EqHighFreq);
+    });
+    param_slider(ui, &mut shared.params, ParamId::EqMidGain);
+    param_slider(ui, &mut shared.params, ParamId::EqMidFreq);
+    param_slider(ui, &mut shared.params, ParamId::EqMidQ);
+}
+
+/// The Granular instrument's grain settings. The sample it granulates is
+/// loaded in the Tone card, as for the Sampler.
+fn granular_controls(ui: &mut egui::Ui, params: &mut SynthParams) {
+    if params.instrument != InstrumentKind::Granular {
+        ui.horizontal(|ui| {
+            ui.label(tr("Heard with the Granular instrument."));
+            if ui.small_button(tr("Use Granular")).clicked() {
+                params.instrument = InstrumentKind::Granular;
+            }
+        });
+    } else if params.sample.loaded().is_none() {
+        ui.weak(tr("Load a sample in the Tone card to granulate it."));
+    }
+    param_slider(ui, params, ParamId::GrainPosition);
+    param_slider(ui, params, ParamId::GrainSpray);
+    ui.columns(2, |columns| {
+        param_slider(&mut columns[0], params, ParamId::GrainSize);
+        param_slider(&mut columns[1], params, ParamId::GrainDensity);
+    });
+}
+
+/// Draw bars for the Additive instrument: press and drag across them to
+/// draw the level of each harmonic, fundamental on the left.
+fn harmonics_controls(ui: &mut egui::Ui, params: &mut SynthParams) {
+    if params.instrument != InstrumentKind::Additive {
+        ui.horizontal(|ui| {
+            ui.label(tr("Heard with the Additive instrument."));
+            if ui.small_button(tr("Use Additive")).clicked() {
+                params.instrument = InstrumentKind::Additive;
+            }
+        });
+    }
+    ui.horizontal(|ui| {
+        let shapes = [
+            ("Sine", Harmonics::sine()),
+            ("Saw", Harmonics::default()),
+            ("Square", Harmonics::square()),
+            ("Organ", Harmonics::organ()),
+        ];
+        for (name, harmonics) in shapes {
+            if ui.small_button(name).clicked() {
+                params.harmonics = harmonics;
+            }
+        }
+    });
+
+    let desired = egui::vec2(ui.available_width().max(200.0), 90.0);
+    let (rect, response) = ui.allocate_exact_size(desired, egui::Sense::click_and_drag());
+    describe(&response, egui::WidgetType::Other, || {
+        let levels: Vec<String> = params
+            .harmonics
+            .0
+            .iter()
+            .enumerate()
+            .filter(|(_, level)| **level > 0.0)
+            .map(|(index, level)| format!("{} at {:.0}%", index + 1, level * 100.0))
+            .collect();
+        format!("Harmonics: {}", levels.join(", "))
+    });
+    let bar_width = rect.width() / HARMONIC_COUNT as f32;
+    let index_at = |x: f32| {
+        (((x - rect.min.x) / bar_width).floor() as isize).clamp(0, HARMONIC_COUNT as isize - 1)
+            as usize
+    };
+    let level_at = |y: f32| (1.0 - (y - rect.min.y) / rect.height()).clamp(0.0, 1.0);
+    if let Some(pos) = response
+        .interact_pointer_pos()
+        .filter(|_| response.is_pointer_button_down_on())
+    {
+        // Fill every bar the pointer crossed since the last frame, so a
+        // quick stroke leaves no gaps.
+        let from = pos - response.drag_delta();
+        let (start, end) = (index_at(from.x), index_at(pos.x));
+        for index in start.min(end)..=start.max(end) {
+            let t = if start == end {
+                1.0
+            } else {
+                (index as f32 - start as f32) / (end as f32 - start as f32)
+            };
+            params.harmonics.0[index] = level_at(egui::lerp(from.y..=pos.y, t));
+        }
+    }
+
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, Rounding::same(4.0), ui.visuals().extreme_bg_color);
+    let hovered = response.hover_pos().map(|pos| index_at(pos.x));
+    for (index, level) in params.harmonics.0.iter().enumerate() {
+        let left = rect.min.x + index as f32 * bar_width;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left + 1.0, egui::lerp(rect.y_range(), 1.0 - level)),
+            egui::pos2(left + bar_width - 1.0, rect.max.y),
+        );
+        let color = if hovered == Some(index) {
+            ui.visuals().selection.bg_fill
+        } else {
+            ACCENT
+        };
+        painter.rect_filled(bar, Rounding::same(1.0), color);
+    }
+    let caption = match hovered {
+        Some(index) => tr_fmt(
+            "Harmonic {number}: {level}%",
+            &[
+                ("number", &(index + 1)),
+                (
+                    "level",
+                    &format!("{:.0}", params.harmonics.0[index] * 100.0),
+                ),
+            ],
+        ),
+        None => tr_fmt("{count} harmonics", &[("count", &HARMONIC_COUNT)]),
+    };
+    painter.text(
+        rect.left_top() + egui::vec2(6.0, 4.0),
+        Align2::LEFT_TOP,
+        caption,
+        FontId::proportional(11.0),
+        ui.visuals().weak_text_color(),
+    );
+}
+
+/// Lowest level the output meters show.
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// Stacked L/R bars: RMS fill, peak tick, a dB scale and a clip lamp that
+/// stays lit until clicked.
+fn level_meter(ui: &mut egui::Ui, meters: &OutputMeters) {
+    let width = 120.0;
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(width + 14.0, 22.0), egui::Sense::click());
+    describe(&response, egui::WidgetType::ProgressIndicator, || {
+        format!(
+            "Output level: left {:.0} dB, right {:.0} dB{}",
+            to_dbfs(meters.rms(0)).max(METER_FLOOR_DB),
+            to_dbfs(meters.rms(1)).max(METER_FLOOR_DB),
+            if meters.clipped(0) || meters.clipped(1) {
+                ", clipped"
+            } else {
+                ""
+            },
+        )
+    });
+    let painter = ui.painter_at(rect);
+    let position = |level: f32| {
+        let db = to_dbfs(level).clamp(METER_FLOOR_DB, 0.0);
+        rect.min.x + (1.0 - db / METER_FLOOR_DB) * width
+    };
+    for channel in 0..2 {
+        let top = rect.min.y + channel as f32 * 7.0;
+        let track = egui::Rect::from_min_size(egui::pos2(rect.min.x, top), egui::vec2(width, 6.0));
+        painter.rect_filled(track, Rounding::same(2.0), ui.visuals().extreme_bg_color);
+        let rms = meters.rms(channel);
+        let fill = egui::Rect::from_x_y_ranges(rect.min.x..=position(rms), track.y_range());
+        let color = if to_dbfs(rms) > -6.0 {
+            ACCENT
+        } else {
+            Color32::from_rgb(80, 200, 120)
+        };
+        painter.rect_filled(fill, Rounding::same(2.0), color);
+        painter.vline(
+            position(meters.peak(channel)),
+            track.y_range(),
+            Stroke::new(2.0, ui.visuals().strong_text_color()),
+        );
+        let lamp = egui::Rect::from_min_size(
+            egui::pos2(rect.min.x + width + 4.0, top),
+            egui::vec2(10.0, 6.0),
+        );
+        let lamp_color = if meters.clipped(channel) {
+            Color32::RED
+        } else {
+            ui.visuals().extreme_bg_color
+        };
+        painter.rect_filled(lamp, Rounding::same(2.0), lamp_color);
+    }
+    for db in [-48.0, -36.0, -24.0, -12.0, -6.0, 0.0] {
+        let x = rect.min.x + (1.0 - db / METER_FLOOR_DB) * width;
+        painter.vline(
+            x,
+            rect.min.y + 14.0..=rect.min.y + 16.0,
+            Stroke::new(1.0, ui.visuals().weak_text_color()),
+        );
+    }
+    painter.text(
+        egui::pos2(rect.min.x, rect.max.y),
+        Align2::LEFT_BOTTOM,
+        "-60",
+        FontId::monospace(7.0),
+        ui.visuals().weak_text_color(),
+    );
+    painter.text(
+        egui::pos2(rect.min.x + width, rect.max.y),
+        Align2::RIGHT_BOTTOM,
+        "0 dB",
+        FontId::monospace(7.0),
+        ui.visuals().weak_text_color(),
+    );
+    let db = |value: f32| format!("{:.1}", to_dbfs(value));
+    let response = response.on_hover_text(tr_fmt(
+        "L peak {left_peak} dB, RMS {left_rms} dB\nR peak {right_peak} dB, RMS {right_rms} dB\n\
+         Click to reset the clip lamps",
+        &[
+            ("left_peak", &db(meters.peak(0))),
+            ("left_rms", &db(meters.rms(0))),
+            ("right_peak", &db(meters.peak(1))),
+            ("right_rms", &db(meters.rms(1))),
+        ],
+    ));
+    if response.clicked() {
+        meters.reset_clip();
+    }
+}
+
+/// How far the limiter is turning the output down; lit while it works.
+fn gain_reduction_readout(ui: &mut egui::Ui, meters: &OutputMeters) {
+    let reduction = meters.gain_reduction_db();
+    let text = egui::RichText::new(format!("GR {reduction:.1} dB")).monospace();
+    let text = if reduction < -0.1 {
+        text.color(ACCENT)
+    } else {
+        text
+    };
+    ui.label(text).on_hover_text(tr(
+        "Gain reduction: how far the limiter (in the Mixer) is turning the mix down to keep \
+         it under its ceiling. A few dB on the loudest notes is fine; more means lower the \
+         gain or the part volumes.",
+    ));
+}
+
+/// Short-term and integrated LUFS, with a button to restart integration.
+fn loudness_readout(ui: &mut egui::Ui, meters: &OutputMeters) {
+    let format_lufs = |lufs: f32| {
+        if lufs.is_finite() {
+            format!("{lufs:.1}")
+        } else {
+            "–".to_string()
+        }
+    };
+    ui.monospace(format!(
+        "S {} I {} LUFS",
+        format_lufs(meters.short_term_lufs()),
+        format_lufs(meters.integrated_lufs())
+    ))
+    .on_hover_text(tr_fmt(
+        "ITU-R BS.1770 loudness. Momentary {momentary} LUFS (400 ms), short-term over 3 s, \
+         integrated since the last reset.",
+        &[("momentary", &format_lufs(meters.momentary_lufs()))],
+    ));
+    if ui
+        .small_button(tr("Reset"))
+        .on_hover_text(tr("Restart integrated loudness"))
+        .clicked()
+    {
+        meters.reset_loudness();
+    }
+}
+
+/// This session's and all-time practice: time, notes, and the notes and
+/// scales used most.
+fn practice_stats(ui: &mut egui::Ui, practice: &PracticeTracker) {
+    let all_time = &practice.stats.total;
+    egui::Grid::new("practice_stats")
+        .num_columns(3)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.strong(tr("Session"));
+            ui.strong(tr("All time"));
+            ui.end_row();
+            ui.label(tr("Time played"));
+            ui.label(format_practice_time(practice.session.seconds_played));
+            ui.label(format_practice_time(all_time.seconds_played));
+            ui.end_row();
+            ui.label(tr("Notes"));
+            ui.label(practice.session.notes_played.to_string());
+            ui.label(all_time.notes_played.to_string());
+            ui.end_row();
+        });
+    ui.weak(tr_fmt(
+        "{count} sessions so far",
+        &[("count", &practice.stats.sessions)],
+    ));
+    let notes: Vec<String> = all_time
+        .top_pitch_classes(5)
+        .into_iter()
+        .map(|(name, count)| format!("{name} ({count})"))
+        .collect();
+    if !notes.is_empty() {
+        ui.label(tr_fmt(
+            "Most played: {list}",
+            &[("list", &notes.join(", "))],
+        ));
+    }
+    let scales: Vec<String> = all_time
+        .top_scales(3)
+        .into_iter()
+        .map(|(label, seconds)| format!("{label} ({})", format_practice_time(seconds)))
+        .collect();
+    if !scales.is_empty() {
+        ui.label(tr_fmt("Scales: {list}", &[("list", &scales.join(", "))]));
+    }
+}
+
+/// Engine figures and the recent event log, newest first, with a button that
+/// copies everything as a plain-text report for bug reports.
+fn diagnostics_controls(ui: &mut egui::Ui, diagnostics: &mut Diagnostics, stats: &EngineStats) {
+    egui::Grid::new("diagnostics_stats")
+        .num_columns(2)
+        .show(ui, |ui| {
+            let rows = [
+                ("Output", stats.output_device.clone()),
+                (
+                    "Input",
+                    stats
+                        .input_device
+                        .clone()
+                        .unwrap_or_else(|| "off".to_string()),
+                ),
+                ("Sample rate", format!("{} Hz", stats.sample_rate)),
+                (
+                    "Buffer",
+                    format!(
+                        "{} frames ({:.1} ms)",
+                        stats.buffer_frames,
+                        stats.buffer_frames as f32 * 1000.0 / stats.sample_rate
+                    ),
+                ),
+                ("Active voices", stats.active_voices.to_string()),
+                ("DSP load", format!("{:.0}%", stats.dsp_load * 100.0)),
+                (
+                    "Xruns",
+                    format!(
+                        "{} under, {} over, {} errors",
+                        stats.xruns.underruns, stats.xruns.overruns, stats.xruns.stream_errors
+                    ),
+                ),
+            ];
+            for (name, value) in rows {
+                ui.label(name);
+                ui.monospace(value);
+                ui.end_row();
+            }
+        });
+    ui.horizontal(|ui| {
+        if ui.button(tr("Copy report")).clicked() {
+            let report = diagnostics.report(stats);
+            ui.ctx().output_mut(|output| output.copied_text = report);
+        }
+        if ui.button(tr("Clear")).clicked() {
+            diagnostics.clear();
+        }
+    });
+    egui::ScrollArea::vertical()
+        .id_source("diagnostics_events")
+        .max_height(160.0)
+        .show(ui, |ui| {
+            let mut any = false;
+            for event in diagnostics.events().rev() {
+                any = true;
+                let text = format!(
+                    "[{}] {}: {}",
+                    format_uptime(event.at),
+                    event.kind.label(),
+                    event.message
+                );
+                if event.kind == EventKind::Device {
+                    ui.weak(text);
+                } else {
+                    ui.colored_label(Color32::RED, text);
+                }
+            }
+            if !any {
+                ui.weak(tr("No events yet."));
+            }
+        });
+}
+
+/// Xrun count and DSP load; turns red once the device has dropped audio.
+/// Clicking it clears the count.
+fn xrun_readout(ui: &mut egui::Ui, watchdog: &AudioWatchdog) {
+    let counts = watchdog.counts();
+    let text = tr_fmt(
+        "Xruns {count} · DSP {load}%",
+        &[
+            ("count", &counts.total()),
+            ("load", &format!("{:.0}", watchdog.load() * 100.0)),
+        ],
+    );
+    let text = if counts.total() > 0 {
+        egui::RichText::new(text).monospace().color(Color32::RED)
+    } else {
+        egui::RichText::new(text).monospace()
+    };
+    let response = ui
+        .add(egui::Label::new(text).sense(egui::Sense::click()))
+        .on_hover_text(tr_fmt(
+            "{underruns} under-runs (late callbacks), {overruns} over-runs (rendering slower \
+             than real time), {errors} stream errors, {frames} frames per buffer. Details go to \
+             the log. Click to clear.",
+            &[
+                ("underruns", &counts.underruns),
+                ("overruns", &counts.overruns),
+                ("errors", &counts.stream_errors),
+                ("frames", &watchdog.buffer_frames()),
+            ],
+        ));
+    if response.clicked() {
+        watchdog.reset();
+    }
+}
+
+/// Compact -1..+1 phase correlation bar; turns red when the mix would cancel in mono.
+fn correlation_meter(ui: &mut egui::Ui, correlation: f32) {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(90.0, 12.0), egui::Sense::hover());
+    describe(&response, egui::WidgetType::ProgressIndicator, || {
+        format!("Phase correlation {correlation:+.2}")
+    });
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, Rounding::same(3.0), ui.visuals().extreme_bg_color);
+    painter.vline(
+        rect.center().x,
+        rect.y_range(),
+        Stroke::new(1.0, ui.visuals().weak_text_color()),
+    );
+    let color = if correlation < 0.0 {
+        Color32::RED
+    } else if correlation < 0.5 {
+        ACCENT
+    } else {
+        Color32::from_rgb(80, 200, 120)
+    };
+    let x = egui::lerp(rect.x_range(), (correlation + 1.0) * 0.5);
+    let bar = egui::Rect::from_x_y_ranges(
+        rect.center().x.min(x)..=rect.center().x.max(x),
+        rect.y_range(),
+    );
+    painter.rect_filled(bar, Rounding::ZERO, color);
+    response.on_hover_text(tr_fmt(
+        "Phase correlation {correlation}. Below 0 the sound partly cancels in mono.",
+        &[("correlation", &format!("{correlation:+.2}"))],
+    ));
+    if correlation < 0.0 {
+        ui.colored_label(Color32::RED, tr("Mono cancel"));
+    }
+}
+
+/// Record toggle for a take of the synth and the microphone together,
+/// shown while an input is open. Stopping saves the take as a WAV named
+/// after the time in the home folder.
+fn take_controls(
+    ui: &mut egui::Ui,
+    mic: &Arc<Mutex<MicBuffer>>,
+    has_input: bool,
+    settings: &AppSettings,
+    recorder: &mut Option<TakeRecorder>,
+    status: &mut Option<String>,
+) {
+    let seconds = match mic.lock() {
+        Ok(mut buffer) => {
+            buffer.take_synth_level = settings.take_synth_level;
+            buffer.take_mic_level = settings.take_mic_level;
+            buffer.take_seconds()
+        }
+        Err(_) => return,
+    };
+    let mut recording = recorder.is_some();
+    // Closing the input mid-take still leaves a way to stop and keep it.
+    if !has_input && !recording {
+        return;
+    }
+    let label = if recording {
+        format!("⏺ {seconds:.0} s")
+    } else {
+        format!("⏺ {}", tr("Record"))
+    };
+    let toggled = ui
+        .toggle_value(&mut recording, label)
+        .on_hover_text(tr(
+            "Record the synth and the microphone into one WAV; set their levels under Take \
+             mix in Settings",
+        ))
+        .changed();
+    if toggled {
+        toggle_take(mic, has_input, recorder, status);
+    }
+    if let Some(status) = status {
+        ui.label(status.as_str());
+    }
+}
+
+/// Starts a take, or stops the running one and saves it as a WAV named
+/// after the time in the home folder. Shared by the Record button and its
+/// shortcut.
+fn toggle_take(
+    mic: &Arc<Mutex<MicBuffer>>,
+    has_input: bool,
+    recorder: &mut Option<TakeRecorder>,
+    status: &mut Option<String>,
+) {
+    let Some(running) = recorder.take() else {
+        if has_input {
+            *recorder = Some(TakeRecorder::start(mic));
+            *status = None;
+        }
+        return;
+    };
+    if let Some(take) = running.finish() {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let path = default_export_path().with_file_name(format!("angel_take_{stamp}.wav"));
+        *status = Some(match export_take(&path, &take) {
+            Ok(()) => tr_fmt("Saved {path}", &[("path", &path.display())]),
+            Err(err) => err,
+        });
+    }
+}
+
+/// Sample rate bounces are rendered at.
+const BOUNCE_SAMPLE_RATE: u32 = 48_000;
+
+/// Length and folder for bounces, and the button that renders the held
+/// notes offline to `angel_bounce_<time>.wav`. Returns true when a setting
+/// changed.
+fn bounce_controls(
+    ui: &mut egui::Ui,
+    shared: &Arc<Mutex<SynthShared>>,
+    settings: &mut AppSettings,
+    status: &mut Option<String>,
+) -> bool {
+    let mut changed = ui
+        .add(
+            egui::Slider::new(&mut settings.bounce_seconds, 0.25..=30.0)
+                .logarithmic(true)
+                .suffix(" s")
+                .text(tr("Hold for")),
+        )
+        .on_hover_text(tr(
+            "How long the notes are held before they are let go to ring out",
+        ))
+        .changed();
+    ui.horizontal(|ui| {
+        ui.label(tr("Folder"));
+        let home = settings.bounce_folder();
+        changed |= ui
+            .add(
+                egui::TextEdit::singleline(&mut settings.bounce_dir)
+                    .hint_text(home.display().to_string())
+                    .desired_width(220.0),
+            )
+            .changed();
+    });
+    if ui.button(tr("Bounce held notes")).clicked() {
+        // Copy the setup and let go of the lock before rendering, so the
+        // audio thread isn't kept waiting.
+        let copy = shared.lock().ok().map(|shared| shared.clone());
+        *status = Some(match copy {
+            Some(copy) if copy.held_notes().is_empty() => {
+                "Hold some notes first, or latch them with Hold".to_string()
+            }
+            Some(copy) => {
+                let frames = copy.bounce_held(settings.bounce_seconds, BOUNCE_SAMPLE_RATE);
+                let stamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_secs());
+                let dir = settings.bounce_folder();
+                let path = dir.join(format!("angel_bounce_{stamp}.wav"));
+                let wav = encode_stereo_pcm16(&frames, BOUNCE_SAMPLE_RATE);
+                match std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&path, wav)) {
+                    Ok(()) => tr_fmt("Saved {path}", &[("path", &path.display())]),
+                    Err(err) => tr_fmt(
+                        "Failed to write {path}: {error}",
+                        &[("path", &path.display()), ("error", &err)],
+                    ),
+                }
+            }
+            None => tr("The synth is unavailable").to_string(),
+        });
+    }
+    if let Some(status) = status {
+        ui.label(status.as_str());
+    }
+    changed
+}
+
+/// Freeze toggle and export actions for the scope trace.
+fn scope_snapshot_controls(
+    ui: &mut egui::Ui,
+    frozen: &mut Option<Vec<f32>>,
+    status: &mut Option<String>,
+    trace: &[f32],
+    sample_rate: f32,
+    gain: f32,
+) {
+    ui.horizontal(|ui| {
+        let mut freeze = frozen.is_some();
+        if ui.toggle_value(&mut freeze, tr("Freeze")).changed() {
+            *frozen = freeze.then(|| trace.to_vec());
+        }
+        let export_dir = default_export_path();
+        if ui.button(tr("Export PNG")).clicked() {
+            let path = export_dir.with_file_name("angel_scope.png");
+            *status = Some(match scope::export_png(&path, trace, gain) {
+                Ok(()) => tr_fmt("Saved {path}", &[("path", &path.display())]),
+                Err(err) => err,
+            });
+        }
+        if ui.button(tr("Export CSV")).clicked() {
+            let path = export_dir.with_file_name("angel_scope.csv");
+            *status = Some(match scope::export_csv(&path, trace, sample_rate) {
+                Ok(()) => tr_fmt("Saved {path}", &[("path", &path.display())]),
+                Err(err) => err,
+            });
+        }
+        if let Some(status) = status {
+            ui.label(status.as_str());
+        }
+    });
+}
+
+fn scope_controls(ui: &mut egui::Ui, settings: &mut AppSettings) -> bool {
+    ui.horizontal(|ui| {
+        let mut changed = ui
+            .checkbox(&mut settings.scope_envelope, tr("Envelope"))
+            .on_hover_text(tr("Show the loudness outline of the last few seconds"))
+            .changed();
+        ui.separator();
+        if settings.scope_envelope {
+            changed |= ui
+                .add(
+                    egui::Slider::new(&mut settings.scope_envelope_seconds, 0.5..=20.0)
+                        .logarithmic(true)
+                        .text("s"),
+                )
+                .changed();
+        } else {
+            changed |= ui
+                .checkbox(&mut settings.scope_trigger, tr("Trigger"))
+                .on_hover_text(tr(
+                    "Lock the trace to rising zero crossings; free-runs when silent",
+                ))
+                .changed();
+            changed |= ui
+                .add(
+                    egui::Slider::new(&mut settings.scope_ms_per_div, 0.1..=20.0)
+                        .logarithmic(true)
+                        .text("ms/div"),
+                )
+                .changed();
+        }
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut settings.scope_gain, 0.25..=8.0)
+                    .logarithmic(true)
+                    .text(tr("Gain")),
+            )
+            .changed();
+        changed
+    })
+    .inner
+}
+
+/// The samples the scope shows this frame, honouring the time base and trigger.
+fn scope_trace(settings: &AppSettings, sample_rate: f32, buffer: &ScopeBuffer) -> Vec<f32> {
+    let screen_seconds = settings.scope_ms_per_div * SCOPE_DIVISIONS as f32 / 1000.0;
+    let window = ((screen_seconds * sample_rate) as usize).clamp(16, SCOPE_CAPACITY / 2);
+    let triggered = settings
+        .scope_trigger
+        .then(|| buffer.triggered(window))
+        .flatten();
+    triggered.unwrap_or_else(|| buffer.latest(window))
+}
+
+/// Allocates the scope area and draws its background and graticule.
+fn scope_frame(ui: &mut egui::Ui, height: f32) -> (egui::Rect, egui::Painter) {
+    let desired = egui::vec2(ui.available_width().max(200.0), height);
+    let (rect, response) = ui.allocate_exact_size(desired, egui::Sense::hover());
+    describe(&response, egui::WidgetType::Other, || {
+        "Oscilloscope".to_string()
+    });
+    let painter = ui.painter_at(rect);
+    painter.rect(
+        rect,
+        Rounding::same(6.0),
+        ui.visuals().faint_bg_color,
+        Stroke::new(1.0, ui.visuals().weak_text_color()),
+    );
+
+    let grid = Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color);
+    for division in 1..SCOPE_DIVISIONS {
+        let x = egui::lerp(rect.x_range(), division as f32 / SCOPE_DIVISIONS as f32);
+        painter.vline(x, rect.y_range(), grid);
+    }
+    painter.hline(rect.x_range(), rect.center().y, grid);
+    (rect, painter)
+}
+
+fn scope_warming_up(ui: &egui::Ui, painter: &egui::Painter, rect: egui::Rect) {
+    painter.text(
+        rect.center(),
+        Align2::CENTER_CENTER,
+        "Scope warming up...",
+        FontId::proportional(14.0),
+        ui.visuals().weak_text_color(),
+    );
+}
+
+/// The min/max envelope, one vertical bar per pair, spread across the width.
+fn draw_envelope(ui: &mut egui::Ui, settings: &AppSettings, height: f32, pairs: &[(f32, f32)]) {
+    let (rect, painter) = scope_frame(ui, height);
+    if pairs.is_empty() {
+        scope_warming_up(ui, &painter, rect);
+        return;
+    }
+    let to_y = |sample: f32| {
+        let norm = ((sample * settings.scope_gain).clamp(-1.0, 1.0) + 1.0) * 0.5;
+        egui::lerp(rect.y_range(), 1.0 - norm)
+    };
+    let stroke = Stroke::new(1.0, ui.visuals().selection.bg_fill);
+    let width = rect.width() / pairs.len() as f32;
+    for (i, (low, high)) in pairs.iter().enumerate() {
+        let x = rect.left() + (i as f32 + 0.5) * width;
+        // Keep near-silent stretches visible as a thin line.
+        let (top, bottom) = (to_y(*high), to_y(*low));
+        painter.vline(x, top.min(bottom - 1.0)..=bottom, stroke);
+    }
+}
+
+fn draw_scope(ui: &mut egui::Ui, settings: &AppSettings, height: f32, data: &[f32]) {
+    let (rect, painter) = scope_frame(ui, height);
+    if data.len() >= 2 {
+        let len = data.len().saturating_sub(1).max(1);
+        let mut points = Vec::with_capacity(data.len());
+        for (i, sample) in data.iter().enumerate() {
+            let t = i as f32 / len as f32;
+            let x = egui::lerp(rect.x_range(), t);
+            let norm = ((*sample * settings.scope_gain).clamp(-1.0, 1.0) + 1.0) * 0.5;
+            let y = egui::lerp(rect.y_range(), 1.0 - norm);
+            points.push(egui::pos2(x, y));
+        }
+        painter.add(egui::Shape::line(
+            points,
+            Stroke::new(2.0, ui.visuals().selection.bg_fill),
+        ));
+    } else {
+        scope_warming_up(ui, &painter, rect);
+    }
+}
+
+/// Plays notes for mapped keys and returns the shortcut actions that fired.
+fn handle_keyboard_events(
+    events: &[KeyEvent],
+    scheme: KeybindScheme,
+    shared: &mut SynthShared,
+    octave_offset: i32,
+    key_velocity: &mut f32,
+    keymap: Option<&Keymap>,
+) -> Vec<Action> {
+    let mut actions = Vec::new();
+    for (key, pressed, modifiers) in events.iter().copied() {
+        if let Some(action) = action_for(scheme, key, modifiers) {
+            if pressed {
+                actions.push(action);
+            }
+            continue;
+        }
+        if let (true, Some(digit)) = (modifiers.alt, velocity_digit(key)) {
+            if pressed {
+                *key_velocity = digit as f32 / 9.0;
+            }
+            continue;
+        }
+        let base = match keymap {
+            Some(keymap) => keymap.note_for(key.name()),
+            None => qwerty_note(key.name()),
+        };
+        let Some(base) = base else {
+            continue;
+        };
+        let Ok(note) = u8::try_from(base as i32 + octave_offset * 12) else {
+            continue;
+        };
+        if note > 127 {
+            continue;
+        }
+        if pressed {
+            shared.press_note(note, note_velocity(*key_velocity, modifiers, scheme));
+        } else {
+            shared.release_note(note);
+        }
+    }
+    actions
+}
+
+/// Shift accents a note to full velocity, Ctrl halves it unless the keybind
+/// scheme has Ctrl shortcuts of its own.
+fn note_velocity(base: f32, modifiers: egui::Modifiers, scheme: KeybindScheme) -> f32 {
+    if modifiers.shift {
+        1.0
+    } else if modifiers.ctrl && !uses_command(scheme) {
+        base * 0.5
+    } else {
+        base
+    }
+}
+
+fn velocity_digit(key: egui::Key) -> Option<u8> {
+    match key {
+        egui::Key::Num1 => Some(1),
+        egui::Key::Num2 => Some(2),
+        egui::Key::Num3 => Some(3),
+        egui::Key::Num4 => Some(4),
+        egui::Key::Num5 => Some(5),
+        egui::Key::Num6 => Some(6),
+        egui::Key::Num7 => Some(7),
+        egui::Key::Num8 => Some(8),
+        egui::Key::Num9 => Some(9),
+        _ => None,
+    }
+}
+
+fn collect_keyboard_events(ctx: &egui::Context) -> Vec<KeyEvent> {
+    let mut events = Vec::new();
+    // Typing into a text field shouldn't play notes, but releases still pass
+    // so a note held when the field took focus doesn't hang.
+    let typing = ctx.wants_keyboard_input();
+    ctx.input(|input| {
+        for event in &input.events {
+            if let egui::Event::Key {
+                key,
+                pressed,
+                repeat: false,
+                modifiers,
+                ..
+            } = event
+                && !(typing && *pressed)
+            {
+                events.push((*key, *pressed, *modifiers));
+            }
+        }
+    });
+    events
+}
+
+/// The piano in a horizontal scroll area, zoomed by `settings.keyboard_zoom`,
+/// with an overview strip underneath while not every key fits. `scroll` is
+/// the left edge of the view as a fraction of the whole keyboard's width.
+fn draw_piano(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    shared: &mut SynthShared,
+    mouse_note: &mut Option<u8>,
+    settings: &AppSettings,
+    reach: &KeyboardReach,
+    scroll: &mut f32,
+) -> egui::Response {
+    let (lowest, highest) = settings.keyboard_notes();
+    let white_key_count = (lowest..=highest).filter(|n| !is_black(*n)).count();
+    let available_width = ui.available_width().max(white_key_count as f32 * 12.0);
+    let scale = settings.keyboard_scale.clamp(0.7, 1.4);
+    let height_scale = if settings.touch_layout {
+        scale * TOUCH_KEY_HEIGHT
+    } else {
+        scale
+    };
+    let fit_width = (available_width / white_key_count as f32).clamp(10.0, 80.0) * scale;
+    // Zooming widens the keys but keeps their height, so the piano never
+    // outgrows its panel vertically.
+    let white_key_size = egui::vec2(
+        fit_width * settings.keyboard_zoom.clamp(1.0, MAX_KEYBOARD_ZOOM),
+        fit_width * height_scale * BASE_WHITE_KEY_HEIGHT / BASE_WHITE_KEY_WIDTH,
+    );
+    let content_width = white_key_count as f32 * white_key_size.x;
+
+    let output = egui::ScrollArea::horizontal()
+        .id_source("piano_scroll")
+        .drag_to_scroll(false)
+        .horizontal_scroll_offset(*scroll * content_width)
+        .show(ui, |ui| {
+            piano_keys(ui, ctx, shared, mouse_note, settings, reach, white_key_size)
+        });
+    *scroll = output.state.offset.x / content_width;
+
+    let visible = output.inner_rect.width() / content_width;
+    if visible < 1.0 {
+        let window = egui::Rangef::new(*scroll, *scroll + visible);
+        if let Some(centre) = keyboard_overview(ui, shared, settings, reach, window) {
+            *scroll = (centre - visible / 2.0).clamp(0.0, 1.0 - visible);
+        }
+    }
+    output.inner
+}
+
+/// Mode picker plus the ribbon strip under the piano. In pitch mode pressing
+/// the strip holds a note that bends continuously as the pointer slides
+/// across the keyboard's range; in parameter mode the strip sweeps the
+/// assigned parameter. Returns true when a setting changed.
+fn ribbon(
+    ui: &mut egui::Ui,
+    shared: &mut SynthShared,
+    settings: &mut AppSettings,
+    held: &mut Option<u8>,
+) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label(tr("Ribbon"));
+        ComboBox::from_id_source("ribbon_mode")
+            .selected_text(settings.ribbon_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in RibbonMode::ALL {
+                    changed |= ui
+                        .selectable_value(&mut settings.ribbon_mode, mode, mode.label())
+                        .changed();
+                }
+            });
+        match settings.ribbon_mode {
+            RibbonMode::Off => {}
+            RibbonMode::Pitch => {
+                changed |= ui
+                    .checkbox(&mut settings.ribbon_quantize, tr("Snap to scale"))
+                    .on_hover_text(tr(
+                        "Snap to the selected scale, or to semitones without one",
+                    ))
+                    .changed();
+            }
+            RibbonMode::Param => {
+                ComboBox::from_id_source("ribbon_param")
+                    .selected_text(settings.ribbon_param.label())
+                    .show_ui(ui, |ui| {
+                        for id in ParamId::ALL {
+                            changed |= ui
+                                .selectable_value(&mut settings.ribbon_param, id, id.label())
+                                .changed();
+                        }
+                    });
+            }
+        }
+    });
+    if settings.ribbon_mode != RibbonMode::Pitch
+        && let Some(note) = held.take()
+    {
+        shared.release_note(note);
+    }
+    if settings.ribbon_mode == RibbonMode::Off {
+        return changed;
+    }
+
+    let height = if settings.touch_layout {
+        RIBBON_HEIGHT * TOUCH_KEY_HEIGHT
+    } else {
+        RIBBON_HEIGHT
+    };
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), height),
+        egui::Sense::click_and_drag(),
+    );
+    let (lowest, highest) = settings.keyboard_notes();
+    let span = (highest - lowest + 1) as f32;
+    describe(&response, egui::WidgetType::Slider, || {
+        match settings.ribbon_mode {
+            RibbonMode::Param => format!(
+                "Ribbon: {} {}",
+                settings.ribbon_param.label(),
+                settings
+                    .ribbon_param
+                    .format(settings.ribbon_param.get(&shared.params))
+            ),
+            _ => "Ribbon: press and slide to play".to_string(),
+        }
+    });
+    let position = response
+        .interact_pointer_pos()
+        .filter(|_| response.is_pointer_button_down_on())
+        .map(|pos| ((pos.x - rect.min.x) / rect.width()).clamp(0.0, 1.0));
+    let mut marker = None;
+    match (settings.ribbon_mode, position) {
+        (RibbonMode::Pitch, Some(position)) => {
+            let mut pitch = lowest as f32 - 0.5 + position * span;
+            if settings.ribbon_quantize {
+                pitch = settings
+                    .scale
+                    .map_or(pitch.round(), |scale| scale.nearest_note(pitch));
+            }
+            let note = *held.get_or_insert_with(|| {
+                let note = pitch.round().clamp(0.0, 127.0) as u8;
+                shared.press_note(note, DEFAULT_KEY_VELOCITY);
+                note
+            });
+            shared.bend_note(note, pitch - note as f32);
+            marker = Some((pitch - lowest as f32 + 0.5) / span);
+        }
+        (RibbonMode::Param, position) => {
+            let param = settings.ribbon_param;
+            if let Some(position) = position {
+                *param.get_mut(&mut shared.params) = param.denormalize(position);
+            }
+            marker = Some(param.normalize(param.get(&shared.params)));
+        }
+        _ => {
+            if let Some(note) = held.take() {
+                shared.release_note(note);
+            }
+        }
+    }
+
+    let visuals = ui.visuals();
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, Rounding::same(4.0), visuals.extreme_bg_color);
+    if settings.ribbon_mode == RibbonMode::Pitch {
+        let grid = visuals.widgets.noninteractive.bg_stroke.color;
+        for note in lowest..=highest {
+            let x = rect.min.x + (note - lowest) as f32 / span * rect.width();
+            let cell = egui::Rect::from_x_y_ranges(x..=x + rect.width() / span, rect.y_range());
+            if settings.scale.is_some_and(|scale| scale.contains(note)) {
+                painter.rect_filled(cell, Rounding::ZERO, SCALE_TINT.gamma_multiply(0.25));
+            }
+            if note % 12 == 0 {
+                painter.line_segment(
+                    [cell.left_top(), cell.left_bottom()],
+                    Stroke::new(1.0, grid),
+                );
+            }
+        }
+    }
+    if let Some(marker) = marker {
+        let x = egui::lerp(rect.x_range(), marker.clamp(0.0, 1.0));
+        painter.line_segment(
+            [egui::pos2(x, rect.min.y), egui::pos2(x, rect.max.y)],
+            Stroke::new(3.0, ACCENT),
+        );
+    }
+    changed
+}
+
+/// A miniature of the whole keyboard with the visible part outlined; clicking
+/// or dragging on it returns where the view should be centred, as a fraction
+/// of the keyboard's width.
+fn keyboard_overview(
+    ui: &mut egui::Ui,
+    shared: &SynthShared,
+    settings: &AppSettings,
+    reach: &KeyboardReach,
+    window: egui::Rangef,
+) -> Option<f32> {
+    let (lowest, highest) = settings.keyboard_notes();
+    let white_notes: Vec<u8> = (lowest..=highest).filter(|n| !is_black(*n)).collect();
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), OVERVIEW_HEIGHT),
+        egui::Sense::click_and_drag(),
+    );
+    describe(&response, egui::WidgetType::Slider, || {
+        format!(
+            "Keyboard overview, showing {:.0}% to {:.0}%",
+            window.min * 100.0,
+            window.max * 100.0
+        )
+    });
+    let painter = ui.painter_at(rect);
+    let key_width = rect.width() / white_notes.len() as f32;
+    let pressed_fill = ui.visuals().selection.bg_fill;
+    for (index, note) in white_notes.iter().enumerate() {
+        let x = rect.min.x + index as f32 * key_width;
+        let key_rect = egui::Rect::from_min_size(
+            egui::pos2(x, rect.min.y),
+            egui::vec2((key_width - 1.0).max(1.0), rect.height()),
+        );
+        let in_reach = reach
+            .range
+            .is_some_and(|(first, last)| (first..=last).contains(note));
+        let fill = if shared.is_pressed(*note) {
+            pressed_fill
+        } else if in_reach {
+            blend_color(Color32::from_rgb(250, 250, 250), ACCENT, 0.3)
+        } else {
+            Color32::from_rgb(250, 250, 250)
+        };
+        painter.rect_filled(key_rect, Rounding::ZERO, fill);
+        if *note < highest && is_black(note + 1) {
+            let black_width = key_width * BLACK_KEY_WIDTH_RATIO;
+            let black = egui::Rect::from_min_size(
+                egui::pos2(x + key_width * 0.7 - black_width / 2.0, rect.min.y),
+                egui::vec2(black_width, rect.height() * BLACK_KEY_HEIGHT_RATIO),
+            );
+            let fill = if shared.is_pressed(note + 1) {
+                pressed_fill
+            } else {
+                Color32::from_rgb(20, 20, 20)
+            };
+            painter.rect_filled(black, Rounding::ZERO, fill);
+        }
+    }
+    let view = egui::Rect::from_x_y_ranges(
+        rect.min.x + window.min * rect.width()..=rect.min.x + window.max * rect.width(),
+        rect.y_range(),
+    );
+    painter.rect_stroke(view, Rounding::same(2.0), Stroke::new(2.0, ACCENT));
+
+    let response = response.on_hover_text(tr("Click or drag to scroll the keyboard"));
+    if !(response.clicked() || response.dragged()) {
+        return None;
+    }
+    let pos = response.interact_pointer_pos()?;
+    Some(((pos.x - rect.min.x) / rect.width()).clamp(0.0, 1.0))
+}
+
+/// Multi-touch playing: every finger holds its own key and slides between
+/// keys independently, so chords can be played on a touch screen. Returns
+/// true while any finger is down on the keyboard.
+fn touch_keys(
+    ui: &egui::Ui,
+    shared: &mut SynthShared,
+    black_keys: &[(u8, egui::Rect)],
+    white_keys: &[(u8, egui::Rect)],
+    rect: egui::Rect,
+) -> bool {
+    let id = Id::new("piano_touches");
+    let mut touches: BTreeMap<u64, u8> = ui.data(|data| data.get_temp(id)).unwrap_or_default();
+    let area = rect.intersect(ui.clip_rect());
+    let events = ui.input(|input| input.events.clone());
+    for event in events {
+        let egui::Event::Touch {
+            id: touch,
+            phase,
+            pos,
+            ..
+        } = event
+        else {
+            continue;
+        };
+        let held = matches!(phase, egui::TouchPhase::Start | egui::TouchPhase::Move);
+        // Black keys sit on top, so they win where the two overlap.
+        let key = black_keys
+            .iter()
+            .chain(white_keys)
+            .find(|(_, key_rect)| held && area.contains(pos) && key_rect.contains(pos))
+            .map(|(note, key_rect)| (*note, click_velocity(*key_rect, pos)));
+        let previous = touches.get(&touch.0).copied();
+        if previous == key.map(|(note, _)| note) {
+            continue;
+        }
+        if let Some(prev) = previous {
+            shared.release_note(prev);
+            touches.remove(&touch.0);
+        }
+        if let Some((note, velocity)) = key {
+            shared.press_note(note, velocity);
+            touches.insert(touch.0, note);
+        }
+    }
+    let touching = !touches.is_empty();
+    ui.data_mut(|data| data.insert_temp(id, touches));
+    touching
+}
+
+fn piano_keys(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    shared: &mut SynthShared,
+    mouse_note: &mut Option<u8>,
+    settings: &AppSettings,
+    reach: &KeyboardReach,
+    white_key_size: egui::Vec2,
+) -> egui::Response {
+    let (lowest, highest) = settings.keyboard_notes();
+    let white_key_count = (lowest..=highest).filter(|n| !is_black(*n)).count();
+    let egui::Vec2 {
+        x: white_key_width,
+        y: white_key_height,
+    } = white_key_size;
+    let black_key_width = white_key_width * BLACK_KEY_WIDTH_RATIO;
+    let black_key_height = white_key_height * BLACK_KEY_HEIGHT_RATIO;
+    let desired_size = egui::vec2(white_key_count as f32 * white_key_width, white_key_height);
+    let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+
+    let pointer_down = ui.input(|i| i.pointer.primary_down());
+    let pointer_pos = ui.input(|i| i.pointer.interact_pos());
+
+    let mut white_layout = Vec::new();
+    let mut black_layout = Vec::new();
+    let mut white_index = 0usize;
+
+    for note in lowest..=highest {
+        if !is_black(note) {
+            let x = rect.min.x + white_index as f32 * white_key_width;
+            let key_rect = egui::Rect::from_min_size(
+                egui::pos2(x, rect.min.y),
+                egui::vec2(white_key_width - 1.0, white_key_height),
+            );
+            white_layout.push((note, key_rect));
+            white_index += 1;
+        } else {
+            let preceding_white = white_index.saturating_sub(1) as f32;
+            let base = rect.min.x + preceding_white * white_key_width;
+            let x = base + white_key_width * 0.7 - black_key_width / 2.0;
+            let key_rect = egui::Rect::from_min_size(
+                egui::pos2(x, rect.min.y),
+                egui::vec2(black_key_width, black_key_height),
+            );
+            black_layout.push((note, key_rect));
+        }
+    }
+
+    describe(&response, egui::WidgetType::Other, || {
+        format!(
+            "Piano keyboard, {} to {}",
+            note_label(lowest, settings.octave_naming),
+            note_label(highest, settings.octave_naming)
+        )
+    });
+    // Every key is also a focusable node, so screen readers can name it and
+    // keyboard users can Tab to it and hold Space or Enter to play it.
+    let mut focus_note = None;
+    for (note, key_rect) in black_layout.iter().chain(&white_layout) {
+        let key = ui.interact(
+            *key_rect,
+            Id::new(("piano_key", note)),
+            egui::Sense::focusable_noninteractive(),
+        );
+        let sounding = shared.is_pressed(*note);
+        key.widget_info(|| {
+            egui::WidgetInfo::selected(
+                egui::WidgetType::Button,
+                sounding,
+                note_label(*note, settings.octave_naming),
+            )
+        });
+        let held = ui.input(|i| i.key_down(egui::Key::Space) || i.key_down(egui::Key::Enter));
+        if key.has_focus() && held {
+            focus_note = Some((*note, DEFAULT_KEY_VELOCITY));
+        }
+    }
+
+    // egui steers the pointer with the first finger too, so while any finger
+    // is on the keys the pointer is left to the touch handling.
+    let touching = touch_keys(ui, shared, &black_layout, &white_layout, rect);
+    let mut pointer_note = None;
+    if let (Some(pos), true, false) = (pointer_pos, pointer_down, touching) {
+        // Keys scrolled out of view are clipped, not gone; ignore them.
+        if rect.intersect(ui.clip_rect()).contains(pos) {
+            pointer_note = black_layout
+                .iter()
+                .chain(&white_layout)
+                .find(|(_, key_rect)| key_rect.contains(pos))
+                .map(|(note, key_rect)| (*note, click_velocity(*key_rect, pos)));
+        }
+    }
+
+    if let Some((note, velocity)) = pointer_note.or(focus_note) {
+        if mouse_note != &Some(note) {
+            match mouse_note.take() {
+                Some(prev) if settings.mouse_glide => shared.glide_note(prev, note, velocity),
+                Some(prev) => {
+                    shared.release_note(prev);
+                    shared.press_note(note, velocity);
+                }
+                None => shared.press_note(note, velocity),
+            }
+            *mouse_note = Some(note);
+        }
+    } else if let Some(prev) = mouse_note.take() {
+        shared.release_note(prev);
+    }
+
+    let white_idle = Color32::from_rgb(250, 250, 250);
+    let pressed_fill = ui.visuals().selection.bg_fill;
+
+    let in_scale = |note: u8| settings.scale.is_some_and(|scale| scale.contains(note));
+    // Colour and strength of a key's highlight.
+    let highlight = |note: u8, anim: f32| match settings.key_coloring {
+        KeyColoring::Plain => (pressed_fill, anim),
+        KeyColoring::PitchClass => (pitch_class_color(note), anim),
+        KeyColoring::Intensity => (pressed_fill, shared.voice_level(note)),
+    };
+    // The Drums instrument names the drum on each of its kit's keys.
+    let drum = |note: u8| {
+        (shared.params.instrument == InstrumentKind::Drums)
+            .then(|| shared.params.drum_kit.pad(note))
+            .flatten()
+            .map(|pad| pad.sound.short_label())
+    };
+    for (note, key_rect) in &white_layout {
+        let active = shared.is_pressed(*note);
+        let anim = ctx.animate_bool(Id::new(("white", note)), active);
+        let idle = if in_scale(*note) {
+            blend_color(white_idle, SCALE_TINT, 0.3)
+        } else {
+            white_idle
+        };
+        let (color, amount) = highlight(*note, anim);
+        let fill = blend_color(idle, color, amount);
+        painter.rect(*key_rect, Rounding::same(4.0), fill, (1.0, Color32::BLACK));
+        painter.text(
+            egui::pos2(key_rect.center().x, key_rect.max.y - 6.0),
+            Align2::CENTER_BOTTOM,
+            note_label(*note, settings.octave_naming),
+            FontId::monospace(12.0),
+            Color32::from_rgb(40, 40, 40),
+        );
+        if let Some(key) = reach.labels.get(note) {
+            painter.text(
+                egui::pos2(key_rect.center().x, key_rect.max.y - 22.0),
+                Align2::CENTER_BOTTOM,
+                key,
+                FontId::proportional(key_label_size(key_rect.width())),
+                ACCENT,
+            );
+        }
+        if let Some(name) = drum(*note) {
+            // Just below where the black keys end.
+            let top = key_rect.min.y + key_rect.height() * BLACK_KEY_HEIGHT_RATIO + 2.0;
+            painter.text(
+                egui::pos2(key_rect.center().x, top),
+                Align2::CENTER_TOP,
+                name,
+                FontId::proportional(key_label_size(key_rect.width())),
+                Color32::from_rgb(40, 40, 40),
+            );
+        }
+        if let Some(theory) = &settings.theory {
+            // Low on the key, clear of the black keys and the labels below.
+            let label_pos = egui::pos2(key_rect.center().x, key_rect.max.y - 40.0);
+            let text_color = Color32::from_rgb(40, 40, 40);
+            theory_marks(&painter, theory, *note, *key_rect, label_pos, text_color);
+        }
+    }
+
+    for (note, key_rect) in &black_layout {
+        let active = shared.is_pressed(*note);
+        let anim = ctx.animate_bool(Id::new(("black", note)), active);
+        let black_idle = Color32::from_rgb(20, 20, 20);
+        let idle = if in_scale(*note) {
+            blend_color(black_idle, SCALE_TINT, 0.45)
+        } else {
+            black_idle
+        };
+        let (color, amount) = highlight(*note, anim);
+        let fill = blend_color(idle, color, amount);
+        painter.rect(
+            *key_rect,
+            Rounding::same(3.0),
+            fill,
+            (1.0, Color32::from_rgb(15, 15, 15)),
+        );
+        if let Some(key) = reach.labels.get(note) {
+            painter.text(
+                egui::pos2(key_rect.center().x, key_rect.max.y - 4.0),
+                Align2::CENTER_BOTTOM,
+                key,
+                FontId::proportional(key_label_size(key_rect.width())),
+                Color32::from_rgb(235, 235, 235),
+            );
+        }
+        if let Some(name) = drum(*note) {
+            painter.text(
+                key_rect.center(),
+                Align2::CENTER_CENTER,
+                name,
+                FontId::proportional(key_label_size(key_rect.width())),
+                Color32::from_rgb(235, 235, 235),
+            );
+        }
+        if let Some(theory) = &settings.theory {
+            let label_pos = egui::pos2(key_rect.center().x, key_rect.min.y + 8.0);
+            let text_color = Color32::from_rgb(235, 235, 235);
+            theory_marks(&painter, theory, *note, *key_rect, label_pos, text_color);
+        }
+    }
+
+    // Root of the selected scale: a dot near the top of every root key.
+    if let Some(scale) = settings.scale {
+        for (note, key_rect) in white_layout.iter().chain(&black_layout) {
+            if scale.is_root(*note) {
+                let center = egui::pos2(key_rect.center().x, key_rect.min.y + 14.0);
+                painter.circle_filled(center, (key_rect.width() * 0.2).clamp(2.0, 5.0), SCALE_ROOT);
+            }
+        }
+    }
+
+    // Split point: a marker along the left edge of the first upper-zone key.
+    if let Some(split) = &shared.split {
+        let marker = white_layout
+            .iter()
+            .chain(&black_layout)
+            .find(|(note, _)| *note == split.note);
+        if let Some((_, key_rect)) = marker {
+            let stroke = Stroke::new(3.0, ui.visuals().warn_fg_color);
+            painter.vline(key_rect.min.x - 1.0, rect.y_range(), stroke);
+        }
+    }
+
+    // Accent strip over the keys the computer keyboard currently reaches.
+    if let Some((first, last)) = reach.range {
+        let span = white_layout
+            .iter()
+            .chain(&black_layout)
+            .filter(|(note, _)| (first..=last).contains(note))
+            .map(|(_, key_rect)| *key_rect)
+            .reduce(|a, b| a.union(b));
+        if let Some(span) = span {
+            let strip = egui::Rect::from_min_max(
+                egui::pos2(span.min.x, rect.min.y),
+                egui::pos2(span.max.x, rect.min.y + 4.0),
+            );
+            painter.rect_filled(strip, Rounding::same(2.0), ACCENT);
+        }
+    }
+
+    response.on_hover_text(tr(
+        "Click and drag to play across keys. Click lower on a key to play louder. \
+         Ctrl+scroll to zoom.",
+    ))
+}
+
+/// The theory overlay on one key: its interval from the root, and an outline
+/// when it belongs to the chosen chord.
+fn theory_marks(
+    painter: &egui::Painter,
+    theory: &TheoryOverlay,
+    note: u8,
+    key_rect: egui::Rect,
+    label_pos: egui::Pos2,
+    text_color: Color32,
+) {
+    if theory.in_chord(note) {
+        painter.rect_stroke(
+            key_rect.shrink(2.0),
+            Rounding::same(3.0),
+            Stroke::new(3.0, CHORD_TONE),
+        );
+    }
+    let align = if is_black(note) {
+        Align2::CENTER_TOP
+    } else {
+        Align2::CENTER_BOTTOM
+    };
+    painter.text(
+        label_pos,
+        align,
+        theory.interval_label(note),
+        FontId::proportional(key_label_size(key_rect.width())),
+        text_color,
+    );
+}
+
+/// Clicking lower on a key plays it louder, like striking a real key nearer its front.
+fn click_velocity(key_rect: egui::Rect, pos: egui::Pos2) -> f32 {
+    let depth = ((pos.y - key_rect.min.y) / key_rect.height()).clamp(0.0, 1.0);
+    MIN_CLICK_VELOCITY + (1.0 - MIN_CLICK_VELOCITY) * depth
+}
+
+fn octave_controls(ui: &mut egui::Ui, octave_offset: &mut i32) {
+    ui.horizontal(|ui| {
+        let down = ui.add_enabled(*octave_offset > -MAX_OCTAVE_SHIFT, egui::Button::new("−"));
+        describe(&down, egui::WidgetType::Button, || {
+            "Octave down".to_string()
+        });
+        if down.clicked() {
+            shift_octave(octave_offset, -1);
+        }
+        let label = if *octave_offset == 0 {
+            tr("Oct 0").to_string()
+        } else {
+            tr_fmt("Oct {offset}", &[("offset", &format!("{octave_offset:+}"))])
+        };
+        ui.strong(label);
+        let up = ui.add_enabled(*octave_offset < MAX_OCTAVE_SHIFT, egui::Button::new("+"));
+        describe(&up, egui::WidgetType::Button, || "Octave up".to_string());
+        if up.clicked() {
+            shift_octave(octave_offset, 1);
+        }
+    });
+}
+
+/// Key and scale to highlight on the piano, or off. Returns true when it changed.
+fn scale_controls(ui: &mut egui::Ui, scale: &mut Option<Scale>) -> bool {
+    let before = *scale;
+    ui.horizontal(|ui| {
+        ui.label(tr("Scale"));
+        ComboBox::from_id_source("scale_kind")
+            .selected_text(scale.map_or("Off", |scale| scale.kind.label()))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(scale, None, tr("Off"));
+                let root = scale.map_or(0, |scale| scale.root);
+                for kind in ScaleKind::ALL {
+                    ui.selectable_value(scale, Some(Scale { root, kind }), kind.label());
+                }
+            })
+            .response
+            .on_hover_text(tr("Tint the keys in this scale and mark its root"));
+        if let Some(scale) = scale {
+            ComboBox::from_id_source("scale_root")
+                .width(48.0)
+                .selected_text(PITCH_CLASS_NAMES[scale.root as usize])
+                .show_ui(ui, |ui| {
+                    for (root, name) in PITCH_CLASS_NAMES.iter().enumerate() {
+                        ui.selectable_value(&mut scale.root, root as u8, *name);
+                    }
+                });
+        }
+    });
+    *scale != before
+}
+
+/// The drone's note, optional fifth and level. Switching it on starts from
+/// the scale's root. Returns true when the drone changed.
+fn drone_controls(
+    ui: &mut egui::Ui,
+    drone: &mut Option<Drone>,
+    scale: Option<Scale>,
+    naming: OctaveNaming,
+) -> bool {
+    let before = *drone;
+    ui.horizontal(|ui| {
+        let mut enabled = drone.is_some();
+        if ui
+            .checkbox(&mut enabled, tr("Drone"))
+            .on_hover_text(tr(
+                "Hold a pedal tone under whatever you play, through the main patch",
+            ))
+            .changed()
+        {
+            *drone = enabled.then(|| Drone {
+                note: 36 + scale.map_or(0, |scale| scale.root % 12),
+                ..Drone::default()
+            });
+        }
+        if let Some(drone) = drone {
+            ui.add(
+                egui::DragValue::new(&mut drone.note)
+                    .clamp_range(0..=127)
+                    .custom_formatter(|n, _| note_label(n as u8, naming)),
+            )
+            .on_hover_text(tr("Drag to change the drone note"));
+            ui.checkbox(&mut drone.fifth, tr("Fifth"))
+                .on_hover_text(tr("Add the fifth above"));
+            ui.add(egui::Slider::new(&mut drone.level, 0.0..=1.0).text(tr("Level")));
+        }
+    });
+    *drone != before
+}
+
+/// The learning overlay's root and chord, plus a line naming what was just
+/// played. Returns true when the overlay changed.
+fn theory_controls(
+    ui: &mut egui::Ui,
+    theory: &mut Option<TheoryOverlay>,
+    scale: Option<Scale>,
+    played: &[u8],
+    naming: OctaveNaming,
+) -> bool {
+    let before = *theory;
+    ui.horizontal(|ui| {
+        let mut enabled = theory.is_some();
+        if ui
+            .checkbox(&mut enabled, tr("Theory"))
+            .on_hover_text(tr(
+                "Label every key with its interval from a root and outline a chord",
+            ))
+            .changed()
+        {
+            // Start from the scale's key so the labels read in the key being played.
+            *theory = enabled.then(|| TheoryOverlay {
+                root: scale.map_or(0, |scale| scale.root),
+                chord: None,
+            });
+        }
+        let Some(theory) = theory else {
+            return;
+        };
+        ComboBox::from_id_source("theory_root")
+            .width(48.0)
+            .selected_text(PITCH_CLASS_NAMES[theory.root as usize])
+            .show_ui(ui, |ui| {
+                for (root, name) in PITCH_CLASS_NAMES.iter().enumerate() {
+                    ui.selectable_value(&mut theory.root, root as u8, *name);
+                }
+            })
+            .response
+            .on_hover_text(tr("Root the intervals are counted from"));
+        ComboBox::from_id_source("theory_chord")
+            .selected_text(theory.chord.map_or("No chord", ChordKind::label))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut theory.chord, None, tr("No chord"));
+                for kind in ChordKind::ALL {
+                    ui.selectable_value(&mut theory.chord, Some(kind), kind.label());
+                }
+            })
+            .response
+            .on_hover_text(tr("Outline this chord's notes on the keys"));
+        if let Some(description) = theory.describe(played) {
+            ui.separator();
+            let names: Vec<String> = played
+                .iter()
+                .map(|note| note_label(*note, naming))
+                .collect();
+            ui.label(tr_fmt(
+                "You played {notes}: {description}",
+                &[("notes", &names.join(" ")), ("description", &description)],
+            ));
+        }
+    });
+    *theory != before
+}
+
+/// Split toggle, split point and the patch for the lower zone, which can be a
+/// preset or a copy of the current sound.
+fn split_controls(
+    ui: &mut egui::Ui,
+    shared: &mut SynthShared,
+    presets: &[Preset],
+    naming: OctaveNaming,
+) {
+    ui.horizontal(|ui| {
+        let mut enabled = shared.split.is_some();
+        let toggled = ui
+            .checkbox(&mut enabled, tr("Split"))
+            .on_hover_text(tr("Play a second patch on the keys below the split point"))
+            .changed();
+        if toggled {
+            shared.split = enabled.then(|| {
+                let lower = presets
+                    .iter()
+                    .find(|preset| preset.params.instrument == InstrumentKind::Bass)
+                    .or(presets.first());
+                KeyboardSplit {
+                    note: DEFAULT_SPLIT_NOTE,
+                    preset: lower.map(|preset| preset.name.clone()).unwrap_or_default(),
+                    lower: lower
+                        .map(|preset| preset.params.clone())
+                        .unwrap_or_default(),
+                    mix: PartMix::default(),
+                }
+            });
+        }
+        let params = shared.params.clone();
+        let Some(split) = &mut shared.split else {
+            return;
+        };
+        ui.label(tr("below"));
+        ui.add(
+            egui::DragValue::new(&mut split.note)
+                .clamp_range(22..=108)
+                .custom_formatter(|n, _| note_label(n as u8, naming)),
+        );
+        ComboBox::from_id_source("split_preset")
+            .selected_text(split.preset.as_str())
+            .show_ui(ui, |ui| {
+                for preset in presets {
+                    if ui
+                        .selectable_label(split.preset == preset.name, preset.name.as_str())
+                        .clicked()
+                    {
+                        split.preset = preset.name.clone();
+                        split.lower = preset.params.clone();
+                    }
+                }
+            });
+        if ui
+            .button(tr("Use current"))
+            .on_hover_text(tr("Copy the sound being edited into the lower zone"))
+            .clicked()
+        {
+            split.preset = "Current sound".to_string();
+            split.lower = params;
+        }
+    });
+}
+
+/// Layer toggle and patch, plus volume, pan and transpose for the main patch
+/// and the layer while one is stacked.
+fn layer_controls(ui: &mut egui::Ui, shared: &mut SynthShared, presets: &[Preset]) {
+    let mut enabled = shared.layer.is_some();
+    let toggled = ui
+        .checkbox(&mut enabled, tr("Layer"))
+        .on_hover_text(tr("Stack a second patch over the whole keyboard"))
+        .changed();
+    if toggled {
+        shared.layer = enabled.then(|| {
+            let first = presets.first();
+            PatchLayer {
+                preset: first.map(|preset| preset.name.clone()).unwrap_or_default(),
+                params: first
+                    .map(|preset| preset.params.clone())
+                    .unwrap_or_default(),
+                mix: PartMix::default(),
+            }
+        });
+    }
+    let params = shared.params.clone();
+    let Some(layer) = &mut shared.layer else {
+        return;
+    };
+    egui::Grid::new("layer_mix")
+        .num_columns(4)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            ui.label(tr("Main"));
+            part_mix_row(ui, &mut shared.main_mix);
+            ui.end_row();
+
+            ui.horizontal(|ui| {
+                ComboBox::from_id_source("layer_preset")
+                    .selected_text(layer.preset.as_str())
+                    .show_ui(ui, |ui| {
+                        for preset in presets {
+                            if ui
+                                .selectable_label(layer.preset == preset.name, preset.name.as_str())
+                                .clicked()
+                            {
+                                layer.preset = preset.name.clone();
+                                layer.params = preset.params.clone();
+                            }
+                        }
+                    });
+                if ui
+                    .button(tr("Use current"))
+                    .on_hover_text(tr("Copy the sound being edited into the layer"))
+                    .clicked()
+                {
+                    layer.preset = "Current sound".to_string();
+                    layer.params = params;
+                }
+            });
+            part_mix_row(ui, &mut layer.mix);
+            ui.end_row();
+        });
+}
+
+/// A strip per part with its volume, pan, transpose, mute, solo, effect
+/// sends and ducking, the shared reverb and delay, then the patch and key range of each
+/// part added here.
+fn mixer_controls(
+    ui: &mut egui::Ui,
+    shared: &mut SynthShared,
+    presets: &[Preset],
+    naming: OctaveNaming,
+) {
+    egui::Grid::new("mixer_strips")
+        .num_columns(10)
+        .striped(true)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            let parts = shared.parts();
+            for part in &parts {
+                ui.label(part.name.as_str());
+                if let Some(mix) = shared.part_mix_mut(part.id) {
+                    part_mix_row(ui, mix);
+                    ui.toggle_value(&mut mix.mute, "M")
+                        .on_hover_text(tr("Mute"));
+                    ui.toggle_value(&mut mix.solo, "S").on_hover_text(tr(
+                        "Solo: while any part is soloed, only soloed parts play",
+                    ));
+                    ui.add(egui::Slider::new(&mut mix.reverb_send, 0.0..=1.0).text(tr("Rev")))
+                        .on_hover_text(tr("Send to the shared reverb"));
+                    ui.add(egui::Slider::new(&mut mix.delay_send, 0.0..=1.0).text(tr("Dly")))
+                        .on_hover_text(tr("Send to the shared delay"));
+                    ducking_menu(ui, &mut mix.ducking, part.id, &parts);
+                    ui.toggle_value(&mut mix.follows_soft_pedal, "U")
+                        .on_hover_text(tr("Una corda: follows the Soft pedal"));
+                }
+                ui.end_row();
+            }
+        });
+
+    send_bus_controls(ui, &mut shared.buses);
+    limiter_controls(ui, &mut shared.limiter);
+
+    let current = shared.params.clone();
+    let mut removed = None;
+    for (index, part) in shared.added_parts.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut part.name).desired_width(90.0));
+            ComboBox::from_id_source(("part_preset", part.id))
+                .selected_text(tr("Load preset"))
+                .show_ui(ui, |ui| {
+                    for preset in presets {
+                        if ui.selectable_label(false, preset.name.as_str()).clicked() {
+                            part.params = preset.params.clone();
+                        }
+                    }
+                });
+            if ui
+                .button(tr("Use current"))
+                .on_hover_text(tr("Copy the sound being edited into this part"))
+                .clicked()
+            {
+                part.params = current.clone();
+            }
+            let mut ranged = matches!(part.source, NoteSource::Range { .. });
+            if ui
+                .checkbox(&mut ranged, tr("Keys"))
+                .on_hover_text(tr("Only play this part on a range of keys"))
+                .changed()
+            {
+                part.source = if ranged {
+                    NoteSource::Range { low: 48, high: 72 }
+                } else {
+                    NoteSource::All
+                };
+            }
+            if let NoteSource::Range { low, high } = &mut part.source {
+                let label = |n: f64, _| note_label(n as u8, naming);
+                ui.add(
+                    egui::DragValue::new(low)
+                        .clamp_range(0..=127)
+                        .custom_formatter(label),
+                );
+                ui.label("–");
+                ui.add(
+                    egui::DragValue::new(high)
+                        .clamp_range(0..=127)
+                        .custom_formatter(label),
+                );
+                *high = (*high).max(*low);
+            }
+            if ui
+                .small_button("✖")
+                .on_hover_text(tr("Remove this part"))
+                .clicked()
+            {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        shared.added_parts.remove(index);
+    }
+    if ui
+        .button(tr("Add part"))
+        .on_hover_text(tr("Add a part playing a copy of the sound being edited"))
+        .clicked()
+    {
+        let name = tr_fmt(
+            "Part {number}",
+            &[("number", &(shared.added_parts.len() + 1))],
+        );
+        shared.add_part(name, current);
+    }
+}
+
+/// Sidechain ducking for the part `id` from any of the other `parts`.
+fn ducking_menu(ui: &mut egui::Ui, ducking: &mut Option<Ducking>, id: u32, parts: &[Part]) {
+    let title = if ducking.is_some() {
+        "Duck ●"
+    } else {
+        "Duck"
+    };
+    ui.menu_button(title, |ui| {
+        let mut on = ducking.is_some();
+        if ui
+            .checkbox(&mut on, tr("Duck under another part"))
+            .on_hover_text(tr("Turn this part down while the chosen part plays"))
+            .changed()
+        {
+            *ducking = on.then(|| Ducking {
+                source: parts
+                    .iter()
+                    .map(|part| part.id)
+                    .find(|source| *source != id)
+                    .unwrap_or(MAIN_PART),
+                ..Ducking::default()
+            });
+        }
+        let Some(ducking) = ducking else {
+            return;
+        };
+        let source_name = parts
+            .iter()
+            .find(|part| part.id == ducking.source)
+            .map_or("(removed)", |part| part.name.as_str());
+        ComboBox::from_id_source(("duck_source", id))
+            .selected_text(source_name)
+            .show_ui(ui, |ui| {
+                for part in parts.iter().filter(|part| part.id != id) {
+                    ui.selectable_value(&mut ducking.source, part.id, part.name.as_str());
+                }
+            });
+        ui.add(egui::Slider::new(&mut ducking.amount, 0.0..=1.0).text(tr("Amount")));
+        ui.add(
+            egui::Slider::new(&mut ducking.attack_ms, 0.1..=100.0)
+                .logarithmic(true)
+                .suffix(" ms")
+                .text(tr("Attack")),
+        );
+        ui.add(
+            egui::Slider::new(&mut ducking.release_ms, 10.0..=2000.0)
+                .logarithmic(true)
+                .suffix(" ms")
+                .text(tr("Release")),
+        );
+    });
+}
+
+fn send_bus_controls(ui: &mut egui::Ui, buses: &mut SendBuses) {
+    ui.horizontal(|ui| {
+        ui.label(tr("Reverb"));
+        ui.add(egui::Slider::new(&mut buses.reverb_size, 0.0..=1.0).text(tr("Size")));
+        ui.add(egui::Slider::new(&mut buses.reverb_damping, 0.0..=1.0).text(tr("Damping")))
+            .on_hover_text(tr("Higher darkens the tail"));
+        ui.add(egui::Slider::new(&mut buses.reverb_return, 0.0..=1.0).text(tr("Return")));
+    });
+    ui.horizontal(|ui| {
+        ui.label(tr("Delay"));
+        let mut millis = buses.delay_seconds * 1000.0;
+        if ui
+            .add(
+                egui::Slider::new(&mut millis, 10.0..=MAX_DELAY_SECONDS * 1000.0)
+                    .logarithmic(true)
+                    .suffix(" ms")
+                    .text(tr("Time")),
+            )
+            .changed()
+        {
+            buses.delay_seconds = millis / 1000.0;
+        }
+        ui.add(egui::Slider::new(&mut buses.delay_feedback, 0.0..=0.9).text(tr("Feedback")));
+        ui.add(egui::Slider::new(&mut buses.delay_return, 0.0..=1.0).text(tr("Return")));
+    });
+}
+
+fn limiter_controls(ui: &mut egui::Ui, limiter: &mut LimiterSettings) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut limiter.enabled, tr("Limiter"))
+            .on_hover_text(tr(
+                "Turns the whole mix down just enough to keep its peaks under the ceiling, \
+                 instead of clipping at the output",
+            ));
+        ui.add_enabled(
+            limiter.enabled,
+            egui::Slider::new(&mut limiter.ceiling_db, CEILING_RANGE_DB)
+                .suffix(" dB")
+                .text(tr("Ceiling")),
+        );
+    });
+}
+
+fn part_mix_row(ui: &mut egui::Ui, mix: &mut PartMix) {
+    ui.add(egui::Slider::new(&mut mix.volume, 0.0..=1.0).text(tr("Vol")));
+    let pan = ui
+        .add(egui::Slider::new(&mut mix.pan, -1.0..=1.0).text(tr("Pan")))
+        .on_hover_text(tr("Double-click to centre"));
+    if pan.double_clicked() {
+        mix.pan = 0.0;
+    }
+    ui.add(
+        egui::DragValue::new(&mut mix.transpose)
+            .clamp_range(-24..=24)
+            .suffix(" st"),
+    )
+    .on_hover_text(tr("Transpose in semitones"));
+}
+
+/// Returns true when the glide toggle changed; the glide time lives in the patch.
+fn mouse_glide_controls(
+    ui: &mut egui::Ui,
+    mouse_glide: &mut bool,
+    params: &mut SynthParams,
+) -> bool {
+    let changed = ui
+        .checkbox(mouse_glide, tr("Glide between keys"))
+        .on_hover_text(tr(
+            "Dragging across the piano slides the pitch instead of retriggering",
+        ))
+        .changed();
+    param_slider(ui, params, ParamId::Glide);
+    ui.checkbox(&mut params.glide_always, tr("Glide every note"))
+        .on_hover_text(tr(
+            "Every new note slides in from the last one, not only dragged and legato notes",
+        ));
+    changed
+}
+
+/// How far one arrow key press moves the focused XY pad, as a fraction of each axis.
+const XY_PAD_STEP: f32 = 0.02;
+
+/// Axis pickers plus a square pad that sets both assigned parameters at once
+/// while dragged. Returns true when an axis assignment changed.
+fn xy_pad(ui: &mut egui::Ui, settings: &mut AppSettings, params: &mut SynthParams) -> bool {
+    let before = (settings.xy_pad_x, settings.xy_pad_y);
+    ui.horizontal(|ui| {
+        for (label, axis) in [("X", &mut settings.xy_pad_x), ("Y", &mut settings.xy_pad_y)] {
+            ui.label(label);
+            ComboBox::from_id_source(("xy_pad_axis", label))
+                .selected_text(axis.label())
+                .show_ui(ui, |ui| {
+                    for id in ParamId::ALL {
+                        ui.selectable_value(axis, id, id.label());
+                    }
+                });
+        }
+    });
+
+    let (x_param, y_param) = (settings.xy_pad_x, settings.xy_pad_y);
+    let side = ui.available_width().clamp(120.0, 220.0);
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::click_and_drag());
+    describe(&response, egui::WidgetType::Slider, || {
+        format!(
+            "XY pad: {} {}, {} {}",
+            x_param.label(),
+            x_param.format(x_param.get(params)),
+            y_param.label(),
+            y_param.format(y_param.get(params)),
+        )
+    });
+    if let Some(pos) = response.interact_pointer_pos() {
+        *x_param.get_mut(params) = x_param.denormalize((pos.x - rect.min.x) / rect.width());
+        *y_param.get_mut(params) = y_param.denormalize((rect.max.y - pos.y) / rect.height());
+    }
+    if response.has_focus() {
+        let nudge = ui.input(|i| {
+            let step = |minus, plus| {
+                (i.key_pressed(plus) as i32 - i.key_pressed(minus) as i32) as f32 * XY_PAD_STEP
+            };
+            egui::vec2(
+                step(egui::Key::ArrowLeft, egui::Key::ArrowRight),
+                step(egui::Key::ArrowDown, egui::Key::ArrowUp),
+            )
+        });
+        for (param, delta) in [(x_param, nudge.x), (y_param, nudge.y)] {
+            if delta != 0.0 {
+                let position = param.normalize(param.get(params)) + delta;
+                *param.get_mut(params) = param.denormalize(position.clamp(0.0, 1.0));
+            }
+        }
+    }
+
+    let visuals = ui.visuals();
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, Rounding::same(4.0), visuals.extreme_bg_color);
+    let grid = Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color);
+    for step in 1..4 {
+        let t = step as f32 / 4.0;
+        let x = egui::lerp(rect.x_range(), t);
+        let y = egui::lerp(rect.y_range(), t);
+        painter.line_segment([egui::pos2(x, rect.min.y), egui::pos2(x, rect.max.y)], grid);
+        painter.line_segment([egui::pos2(rect.min.x, y), egui::pos2(rect.max.x, y)], grid);
+    }
+    let puck = egui::pos2(
+        egui::lerp(rect.x_range(), x_param.normalize(x_param.get(params))),
+        egui::lerp(rect.y_range(), 1.0 - y_param.normalize(y_param.get(params))),
+    );
+    painter.circle_filled(puck, 7.0, ACCENT);
+
+    before != (settings.xy_pad_x, settings.xy_pad_y)
+}
+
+fn shift_octave(octave_offset: &mut i32, delta: i32) {
+    *octave_offset = (*octave_offset + delta).clamp(-MAX_OCTAVE_SHIFT, MAX_OCTAVE_SHIFT);
+}
+
+/// What the computer keyboard can play with the current keymap and octave shift.
+struct KeyboardReach {
+    /// Lowest and highest reachable note.
+    range: Option<(u8, u8)>,
+    /// Key to press for each note, when key labels are shown.
+    labels: BTreeMap<u8, String>,
+}
+
+impl KeyboardReach {
+    fn new(keymap: Option<&Keymap>, octave_offset: i32, with_labels: bool) -> Self {
+        let shift = |note: u8| (note as i32 + octave_offset * 12).clamp(0, 127) as u8;
+        let range = match keymap {
+            Some(keymap) => keymap.note_range(),
+            None => Some(qwerty_range()),
+        }
+        .map(|(first, last)| (shift(first), shift(last)));
+        let mut labels = BTreeMap::new();
+        if with_labels {
+            let bindings: Vec<(&str, u8)> = match keymap {
+                Some(keymap) => keymap.sorted_by_note(),
+                None => qwerty_bindings().collect(),
+            };
+            for (key, note) in bindings {
+                // Several keys can share a note; the first one is enough of a hint.
+                labels.entry(shift(note)).or_insert_with(|| key_symbol(key));
+            }
+        }
+        Self { range, labels }
+    }
+}
+
+/// Short printable form of a stored key name, e.g. ";" for "Semicolon".
+fn key_symbol(name: &str) -> String {
+    egui::Key::from_name(name)
+        .map_or(name, |key| key.symbol_or_name())
+        .to_string()
+}
+
+/// Key labels shrink with the keys so they stay inside them when zoomed out.
+fn key_label_size(key_width: f32) -> f32 {
+    (key_width * 0.5).clamp(7.0, 14.0)
+}
+
+fn output_selector(
+    ui: &mut egui::Ui,
+    devices: &[String],
+    selected: &mut Option<String>,
+    audio_error: &mut Option<String>,
+) -> bool {
+    let before = selected.clone();
+    ComboBox::from_id_source("output_selector")
+        .width(180.0)
+        .selected_text(selected.as_deref().unwrap_or("Default output"))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(selected, None, tr("Default output"));
+            for name in devices {
+                ui.selectable_value(selected, Some(name.clone()), name);
+            }
+        });
+    let changed = before != *selected;
+    if changed {
+        *audio_error = None;
+    }
+    changed
+}
+
+/// Microphone picker: off, the system default, or a named input.
+/// Off, the default named `default_label`, or one of `devices`.
+fn input_selector(
+    ui: &mut egui::Ui,
+    id: &str,
+    default_label: &str,
+    devices: &[String],
+    enabled: &mut bool,
+    selected: &mut Option<String>,
+) -> bool {
+    let before = (*enabled, selected.clone());
+    let text = match (*enabled, selected.as_deref()) {
+        (false, _) => "Off".to_string(),
+        (true, None) => default_label.to_string(),
+        (true, Some(name)) => name.to_string(),
+    };
+    ComboBox::from_id_source(id)
+        .width(180.0)
+        .selected_text(text)
+        .show_ui(ui, |ui| {
+            if ui.selectable_label(!*enabled, tr("Off")).clicked() {
+                *enabled = false;
+            }
+            if ui
+                .selectable_label(*enabled && selected.is_none(), default_label)
+                .clicked()
+            {
+                *enabled = true;
+                *selected = None;
+            }
+            for name in devices {
+                let current = *enabled && selected.as_deref() == Some(name.as_str());
+                if ui.selectable_label(current, name).clicked() {
+                    *enabled = true;
+                    *selected = Some(name.clone());
+                }
+            }
+        });
+    before != (*enabled, selected.clone())
+}
+
+fn keybind_selector(ui: &mut egui::Ui, settings: &mut AppSettings) -> bool {
+    let before = settings.keybinds;
+    ComboBox::from_id_source("keybinds_selector")
+        .selected_text(settings.keybinds.label())
+        .show_ui(ui, |ui| {
+            for scheme in KeybindScheme::ALL {
+                ui.selectable_value(&mut settings.keybinds, scheme, scheme.label());
+            }
+        });
+    settings.keybinds != before
+}
+
+fn settings_popup(ctx: &egui::Context, app: &mut SynthApp) {
+    let mut imported = false;
+    let mut startup_changed = false;
+    let mut window_changed = false;
+    let mut display_changed = false;
+    let mut midi_changed = false;
+    let mut midi_input_changed = false;
+    let mut input_changed = false;
+    let mut mic_mode_changed = false;
+    let mut log_changed = false;
+    let mut tuning_changed = false;
+    if app.settings_open {
+        egui::Window::new(tr("Settings"))
+            .id(Id::new("settings_window"))
+            .open(&mut app.settings_open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size(egui::vec2(420.0, 320.0))
+            .show(ctx, |ui| {
+                ui.heading(tr("App Settings"));
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(tr("Theme"));
+                    let _ = theme_selector(ui, ctx, &mut app.settings);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Language"));
+                    display_changed = language_selector(ui, &mut app.settings.language);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Middle C"));
+                    ComboBox::from_id_source("octave_naming")
+                        .selected_text(app.settings.octave_naming.label())
+                        .show_ui(ui, |ui| {
+                            for naming in OctaveNaming::ALL {
+                                display_changed |= ui
+                                    .selectable_value(
+                                        &mut app.settings.octave_naming,
+                                        naming,
+                                        naming.label(),
+                                    )
+                                    .changed();
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(tr("Octave numbering used for note names throughout the app"));
+                window_changed = ui
+                    .checkbox(&mut app.settings.always_on_top, tr("Always on top"))
+                    .on_hover_text(tr("Keep the synth above notation software or a lesson video"))
+                    .changed();
+                ui.horizontal(|ui| {
+                    ui.label(tr("Log"));
+                    ComboBox::from_id_source("log_level")
+                        .selected_text(app.settings.log_level.label())
+                        .show_ui(ui, |ui| {
+                            for level in LogLevel::ALL {
+                                log_changed |= ui
+                                    .selectable_value(
+                                        &mut app.settings.log_level,
+                                        level,
+                                        level.label(),
+                                    )
+                                    .changed();
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(tr_fmt(
+                    "How much is written to {path}; older logs are kept next to it",
+                    &[("path", &logging::log_path(&config_dir()).display())],
+                ));
+                ui.horizontal(|ui| {
+                    ui.label(tr("Keybinds"));
+                    let _ = keybind_selector(ui, &mut app.settings);
+                });
+                egui::CollapsingHeader::new(tr("Shortcuts"))
+                    .id_source("shortcuts")
+                    .show(ui, |ui| {
+                        egui::Grid::new("shortcut_table")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for action in Action::ALL {
+                                    ui.label(tr(action.label()));
+                                    ui.monospace(chord_label(app.settings.keybinds, action));
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Keymap"));
+                    ui.label(
+                        app.settings
+                            .active_keymap
+                            .as_deref()
+                            .unwrap_or(tr("Built-in")),
+                    );
+                    if ui.button(tr("Edit keymaps…")).clicked() {
+                        app.keymap_editor.open = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Gamepad"));
+                    let pads = app.gamepad.connected();
+                    match (&app.gamepad.error, pads.is_empty()) {
+                        (Some(err), _) => ui.colored_label(Color32::RED, err),
+                        (None, true) => ui.label(tr("None connected")),
+                        (None, false) => ui.label(pads.join(", ")),
+                    };
+                })
+                .response
+                .on_hover_text(
+                    tr("D-pad and face buttons play a C major scale, hold LB for triads. \
+                     Left stick up adds vibrato, right stick sweeps the filter, \
+                     RT/LT raise and lower the master gain."),
+                );
+                ui.horizontal(|ui| {
+                    ui.label(tr("Output"));
+                    let _ = output_selector(
+                        ui,
+                        &app.output_devices,
+                        &mut app.settings.output_device,
+                        &mut app.audio_error,
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Input"));
+                    input_changed = input_selector(
+                        ui,
+                        "input_selector",
+                        "Default input",
+                        &app.input_devices,
+                        &mut app.settings.mic_enabled,
+                        &mut app.settings.input_device,
+                    );
+                    if let Some(err) = &app.mic_error {
+                        ui.colored_label(Color32::RED, err);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("MIDI in"));
+                    midi_input_changed = input_selector(
+                        ui,
+                        "midi_selector",
+                        "First port found",
+                        &app.midi_ports,
+                        &mut app.settings.midi_enabled,
+                        &mut app.settings.midi_port,
+                    );
+                    if ui
+                        .small_button(tr("Rescan"))
+                        .on_hover_text(tr("Look again for controllers plugged in since launch"))
+                        .clicked()
+                    {
+                        app.midi_ports = list_midi_port_names();
+                        midi_input_changed = true;
+                    }
+                    match (&app.midi_error, &app.midi_input) {
+                        (Some(err), _) => {
+                            ui.colored_label(Color32::RED, err);
+                        }
+                        (None, None) if app.settings.midi_enabled => {
+                            ui.label(tr("None connected"));
+                        }
+                        _ => {}
+                    }
+                })
+                .response
+                .on_hover_text(
+                    tr("Play from a MIDI keyboard on any channel. The sustain pedal (CC 64) \
+                     latches like Hold, CC 67 works the Soft pedal, and the CC mappings \
+                     below drive the sound."),
+                );
+                ui.horizontal(|ui| {
+                    ui.label(tr("Bend range"));
+                    midi_changed |= ui
+                        .add(
+                            egui::Slider::new(&mut app.settings.bend_range, 0.0..=24.0)
+                                .step_by(1.0)
+                                .suffix(" st"),
+                        )
+                        .changed();
+                })
+                .response
+                .on_hover_text(tr("Semitones the pitch bend reaches at either end"));
+                ui.horizontal(|ui| {
+                    ui.label(tr("Tuning"));
+                    ComboBox::from_id_source("temperament")
+                        .selected_text(&app.tuning_name)
+                        .show_ui(ui, |ui| {
+                            for temperament in Temperament::ALL {
+                                let current = app.settings.tuning_file.is_none()
+                                    && app.settings.temperament == temperament;
+                                if ui.selectable_label(current, temperament.label()).clicked() {
+                                    app.settings.temperament = temperament;
+                                    app.settings.tuning_file = None;
+                                    tuning_changed = true;
+                                }
+                            }
+                        });
+                    let naming = app.settings.octave_naming;
+                    ui.label(tr("Root"));
+                    tuning_changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut app.settings.tuning_root)
+                                .clamp_range(0..=127)
+                                .custom_formatter(|n, _| note_label(n as u8, naming)),
+                        )
+                        .on_hover_text(
+                            tr("Key the tuning is built up from, which keeps its equal-tempered pitch; \
+                             just and meantone sound purest in keys near it"),
+                        )
+                        .changed();
+                })
+                .response
+                .on_hover_text(
+                    app.settings.tuning_file.as_deref().unwrap_or(
+                        "Historical temperaments for early music, or load a Scala .scl file below",
+                    ),
+                );
+                ui.horizontal(|ui| {
+                    let field = ui.add(
+                        egui::TextEdit::singleline(&mut app.tuning_input)
+                            .hint_text(tr("Path to a .scl"))
+                            .desired_width(200.0),
+                    );
+                    let entered =
+                        field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    let load = ui
+                        .button(tr("Load"))
+                        .on_hover_text(tr("Tune every part to a Scala scale"))
+                        .clicked();
+                    if (load || entered) && !app.tuning_input.trim().is_empty() {
+                        app.settings.tuning_file = Some(app.tuning_input.trim().to_string());
+                        tuning_changed = true;
+                    }
+                });
+                if let Some(err) = &app.tuning_error {
+                    ui.colored_label(Color32::RED, err);
+                }
+                ui.horizontal(|ui| {
+                    ui.label(tr("Mic mode"));
+                    ComboBox::from_id_source("mic_mode")
+                        .selected_text(app.settings.mic_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in MicMode::ALL {
+                                mic_mode_changed |= ui
+                                    .selectable_value(
+                                        &mut app.settings.mic_mode,
+                                        mode,
+                                        mode.label(),
+                                    )
+                                    .changed();
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(
+                    tr("Autotune pulls your voice onto the nearest note of the scale picked \
+                     above the piano, as strongly and as fast as the Autotune and Retune time \
+                     sliders say, and plays it through the EQ. Play notes turns the pitch you sing, hum or whistle \
+                     into synth notes, one at a time."),
+                );
+                ui.horizontal(|ui| {
+                    ui.label(tr("Take mix"));
+                    mic_mode_changed |= ui
+                        .add(
+                            egui::Slider::new(&mut app.settings.take_synth_level, 0.0..=1.5)
+                                .text(tr("synth")),
+                        )
+                        .changed();
+                    mic_mode_changed |= ui
+                        .add(
+                            egui::Slider::new(&mut app.settings.take_mic_level, 0.0..=2.0)
+                                .text(tr("mic")),
+                        )
+                        .changed();
+                })
+                .response
+                .on_hover_text(tr("Levels of the synth and the dry microphone in a recorded take"));
+                egui::CollapsingHeader::new(tr("MIDI controls"))
+                    .id_source("midi_controls")
+                    .show(ui, |ui| {
+                        midi_changed = cc_mapping_controls(ui, &mut app.settings.cc_mappings);
+                        ui.separator();
+                        midi_changed |= breath_controls(ui, &mut app.settings.breath);
+                    });
+                ui.separator();
+                ui.label(tr("On launch"));
+                startup_changed = startup_controls(ui, &mut app.settings);
+                ui.separator();
+                ui.label(tr("Layout & sizing"));
+                let _ = layout_controls(ui, &mut app.settings);
+                ui.separator();
+                ui.label(tr("Import / export"));
+                imported = transfer_controls(
+                    ui,
+                    &mut app.transfer_path,
+                    &mut app.transfer_status,
+                    &mut app.settings,
+                );
+            });
+    }
+    if input_changed {
+        app.switch_input_device();
+    }
+    if tuning_changed || imported {
+        app.apply_tuning();
+    }
+    if midi_input_changed {
+        app.switch_midi_input();
+    }
+    if (midi_changed || imported)
+        && let Some(midi) = &app.midi_input
+    {
+        midi.set_mappings(&app.settings.cc_mappings);
+    }
+    if imported || log_changed {
+        logging::set_level(app.settings.log_level);
+    }
+    if imported || window_changed {
+        apply_window_level(ctx, app.settings.always_on_top);
+    }
+    if imported {
+        apply_theme(ctx, app.settings.theme);
+        apply_touch_layout(ctx, app.settings.touch_layout);
+        set_language(app.settings.language);
+        if let Ok(mut shared) = app.shared.lock() {
+            shared.params = app.settings.params.clone();
+            shared.split = app.settings.split.clone();
+            shared.main_mix = app.settings.main_mix;
+            shared.layer = app.settings.layer.clone();
+            shared.added_parts = app.settings.added_parts.clone();
+            shared.buses = app.settings.buses;
+            shared.limiter = app.settings.limiter;
+        }
+        app.saver.save(&app.settings);
+    } else if startup_changed
+        || window_changed
+        || display_changed
+        || midi_changed
+        || input_changed
+        || midi_input_changed
+        || mic_mode_changed
+        || log_changed
+        || tuning_changed
+    {
+        app.saver.save(&app.settings);
+    }
+}
+
+/// The CC mapping table, with a template picker that replaces it with a
+/// controller's stock layout. Returns true when anything changed.
+/// Routing of a breath controller to volume and cutoff. Returns true when
+/// anything changed.
+fn breath_controls(ui: &mut egui::Ui, breath: &mut BreathRouting) -> bool {
+    let mut changed = ui
+        .checkbox(
+            &mut breath.enabled,
+            tr_fmt("Breath controller (CC {cc})", &[("cc", &BREATH_CC)]),
+        )
+        .on_hover_text(tr(
+            "Let breath pressure shape the sound, for wind controllers",
+        ))
+        .changed();
+    ui.add_enabled_ui(breath.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(tr("Response"));
+            ComboBox::from_id_source("breath_curve")
+                .selected_text(breath.curve.label())
+                .show_ui(ui, |ui| {
+                    for curve in BreathCurve::ALL {
+                        changed |= ui
+                            .selectable_value(&mut breath.curve, curve, curve.label())
+                            .changed();
+                    }
+                });
+        });
+        changed |= ui
+            .add(egui::Slider::new(&mut breath.amplitude, 0.0..=1.0).text(tr("To volume")))
+            .on_hover_text(tr("At full, notes are silent until you blow"))
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut breath.cutoff, 0.0..=1.0).text(tr("To cutoff")))
+            .on_hover_text(tr("How far the filter closes as the breath eases off"))
+            .changed();
+    });
+    changed
+}
+
+fn cc_mapping_controls(ui: &mut egui::Ui, mappings: &mut Vec<CcMapping>) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label(tr("Template"));
+        ComboBox::from_id_source("controller_template")
+            .selected_text(tr("Load template…"))
+            .show_ui(ui, |ui| {
+                for template in ControllerTemplate::ALL {
+                    if ui.selectable_label(false, template.label()).clicked() {
+                        *mappings = template.mappings();
+                        changed = true;
+                    }
+                }
+            });
+    })
+    .response
+    .on_hover_text(tr(
+        "Replace the table with the stock knob and fader layout of a controller",
+    ));
+
+    if mappings.is_empty() {
+        ui.weak(tr("No controls mapped"));
+    }
+    let mut removed = None;
+    egui::Grid::new("cc_mappings").striped(true).show(ui, |ui| {
+        for (index, mapping) in mappings.iter_mut().enumerate() {
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut mapping.cc)
+                        .clamp_range(0..=119)
+                        .prefix("CC "),
+                )
+                .changed();
+            ComboBox::from_id_source(("cc_param", index))
+                .selected_text(mapping.param.label())
+                .show_ui(ui, |ui| {
+                    for param in ParamId::ALL {
+                        changed |= ui
+                            .selectable_value(&mut mapping.param, param, param.label())
+                            .changed();
+                    }
+                });
+            if ui.small_button("✕").on_hover_text(tr("Remove")).clicked() {
+                removed = Some(index);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(index) = removed {
+        mappings.remove(index);
+        changed = true;
+    }
+    if ui.button(tr("Add mapping")).clicked() {
+        let cc = (0..=119)
+            .find(|cc| mappings.iter().all(|mapping| mapping.cc != *cc))
+            .unwrap_or(0);
+        mappings.push(CcMapping {
+            cc,
+            param: ParamId::FilterCutoff,
+        });
+        changed = true;
+    }
+    changed
+}
+
+fn apply_window_level(ctx: &egui::Context, always_on_top: bool) {
+    let level = if always_on_top {
+        egui::WindowLevel::AlwaysOnTop
+    } else {
+        egui::WindowLevel::Normal
+    };
+    ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+}
+
+/// Lobby for hosting, finding and joining LAN jam sessions, and the player
+/// list with latencies once in one.
+fn jam_window(ctx: &egui::Context, app: &mut SynthApp) {
+    let jam = &mut app.jam;
+    let mut leave = false;
+    egui::Window::new(tr("Jam session"))
+        .open(&mut jam.open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if let Some(session) = &mut jam.session {
+                if session.is_host() {
+                    ui.label(tr_fmt(
+                        "Hosting on port {port}",
+                        &[("port", &session.local_port().unwrap_or(jam.port))],
+                    ));
+                } else {
+                    ui.label(tr("Joined"));
+                }
+                if session.peers.is_empty() {
+                    ui.weak(tr("Waiting for other players…"));
+                }
+                egui::Grid::new("jam_peers").striped(true).show(ui, |ui| {
+                    for peer in &session.peers {
+                        ui.label(&peer.name);
+                        ui.monospace(peer.addr.to_string());
+                        match peer.rtt_ms {
+                            Some(rtt) => ui.label(format!("{:.0} ms", rtt / 2.0)),
+                            None => ui.weak(tr("measuring…")),
+                        }
+                        .on_hover_text(tr("One-way latency, half the measured round trip"));
+                        ui.end_row();
+                    }
+                });
+                ui.add(
+                    egui::Slider::new(&mut session.jitter_ms, 0.0..=200.0)
+                        .text(tr("Jitter buffer (ms)")),
+                )
+                .on_hover_text(tr(
+                    "Remote notes are delayed by this much so they keep their timing over a \
+                     busy network",
+                ));
+                leave = ui.button(tr("Leave")).clicked();
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Your name"));
+                ui.text_edit_singleline(&mut jam.name);
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Port"));
+                ui.add(egui::DragValue::new(&mut jam.port).clamp_range(1024..=65535));
+                if ui.button(tr("Host")).clicked() {
+                    match JamSession::host(&jam.name, jam.port) {
+                        Ok(session) => jam.session = Some(session),
+                        Err(err) => jam.error = Some(err),
+                    }
+                }
+            });
+            ui.separator();
+            let mut join = None;
+            ui.horizontal(|ui| {
+                ui.label(tr("Join"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut jam.address).hint_text(tr("host or host:port")),
+                );
+                if ui.button(tr("Join")).clicked() {
+                    join = Some(jam.address.clone());
+                }
+            });
+            if ui.button(tr("Find sessions")).clicked() {
+                match JamLobby::search(jam.port) {
+                    Ok(lobby) => jam.lobby = Some(lobby),
+                    Err(err) => jam.error = Some(err),
+                }
+            }
+            if let Some(lobby) = &mut jam.lobby {
+                lobby.poll();
+                if lobby.found.is_empty() {
+                    ui.weak(tr("Searching…"));
+                }
+                for (name, addr) in &lobby.found {
+                    let label = tr_fmt(
+                        "Join {name} ({address})",
+                        &[("name", name), ("address", addr)],
+                    );
+                    if ui.button(label).clicked() {
+                        join = Some(addr.to_string());
+                    }
+                }
+            }
+            if let Some(address) = join {
+                match JamSession::join(&jam.name, &address) {
+                    Ok(session) => {
+                        jam.session = Some(session);
+                        jam.lobby = None;
+                        jam.error = None;
+                    }
+                    Err(err) => jam.error = Some(err),
+                }
+            }
+            if let Some(err) = &jam.error {
+                ui.colored_label(Color32::RED, err);
+            }
+        });
+    if leave && let (Some(session), Ok(mut shared)) = (jam.session.take(), app.shared.lock()) {
+        session.leave(&mut shared);
+    }
+}
+
+/// Ear training: asks an interval or chord, takes the answer by name or
+/// from the keyboard, and keeps score in the settings.
+fn ear_window(ctx: &egui::Context, app: &mut SynthApp) {
+    let ear = &mut app.ear;
+    let stats = &mut app.settings.ear_stats;
+    let mut answered = false;
+    egui::Window::new(tr("Ear training"))
+        .open(&mut app.ear_open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for exercise in Exercise::ALL {
+                    ui.selectable_value(&mut ear.exercise, exercise, exercise.label());
+                }
+                ui.separator();
+                for mode in AnswerMode::ALL {
+                    ui.selectable_value(&mut ear.answer_mode, mode, mode.label());
+                }
+            });
+            ui.horizontal(|ui| {
+                let label = if ear.is_asking() { "Skip" } else { "Next" };
+                if ui.button(label).clicked() {
+                    ear.next_question();
+                }
+                if ui
+                    .add_enabled(!ear.is_playing(), egui::Button::new(tr("Replay")))
+                    .clicked()
+                {
+                    ear.replay();
+                }
+            });
+
+            if ear.is_asking() {
+                match ear.answer_mode {
+                    AnswerMode::Identify => {
+                        ui.label(tr("What was that?"));
+                        ui.horizontal_wrapped(|ui| {
+                            for (index, (name, _)) in ear.exercise.choices().iter().enumerate() {
+                                if ui.button(*name).clicked() {
+                                    ear.answer(index, stats);
+                                    answered = true;
+                                }
+                            }
+                        });
+                    }
+                    AnswerMode::PlayBack => {
+                        ui.label(tr("Play it back on the piano, starting from any note."));
                     }
                 }
             }
-        }
-    }
+            if let Some((correct, text)) = &ear.feedback {
+                let color = if *correct {
+                    Color32::from_rgb(60, 190, 110)
+                } else {
+                    ui.visuals().warn_fg_color
+                };
+                ui.colored_label(color, text);
+            }
 
-    if pointer_down {
-        if let Some(note) = pointer_note {
-            if mouse_note != &Some(note) {
-                if let Some(prev) = mouse_note.take() {
-                    shared.release_note(prev);
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.strong(tr_fmt("Streak {count}", &[("count", &stats.streak)]));
+                ui.label(tr_fmt("best {count}", &[("count", &stats.best_streak)]));
+                ui.label(tr_fmt(
+                    "{correct} of {attempts} right",
+                    &[("correct", &stats.correct), ("attempts", &stats.attempts)],
+                ));
+                if ui.small_button(tr("Reset")).clicked() {
+                    *stats = EarStats::default();
+                    answered = true;
                 }
-                shared.press_note(note);
-                *mouse_note = Some(note);
-            }
-        } else if let Some(prev) = mouse_note.take() {
-            shared.release_note(prev);
-        }
-    } else if let Some(prev) = mouse_note.take() {
-        shared.release_note(prev);
+            });
+        });
+    if answered {
+        app.saver.save(&app.settings);
     }
+}
 
-    let white_idle = Color32::from_rgb(250, 250, 250);
-    let pressed_fill = ui.visuals().selection.bg_fill;
-
-    for (note, key_rect) in &white_layout {
-        let active = shared.is_pressed(*note);
-        let anim = ctx.animate_bool(Id::new(("white", note)), active);
-        let fill = blend_color(white_idle, pressed_fill, anim);
-        painter.rect(*key_rect, Rounding::same(4.0), fill, (1.0, Color32::BLACK));
-        painter.text(
-            egui::pos2(key_rect.center().x, key_rect.max.y - 6.0),
-            Align2::CENTER_BOTTOM,
-            note_label(*note),
-            FontId::monospace(12.0),
-            Color32::from_rgb(40, 40, 40),
-        );
+fn keymap_window(ctx: &egui::Context, app: &mut SynthApp) {
+    if !app.keymap_editor.open {
+        return;
     }
+    let editor = &mut app.keymap_editor;
+    let settings = &mut app.settings;
+    let mut changed = false;
+    egui::Window::new(tr("Keymap editor"))
+        .open(&mut editor.open)
+        .resizable(true)
+        .default_size(egui::vec2(360.0, 420.0))
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut editor.new_name);
+                let name = editor.new_name.trim().to_string();
+                let taken = settings.keymaps.iter().any(|keymap| keymap.name == name);
+                if ui
+                    .add_enabled(
+                        !name.is_empty() && !taken,
+                        egui::Button::new(tr("New keymap")),
+                    )
+                    .clicked()
+                {
+                    settings.keymaps.push(Keymap::qwerty(name));
+                    editor.editing = settings.keymaps.len() - 1;
+                    editor.new_name.clear();
+                    changed = true;
+                }
+            });
+            if settings.keymaps.is_empty() {
+                ui.label(tr("Create a keymap to start assigning keys."));
+                return;
+            }
+            editor.editing = editor.editing.min(settings.keymaps.len() - 1);
 
-    for (note, key_rect) in &black_layout {
-        let active = shared.is_pressed(*note);
-        let anim = ctx.animate_bool(Id::new(("black", note)), active);
-        let fill = blend_color(Color32::from_rgb(20, 20, 20), pressed_fill, anim);
-        painter.rect(
-            *key_rect,
-            Rounding::same(3.0),
-            fill,
-            (1.0, Color32::from_rgb(15, 15, 15)),
-        );
-    }
+            ui.horizontal(|ui| {
+                ComboBox::from_id_source("keymap_editing")
+                    .selected_text(settings.keymaps[editor.editing].name.as_str())
+                    .show_ui(ui, |ui| {
+                        for (index, keymap) in settings.keymaps.iter().enumerate() {
+                            ui.selectable_value(&mut editor.editing, index, keymap.name.as_str());
+                        }
+                    });
+                if ui.button(tr("Delete")).clicked() {
+                    let removed = settings.keymaps.remove(editor.editing);
+                    if settings.active_keymap.as_deref() == Some(removed.name.as_str()) {
+                        settings.active_keymap = None;
+                    }
+                    editor.editing = editor.editing.saturating_sub(1);
+                    editor.learn_note = None;
+                    changed = true;
+                }
+            });
+            let Some(keymap) = settings.keymaps.get_mut(editor.editing) else {
+                return;
+            };
 
-    response.on_hover_text("Click and drag to glide");
-}
+            let mut active = settings.active_keymap.as_deref() == Some(keymap.name.as_str());
+            if ui
+                .checkbox(&mut active, tr("Use for the computer keyboard"))
+                .changed()
+            {
+                settings.active_keymap = active.then(|| keymap.name.clone());
+                changed = true;
+            }
+            ui.separator();
+            match editor.learn_note {
+                Some(note) => {
+                    ui.colored_label(
+                        ACCENT,
+                        tr_fmt(
+                            "Press a computer key for {note}",
+                            &[("note", &note_label(note, settings.octave_naming))],
+                        ),
+                    );
+                }
+                None => {
+                    ui.label(tr("Click a key on the piano, then press a computer key."));
+                }
+            }
+            ui.separator();
 
-fn output_selector(
-    ui: &mut egui::Ui,
-    devices: &[String],
-    selected: &mut Option<String>,
-    audio_error: &mut Option<String>,
-) -> bool {
-    let before = selected.clone();
-    ComboBox::from_id_source("output_selector")
-        .width(180.0)
-        .selected_text(selected.as_deref().unwrap_or("Default output"))
-        .show_ui(ui, |ui| {
-            ui.selectable_value(selected, None, "Default output");
-            for name in devices {
-                ui.selectable_value(selected, Some(name.clone()), name);
+            let mut unbind = None;
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("keymap_bindings")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let naming = settings.octave_naming;
+                        for (key, note) in keymap.sorted_by_note() {
+                            ui.label(note_label(note, naming));
+                            ui.monospace(key);
+                            if ui.small_button("✕").clicked() {
+                                unbind = Some(key.to_string());
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+            if let Some(key) = unbind {
+                keymap.unbind(&key);
+                changed = true;
             }
         });
-    let changed = before != *selected;
     if changed {
-        *audio_error = None;
+        app.saver.save(&app.settings);
     }
-    changed
-}
-
-fn keybind_selector(ui: &mut egui::Ui, settings: &mut AppSettings) -> bool {
-    let before = settings.keybinds;
-    ComboBox::from_id_source("keybinds_selector")
-        .selected_text(settings.keybinds.label())
-        .show_ui(ui, |ui| {
-            for scheme in KeybindScheme::ALL {
-                ui.selectable_value(&mut settings.keybinds, scheme, scheme.label());
-            }
-        });
-    settings.keybinds != before
 }
 
-fn settings_popup(ctx: &egui::Context, app: &mut SynthApp) {
-    if app.settings_open {
-        egui::Window::new("Settings")
-            .open(&mut app.settings_open)
-            .collapsible(false)
-            .resizable(true)
-            .default_size(egui::vec2(420.0, 320.0))
-            .show(ctx, |ui| {
-                ui.heading("App Settings");
-                ui.separator();
-                ui.horizontal(|ui| {
-                    ui.label("Theme");
-                    let _ = theme_selector(ui, ctx, &mut app.settings);
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Keybinds");
-                    let _ = keybind_selector(ui, &mut app.settings);
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Output");
-                    let _ = output_selector(
-                        ui,
-                        &app.output_devices,
-                        &mut app.settings.output_device,
-                        &mut app.audio_error,
-                    );
-                });
-                ui.separator();
-                ui.label("Layout & sizing");
-                let _ = layout_controls(ui, &mut app.settings);
+fn startup_controls(ui: &mut egui::Ui, settings: &mut AppSettings) -> bool {
+    let before = (settings.startup_patch, settings.startup_preset.clone());
+    ui.horizontal(|ui| {
+        ui.label(tr("Load"));
+        ComboBox::from_id_source("startup_patch")
+            .selected_text(settings.startup_patch.label())
+            .show_ui(ui, |ui| {
+                for option in StartupPatch::ALL {
+                    ui.selectable_value(&mut settings.startup_patch, option, option.label());
+                }
             });
-    }
+        if settings.startup_patch == StartupPatch::Preset {
+            let presets = all_presets(&settings.user_presets);
+            let selected = if settings.startup_preset.is_empty() {
+                "Choose preset"
+            } else {
+                settings.startup_preset.as_str()
+            };
+            ComboBox::from_id_source("startup_preset")
+                .selected_text(selected.to_string())
+                .show_ui(ui, |ui| {
+                    for preset in &presets {
+                        ui.selectable_value(
+                            &mut settings.startup_preset,
+                            preset.name.clone(),
+                            preset.name.as_str(),
+                        );
+                    }
+                });
+        }
+    });
+    before != (settings.startup_patch, settings.startup_preset.clone())
 }
 
-fn layout_grid(ui: &mut egui::Ui, shared: &mut SynthShared, settings: &mut AppSettings) -> bool {
-    let mut changed = false;
-    let resolved = match settings.layout_mode {
-        LayoutMode::Auto => auto_layout_for_width(ui.available_width()),
-        other => other,
-    };
-
-    match resolved {
-        LayoutMode::Stacked => {
-            changed |= layout_controls(ui, settings);
-            ui.add_space(6.0);
-            fl_card(
-                ui,
-                "Tone & Filter",
-                settings.card_padding,
-                settings.card_rounding,
-                |ui| tone_controls(ui, shared),
-            );
-            ui.add_space(6.0);
-            fl_card(
-                ui,
-                "Motion & Noise",
-                settings.card_padding,
-                settings.card_rounding,
-                |ui| modulation_controls(ui, shared),
-            );
-            ui.add_space(6.0);
-            fl_card(
-                ui,
-                "EQ",
-                settings.card_padding,
-                settings.card_rounding,
-                |ui| eq_controls(ui, shared),
-            );
-        }
-        LayoutMode::TwoColumn => {
-            ui.columns(2, |columns| {
-                columns[0].vertical(|ui| {
-                    changed |= layout_controls(ui, settings);
-                    fl_card(
-                        ui,
-                        "Tone & Filter",
-                        settings.card_padding,
-                        settings.card_rounding,
-                        |ui| tone_controls(ui, shared),
-                    );
-                    ui.add_space(6.0);
-                    fl_card(
-                        ui,
-                        "Motion & Noise",
-                        settings.card_padding,
-                        settings.card_rounding,
-                        |ui| modulation_controls(ui, shared),
-                    );
-                });
-                columns[1].vertical(|ui| {
-                    fl_card(
-                        ui,
-                        "EQ",
-                        settings.card_padding,
-                        settings.card_rounding,
-                        |ui| eq_controls(ui, shared),
-                    );
-                });
+/// Returns true when `settings` was replaced by an imported file.
+fn transfer_controls(
+    ui: &mut egui::Ui,
+    path: &mut String,
+    status: &mut Option<String>,
+    settings: &mut AppSettings,
+) -> bool {
+    let mut imported = false;
+    ui.horizontal(|ui| {
+        ui.label(tr("File"));
+        ui.text_edit_singleline(path);
+    });
+    ui.horizontal(|ui| {
+        if ui.button(tr("Export")).clicked() {
+            *status = Some(match settings.export_to(Path::new(path.as_str())) {
+                Ok(()) => tr_fmt("Exported to {path}", &[("path", path)]),
+                Err(err) => err,
             });
         }
-        LayoutMode::ThreeColumn | LayoutMode::Auto => {
-            ui.columns(3, |columns| {
-                columns[0].vertical(|ui| {
-                    fl_card(
-                        ui,
-                        "Tone & Filter",
-                        settings.card_padding,
-                        settings.card_rounding,
-                        |ui| tone_controls(ui, shared),
-                    );
-                });
-                columns[1].vertical(|ui| {
-                    fl_card(
-                        ui,
-                        "Motion & Noise",
-                        settings.card_padding,
-                        settings.card_rounding,
-                        |ui| modulation_controls(ui, shared),
-                    );
-                });
-                columns[2].vertical(|ui| {
-                    fl_card(
-                        ui,
-                        "EQ",
-                        settings.card_padding,
-                        settings.card_rounding,
-                        |ui| eq_controls(ui, shared),
-                    );
-                    ui.add_space(6.0);
-                    changed |= layout_controls(ui, settings);
-                });
+        if ui.button(tr("Import")).clicked() {
+            *status = Some(match AppSettings::import_from(Path::new(path.as_str())) {
+                Ok(mut incoming) => {
+                    // Device names differ between machines, so keep the current output.
+                    incoming.output_device = settings.output_device.clone();
+                    *settings = incoming;
+                    imported = true;
+                    tr_fmt("Imported {path}", &[("path", path)])
+                }
+                Err(err) => err,
             });
         }
+    });
+    if let Some(message) = status {
+        ui.label(message.as_str());
     }
-    changed
+    imported
 }
 
+/// Dock placement for every panel plus card and keyboard sizing.
 fn layout_controls(ui: &mut egui::Ui, settings: &mut AppSettings) -> bool {
     let mut changed = false;
-    fl_card(
-        ui,
-        "Layout",
-        settings.card_padding,
-        settings.card_rounding,
-        |ui| {
-            let before = settings.layout_mode;
-            ComboBox::from_id_source("layout_mode")
-                .selected_text(settings.layout_mode.label())
-                .show_ui(ui, |ui| {
-                    for mode in LayoutMode::ALL {
-                        ui.selectable_value(&mut settings.layout_mode, mode, mode.label());
-                    }
-                });
-            if settings.layout_mode != before {
-                changed = true;
+    egui::Grid::new("panel_docks")
+        .num_columns(2)
+        .show(ui, |ui| {
+            for panel in PanelKind::ALL {
+                let current = settings.panel_slot(panel);
+                let mut slot = current;
+                ui.label(tr(panel.label()));
+                ComboBox::from_id_source(("panel_dock", panel))
+                    .selected_text(tr(slot.label()))
+                    .show_ui(ui, |ui| {
+                        for option in DockSlot::ALL {
+                            ui.selectable_value(&mut slot, option, tr(option.label()));
+                        }
+                    });
+                ui.end_row();
+                if slot != current {
+                    settings.set_panel_slot(panel, slot);
+                    changed = true;
+                }
             }
+        });
 
-            if ui
-                .add(
-                    egui::Slider::new(&mut settings.card_padding, 4.0..=24.0)
-                        .text("Card padding (px)"),
-                )
-                .changed()
-            {
-                changed = true;
-            }
-            if ui
-                .add(
-                    egui::Slider::new(&mut settings.card_rounding, 0.0..=18.0)
-                        .text("Card rounding (px)"),
-                )
-                .changed()
-            {
-                changed = true;
-            }
-            if ui
-                .add(
-                    egui::Slider::new(&mut settings.scope_height, 80.0..=220.0)
-                        .text("Scope height (px)"),
-                )
-                .changed()
-            {
-                changed = true;
-            }
-            if ui
-                .add(
-                    egui::Slider::new(&mut settings.keyboard_scale, 0.7..=1.4)
-                        .text("Keyboard height scale"),
-                )
-                .changed()
-            {
-                changed = true;
-            }
-        },
-    );
+    if ui
+        .add(
+            egui::Slider::new(&mut settings.card_padding, 4.0..=24.0).text(tr("Card padding (px)")),
+        )
+        .changed()
+    {
+        changed = true;
+    }
+    if ui
+        .add(
+            egui::Slider::new(&mut settings.card_rounding, 0.0..=18.0)
+                .text(tr("Card rounding (px)")),
+        )
+        .changed()
+    {
+        changed = true;
+    }
+    if ui
+        .add(
+            egui::Slider::new(&mut settings.scope_height, 80.0..=220.0)
+                .text(tr("Scope height (px)")),
+        )
+        .changed()
+    {
+        changed = true;
+    }
+    if ui
+        .add(
+            egui::Slider::new(&mut settings.keyboard_scale, 0.7..=1.4)
+                .text(tr("Keyboard height scale")),
+        )
+        .changed()
+    {
+        changed = true;
+    }
+    changed |= keyboard_range_controls(ui, settings);
+    if ui
+        .checkbox(&mut settings.touch_layout, tr("Touch layout"))
+        .on_hover_text(tr(
+            "Taller keys and bigger controls for playing on a touch screen",
+        ))
+        .changed()
+    {
+        apply_touch_layout(ui.ctx(), settings.touch_layout);
+        changed = true;
+    }
+    ui.horizontal(|ui| {
+        ui.label(tr("Key colours"));
+        ComboBox::from_id_source("key_coloring")
+            .selected_text(settings.key_coloring.label())
+            .show_ui(ui, |ui| {
+                for coloring in KeyColoring::ALL {
+                    changed |= ui
+                        .selectable_value(&mut settings.key_coloring, coloring, coloring.label())
+                        .changed();
+                }
+            });
+    });
     changed
 }
 
-fn auto_layout_for_width(width: f32) -> LayoutMode {
-    if width < 720.0 {
-        LayoutMode::Stacked
-    } else if width < 1080.0 {
-        LayoutMode::TwoColumn
-    } else {
-        LayoutMode::ThreeColumn
+/// Stores a dock's size once the user has resized it.
+fn remember_size(stored: &mut f32, actual: f32) -> bool {
+    if (actual - *stored).abs() < 1.0 {
+        return false;
+    }
+    *stored = actual;
+    true
+}
+
+fn keyboard_range_controls(ui: &mut egui::Ui, settings: &mut AppSettings) -> bool {
+    let naming = settings.octave_naming;
+    let before = (
+        settings.keyboard_range,
+        settings.custom_lowest_note,
+        settings.custom_highest_note,
+    );
+    ComboBox::from_id_source("keyboard_range")
+        .selected_text(settings.keyboard_range.label())
+        .show_ui(ui, |ui| {
+            for range in KeyboardRange::ALL {
+                ui.selectable_value(&mut settings.keyboard_range, range, range.label());
+            }
+        });
+    if settings.keyboard_range == KeyboardRange::Custom {
+        ui.horizontal(|ui| {
+            ui.label(tr("From"));
+            ui.add(
+                egui::DragValue::new(&mut settings.custom_lowest_note)
+                    .clamp_range(21..=96)
+                    .custom_formatter(|n, _| note_label(n as u8, naming)),
+            );
+            ui.label(tr("to"));
+            ui.add(
+                egui::DragValue::new(&mut settings.custom_highest_note)
+                    .clamp_range(33..=108)
+                    .custom_formatter(|n, _| note_label(n as u8, naming)),
+            );
+        });
     }
+    before
+        != (
+            settings.keyboard_range,
+            settings.custom_lowest_note,
+            settings.custom_highest_note,
+        )
 }
+
+/// Card around a panel. The title bar collapses the body and doubles as a
+/// drag handle carrying the panel as payload; the returned response covers
+/// the whole card so callers can take drops on it.
 fn fl_card(
     ui: &mut egui::Ui,
-    title: &str,
+    panel: PanelKind,
     padding: f32,
     rounding: f32,
+    collapsed: &mut bool,
     add_contents: impl FnOnce(&mut egui::Ui),
-) {
+) -> egui::Response {
     let visuals = ui.visuals().clone();
-    egui::Frame::none()
+    let rounding = Rounding::same(rounding.clamp(0.0, 18.0));
+    let card = egui::Frame::none()
         .fill(visuals.widgets.noninteractive.bg_fill)
         .stroke(visuals.widgets.noninteractive.bg_stroke)
-        .rounding(Rounding::same(rounding.clamp(0.0, 18.0)))
+        .rounding(rounding)
         .inner_margin(egui::Margin::same(padding.clamp(4.0, 24.0)))
         .show(ui, |ui| {
             ui.horizontal(|ui| {
-                ui.colored_label(ACCENT, title);
+                let (toggle, hint) = if *collapsed {
+                    ("+", tr("Expand"))
+                } else {
+                    ("−", tr("Collapse"))
+                };
+                let toggle = ui.small_button(toggle).on_hover_text(hint);
+                describe(&toggle, egui::WidgetType::Button, || {
+                    format!("{hint} {}", tr(panel.label()))
+                });
+                if toggle.clicked() {
+                    *collapsed = !*collapsed;
+                }
+                ui.dnd_drag_source(Id::new(("card_drag", panel)), panel, |ui| {
+                    ui.colored_label(ACCENT, tr(panel.label()));
+                })
+                .response
+                .on_hover_text(tr("Drag onto another card to move this one in front of it"));
                 ui.add_space(6.0);
                 ui.separator();
             });
-            ui.add_space(6.0);
-            add_contents(ui);
+            if !*collapsed {
+                ui.add_space(6.0);
+                add_contents(ui);
+            }
+        })
+        .response;
+    if card
+        .dnd_hover_payload::<PanelKind>()
+        .is_some_and(|dragged| *dragged != panel)
+    {
+        ui.painter()
+            .rect_stroke(card.rect, rounding, Stroke::new(2.0, ACCENT));
+    }
+    card
+}
+
+fn language_selector(ui: &mut egui::Ui, language: &mut Language) -> bool {
+    let before = *language;
+    ComboBox::from_id_source("language_selector")
+        .selected_text(language.label())
+        .show_ui(ui, |ui| {
+            for option in Language::ALL {
+                ui.selectable_value(language, option, option.label());
+            }
         });
+    if *language != before {
+        set_language(*language);
+    }
+    *language != before
 }
 
 fn theme_selector(ui: &mut egui::Ui, ctx: &egui::Context, settings: &mut AppSettings) -> bool {
@@ -812,6 +5609,23 @@ fn theme_selector(ui: &mut egui::Ui, ctx: &egui::Context, settings: &mut AppSett
     }
 }
 
+/// Enlarges the hit areas of every widget for fingers, or restores egui's
+/// mouse-sized defaults.
+fn apply_touch_layout(ctx: &egui::Context, touch: bool) {
+    let mut spacing = egui::style::Spacing::default();
+    if touch {
+        spacing.interact_size = egui::vec2(56.0, 40.0);
+        spacing.button_padding = egui::vec2(12.0, 8.0);
+        spacing.item_spacing = egui::vec2(10.0, 8.0);
+        spacing.slider_width = 220.0;
+        spacing.icon_width = 24.0;
+        spacing.icon_width_inner = 14.0;
+        spacing.combo_width = 140.0;
+        spacing.scroll.bar_width = 16.0;
+    }
+    ctx.style_mut(|style| style.spacing = spacing);
+}
+
 fn apply_theme(ctx: &egui::Context, theme: ThemeKind) {
     match theme {
         ThemeKind::Fl => apply_fl_theme(ctx),
@@ -829,6 +5643,7 @@ fn apply_theme(ctx: &egui::Context, theme: ThemeKind) {
         ThemeKind::Industrial => apply_industrial_theme(ctx),
         ThemeKind::Candy => apply_candy_theme(ctx),
         ThemeKind::Terminal => apply_terminal_theme(ctx),
+        ThemeKind::HighContrast => apply_high_contrast_theme(ctx),
     }
 }
 
@@ -1072,6 +5887,42 @@ fn apply_terminal_theme(ctx: &egui::Context) {
     );
 }
 
+/// Pure black and white with yellow for selection and focus, and heavier
+/// outlines so every control's edge is visible.
+fn apply_high_contrast_theme(ctx: &egui::Context) {
+    let mut visuals = egui::Visuals::dark();
+    let widgets = &mut visuals.widgets;
+    for (state, width) in [
+        (&mut widgets.noninteractive, 1.0),
+        (&mut widgets.inactive, 1.5),
+        (&mut widgets.hovered, 2.0),
+        (&mut widgets.active, 2.0),
+        (&mut widgets.open, 2.0),
+    ] {
+        state.fg_stroke = Stroke::new(width, Color32::WHITE);
+        state.bg_stroke = Stroke::new(width, Color32::WHITE);
+    }
+    widgets.hovered.bg_stroke.color = Color32::YELLOW;
+    widgets.active.bg_stroke.color = Color32::YELLOW;
+    visuals.extreme_bg_color = Color32::BLACK;
+    visuals.faint_bg_color = Color32::BLACK;
+    visuals.panel_fill = Color32::BLACK;
+    visuals.hyperlink_color = Color32::YELLOW;
+    apply_palette(
+        ctx,
+        visuals,
+        Some(Color32::WHITE),
+        Color32::YELLOW,
+        Stroke::new(2.0, Color32::BLACK),
+        Color32::BLACK,
+        Color32::BLACK,
+        Color32::from_rgb(40, 40, 0),
+        Color32::from_rgb(80, 80, 0),
+        Stroke::new(1.5, Color32::WHITE),
+        Color32::BLACK,
+    );
+}
+
 fn apply_palette(
     ctx: &egui::Context,
     mut visuals: egui::Visuals,
@@ -1103,12 +5954,35 @@ fn is_black(note: u8) -> bool {
     matches!(note % 12, 1 | 3 | 6 | 8 | 10)
 }
 
-fn note_label(note: u8) -> String {
-    const NAMES: [&str; 12] = [
-        "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
-    ];
-    let octave = (note / 12).saturating_sub(1);
-    format!("{}{}", NAMES[(note % 12) as usize], octave)
+fn note_label(note: u8, naming: OctaveNaming) -> String {
+    let octave = (note / 12) as i32 + naming.lowest_octave();
+    format!("{}{}", PITCH_CLASS_NAMES[(note % 12) as usize], octave)
+}
+
+/// Names a custom-painted widget for screen readers via AccessKit; egui's
+/// own widgets describe themselves.
+fn describe(response: &egui::Response, kind: egui::WidgetType, label: impl Fn() -> String) {
+    response.widget_info(|| egui::WidgetInfo::labeled(kind, label()));
+}
+
+/// Sounding note names plus the chord they spell, e.g. "C4 E4 G4 — C major".
+fn held_notes_readout(ui: &mut egui::Ui, notes: &[u8], naming: OctaveNaming) {
+    if notes.is_empty() {
+        return;
+    }
+    ui.separator();
+    let names: Vec<String> = notes.iter().map(|note| note_label(*note, naming)).collect();
+    let mut text = names.join(" ");
+    if let Some(chord) = chord_name(notes) {
+        text.push_str(" — ");
+        text.push_str(&chord);
+    }
+    ui.strong(text);
+}
+
+/// One hue per pitch class, going round the colour wheel from C.
+fn pitch_class_color(note: u8) -> Color32 {
+    egui::ecolor::Hsva::new((note % 12) as f32 / 12.0, 0.75, 0.95, 1.0).into()
 }
 
 fn blend_color(from: Color32, to: Color32, t: f32) -> Color32 {
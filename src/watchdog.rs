@@ -0,0 +1,206 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// A callback arriving this many buffer lengths after the previous one means
+/// the device most likely ran dry in between.
+const LATE_FACTOR: f32 = 1.5;
+/// Share of each callback's load blended into the displayed value once a
+/// spike has passed; spikes themselves show at once.
+const LOAD_DECAY: f32 = 0.02;
+
+/// Xruns counted since the stream opened or the counter was reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct XrunCounts {
+    /// Callbacks that came too late, so the device played a gap.
+    pub underruns: u32,
+    /// Callbacks that took longer to render than the audio they produced.
+    pub overruns: u32,
+    /// Errors reported by the audio backend.
+    pub stream_errors: u32,
+}
+
+impl XrunCounts {
+    pub fn total(self) -> u32 {
+        self.underruns + self.overruns + self.stream_errors
+    }
+}
+
+/// Callback deadline tracking for one output stream. The audio thread writes
+/// through a `CallbackTimer`; the UI reads counts and details without locking.
+/// Durations are stored in microseconds, the load as `f32` bits. Backend
+/// errors arrive on cpal's error callback, off the realtime path, so they
+/// can queue their messages behind a lock.
+pub struct AudioWatchdog {
+    sample_rate: f32,
+    underruns: AtomicU32,
+    overruns: AtomicU32,
+    stream_errors: AtomicU32,
+    load: AtomicU32,
+    buffer_frames: AtomicU32,
+    /// The latest under-run: the gap between callbacks and the gap expected.
+    late_gap_us: AtomicU32,
+    late_expected_us: AtomicU32,
+    /// The latest over-run: render time and the buffer it had to fill.
+    overrun_render_us: AtomicU32,
+    overrun_budget_us: AtomicU32,
+    errors: Mutex<Vec<String>>,
+}
+
+impl AudioWatchdog {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            underruns: AtomicU32::new(0),
+            overruns: AtomicU32::new(0),
+            stream_errors: AtomicU32::new(0),
+            load: AtomicU32::new(0),
+            buffer_frames: AtomicU32::new(0),
+            late_gap_us: AtomicU32::new(0),
+            late_expected_us: AtomicU32::new(0),
+            overrun_render_us: AtomicU32::new(0),
+            overrun_budget_us: AtomicU32::new(0),
+            errors: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn counts(&self) -> XrunCounts {
+        XrunCounts {
+            underruns: self.underruns.load(Ordering::Relaxed),
+            overruns: self.overruns.load(Ordering::Relaxed),
+            stream_errors: self.stream_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Render time as a share of the buffer length; 1 means no headroom left.
+    pub fn load(&self) -> f32 {
+        f32::from_bits(self.load.load(Ordering::Relaxed))
+    }
+
+    /// Frames in the latest callback, 0 before the first one.
+    pub fn buffer_frames(&self) -> u32 {
+        self.buffer_frames.load(Ordering::Relaxed)
+    }
+
+    pub fn record_stream_error(&self, message: String) {
+        self.stream_errors.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut errors) = self.errors.lock() {
+            errors.push(message);
+        }
+    }
+
+    /// Backend error messages reported since the last call.
+    pub fn take_errors(&self) -> Vec<String> {
+        self.errors
+            .lock()
+            .map(|mut errors| std::mem::take(&mut *errors))
+            .unwrap_or_default()
+    }
+
+    pub fn reset(&self) {
+        self.underruns.store(0, Ordering::Relaxed);
+        self.overruns.store(0, Ordering::Relaxed);
+        self.stream_errors.store(0, Ordering::Relaxed);
+    }
+
+    /// Log lines for the xruns counted since `seen`, which is then brought
+    /// up to date. Stream errors come with their own messages; see `take_errors`.
+    pub fn describe_new(&self, seen: &mut XrunCounts) -> Vec<String> {
+        let counts = self.counts();
+        let mut lines = Vec::new();
+        let ms = |micros: &AtomicU32| micros.load(Ordering::Relaxed) as f32 / 1000.0;
+        if counts.underruns > seen.underruns {
+            lines.push(format!(
+                "Audio under-run ({} so far): callback came {:.1} ms after the previous one, \
+                 expected {:.1} ms",
+                counts.underruns,
+                ms(&self.late_gap_us),
+                ms(&self.late_expected_us)
+            ));
+        }
+        if counts.overruns > seen.overruns {
+            lines.push(format!(
+                "Audio over-run ({} so far): rendering took {:.1} ms for a {:.1} ms buffer \
+                 of {} frames at {} Hz",
+                counts.overruns,
+                ms(&self.overrun_render_us),
+                ms(&self.overrun_budget_us),
+                self.buffer_frames(),
+                self.sample_rate
+            ));
+        }
+        *seen = counts;
+        lines
+    }
+}
+
+/// The audio thread's side of an `AudioWatchdog`: call `start` at the top of
+/// every callback and `finish` once its buffer is filled.
+pub struct CallbackTimer {
+    watchdog: Arc<AudioWatchdog>,
+    /// When the previous callback started and how much audio it delivered.
+    previous: Option<(Instant, f32)>,
+    started: Instant,
+    budget: f32,
+}
+
+impl CallbackTimer {
+    pub fn new(watchdog: Arc<AudioWatchdog>) -> Self {
+        Self {
+            watchdog,
+            previous: None,
+            started: Instant::now(),
+            budget: 0.0,
+        }
+    }
+
+    pub fn start(&mut self, frames: usize) {
+        let now = Instant::now();
+        let watchdog = &self.watchdog;
+        if let Some((previous, expected)) = self.previous {
+            let gap = now.duration_since(previous).as_secs_f32();
+            if gap > expected * LATE_FACTOR {
+                watchdog.late_gap_us.store(micros(gap), Ordering::Relaxed);
+                watchdog
+                    .late_expected_us
+                    .store(micros(expected), Ordering::Relaxed);
+                watchdog.underruns.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.started = now;
+        self.budget = frames as f32 / watchdog.sample_rate;
+        self.previous = Some((now, self.budget));
+        watchdog
+            .buffer_frames
+            .store(frames as u32, Ordering::Relaxed);
+    }
+
+    pub fn finish(&mut self) {
+        if self.budget <= 0.0 {
+            return;
+        }
+        let watchdog = &self.watchdog;
+        let render = self.started.elapsed().as_secs_f32();
+        let load = render / self.budget;
+        let shown = watchdog.load();
+        let shown = if load > shown {
+            load
+        } else {
+            shown + (load - shown) * LOAD_DECAY
+        };
+        watchdog.load.store(shown.to_bits(), Ordering::Relaxed);
+        if render > self.budget {
+            watchdog
+                .overrun_render_us
+                .store(micros(render), Ordering::Relaxed);
+            watchdog
+                .overrun_budget_us
+                .store(micros(self.budget), Ordering::Relaxed);
+            watchdog.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn micros(seconds: f32) -> u32 {
+    (seconds * 1_000_000.0) as u32
+}
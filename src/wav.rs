@@ -0,0 +1,118 @@
+/// Minimal WAV encoder for 16-bit PCM stereo, which every DAW and player
+/// reads. Samples are clamped to ±1 first.
+pub fn encode_stereo_pcm16(frames: &[[f32; 2]], sample_rate: u32) -> Vec<u8> {
+    const CHANNELS: u16 = 2;
+    const BITS: u16 = 16;
+    let block_align = CHANNELS * BITS / 8;
+    let data_len = frames.len() as u32 * block_align as u32;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16_u32.to_le_bytes());
+    wav.extend_from_slice(&1_u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&CHANNELS.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&BITS.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in frames.as_flattened() {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+    wav
+}
+
+/// A decoded WAV file, mixed down to one channel.
+pub struct MonoWav {
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+}
+
+/// Reads 8, 16, 24 or 32-bit integer and 32 or 64-bit float WAVs, averaging
+/// the channels together.
+pub fn decode_mono(wav: &[u8]) -> Result<MonoWav, String> {
+    let fmt = find_chunk(wav, b"fmt ").ok_or("Not a WAV file")?;
+    let data = find_chunk(wav, b"data").ok_or("The WAV file has no audio data")?;
+    if fmt.len() < 16 {
+        return Err("The WAV format header is damaged".to_string());
+    }
+    let u16_at = |at: usize| u16::from_le_bytes([fmt[at], fmt[at + 1]]);
+    let mut format = u16_at(0);
+    // WAVE_FORMAT_EXTENSIBLE keeps the real format in its sub-format GUID.
+    if format == 0xFFFE && fmt.len() >= 26 {
+        format = u16_at(24);
+    }
+    let channels = u16_at(2).max(1) as usize;
+    let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+    let bits = u16_at(14);
+    let decode: fn(&[u8]) -> f32 = match (format, bits) {
+        (1, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+        (1, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32_768.0,
+        (1, 24) => |b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2_147_483_648.0,
+        (1, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0,
+        (3, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        (3, 64) => |b| f64::from_le_bytes(b[..8].try_into().unwrap_or_default()) as f32,
+        _ => {
+            return Err(format!(
+                "Unsupported WAV format ({bits}-bit, format {format})"
+            ));
+        }
+    };
+    let width = bits as usize / 8;
+    let samples = data
+        .chunks_exact(width * channels)
+        .map(|frame| frame.chunks_exact(width).map(decode).sum::<f32>() / channels as f32)
+        .collect();
+    Ok(MonoWav {
+        sample_rate,
+        samples,
+    })
+}
+
+/// The body of the first RIFF chunk tagged `id`.
+pub fn find_chunk<'a>(wav: &'a [u8], id: &[u8; 4]) -> Option<&'a [u8]> {
+    if wav.get(..4) != Some(b"RIFF".as_slice()) || wav.get(8..12) != Some(b"WAVE".as_slice()) {
+        return None;
+    }
+    let mut at: usize = 12;
+    while let Some(header) = wav.get(at..at.checked_add(8)?) {
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let body = at + 8;
+        if &header[..4] == id {
+            // Some writers leave the data length too long; take what is there.
+            return wav.get(body..body.saturating_add(len).min(wav.len()));
+        }
+        // Chunks are padded to an even length.
+        at = body.checked_add(len)?.checked_add(len % 2)?;
+    }
+    None
+}
+
+/// Root note and first loop from the `smpl` chunk samplers and editors
+/// write. The loop is in sample frames, its end one past the last played.
+pub fn smpl_info(wav: &[u8]) -> (Option<f32>, Option<(usize, usize)>) {
+    let Some(smpl) = find_chunk(wav, b"smpl") else {
+        return (None, None);
+    };
+    let u32_at = |at: usize| {
+        smpl.get(at..at + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    // The pitch fraction is a share of a semitone scaled to the full u32.
+    let root = u32_at(12)
+        .filter(|note| *note <= 127)
+        .map(|note| note as f32 + u32_at(16).unwrap_or(0) as f32 / 4_294_967_296.0);
+    let loop_points = match (u32_at(28), u32_at(44), u32_at(48)) {
+        // The chunk's loop end is the last sample played, not one past it.
+        (Some(loops), Some(start), Some(end)) if loops > 0 && start < end => {
+            Some((start as usize, end as usize + 1))
+        }
+        _ => None,
+    };
+    (root, loop_points)
+}
@@ -0,0 +1,135 @@
+use std::fs;
+
+use crate::files::{FileCache, FileRef, Loadable};
+use crate::synth::{SynthParams, Waveform};
+use crate::wav::{decode_mono, find_chunk};
+
+/// Samples per frame once loaded; frames of other lengths are resampled.
+const FRAME_LEN: usize = 2048;
+/// Frame length of files without a `clm ` chunk saying otherwise; Serum and
+/// most wavetable editors write 2048-sample frames.
+const STANDARD_FRAME_LEN: usize = 2048;
+const MAX_FRAMES: usize = 256;
+/// Shapes the position sweeps through while no table is loaded.
+const BASIC_SHAPES: [Waveform; 4] = [
+    Waveform::Sine,
+    Waveform::Triangle,
+    Waveform::Saw,
+    Waveform::Square,
+];
+
+/// Single-cycle frames read from a WAV, normalised to a peak of 1.
+pub struct Wavetable {
+    /// `FRAME_LEN` samples per frame, back to back.
+    samples: Vec<f32>,
+    frames: usize,
+}
+
+impl Wavetable {
+    /// Reads a single-cycle or multi-frame table. The frame length comes from
+    /// the `clm ` chunk when there is one; otherwise a file that is a whole
+    /// number of standard frames long is a table, and anything else one cycle.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|err| format!("Could not read {path}: {err}"))?;
+        let wav = decode_mono(&bytes).map_err(|err| format!("{path}: {err}"))?;
+        let samples = wav.samples;
+        let frame_len = clm_frame_len(&bytes)
+            .filter(|len| (2..=samples.len()).contains(len))
+            .unwrap_or(if samples.len() % STANDARD_FRAME_LEN == 0 {
+                STANDARD_FRAME_LEN
+            } else {
+                samples.len()
+            });
+        if frame_len < 2 {
+            return Err(format!("{path} is too short for a wavetable"));
+        }
+        let frames = (samples.len() / frame_len).min(MAX_FRAMES);
+        let mut table = Vec::with_capacity(frames * FRAME_LEN);
+        for cycle in samples.chunks_exact(frame_len).take(frames) {
+            let step = frame_len as f32 / FRAME_LEN as f32;
+            table.extend((0..FRAME_LEN).map(|index| interpolate(cycle, index as f32 * step)));
+        }
+        let peak = table
+            .iter()
+            .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+        if peak < 1e-6 {
+            return Err(format!("{path} is silent"));
+        }
+        for sample in &mut table {
+            *sample /= peak;
+        }
+        Ok(Self {
+            samples: table,
+            frames,
+        })
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// `position` 0..1 sweeps from the first frame to the last, blending
+    /// neighbouring frames in between.
+    pub fn sample(&self, position: f32, phase: f32) -> f32 {
+        let at = position.clamp(0.0, 1.0) * (self.frames - 1) as f32;
+        let first = at as usize;
+        let second = (first + 1).min(self.frames - 1);
+        let a = self.frame_sample(first, phase);
+        let b = self.frame_sample(second, phase);
+        a + (b - a) * (at - first as f32)
+    }
+
+    fn frame_sample(&self, frame: usize, phase: f32) -> f32 {
+        let cycle = &self.samples[frame * FRAME_LEN..(frame + 1) * FRAME_LEN];
+        interpolate(cycle, phase.rem_euclid(1.0) * FRAME_LEN as f32)
+    }
+}
+
+impl Loadable for Wavetable {
+    fn load(path: &str) -> Result<Self, String> {
+        Wavetable::load(path)
+    }
+}
+
+/// A wavetable WAV named by its path, with the table attached once loaded.
+pub type WavetableFile = FileRef<Wavetable>;
+
+pub type WavetableCache = FileCache<Wavetable>;
+
+impl WavetableCache {
+    /// Attaches the table `params` names, loading it the first time.
+    pub fn resolve(&mut self, params: &mut SynthParams) {
+        self.resolve_file(&mut params.wavetable);
+    }
+}
+
+/// The oscillator of a `Waveform::Wavetable` patch: its loaded table, or a
+/// sweep through sine, triangle, saw and square until one is loaded.
+pub fn wavetable_sample(params: &SynthParams, phase: f32) -> f32 {
+    let position = params.wavetable_position;
+    if let Some(table) = params.wavetable.loaded() {
+        return table.sample(position, phase);
+    }
+    let at = position.clamp(0.0, 1.0) * (BASIC_SHAPES.len() - 1) as f32;
+    let first = (at as usize).min(BASIC_SHAPES.len() - 2);
+    let a = BASIC_SHAPES[first].sample(phase);
+    let b = BASIC_SHAPES[first + 1].sample(phase);
+    a + (b - a) * (at - first as f32)
+}
+
+/// Linear interpolation at `pos` samples into one cycle, wrapping at the end.
+fn interpolate(cycle: &[f32], pos: f32) -> f32 {
+    let index = pos as usize % cycle.len();
+    let next = (index + 1) % cycle.len();
+    cycle[index] + (cycle[next] - cycle[index]) * pos.fract()
+}
+
+/// Frame length from the `<!>2048 …` text Serum writes into a `clm ` chunk.
+fn clm_frame_len(wav: &[u8]) -> Option<usize> {
+    let text = String::from_utf8_lossy(find_chunk(wav, b"clm ")?);
+    text.strip_prefix("<!>")?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}